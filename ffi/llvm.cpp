@@ -1,5 +1,6 @@
 #include "nitro.hpp"
 
+#include <llvm/Config/llvm-config.h>
 #include <llvm/Support/TargetSelect.h>
 #include <llvm/TargetParser/Host.h>
 
@@ -15,3 +16,8 @@ extern "C" void llvm_process_triple(nitro_string &t)
 {
     nitro_string_set(t, llvm::sys::getProcessTriple().c_str());
 }
+
+extern "C" void llvm_version(nitro_string &v)
+{
+    nitro_string_set(v, LLVM_VERSION_STRING);
+}
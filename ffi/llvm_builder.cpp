@@ -1,4 +1,7 @@
 #include <llvm/IR/IRBuilder.h>
+#include <llvm/IR/InlineAsm.h>
+
+#include <vector>
 
 using namespace llvm;
 
@@ -22,6 +25,27 @@ extern "C" CallInst *llvm_builder_call(IRBuilder<> *ib, Function *fn, Value **ar
     return ib->CreateCall(fn->getFunctionType(), fn, ArrayRef<Value *>(args, nargs));
 }
 
+extern "C" CallInst *llvm_builder_asm(
+    IRBuilder<> *ib,
+    const char *inst,
+    const char *constraints,
+    Value **args,
+    size_t nargs,
+    bool intel)
+{
+    std::vector<Type *> params;
+
+    for (size_t i = 0; i < nargs; i++) {
+        params.push_back(args[i]->getType());
+    }
+
+    auto ty = FunctionType::get(ib->getVoidTy(), params, false);
+    auto dialect = intel ? InlineAsm::AD_Intel : InlineAsm::AD_ATT;
+    auto callee = InlineAsm::get(ty, inst, constraints, true, false, dialect);
+
+    return ib->CreateCall(ty, callee, ArrayRef<Value *>(args, nargs));
+}
+
 extern "C" ReturnInst *llvm_builder_ret_void(IRBuilder<> *ib)
 {
     return ib->CreateRetVoid();
@@ -31,3 +55,54 @@ extern "C" ReturnInst *llvm_builder_ret(IRBuilder<> *ib, Value *v)
 {
     return ib->CreateRet(v);
 }
+
+extern "C" BranchInst *llvm_builder_cond_br(
+    IRBuilder<> *ib,
+    Value *cond,
+    BasicBlock *then,
+    BasicBlock *otherwise)
+{
+    return ib->CreateCondBr(cond, then, otherwise);
+}
+
+extern "C" UnreachableInst *llvm_builder_unreachable(IRBuilder<> *ib)
+{
+    return ib->CreateUnreachable();
+}
+
+extern "C" Value *llvm_builder_icmp_ne(IRBuilder<> *ib, Value *lhs, Value *rhs)
+{
+    return ib->CreateICmpNE(lhs, rhs);
+}
+
+extern "C" Value *llvm_builder_cast(
+    IRBuilder<> *ib,
+    Value *v,
+    bool from_signed,
+    Type *ty,
+    bool to_signed)
+{
+    auto op = CastInst::getCastOpcode(v, from_signed, ty, to_signed);
+
+    return ib->CreateCast(op, v, ty);
+}
+
+extern "C" Value *llvm_builder_gep8(IRBuilder<> *ib, Value *ptr, Value *off)
+{
+    return ib->CreateGEP(ib->getInt8Ty(), ptr, off);
+}
+
+extern "C" Value *llvm_builder_ptr_diff(IRBuilder<> *ib, Value *lhs, Value *rhs, Type *ty)
+{
+    return ib->CreateSub(ib->CreatePtrToInt(lhs, ty), ib->CreatePtrToInt(rhs, ty));
+}
+
+extern "C" LoadInst *llvm_builder_load(IRBuilder<> *ib, Type *ty, Value *ptr)
+{
+    return ib->CreateLoad(ty, ptr);
+}
+
+extern "C" StoreInst *llvm_builder_store(IRBuilder<> *ib, Value *v, Value *ptr)
+{
+    return ib->CreateStore(v, ptr);
+}
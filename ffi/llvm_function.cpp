@@ -17,7 +17,32 @@ extern "C" void llvm_function_set_stdcall(Function *fn)
     fn->setCallingConv(CallingConv::X86_StdCall);
 }
 
+extern "C" void llvm_function_set_fastcc(Function *fn)
+{
+    fn->setCallingConv(CallingConv::Fast);
+}
+
 extern "C" void llvm_function_set_noreturn(Function *fn)
 {
     fn->addFnAttr(Attribute::NoReturn);
 }
+
+extern "C" void llvm_function_set_inline_hint(Function *fn)
+{
+    fn->addFnAttr(Attribute::InlineHint);
+}
+
+extern "C" void llvm_function_set_inline_always(Function *fn)
+{
+    fn->addFnAttr(Attribute::AlwaysInline);
+}
+
+extern "C" void llvm_function_set_inline_never(Function *fn)
+{
+    fn->addFnAttr(Attribute::NoInline);
+}
+
+extern "C" void llvm_function_set_cold(Function *fn)
+{
+    fn->addFnAttr(Attribute::Cold);
+}
@@ -0,0 +1,13 @@
+#include <llvm/IR/GlobalVariable.h>
+#include <llvm/IR/Module.h>
+
+using namespace llvm;
+
+extern "C" GlobalVariable *llvm_global_new_const(
+    Module *md,
+    Type *type,
+    Constant *init,
+    const char *name)
+{
+    return new GlobalVariable(*md, type, true, GlobalValue::ExternalLinkage, init, name);
+}
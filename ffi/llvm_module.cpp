@@ -1,4 +1,7 @@
+#include "nitro.hpp"
+
 #include <llvm/IR/Module.h>
+#include <llvm/Support/raw_ostream.h>
 
 using namespace llvm;
 
@@ -21,3 +24,17 @@ extern "C" Function *llvm_module_get_function(const Module *md, const char *name
 {
     return md->getFunction(name);
 }
+
+extern "C" GlobalVariable *llvm_module_get_global(const Module *md, const char *name)
+{
+    return md->getGlobalVariable(name);
+}
+
+extern "C" void llvm_module_print(const Module *md, nitro_string &s)
+{
+    std::string ir;
+    raw_string_ostream os(ir);
+
+    md->print(os, nullptr);
+    nitro_string_set(s, ir.c_str());
+}
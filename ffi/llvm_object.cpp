@@ -0,0 +1,60 @@
+#include "nitro.hpp"
+
+#include <llvm/Object/ObjectFile.h>
+#include <llvm/Object/SymbolSize.h>
+#include <llvm/Support/Error.h>
+
+#include <cstdint>
+#include <string>
+
+using namespace llvm;
+using namespace llvm::object;
+
+extern "C" bool llvm_object_symbol_sizes(
+    const char *file,
+    void *cx,
+    void (*cb) (void *, const char *, uint64_t),
+    nitro_string &err)
+{
+    // Open the object.
+    auto bin = ObjectFile::createObjectFile(file);
+
+    if (!bin) {
+        nitro_string_set(err, toString(bin.takeError()).c_str());
+        return false;
+    }
+
+    // Enumerate defined functions.
+    auto obj = bin->getBinary();
+
+    for (auto &[sym, size] : computeSymbolSizes(*obj)) {
+        auto type = sym.getType();
+
+        if (!type) {
+            nitro_string_set(err, toString(type.takeError()).c_str());
+            return false;
+        } else if (*type != SymbolRef::ST_Function) {
+            continue;
+        }
+
+        auto flags = sym.getFlags();
+
+        if (!flags) {
+            nitro_string_set(err, toString(flags.takeError()).c_str());
+            return false;
+        } else if (*flags & SymbolRef::SF_Undefined) {
+            continue;
+        }
+
+        auto name = sym.getName();
+
+        if (!name) {
+            nitro_string_set(err, toString(name.takeError()).c_str());
+            return false;
+        }
+
+        cb(cx, name->str().c_str(), size);
+    }
+
+    return true;
+}
@@ -1,6 +1,9 @@
+#include <llvm/IR/Constants.h>
 #include <llvm/IR/DerivedTypes.h>
 #include <llvm/IR/Type.h>
 
+#include <vector>
+
 using namespace llvm;
 
 extern "C" Type *llvm_type_void(LLVMContext *cx)
@@ -33,3 +36,25 @@ extern "C" FunctionType *llvm_type_func(Type *ret, Type *params[], size_t count,
     ArrayRef<Type *> arr(params, count);
     return FunctionType::get(ret, arr, va);
 }
+
+extern "C" StructType *llvm_type_struct(LLVMContext *cx, Type *elems[], size_t count)
+{
+    ArrayRef<Type *> arr(elems, count);
+    return StructType::get(*cx, arr);
+}
+
+extern "C" Constant *llvm_const_struct(StructType *ty, Value *values[], size_t count)
+{
+    std::vector<Constant *> elems;
+
+    for (size_t i = 0; i < count; i++) {
+        elems.push_back(cast<Constant>(values[i]));
+    }
+
+    return ConstantStruct::get(ty, elems);
+}
+
+extern "C" Constant *llvm_const_zero(Type *ty)
+{
+    return Constant::getNullValue(ty);
+}
@@ -1,7 +1,9 @@
-use super::expr::Expression;
+use super::expr::{Call, Expression};
+use super::SourceWriter;
 use crate::codegen::Codegen;
 use crate::lexer::{AttributeName, Lexer, SyntaxError, Token};
-use crate::pkg::{Extern, Public, Representation};
+use crate::pkg::{Abi, Extern, Public, Representation, TargetOs};
+use std::collections::HashMap;
 
 /// A collection of attributes in the source file.
 #[derive(Default)]
@@ -9,9 +11,16 @@ pub(super) struct Attributes {
     public: Option<(AttributeName, Public)>,
     condition: Option<(AttributeName, Vec<Expression>)>,
     ext: Option<(AttributeName, Extern)>,
+    abi: Option<(AttributeName, Abi)>,
     repr: Option<(AttributeName, Representation)>,
     entry: Option<AttributeName>,
+    test: Option<AttributeName>,
+    inline: Option<(AttributeName, Inline)>,
+    cold: Option<AttributeName>,
+    reexport: Option<AttributeName>,
+    no_prelude: Option<AttributeName>,
     customs: Vec<(AttributeName, Option<Vec<Vec<Expression>>>)>,
+    order: Vec<ParsedAttribute>,
 }
 
 impl Attributes {
@@ -49,6 +58,10 @@ impl Attributes {
         self.ext.as_ref()
     }
 
+    pub fn abi(&self) -> Option<&(AttributeName, Abi)> {
+        self.abi.as_ref()
+    }
+
     pub fn repr(&self) -> Option<&(AttributeName, Representation)> {
         self.repr.as_ref()
     }
@@ -57,7 +70,39 @@ impl Attributes {
         self.entry.as_ref()
     }
 
+    pub fn test(&self) -> Option<&AttributeName> {
+        self.test.as_ref()
+    }
+
+    pub fn inline(&self) -> Option<&(AttributeName, Inline)> {
+        self.inline.as_ref()
+    }
+
+    pub fn cold(&self) -> Option<&AttributeName> {
+        self.cold.as_ref()
+    }
+
+    pub fn reexport(&self) -> Option<&AttributeName> {
+        self.reexport.as_ref()
+    }
+
+    pub fn no_prelude(&self) -> Option<&AttributeName> {
+        self.no_prelude.as_ref()
+    }
+
     pub fn run_condition(&self, cg: &Codegen) -> Result<bool, SyntaxError> {
+        self.eval_condition(cg.target().os(), cg.defines())
+    }
+
+    /// Evaluate `@if` against `os` and `defines` without requiring a [`Codegen`].
+    ///
+    /// Any identifier other than `os` on the left side of `==` and `!=` is a key in `defines`. An
+    /// unknown key is not equal to any value so a package can probe an optional switch.
+    pub fn eval_condition(
+        &self,
+        os: TargetOs,
+        defines: &HashMap<String, String>,
+    ) -> Result<bool, SyntaxError> {
         // Always return true if no condition.
         let cond = match &self.condition {
             Some(v) => &v.1,
@@ -68,11 +113,19 @@ impl Attributes {
         let mut expr = cond.iter();
         let lhs = match expr.next().unwrap() {
             Expression::Value(v) => v,
+            Expression::Call(c) => {
+                let res = Self::eval_defined(c, defines)?;
+
+                if let Some(e) = expr.next() {
+                    return Err(SyntaxError::new(e.span(), "unsupported expression"));
+                }
+
+                return Ok(res);
+            }
             e => return Err(SyntaxError::new(e.span(), "expect an identifier")),
         };
 
         // Get second expression.
-        let os = cg.target().os();
         let (equal, span) = match expr.next() {
             Some(Expression::NotEqual(f, s)) => (false, f.span() + s.span()),
             Some(Expression::Equal(f, s)) => (true, f.span() + s.span()),
@@ -86,10 +139,12 @@ impl Attributes {
             }
         };
 
-        // Check if first expression is "os".
-        if lhs.value() != "os" {
-            return Err(SyntaxError::new(lhs.span().clone(), "unknown expression"));
-        }
+        // Get the value of the first expression.
+        let value = if lhs.value() == "os" {
+            Some(os.name())
+        } else {
+            defines.get(lhs.value()).map(|v| v.as_str())
+        };
 
         // Get third argument.
         let rhs = match expr.next() {
@@ -100,18 +155,115 @@ impl Attributes {
 
         // Compare.
         let res = if equal {
-            rhs.value() == os.name()
+            value == Some(rhs.value())
         } else {
-            rhs.value() != os.name()
+            value != Some(rhs.value())
         };
 
-        if expr.next().is_some() {
-            todo!()
+        if let Some(e) = expr.next() {
+            return Err(SyntaxError::new(e.span(), "unsupported expression"));
         }
 
         Ok(res)
     }
 
+    /// Evaluate `defined(key)`.
+    fn eval_defined(call: &Call, defines: &HashMap<String, String>) -> Result<bool, SyntaxError> {
+        if call
+            .name()
+            .as_local()
+            .is_none_or(|n| n.value() != "defined")
+        {
+            return Err(SyntaxError::new(call.span(), "unknown function"));
+        }
+
+        match call.args() {
+            [arg] => match arg.as_slice() {
+                [Expression::Value(key)] => Ok(defines.contains_key(key.value())),
+                _ => Err(SyntaxError::new(call.span(), "expect a single identifier")),
+            },
+            _ => Err(SyntaxError::new(call.span(), "expect a single identifier")),
+        }
+    }
+
+    /// Write all attributes in the same order as the source, one attribute per line.
+    pub fn format(&self, w: &mut SourceWriter) {
+        for a in &self.order {
+            w.mark(self.name(a).span());
+
+            match a {
+                ParsedAttribute::Public => w.write(&self.public.as_ref().unwrap().0),
+                ParsedAttribute::Condition => {
+                    let (n, v) = self.condition.as_ref().unwrap();
+
+                    w.write(format_args!("{n}("));
+                    w.write_exprs(v);
+                    w.write(")");
+                }
+                ParsedAttribute::Ext => match self.ext.as_ref().unwrap() {
+                    (n, Extern::C) => w.write(format_args!("{n}(C)")),
+                },
+                ParsedAttribute::Abi => match self.abi.as_ref().unwrap() {
+                    (n, Abi::C) => w.write(format_args!("{n}(\"C\")")),
+                    (n, Abi::Nitro) => w.write(format_args!("{n}(\"nitro\")")),
+                },
+                ParsedAttribute::Repr => match self.repr.as_ref().unwrap() {
+                    (n, Representation::I32) => w.write(format_args!("{n}(i32)")),
+                    (n, Representation::U8) => w.write(format_args!("{n}(u8)")),
+                    (n, Representation::Un) => w.write(format_args!("{n}(un)")),
+                },
+                ParsedAttribute::Entry => w.write(self.entry.as_ref().unwrap()),
+                ParsedAttribute::Test => w.write(self.test.as_ref().unwrap()),
+                ParsedAttribute::Inline => match self.inline.as_ref().unwrap() {
+                    (n, Inline::Hint) => w.write(n),
+                    (n, Inline::Always) => w.write(format_args!("{n}(always)")),
+                    (n, Inline::Never) => w.write(format_args!("{n}(never)")),
+                },
+                ParsedAttribute::Cold => w.write(self.cold.as_ref().unwrap()),
+                ParsedAttribute::Reexport => w.write(self.reexport.as_ref().unwrap()),
+                ParsedAttribute::NoPrelude => w.write(self.no_prelude.as_ref().unwrap()),
+                ParsedAttribute::Custom(i) => {
+                    let (n, args) = &self.customs[*i];
+
+                    w.write(n);
+
+                    if let Some(args) = args {
+                        w.write("(");
+
+                        for (i, a) in args.iter().enumerate() {
+                            if i != 0 {
+                                w.write(", ");
+                            }
+
+                            w.write_exprs(a);
+                        }
+
+                        w.write(")");
+                    }
+                }
+            }
+
+            w.newline();
+        }
+    }
+
+    fn name(&self, a: &ParsedAttribute) -> &AttributeName {
+        match a {
+            ParsedAttribute::Public => &self.public.as_ref().unwrap().0,
+            ParsedAttribute::Condition => &self.condition.as_ref().unwrap().0,
+            ParsedAttribute::Ext => &self.ext.as_ref().unwrap().0,
+            ParsedAttribute::Abi => &self.abi.as_ref().unwrap().0,
+            ParsedAttribute::Repr => &self.repr.as_ref().unwrap().0,
+            ParsedAttribute::Entry => self.entry.as_ref().unwrap(),
+            ParsedAttribute::Test => self.test.as_ref().unwrap(),
+            ParsedAttribute::Inline => &self.inline.as_ref().unwrap().0,
+            ParsedAttribute::Cold => self.cold.as_ref().unwrap(),
+            ParsedAttribute::Reexport => self.reexport.as_ref().unwrap(),
+            ParsedAttribute::NoPrelude => self.no_prelude.as_ref().unwrap(),
+            ParsedAttribute::Custom(i) => &self.customs[*i].0,
+        }
+    }
+
     pub fn to_external(&self) -> crate::pkg::Attributes {
         crate::pkg::Attributes::new(
             self.public.as_ref().map(|v| v.1),
@@ -121,7 +273,69 @@ impl Attributes {
     }
 
     fn parse_single(&mut self, lex: &mut Lexer, name: AttributeName) -> Result<(), SyntaxError> {
+        let kind = match name.value() {
+            "abi" => ParsedAttribute::Abi,
+            "cold" => ParsedAttribute::Cold,
+            "entry" => ParsedAttribute::Entry,
+            "ext" => ParsedAttribute::Ext,
+            "if" => ParsedAttribute::Condition,
+            "inline" => ParsedAttribute::Inline,
+            "no_prelude" => ParsedAttribute::NoPrelude,
+            "pub" => ParsedAttribute::Public,
+            "reexport" => ParsedAttribute::Reexport,
+            "repr" => ParsedAttribute::Repr,
+            "test" => ParsedAttribute::Test,
+            _ => ParsedAttribute::Custom(self.customs.len()),
+        };
+
         match name.value() {
+            "abi" => {
+                // Check for multiple abi.
+                if self.abi.is_some() {
+                    return Err(SyntaxError::new(
+                        name.span(),
+                        "multiple abi attribute is not allowed",
+                    ));
+                }
+
+                // Parse argument.
+                lex.next_op()?;
+
+                let abi = match lex.next()? {
+                    Some(Token::StringLiteral(v)) => match v.value() {
+                        "C" => Abi::C,
+                        "nitro" => Abi::Nitro,
+                        _ => return Err(SyntaxError::new(v.span().clone(), "unknown ABI")),
+                    },
+                    Some(t) => {
+                        return Err(SyntaxError::new(
+                            t.span().clone(),
+                            "expect a string literal",
+                        ));
+                    }
+                    None => {
+                        return Err(SyntaxError::new(
+                            lex.last().unwrap().clone(),
+                            "expect a string literal after this",
+                        ));
+                    }
+                };
+
+                lex.next_cp()?;
+
+                self.abi = Some((name, abi));
+            }
+            "cold" => {
+                // Check for multiple cold.
+                if self.cold.is_some() {
+                    return Err(SyntaxError::new(
+                        name.span(),
+                        "multiple cold attribute is not allowed",
+                    ));
+                }
+
+                self.cold = Some(name);
+            }
             "entry" => {
                 // Check for multiple entry.
                 if self.entry.is_some() {
@@ -169,6 +383,40 @@ impl Attributes {
                 self.condition = Some((name, Expression::parse(lex)?));
                 lex.next_cp()?;
             }
+            "inline" => {
+                // Parse argument.
+                let inline = match lex.next()? {
+                    Some(Token::OpenParenthesis(_)) => {
+                        let arg = lex.next_ident()?;
+                        lex.next_cp()?;
+
+                        match arg.value() {
+                            "always" => Inline::Always,
+                            "never" => Inline::Never,
+                            _ => return Err(SyntaxError::new(arg.span(), "unknown inline mode")),
+                        }
+                    }
+                    Some(_) => {
+                        lex.undo();
+                        Inline::Hint
+                    }
+                    None => Inline::Hint,
+                };
+
+                // Check for multiple inline.
+                if let Some((_, v)) = &self.inline {
+                    return Err(SyntaxError::new(
+                        name.span(),
+                        if *v == inline {
+                            "multiple inline attribute is not allowed"
+                        } else {
+                            "conflicting inline attribute"
+                        },
+                    ));
+                }
+
+                self.inline = Some((name, inline));
+            }
             "pub" => {
                 // Check for multiple pub.
                 if self.public.is_some() {
@@ -182,7 +430,13 @@ impl Attributes {
                 self.public = Some(match lex.next()? {
                     Some(Token::OpenParenthesis(_)) => match lex.next()? {
                         Some(Token::CloseParenthesis(_)) => (name, Public::External),
-                        _ => todo!(),
+                        Some(t) => return Err(SyntaxError::new(t.span().clone(), "expect ')'")),
+                        None => {
+                            return Err(SyntaxError::new(
+                                lex.last().unwrap().clone(),
+                                "expect ')' after this",
+                            ));
+                        }
                     },
                     Some(_) => {
                         lex.undo();
@@ -191,6 +445,28 @@ impl Attributes {
                     None => (name, Public::External),
                 });
             }
+            "no_prelude" => {
+                // Check for multiple no_prelude.
+                if self.no_prelude.is_some() {
+                    return Err(SyntaxError::new(
+                        name.span(),
+                        "multiple no_prelude attribute is not allowed",
+                    ));
+                }
+
+                self.no_prelude = Some(name);
+            }
+            "reexport" => {
+                // Check for multiple reexport.
+                if self.reexport.is_some() {
+                    return Err(SyntaxError::new(
+                        name.span(),
+                        "multiple reexport attribute is not allowed",
+                    ));
+                }
+
+                self.reexport = Some(name);
+            }
             "repr" => {
                 // Check for multiple repr.
                 if self.repr.is_some() {
@@ -215,6 +491,17 @@ impl Attributes {
                     },
                 ));
             }
+            "test" => {
+                // Check for multiple test.
+                if self.test.is_some() {
+                    return Err(SyntaxError::new(
+                        name.span(),
+                        "multiple test attribute is not allowed",
+                    ));
+                }
+
+                self.test = Some(name);
+            }
             v if v.chars().next().unwrap().is_ascii_lowercase() => {
                 return Err(SyntaxError::new(
                     name.span(),
@@ -228,14 +515,178 @@ impl Attributes {
                     Some(Token::CloseParenthesis(v)) => {
                         return Err(SyntaxError::new(v.span(), "expect '('"));
                     }
-                    _ => {
+                    Some(_) => {
                         lex.undo();
                         None
                     }
+                    None => None,
                 },
             )),
         }
 
+        self.order.push(kind);
+
         Ok(())
     }
 }
+
+/// An attribute in the order it appears in the source. The arguments are stored in the typed
+/// fields of [`Attributes`].
+enum ParsedAttribute {
+    Public,
+    Condition,
+    Ext,
+    Abi,
+    Repr,
+    Entry,
+    Test,
+    Inline,
+    Cold,
+    Reexport,
+    NoPrelude,
+    Custom(usize),
+}
+
+/// Argument of `@inline`. This is a hint for the optimizer so it is not a part of the package.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(super) enum Inline {
+    Hint,
+    Always,
+    Never,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn condition_defined() {
+        let defines = defines(&[("debug", "1")]);
+
+        assert!(eval("defined(debug)", TargetOs::Linux, &defines).unwrap());
+        assert!(!eval("defined(trace)", TargetOs::Linux, &defines).unwrap());
+        assert!(!eval("defined(debug)", TargetOs::Linux, &HashMap::new()).unwrap());
+    }
+
+    #[test]
+    fn condition_equality() {
+        let defines = defines(&[("mode", "fast")]);
+
+        assert!(eval("mode == \"fast\"", TargetOs::Linux, &defines).unwrap());
+        assert!(!eval("mode == \"slow\"", TargetOs::Linux, &defines).unwrap());
+        assert!(!eval("mode != \"fast\"", TargetOs::Linux, &defines).unwrap());
+        assert!(eval("mode != \"slow\"", TargetOs::Linux, &defines).unwrap());
+
+        // An undefined key is not equal to any value.
+        assert!(!eval("level == \"\"", TargetOs::Linux, &defines).unwrap());
+        assert!(eval("level != \"\"", TargetOs::Linux, &defines).unwrap());
+    }
+
+    #[test]
+    fn condition_os() {
+        // The os is not affected by the defines, even the one with the same name.
+        let defines = defines(&[("os", "win32"), ("mode", "fast")]);
+
+        assert!(eval("os == \"linux\"", TargetOs::Linux, &defines).unwrap());
+        assert!(!eval("os == \"win32\"", TargetOs::Linux, &defines).unwrap());
+        assert!(eval("os != \"linux\"", TargetOs::Darwin, &defines).unwrap());
+        assert!(eval("unix", TargetOs::Darwin, &defines).unwrap());
+        assert!(!eval("unix", TargetOs::Win32, &defines).unwrap());
+        assert!(eval("win32", TargetOs::Win32, &defines).unwrap());
+        assert!(eval("mode == \"fast\"", TargetOs::Win32, &defines).unwrap());
+    }
+
+    #[test]
+    fn condition_error() {
+        let defines = HashMap::new();
+        let e = eval("exists(debug)", TargetOs::Linux, &defines).unwrap_err();
+
+        assert_eq!(e.reason(), "unknown function");
+
+        let e = eval("defined(debug) defined(trace)", TargetOs::Linux, &defines).unwrap_err();
+
+        assert_eq!(e.reason(), "unsupported expression");
+
+        let e = eval("mode == fast", TargetOs::Linux, &defines).unwrap_err();
+
+        assert_eq!(e.reason(), "expect a string literal");
+    }
+
+    #[test]
+    fn inline_forms() {
+        let forms = [
+            ("@inline", Inline::Hint),
+            ("@inline(always)", Inline::Always),
+            ("@inline(never)", Inline::Never),
+        ];
+
+        for (src, expected) in forms {
+            let attrs = parse(src).unwrap();
+
+            assert!(
+                matches!(attrs.inline(), Some((n, v)) if n.span().as_str() == "@inline" && *v == expected)
+            );
+            assert!(attrs.cold().is_none());
+            assert_eq!(format(&attrs), format!("{src}\n"));
+        }
+
+        let attrs = parse("@cold @inline(never)").unwrap();
+
+        assert!(matches!(attrs.inline(), Some((_, Inline::Never))));
+        assert_eq!(attrs.cold().unwrap().span().as_str(), "@cold");
+        assert_eq!(format(&attrs), "@cold\n@inline(never)\n");
+    }
+
+    #[test]
+    fn inline_error() {
+        let e = parse("@inline(always) @inline(never)").err().unwrap();
+
+        assert_eq!(e.reason(), "conflicting inline attribute");
+        assert_eq!(e.span().column(), 17);
+
+        let e = parse("@inline @inline").err().unwrap();
+
+        assert_eq!(e.reason(), "multiple inline attribute is not allowed");
+
+        let e = parse("@inline(sometimes)").err().unwrap();
+
+        assert_eq!(e.reason(), "unknown inline mode");
+        assert_eq!(e.span().as_str(), "sometimes");
+
+        let e = parse("@cold @cold").err().unwrap();
+
+        assert_eq!(e.reason(), "multiple cold attribute is not allowed");
+    }
+
+    fn eval(
+        cond: &str,
+        os: TargetOs,
+        defines: &HashMap<String, String>,
+    ) -> Result<bool, SyntaxError> {
+        parse(&format!("@if({cond})"))
+            .unwrap()
+            .eval_condition(os, defines)
+    }
+
+    /// Parse `attrs` on a class.
+    fn parse(attrs: &str) -> Result<Attributes, SyntaxError> {
+        let src = format!("{attrs}\nclass Foo;");
+        let mut lex = Lexer::new(&src);
+        let first = match lex.next().unwrap() {
+            Some(Token::AttributeName(v)) => v,
+            _ => unreachable!(),
+        };
+
+        Attributes::parse(&mut lex, first)
+    }
+
+    fn format(attrs: &Attributes) -> String {
+        let mut w = SourceWriter::new(Vec::new());
+        attrs.format(&mut w);
+        w.into_string()
+    }
+
+    fn defines(list: &[(&str, &str)]) -> HashMap<String, String> {
+        list.iter().map(|&(k, v)| (k.into(), v.into())).collect()
+    }
+}
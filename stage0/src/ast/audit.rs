@@ -0,0 +1,183 @@
+use crate::pkg::FunctionSignature;
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+/// Audit items of a source file.
+#[derive(Serialize)]
+pub struct AuditFile<'a> {
+    file: &'a Path,
+    items: Vec<AuditItem>,
+}
+
+impl<'a> AuditFile<'a> {
+    pub fn new(file: &'a Path, items: Vec<AuditItem>) -> Self {
+        Self { file, items }
+    }
+
+    pub fn file(&self) -> &'a Path {
+        self.file
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+impl Display for AuditFile<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}:", self.file.display())?;
+
+        for item in &self.items {
+            writeln!(f, "  {item}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// An item in the source file that touches the outside world (e.g. an extern function).
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum AuditItem {
+    /// An `@ext` function.
+    Extern {
+        line: usize,
+        column: usize,
+        ty: String,
+        signature: FunctionSignature,
+    },
+    /// An `asm` expression.
+    Asm {
+        line: usize,
+        column: usize,
+        ty: String,
+        function: String,
+        instruction: String,
+    },
+    /// A public function that has a pointer in its signature.
+    Pointer {
+        line: usize,
+        column: usize,
+        ty: String,
+        signature: FunctionSignature,
+    },
+}
+
+impl AuditItem {
+    pub fn line(&self) -> usize {
+        match self {
+            Self::Extern { line, .. } | Self::Asm { line, .. } | Self::Pointer { line, .. } => {
+                *line
+            }
+        }
+    }
+
+    pub fn column(&self) -> usize {
+        match self {
+            Self::Extern { column, .. }
+            | Self::Asm { column, .. }
+            | Self::Pointer { column, .. } => *column,
+        }
+    }
+}
+
+impl Display for AuditItem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: ", self.line(), self.column())?;
+
+        match self {
+            Self::Extern { ty, signature, .. } => write!(f, "extern {signature} in {ty}"),
+            Self::Asm {
+                ty,
+                function,
+                instruction,
+                ..
+            } => write!(f, "asm {instruction:?} in {ty}.{function}"),
+            Self::Pointer { ty, signature, .. } => write!(f, "pointer {signature} in {ty}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::SourceFile;
+    use crate::lexer::Lexer;
+
+    #[test]
+    fn report() {
+        let src = SourceFile::parse_str("Io.nt", FIXTURE.into(), Lexer::DEFAULT_MAX_DEPTH).unwrap();
+        let file = AuditFile::new(Path::new("src/Io.nt"), src.audit());
+
+        assert_eq!(
+            file.to_string(),
+            "src/Io.nt:
+  6:8: pointer fn Read(buf: *UInt8, len: UInt): UInt in Io
+  11:9: asm \"nop\" in Io.Wait
+  15:8: extern fn abort(): ! in Io
+"
+        );
+        assert_eq!(
+            serde_json::to_value(&file).unwrap(),
+            serde_json::json!({
+                "file": "src/Io.nt",
+                "items": [
+                    {
+                        "kind": "pointer",
+                        "line": 6,
+                        "column": 8,
+                        "ty": "Io",
+                        "signature": {
+                            "name": "Read",
+                            "params": [
+                                { "name": "buf", "ty": "*UInt8" },
+                                { "name": "len", "ty": "UInt" }
+                            ],
+                            "ret": "UInt"
+                        }
+                    },
+                    {
+                        "kind": "asm",
+                        "line": 11,
+                        "column": 9,
+                        "ty": "Io",
+                        "function": "Wait",
+                        "instruction": "nop"
+                    },
+                    {
+                        "kind": "extern",
+                        "line": 15,
+                        "column": 8,
+                        "ty": "Io",
+                        "signature": { "name": "abort", "params": [], "ret": "!" }
+                    }
+                ]
+            })
+        );
+    }
+
+    /// One of each item plus a function that has nothing to audit.
+    const FIXTURE: &str = "@pub
+class Io;
+
+impl Io {
+    @pub
+    fn Read(buf: *UInt8, len: UInt): UInt {
+        0
+    }
+
+    fn Wait() {
+        asm(\"nop\");
+    }
+
+    @ext(C)
+    fn abort(): !;
+
+    @pub
+    fn Size(): UInt {
+        0
+    }
+}
+";
+}
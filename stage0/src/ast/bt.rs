@@ -1,4 +1,4 @@
-use super::Attributes;
+use super::{Attributes, SourceWriter};
 use crate::lexer::Identifier;
 
 /// A struct or class in a source file.
@@ -28,4 +28,20 @@ impl BasicType {
     pub fn name(&self) -> &Identifier {
         &self.name
     }
+
+    pub fn format(&self, w: &mut SourceWriter) {
+        self.attrs.format(w);
+        w.mark(self.name.span());
+
+        // A primitive struct without repr attribute is not allowed so we need to keep the body.
+        if self.is_ref {
+            w.write(format_args!("class {};", self.name));
+        } else if self.attrs.repr().is_some() {
+            w.write(format_args!("struct {};", self.name));
+        } else {
+            w.write(format_args!("struct {} {{}}", self.name));
+        }
+
+        w.newline();
+    }
 }
@@ -0,0 +1,169 @@
+use super::{Attributes, Function, SourceWriter, Type, Use};
+use crate::codegen::{Codegen, LlvmGlobal};
+use crate::lexer::{
+    ConstKeyword, FloatLiteral, Identifier, Lexer, StringLiteral, SyntaxError, Token,
+    UnsignedLiteral,
+};
+use crate::pkg::ConstValue;
+use std::ffi::CString;
+use std::fmt::{Display, Formatter};
+
+/// A constant in the implementation block (e.g. `const MAX: Int32 = 2147483647;`).
+pub(super) struct Constant {
+    attrs: Attributes,
+    def: ConstKeyword,
+    name: Identifier,
+    ty: Type,
+    value: ConstLiteral,
+}
+
+impl Constant {
+    pub fn parse(
+        lex: &mut Lexer,
+        attrs: Attributes,
+        def: ConstKeyword,
+    ) -> Result<Self, SyntaxError> {
+        // Parse name and type.
+        let name = lex.next_ident()?;
+
+        lex.next_colon()?;
+
+        let ty = Type::parse(lex)?;
+
+        if ty.as_value().is_none() {
+            return Err(SyntaxError::new(
+                ty.name().span(),
+                "the type of a constant must be a struct",
+            ));
+        }
+
+        // Parse value.
+        let eq = lex.next_equals()?;
+        let value = match lex.next()? {
+            Some(Token::UnsignedLiteral(v)) => ConstLiteral::Unsigned(v),
+            Some(Token::FloatLiteral(v)) => ConstLiteral::Float(v),
+            Some(Token::StringLiteral(v)) => ConstLiteral::String(v),
+            Some(t) => return Err(SyntaxError::new(t.span().clone(), "expect a literal")),
+            None => {
+                return Err(SyntaxError::new(
+                    eq.span().clone(),
+                    "expect a literal after this",
+                ));
+            }
+        };
+
+        lex.next_semicolon()?;
+
+        Ok(Self {
+            attrs,
+            def,
+            name,
+            ty,
+            value,
+        })
+    }
+
+    pub fn attrs(&self) -> &Attributes {
+        &self.attrs
+    }
+
+    pub fn name(&self) -> &Identifier {
+        &self.name
+    }
+
+    pub fn ty(&self) -> &Type {
+        &self.ty
+    }
+
+    pub fn value(&self) -> &ConstLiteral {
+        &self.value
+    }
+
+    pub fn format(&self, w: &mut SourceWriter) {
+        self.attrs.format(w);
+        w.mark(self.def.span());
+        w.write(format_args!("{} {}: ", self.def, self.name));
+        self.ty.format(w);
+        w.write(format_args!(" = {};", self.value));
+        w.newline();
+    }
+
+    pub fn build<'a, 'b: 'a, U: IntoIterator<Item = &'a Use> + Clone>(
+        &self,
+        cx: &Codegen<'b>,
+        container: &str,
+        uses: U,
+    ) -> Result<Option<crate::pkg::Constant>, SyntaxError> {
+        // Check condition.
+        if !self.attrs.run_condition(cx)? {
+            return Ok(None);
+        }
+
+        // Get the value. There is no type that can hold a float or a string yet.
+        let ty = self.ty.build(cx, uses.clone())?.unwrap();
+        let (value, ext) = match &self.value {
+            ConstLiteral::Unsigned(v) => (
+                Function::build_unsigned(&ty, self.ty.as_value(), v)?,
+                ConstValue::Unsigned(v.value()),
+            ),
+            ConstLiteral::Float(v) => {
+                return Err(SyntaxError::new(
+                    v.span().clone(),
+                    "a floating point constant is not supported yet",
+                ));
+            }
+            ConstLiteral::String(v) => {
+                return Err(SyntaxError::new(
+                    v.span().clone(),
+                    "a string constant is not supported yet",
+                ));
+            }
+        };
+
+        // Build symbol name.
+        let ext = crate::pkg::Constant::new(
+            self.name.value().to_owned(),
+            self.ty.to_external(cx, uses)?,
+            ext,
+        );
+
+        let name = CString::new(ext.mangle(
+            if cx.executable() {
+                None
+            } else {
+                Some((cx.pkg().as_str(), cx.version().major()))
+            },
+            container,
+        ))
+        .unwrap();
+
+        // Emit the constant.
+        if LlvmGlobal::get(cx, &name).is_some() {
+            return Err(SyntaxError::new(
+                self.name.span().clone(),
+                "multiple definition of the same name",
+            ));
+        }
+
+        LlvmGlobal::new_const(cx, &name, &ty, value);
+
+        Ok(Some(ext))
+    }
+}
+
+/// A value of [`Constant`]. Only a literal is supported for now.
+pub(super) enum ConstLiteral {
+    Unsigned(UnsignedLiteral),
+    Float(FloatLiteral),
+    String(StringLiteral),
+}
+
+impl Display for ConstLiteral {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unsigned(v) => v.fmt(f),
+            Self::Float(v) => v.fmt(f),
+            Self::String(v) => v.fmt(f),
+        }
+    }
+}
@@ -1,34 +1,131 @@
-use super::{Path, Statement};
+use super::{Block, Path, SourceWriter, Statement, Type};
 use crate::lexer::{
-    AsmKeyword, Equals, ExclamationMark, Identifier, IfKeyword, Lexer, NullKeyword, Span,
-    StringLiteral, SyntaxError, Token, UnsignedLiteral,
+    AsKeyword, AsmKeyword, CloseCurly, CloseParenthesis, Equals, ExclamationMark, Identifier,
+    IfKeyword, Lexer, MatchKeyword, NullKeyword, OpenCurly, OpenParenthesis, Span, StringLiteral,
+    SyntaxError, Token, UnsignedLiteral,
 };
+use std::collections::HashSet;
 
 /// An expression.
 pub(super) enum Expression {
     Value(Identifier),
+    Const(ConstRef),
     Call(Call),
     Equal(Equals, Equals),
     NotEqual(ExclamationMark, Equals),
+    As(AsKeyword, Type),
     Unsigned(UnsignedLiteral),
     String(StringLiteral),
     Null(NullKeyword),
     Asm(Asm),
     If(If),
+    Match(Match),
+    Construct(Construct),
+    Tuple(Tuple),
 }
 
 impl Expression {
     pub fn span(&self) -> Span {
         match self {
             Self::Value(v) => v.span().clone(),
+            Self::Const(v) => v.span(),
             Self::Call(v) => v.span(),
             Self::Equal(f, s) => f.span() + s.span(),
             Self::NotEqual(f, s) => f.span() + s.span(),
+            Self::As(d, t) => d.span() + &t.name().span(),
             Self::Unsigned(v) => v.span().clone(),
             Self::String(v) => v.span().clone(),
             Self::Null(v) => v.span().clone(),
             Self::Asm(v) => v.span().clone(),
             Self::If(v) => v.span().clone(),
+            Self::Match(v) => v.span().clone(),
+            Self::Construct(v) => v.span(),
+            Self::Tuple(v) => v.span(),
+        }
+    }
+
+    /// Returns the blocks that are nested in this expression (e.g. the body of `if`). The blocks
+    /// inside the returned blocks are not included.
+    pub fn blocks(&self) -> Vec<&[Statement]> {
+        let mut list = Vec::new();
+        self.collect_blocks(&mut list);
+        list
+    }
+
+    /// Invoke `f` with this expression and all expressions nested in it, including the expressions
+    /// in the nested blocks.
+    pub fn walk<'a>(&'a self, f: &mut dyn FnMut(&'a Self)) {
+        f(self);
+
+        let (exprs, blocks): (Vec<&Self>, Vec<&[Statement]>) = match self {
+            Self::Call(v) => (v.args.iter().flatten().collect(), Vec::new()),
+            Self::Asm(v) => (v.inputs.iter().flat_map(|(_, v)| v).collect(), Vec::new()),
+            Self::If(v) => (v.cond.iter().collect(), vec![&*v.body]),
+            Self::Match(v) => (
+                v.value.iter().collect(),
+                v.arms.iter().map(|a| &*a.body).collect(),
+            ),
+            Self::Construct(v) => (v.fields.iter().flat_map(|(_, v)| v).collect(), Vec::new()),
+            Self::Tuple(v) => (v.elems.iter().flatten().collect(), Vec::new()),
+            _ => return,
+        };
+
+        for e in exprs {
+            e.walk(f);
+        }
+
+        for e in blocks.into_iter().flatten().flat_map(|s| s.exprs()) {
+            e.walk(f);
+        }
+    }
+
+    fn collect_blocks<'a>(&'a self, list: &mut Vec<&'a [Statement]>) {
+        let exprs: Vec<&Self> = match self {
+            Self::Call(v) => v.args.iter().flatten().collect(),
+            Self::Asm(v) => v.inputs.iter().flat_map(|(_, v)| v).collect(),
+            Self::If(v) => {
+                list.push(&*v.body);
+                v.cond.iter().collect()
+            }
+            Self::Match(v) => {
+                list.extend(v.arms.iter().map(|a| &*a.body));
+                v.value.iter().collect()
+            }
+            Self::Construct(v) => v.fields.iter().flat_map(|(_, v)| v).collect(),
+            Self::Tuple(v) => v.elems.iter().flatten().collect(),
+            _ => return,
+        };
+
+        for e in exprs {
+            e.collect_blocks(list);
+        }
+    }
+
+    pub fn format(&self, w: &mut SourceWriter) {
+        match self {
+            Self::Value(v) => w.write(v),
+            Self::Const(v) => w.write(format_args!("{}.{}", v.ty, v.name)),
+            Self::Call(v) => v.format(w),
+            Self::Equal(f, s) => {
+                w.write(f);
+                w.write(s);
+            }
+            Self::NotEqual(f, s) => {
+                w.write(f);
+                w.write(s);
+            }
+            Self::As(d, t) => {
+                w.write(format_args!("{d} "));
+                t.format(w);
+            }
+            Self::Unsigned(v) => w.write(v),
+            Self::String(v) => w.write(v),
+            Self::Null(v) => w.write(v),
+            Self::Asm(v) => v.format(w),
+            Self::If(v) => v.format(w),
+            Self::Match(v) => v.format(w),
+            Self::Construct(v) => v.format(w),
+            Self::Tuple(v) => v.format(w),
         }
     }
 
@@ -69,12 +166,29 @@ impl Expression {
     }
 
     pub fn parse(lex: &mut Lexer) -> Result<Vec<Self>, SyntaxError> {
+        Self::parse_exprs(lex, true)
+    }
+
+    /// Parse an expression that is followed by a block (e.g. the condition of `if`). A struct
+    /// construction is not allowed here since it is ambiguous with the block.
+    fn parse_exprs(lex: &mut Lexer, construct: bool) -> Result<Vec<Self>, SyntaxError> {
         let mut exprs = Vec::new();
 
+        lex.enter()?;
+
         loop {
-            // Check the first item.
-            let next = match lex.next()? {
-                Some(v) => v,
+            let more = match lex.next()? {
+                Some(Token::Identifier(v)) => Self::parse_ident(lex, &mut exprs, v, construct)?,
+                Some(
+                    v @ (Token::AsmKeyword(_)
+                    | Token::IfKeyword(_)
+                    | Token::MatchKeyword(_)
+                    | Token::OpenParenthesis(_)),
+                ) => {
+                    exprs.push(Self::parse_nested(lex, v)?);
+                    true
+                }
+                Some(v) => Self::parse_other(lex, &mut exprs, v)?,
                 None => {
                     return Err(SyntaxError::new(
                         lex.last().unwrap().clone(),
@@ -83,85 +197,370 @@ impl Expression {
                 }
             };
 
-            let ident = match next {
-                Token::Identifier(v) => v,
-                Token::UnsignedLiteral(v) => {
-                    exprs.push(Expression::Unsigned(v));
-                    continue;
-                }
-                Token::StringLiteral(v) => {
-                    exprs.push(Expression::String(v));
-                    continue;
-                }
-                Token::NullKeyword(v) => {
-                    exprs.push(Expression::Null(v));
-                    break;
-                }
-                Token::AsmKeyword(v) => {
-                    exprs.push(Expression::Asm(Self::parse_asm(lex, v)?));
-                    continue;
+            if !more {
+                break;
+            }
+        }
+
+        lex.leave();
+
+        Ok(exprs)
+    }
+
+    /// Parse an expression that has a nested expression (e.g. `if` or tuple). This is a separate
+    /// function to keep the stack frame of [`Self::parse_exprs()`] small since it is on the
+    /// recursion path.
+    fn parse_nested(lex: &mut Lexer, first: Token) -> Result<Self, SyntaxError> {
+        match first {
+            Token::AsmKeyword(v) => Self::parse_asm(lex, v).map(Expression::Asm),
+            Token::IfKeyword(v) => Self::parse_if(lex, v).map(Expression::If),
+            Token::MatchKeyword(v) => Self::parse_match(lex, v).map(Expression::Match),
+            Token::OpenParenthesis(v) => Self::parse_tuple(lex, v).map(Expression::Tuple),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Parse an expression that does not contain a nested expression. Returns `false` if the
+    /// expression cannot be continued.
+    fn parse_other(
+        lex: &mut Lexer,
+        exprs: &mut Vec<Self>,
+        first: Token,
+    ) -> Result<bool, SyntaxError> {
+        let more = match first {
+            Token::UnsignedLiteral(v) => {
+                exprs.push(Expression::Unsigned(v));
+                true
+            }
+            Token::StringLiteral(v) => {
+                exprs.push(Expression::String(v));
+                true
+            }
+            Token::NullKeyword(v) => {
+                exprs.push(Expression::Null(v));
+                false
+            }
+            Token::AsKeyword(v) => {
+                if exprs.is_empty() {
+                    return Err(SyntaxError::new(
+                        v.span().clone(),
+                        "expect an expression before 'as'",
+                    ));
                 }
-                Token::IfKeyword(v) => {
-                    exprs.push(Expression::If(Self::parse_if(lex, v)?));
-                    continue;
+
+                exprs.push(Expression::As(v, Type::parse(lex)?));
+                true
+            }
+            _ => {
+                lex.undo();
+                false
+            }
+        };
+
+        Ok(more)
+    }
+
+    /// Parse an expression that starts with `ident`. Returns `false` if the expression cannot be
+    /// continued.
+    fn parse_ident(
+        lex: &mut Lexer,
+        exprs: &mut Vec<Self>,
+        ident: Identifier,
+        construct: bool,
+    ) -> Result<bool, SyntaxError> {
+        // A bare underscore is a throwaway binding so it cannot be read.
+        if ident.value() == "_" {
+            return Err(SyntaxError::new(
+                ident.span().clone(),
+                "'_' can only be used as a binding",
+            ));
+        }
+
+        // Check the token after the identifier.
+        let second = match lex.next()? {
+            Some(v) => v,
+            None => {
+                exprs.push(Expression::Value(ident));
+                return Ok(false);
+            }
+        };
+
+        let more = match second {
+            Token::ExclamationMark(ex) => {
+                let eq = lex.next_equals()?;
+
+                exprs.push(Expression::Value(ident));
+                exprs.push(Expression::NotEqual(ex, eq));
+                true
+            }
+            Token::Equals(eq1) => {
+                let eq2 = lex.next_equals()?;
+
+                exprs.push(Expression::Value(ident));
+                exprs.push(Expression::Equal(eq1, eq2));
+                true
+            }
+            Token::FullStop(mut dot) => {
+                // The last component is the name of the constant (e.g. `Int32.MAX`) or the
+                // function if it is followed by '(' (e.g. `nitro.intrin.PtrAdd()`).
+                let mut ty = vec![Token::Identifier(ident)];
+                let mut name = lex.next_ident()?;
+                let call = loop {
+                    match lex.next()? {
+                        Some(Token::FullStop(v)) => {
+                            ty.push(Token::FullStop(dot));
+                            ty.push(Token::Identifier(name));
+                            dot = v;
+                            name = lex.next_ident()?;
+                        }
+                        Some(Token::OpenParenthesis(_)) => break true,
+                        Some(_) => {
+                            lex.undo();
+                            break false;
+                        }
+                        None => break false,
+                    }
+                };
+
+                if call {
+                    let args = Self::parse_args(lex)?;
+
+                    ty.push(Token::FullStop(dot));
+                    ty.push(Token::Identifier(name));
+
+                    exprs.push(Expression::Call(Call::new(Path::new(ty)?, args)));
+                } else {
+                    exprs.push(Expression::Const(ConstRef::new(Path::new(ty)?, name)));
                 }
-                _ => {
-                    lex.undo();
-                    break;
+
+                true
+            }
+            Token::OpenParenthesis(_) => {
+                let args = Self::parse_args(lex)?;
+                let name = Path::new(vec![Token::Identifier(ident)])?;
+
+                exprs.push(Expression::Call(Call::new(name, args)));
+                true
+            }
+            Token::AsKeyword(_) => {
+                lex.undo();
+                exprs.push(Expression::Value(ident));
+                true
+            }
+            Token::OpenCurly(oc) if construct => {
+                let name = Path::new(vec![Token::Identifier(ident)])?;
+
+                exprs.push(Expression::Construct(Self::parse_construct(lex, name, oc)?));
+                true
+            }
+            _ => {
+                lex.undo();
+                exprs.push(Expression::Value(ident));
+                false
+            }
+        };
+
+        Ok(more)
+    }
+
+    fn parse_tuple(lex: &mut Lexer, open: OpenParenthesis) -> Result<Tuple, SyntaxError> {
+        let mut elems = Vec::new();
+
+        let close = loop {
+            elems.push(Self::parse(lex)?);
+
+            match lex.next()? {
+                Some(Token::Comma(_)) => {}
+                Some(Token::CloseParenthesis(v)) => break v,
+                Some(t) => return Err(SyntaxError::new(t.span().clone(), "expect an ')'")),
+                None => {
+                    return Err(SyntaxError::new(
+                        lex.last().unwrap().clone(),
+                        "expect an ')' after this",
+                    ));
                 }
-            };
+            }
+        };
+
+        if elems.len() < 2 {
+            return Err(SyntaxError::new(
+                open.span() + close.span(),
+                "a tuple must have at least two elements",
+            ));
+        }
+
+        Ok(Tuple::new(open, elems, close))
+    }
+
+    fn parse_if(lex: &mut Lexer, def: IfKeyword) -> Result<If, SyntaxError> {
+        // Parse condition.
+        let exprs = Self::parse_exprs(lex, false)?;
+        lex.next_oc()?;
+
+        // Parse the body.
+        let body = Block::parse(lex)?;
+
+        Ok(If::new(def, exprs, body))
+    }
+
+    fn parse_match(lex: &mut Lexer, def: MatchKeyword) -> Result<Match, SyntaxError> {
+        // Parse the value to match.
+        let value = Self::parse_exprs(lex, false)?;
+        lex.next_oc()?;
 
-            // Check the token after the identifier.
-            let second = match lex.next()? {
+        // Parse arms.
+        let mut arms = Vec::new();
+        let mut literals = HashSet::new();
+        let mut wildcard = false;
+
+        loop {
+            // Parse the pattern.
+            let next = match lex.next()? {
                 Some(v) => v,
                 None => {
-                    exprs.push(Expression::Value(ident));
-                    break;
+                    return Err(SyntaxError::new(
+                        lex.last().unwrap().clone(),
+                        "expect an '}' after this",
+                    ));
                 }
             };
 
-            match second {
-                Token::ExclamationMark(ex) => {
-                    let eq = lex.next_equals()?;
+            let pattern = match next {
+                Token::CloseCurly(_) => break,
+                Token::UnsignedLiteral(v) => MatchPattern::Unsigned(v),
+                Token::Identifier(v) if v.value() == "_" => MatchPattern::Wildcard(v),
+                t => return Err(SyntaxError::new(t.span().clone(), "expect a pattern")),
+            };
 
-                    exprs.push(Expression::Value(ident));
-                    exprs.push(Expression::NotEqual(ex, eq));
-                    continue;
+            // Check if the arm is reachable.
+            if wildcard {
+                return Err(SyntaxError::new(
+                    pattern.span().clone(),
+                    "unreachable pattern",
+                ));
+            }
+
+            match &pattern {
+                MatchPattern::Unsigned(v) => {
+                    if !literals.insert(v.value()) {
+                        return Err(SyntaxError::new(v.span().clone(), "unreachable pattern"));
+                    }
                 }
-                Token::Equals(eq1) => {
-                    let eq2 = lex.next_equals()?;
+                MatchPattern::Wildcard(_) => wildcard = true,
+            }
 
-                    exprs.push(Expression::Value(ident));
-                    exprs.push(Expression::Equal(eq1, eq2));
-                    continue;
+            // Parse '=>'.
+            let eq = lex.next_equals()?;
+
+            match lex.next()? {
+                Some(Token::GreaterThan(_)) => {}
+                Some(t) => return Err(SyntaxError::new(t.span().clone(), "expect an '>'")),
+                None => {
+                    return Err(SyntaxError::new(
+                        eq.span().clone(),
+                        "expect an '>' after this",
+                    ))
                 }
-                Token::OpenParenthesis(_) => {
-                    let args = Self::parse_args(lex)?;
-                    let name = Path::new(vec![Token::Identifier(ident)]);
+            }
+
+            // Parse the body.
+            let body = match lex.next()? {
+                Some(Token::OpenCurly(_)) => {
+                    let body = Block::parse(lex)?;
+
+                    // The comma is optional for a block.
+                    match lex.next()? {
+                        Some(Token::Comma(_)) => {}
+                        Some(_) => lex.undo(),
+                        None => {}
+                    }
 
-                    exprs.push(Expression::Call(Call::new(name, args)));
+                    arms.push(MatchArm::new(pattern, body));
                     continue;
                 }
-                _ => {
+                Some(_) => {
                     lex.undo();
-                    exprs.push(Expression::Value(ident));
-                    break;
+                    Block::bare(Statement::Value(Self::parse(lex)?))
+                }
+                None => {
+                    return Err(SyntaxError::new(
+                        lex.last().unwrap().clone(),
+                        "expect an expression after this",
+                    ));
+                }
+            };
+
+            arms.push(MatchArm::new(pattern, body));
+
+            // Check for ',' or '}'.
+            match lex.next()? {
+                Some(Token::Comma(_)) => {}
+                Some(Token::CloseCurly(_)) => break,
+                Some(t) => return Err(SyntaxError::new(t.span().clone(), "expect ','")),
+                None => {
+                    return Err(SyntaxError::new(
+                        lex.last().unwrap().clone(),
+                        "expect an '}' after this",
+                    ));
                 }
             }
         }
 
-        Ok(exprs)
+        let close = CloseCurly::new(lex.last().unwrap().clone());
+
+        // Integer values cannot be enumerated so a catch-all arm is required.
+        if !wildcard {
+            return Err(SyntaxError::new(
+                def.span().clone(),
+                "non-exhaustive match, add a '_' arm to cover the remaining values",
+            ));
+        }
+
+        Ok(Match::new(def, value, arms, close))
     }
 
-    fn parse_if(lex: &mut Lexer, def: IfKeyword) -> Result<If, SyntaxError> {
-        // Parse condition.
-        let exprs = Self::parse(lex)?;
-        lex.next_oc()?;
+    fn parse_construct(
+        lex: &mut Lexer,
+        name: Path,
+        oc: OpenCurly,
+    ) -> Result<Construct, SyntaxError> {
+        let mut fields = Vec::new();
 
-        // Parse the body.
-        let body = Statement::parse_block(lex)?;
+        loop {
+            // Check for '}'.
+            let next = match lex.next()? {
+                Some(v) => v,
+                None => {
+                    return Err(SyntaxError::new(
+                        lex.last().unwrap().clone(),
+                        "expect '}' after this",
+                    ));
+                }
+            };
 
-        Ok(If::new(def, exprs, body))
+            let field = match next {
+                Token::CloseCurly(cc) => return Ok(Construct::new(name, oc, fields, cc)),
+                Token::Identifier(v) => v,
+                t => return Err(SyntaxError::new(t.span().clone(), "expect a field name")),
+            };
+
+            // Parse the value.
+            lex.next_equals()?;
+            fields.push((field, Self::parse(lex)?));
+
+            // Check for ','.
+            match lex.next()? {
+                Some(Token::Comma(_)) => {}
+                Some(Token::CloseCurly(cc)) => return Ok(Construct::new(name, oc, fields, cc)),
+                Some(t) => return Err(SyntaxError::new(t.span().clone(), "expect '}'")),
+                None => {
+                    return Err(SyntaxError::new(
+                        lex.last().unwrap().clone(),
+                        "expect '}' after this",
+                    ));
+                }
+            }
+        }
     }
 
     fn parse_asm(lex: &mut Lexer, def: AsmKeyword) -> Result<Asm, SyntaxError> {
@@ -244,12 +643,14 @@ impl Expression {
     }
 
     fn parse_asm_in(lex: &mut Lexer) -> Result<(AsmIn, Vec<Self>), SyntaxError> {
-        // Load target register.
+        // Load operand constraint.
         lex.next_op()?;
 
-        let reg = match lex.next()? {
+        let op = match lex.next()? {
             Some(v) => match v {
                 Token::StringLiteral(v) => AsmIn::Register(v),
+                Token::Identifier(v) if v.value() == "mem" => AsmIn::Memory(v),
+                Token::Identifier(v) if v.value() == "imm" => AsmIn::Immediate(v),
                 t => return Err(SyntaxError::new(t.span().clone(), "invalid input")),
             },
             None => {
@@ -261,9 +662,23 @@ impl Expression {
         };
 
         // Load the value.
-        lex.next_cp()?;
+        let cp = lex.next_cp()?;
+        let val = Self::parse(lex)?;
 
-        Ok((reg, Self::parse(lex)?))
+        if let AsmIn::Immediate(_) = op {
+            match val.as_slice() {
+                [Self::Unsigned(_)] => {}
+                [] => {
+                    return Err(SyntaxError::new(
+                        cp.span().clone(),
+                        "expect a literal after this",
+                    ));
+                }
+                [e, ..] => return Err(SyntaxError::new(e.span(), "expect a literal")),
+            }
+        }
+
+        Ok((op, val))
     }
 
     fn parse_asm_out(lex: &mut Lexer) -> Result<(AsmOut, Identifier), SyntaxError> {
@@ -273,6 +688,7 @@ impl Expression {
         let reg = match lex.next()? {
             Some(v) => match v {
                 Token::ExclamationMark(v) => AsmOut::Never(v),
+                Token::StringLiteral(v) => AsmOut::Register(v),
                 t => return Err(SyntaxError::new(t.span().clone(), "invalid output")),
             },
             None => {
@@ -300,6 +716,30 @@ impl Expression {
     }
 }
 
+/// A reference to a constant (e.g. `Int32.MAX`).
+pub(super) struct ConstRef {
+    ty: Path,
+    name: Identifier,
+}
+
+impl ConstRef {
+    pub fn new(ty: Path, name: Identifier) -> Self {
+        Self { ty, name }
+    }
+
+    pub fn ty(&self) -> &Path {
+        &self.ty
+    }
+
+    pub fn name(&self) -> &Identifier {
+        &self.name
+    }
+
+    pub fn span(&self) -> Span {
+        &self.ty.span() + self.name.span()
+    }
+}
+
 /// A function call.
 pub(super) struct Call {
     name: Path,
@@ -311,9 +751,125 @@ impl Call {
         Self { name, args }
     }
 
+    pub fn name(&self) -> &Path {
+        &self.name
+    }
+
+    pub fn args(&self) -> &[Vec<Expression>] {
+        &self.args
+    }
+
     pub fn span(&self) -> Span {
         self.name.span()
     }
+
+    pub fn format(&self, w: &mut SourceWriter) {
+        w.write(&self.name);
+        w.write("(");
+
+        for (i, a) in self.args.iter().enumerate() {
+            if i != 0 {
+                w.write(", ");
+            }
+
+            w.write_exprs(a);
+        }
+
+        w.write(")");
+    }
+}
+
+/// A tuple construction (e.g. `(1, 2)`).
+pub(super) struct Tuple {
+    open: OpenParenthesis,
+    elems: Vec<Vec<Expression>>,
+    close: CloseParenthesis,
+}
+
+impl Tuple {
+    pub fn new(
+        open: OpenParenthesis,
+        elems: Vec<Vec<Expression>>,
+        close: CloseParenthesis,
+    ) -> Self {
+        Self { open, elems, close }
+    }
+
+    pub fn elems(&self) -> &[Vec<Expression>] {
+        &self.elems
+    }
+
+    pub fn span(&self) -> Span {
+        self.open.span() + self.close.span()
+    }
+
+    pub fn format(&self, w: &mut SourceWriter) {
+        w.write(&self.open);
+
+        for (i, e) in self.elems.iter().enumerate() {
+            if i != 0 {
+                w.write(", ");
+            }
+
+            w.write_exprs(e);
+        }
+
+        w.write(&self.close);
+    }
+}
+
+/// A struct construction (e.g. `Foo {}`).
+pub(super) struct Construct {
+    name: Path,
+    oc: OpenCurly,
+    fields: Vec<(Identifier, Vec<Expression>)>,
+    cc: CloseCurly,
+}
+
+impl Construct {
+    pub fn new(
+        name: Path,
+        oc: OpenCurly,
+        fields: Vec<(Identifier, Vec<Expression>)>,
+        cc: CloseCurly,
+    ) -> Self {
+        Self {
+            name,
+            oc,
+            fields,
+            cc,
+        }
+    }
+
+    pub fn name(&self) -> &Path {
+        &self.name
+    }
+
+    pub fn fields(&self) -> &[(Identifier, Vec<Expression>)] {
+        self.fields.as_ref()
+    }
+
+    pub fn span(&self) -> Span {
+        &self.name.span() + self.cc.span()
+    }
+
+    pub fn format(&self, w: &mut SourceWriter) {
+        w.write(&self.name);
+        w.write(" ");
+        w.write(&self.oc);
+
+        for (i, (n, v)) in self.fields.iter().enumerate() {
+            w.write(if i == 0 { " " } else { ", " });
+            w.write(format_args!("{n} = "));
+            w.write_exprs(v);
+        }
+
+        if !self.fields.is_empty() {
+            w.write(" ");
+        }
+
+        w.write(&self.cc);
+    }
 }
 
 /// An inline assembly (e.g. `asm("nop")`).
@@ -342,31 +898,332 @@ impl Asm {
     pub fn span(&self) -> &Span {
         self.def.span()
     }
+
+    pub fn inst(&self) -> &StringLiteral {
+        &self.inst
+    }
+
+    pub fn inputs(&self) -> &[(AsmIn, Vec<Expression>)] {
+        &self.inputs
+    }
+
+    pub fn outputs(&self) -> &[(AsmOut, Identifier)] {
+        &self.outputs
+    }
+
+    pub fn format(&self, w: &mut SourceWriter) {
+        w.write(&self.def);
+        w.write("(");
+        w.write(&self.inst);
+
+        for (reg, val) in &self.inputs {
+            match reg {
+                AsmIn::Register(v) => w.write(format_args!(", in({v}) ")),
+                AsmIn::Memory(v) => w.write(format_args!(", in({v}) ")),
+                AsmIn::Immediate(v) => w.write(format_args!(", in({v}) ")),
+            }
+
+            w.write_exprs(val);
+        }
+
+        for (reg, var) in &self.outputs {
+            match reg {
+                AsmOut::Never(v) => w.write(format_args!(", out({v}) {var}")),
+                AsmOut::Register(v) => w.write(format_args!(", out({v}) {var}")),
+            }
+        }
+
+        w.write(")");
+    }
 }
 
 /// An input of the inline assembly (e.g. `in("rax")`).
 pub(super) enum AsmIn {
+    /// `in("reg")`, which map to the LLVM constraint `{reg}`.
     Register(StringLiteral),
+    /// `in(mem)`, which map to the LLVM constraint `m`.
+    Memory(Identifier),
+    /// `in(imm)`, which map to the LLVM constraint `i`. The value is always a literal.
+    Immediate(Identifier),
 }
 
 /// An output of the inline assembly (e.h. `out("rax")`).
 pub(super) enum AsmOut {
+    /// `out(!)`, which indicates the assembly never returns.
     Never(ExclamationMark),
+    /// `out("reg")`, which map to the LLVM constraint `~{reg}` when the output is discarded.
+    Register(StringLiteral),
 }
 
 /// An if expression.
 pub(super) struct If {
     def: IfKeyword,
     cond: Vec<Expression>,
-    body: Vec<Statement>,
+    body: Block,
 }
 
 impl If {
-    pub fn new(def: IfKeyword, cond: Vec<Expression>, body: Vec<Statement>) -> Self {
+    pub fn new(def: IfKeyword, cond: Vec<Expression>, body: Block) -> Self {
         Self { def, cond, body }
     }
 
     pub fn span(&self) -> &Span {
         self.def.span()
     }
+
+    pub fn format(&self, w: &mut SourceWriter) {
+        w.write(&self.def);
+        w.write(" ");
+        w.write_exprs(&self.cond);
+        w.write(" ");
+        self.body.format(w);
+    }
+}
+
+/// A match expression.
+pub(super) struct Match {
+    def: MatchKeyword,
+    value: Vec<Expression>,
+    arms: Vec<MatchArm>,
+    close: CloseCurly,
+}
+
+impl Match {
+    pub fn new(
+        def: MatchKeyword,
+        value: Vec<Expression>,
+        arms: Vec<MatchArm>,
+        close: CloseCurly,
+    ) -> Self {
+        Self {
+            def,
+            value,
+            arms,
+            close,
+        }
+    }
+
+    pub fn span(&self) -> &Span {
+        self.def.span()
+    }
+
+    pub fn format(&self, w: &mut SourceWriter) {
+        w.write(&self.def);
+        w.write(" ");
+        w.write_exprs(&self.value);
+        w.write(" {");
+        w.newline();
+        w.indent();
+
+        for arm in &self.arms {
+            w.mark(arm.pattern.span());
+
+            match &arm.pattern {
+                MatchPattern::Unsigned(v) => w.write(v),
+                MatchPattern::Wildcard(v) => w.write(v),
+            }
+
+            w.write(" => ");
+
+            match &*arm.body {
+                [Statement::Value(v)] => {
+                    w.write_exprs(v);
+                    w.write(",");
+                }
+                _ => arm.body.format(w),
+            }
+
+            w.newline();
+        }
+
+        w.mark(self.close.span());
+        w.unindent();
+        w.write("}");
+    }
+}
+
+/// An arm of the match expression (e.g. `0 => foo()`).
+pub(super) struct MatchArm {
+    pattern: MatchPattern,
+    body: Block,
+}
+
+impl MatchArm {
+    pub fn new(pattern: MatchPattern, body: Block) -> Self {
+        Self { pattern, body }
+    }
+}
+
+/// A pattern of the match arm.
+pub(super) enum MatchPattern {
+    Unsigned(UnsignedLiteral),
+    Wildcard(Identifier),
+}
+
+impl MatchPattern {
+    pub fn span(&self) -> &Span {
+        match self {
+            Self::Unsigned(v) => v.span(),
+            Self::Wildcard(v) => v.span(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AsmIn, AsmOut, Expression};
+    use crate::lexer::Lexer;
+
+    fn parse(src: &str) -> Vec<Expression> {
+        Expression::parse(&mut Lexer::new(src)).unwrap()
+    }
+
+    #[test]
+    fn call_with_path() {
+        let exprs = parse("nitro.intrin.PtrAdd(p, 1);");
+        let call = match exprs.as_slice() {
+            [Expression::Call(v)] => v,
+            _ => panic!("expect a single call"),
+        };
+
+        assert_eq!(call.name().to_string(), "nitro.intrin.PtrAdd");
+        assert_eq!(call.args().len(), 2);
+    }
+
+    #[test]
+    fn call_with_path_and_single_argument() {
+        let exprs = parse("nitro.intrin.PtrReadI32(x);");
+        let call = match exprs.as_slice() {
+            [Expression::Call(v)] => v,
+            _ => panic!("expect a single call"),
+        };
+
+        assert_eq!(call.name().to_string(), "nitro.intrin.PtrReadI32");
+        assert!(matches!(call.args(), [a] if matches!(a.as_slice(), [Expression::Value(_)])));
+    }
+
+    #[test]
+    fn const_with_path() {
+        let exprs = parse("nitro.Int32.MAX;");
+        let cons = match exprs.as_slice() {
+            [Expression::Const(v)] => v,
+            _ => panic!("expect a single constant"),
+        };
+
+        assert_eq!(cons.ty().to_string(), "nitro.Int32");
+        assert_eq!(cons.name().value(), "MAX");
+    }
+
+    #[test]
+    fn asm_inputs() {
+        let exprs = parse("asm(\"nop\", in(\"ecx\") 7, in(mem) 1 as UInt8, in(imm) 2, out(!) _);");
+        let asm = match exprs.as_slice() {
+            [Expression::Asm(v)] => v,
+            _ => panic!("expect a single asm"),
+        };
+
+        assert_eq!(asm.inst().value(), "nop");
+        assert!(matches!(
+            asm.inputs(),
+            [
+                (AsmIn::Register(r), a),
+                (AsmIn::Memory(_), b),
+                (AsmIn::Immediate(_), c),
+            ] if r.value() == "ecx"
+                && matches!(a.as_slice(), [Expression::Unsigned(_)])
+                && matches!(b.as_slice(), [Expression::Unsigned(_), Expression::As(_, _)])
+                && matches!(c.as_slice(), [Expression::Unsigned(_)])
+        ));
+        assert!(matches!(asm.outputs(), [(AsmOut::Never(_), v)] if v.value() == "_"));
+    }
+
+    #[test]
+    fn asm_outputs() {
+        let exprs = parse("asm(\"syscall\", out(\"rcx\") _, out(\"r11\") _);");
+        let asm = match exprs.as_slice() {
+            [Expression::Asm(v)] => v,
+            _ => panic!("expect a single asm"),
+        };
+        let regs: Vec<&str> = asm
+            .outputs()
+            .iter()
+            .map(|(r, _)| match r {
+                AsmOut::Register(v) => v.value(),
+                AsmOut::Never(_) => panic!("unexpected out(!)"),
+            })
+            .collect();
+
+        assert!(asm.inputs().is_empty());
+        assert_eq!(regs, ["rcx", "r11"]);
+    }
+
+    #[test]
+    fn asm_error() {
+        let e = Expression::parse(&mut Lexer::new("asm(\"int 0x29\", in(imm) Int32.MAX);"))
+            .err()
+            .unwrap();
+
+        assert_eq!(e.reason(), "expect a literal");
+        assert_eq!(e.span().as_str(), "Int32.MAX");
+
+        let e = Expression::parse(&mut Lexer::new("asm(\"int 0x29\", in(imm) 1 as UInt8);"))
+            .err()
+            .unwrap();
+
+        assert_eq!(e.reason(), "expect a literal");
+        assert_eq!(e.span().as_str(), "1");
+
+        let e = Expression::parse(&mut Lexer::new("asm(\"nop\", in(reg) 1);"))
+            .err()
+            .unwrap();
+
+        assert_eq!(e.reason(), "invalid input");
+        assert_eq!(e.span().as_str(), "reg");
+    }
+
+    #[test]
+    fn construct_empty() {
+        let exprs = parse("Point {};");
+        let cons = match exprs.as_slice() {
+            [Expression::Construct(v)] => v,
+            _ => panic!("expect a single construction"),
+        };
+
+        assert_eq!(cons.name().to_string(), "Point");
+        assert!(cons.fields().is_empty());
+        assert_eq!(cons.span().as_str(), "Point {}");
+    }
+
+    #[test]
+    fn construct_fields() {
+        let exprs = parse("Point { x = 1, y = 2, };");
+        let cons = match exprs.as_slice() {
+            [Expression::Construct(v)] => v,
+            _ => panic!("expect a single construction"),
+        };
+        let fields: Vec<&str> = cons.fields().iter().map(|(n, _)| n.value()).collect();
+
+        assert_eq!(fields, ["x", "y"]);
+        assert!(cons
+            .fields()
+            .iter()
+            .all(|(_, v)| matches!(v.as_slice(), [Expression::Unsigned(_)])));
+    }
+
+    #[test]
+    fn construct_error() {
+        let e = Expression::parse(&mut Lexer::new("Point { 1 };"))
+            .err()
+            .unwrap();
+
+        assert_eq!(e.reason(), "expect a field name");
+        assert_eq!(e.span().as_str(), "1");
+
+        let e = Expression::parse(&mut Lexer::new("Point { x = 1; y = 2 };"))
+            .err()
+            .unwrap();
+
+        assert_eq!(e.reason(), "expect '}'");
+        assert_eq!(e.span().as_str(), ";");
+    }
 }
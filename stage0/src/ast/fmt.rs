@@ -0,0 +1,125 @@
+use super::expr::Expression;
+use crate::lexer::Span;
+use std::collections::VecDeque;
+use std::fmt::{Display, Write};
+
+/// A writer to produce a source with the canonical formatting.
+///
+/// The comments are not part of the AST nodes so each node must call [`Self::mark()`] with the
+/// span where it begins in the source. All comments before that span will be written at that point.
+pub(super) struct SourceWriter {
+    buf: String,
+    indent: usize,
+    line: bool,
+    comments: VecDeque<Span>,
+    line_comment: Option<usize>,
+}
+
+impl SourceWriter {
+    const INDENT: &'static str = "    ";
+
+    /// `comments` must be in the same order as the source.
+    pub fn new(comments: Vec<Span>) -> Self {
+        Self {
+            buf: String::new(),
+            indent: 0,
+            line: true,
+            comments: comments.into(),
+            line_comment: None,
+        }
+    }
+
+    /// Returns `true` if there are comments before `span` that was not written yet.
+    pub fn has_comments(&self, span: &Span) -> bool {
+        self.comments
+            .front()
+            .is_some_and(|c| c.offset() < span.offset())
+    }
+
+    /// Write all comments before `span` that was not written yet. A comment that follow some code
+    /// on the same line in the source will be appended to the last line that was written.
+    pub fn mark(&mut self, span: &Span) {
+        while self.has_comments(span) {
+            let c = self.comments.pop_front().unwrap();
+            self.write_comment(c);
+        }
+    }
+
+    /// Write all remaining comments.
+    pub fn flush(&mut self) {
+        while let Some(c) = self.comments.pop_front() {
+            self.write_comment(c);
+        }
+    }
+
+    pub fn write<V: Display>(&mut self, v: V) {
+        if self.line {
+            for _ in 0..self.indent {
+                self.buf.push_str(Self::INDENT);
+            }
+
+            self.line = false;
+        }
+
+        write!(self.buf, "{v}").unwrap();
+    }
+
+    pub fn newline(&mut self) {
+        self.buf.push('\n');
+        self.line = true;
+    }
+
+    /// Write an empty line unless we are at the beginning of the output.
+    pub fn separator(&mut self) {
+        if !self.buf.is_empty() {
+            self.newline();
+        }
+    }
+
+    pub fn indent(&mut self) {
+        self.indent += 1;
+    }
+
+    pub fn unindent(&mut self) {
+        self.indent -= 1;
+    }
+
+    pub fn write_exprs(&mut self, exprs: &[Expression]) {
+        for (i, e) in exprs.iter().enumerate() {
+            if i != 0 {
+                self.write(" ");
+            }
+
+            e.format(self);
+        }
+    }
+
+    pub fn into_string(self) -> String {
+        self.buf
+    }
+
+    fn write_comment(&mut self, c: Span) {
+        let src = c.source();
+        let begin = src[..c.offset()].rfind('\n').map_or(0, |i| i + 1);
+        let trailing = !src[begin..c.offset()].trim().is_empty();
+        let end = self.buf.trim_end().len();
+        let text = c.as_str();
+
+        // Do not append to a line that already ended with a line comment since the appended comment
+        // would become a part of it.
+        if trailing && end != 0 && self.line_comment != Some(end) {
+            self.buf.insert_str(end, &format!(" {text}"));
+        } else {
+            if !self.line {
+                self.newline();
+            }
+
+            self.write(text);
+            self.newline();
+        }
+
+        if text.starts_with("//") {
+            self.line_comment = Some(self.buf.trim_end().len());
+        }
+    }
+}
@@ -1,7 +1,18 @@
-use super::{Attributes, Statement, Type, Use};
-use crate::codegen::{BasicBlock, Builder, Codegen, LlvmFunc, LlvmType, LlvmVoid};
-use crate::lexer::{Identifier, SyntaxError};
-use crate::pkg::Extern;
+use super::attr::Inline;
+use super::expr::{Asm, AsmIn, AsmOut, Call, ConstRef, Construct, Expression, Tuple};
+use super::stmt::{Block, Let};
+use super::{
+    Attributes, ConstLiteral, Intrinsic, Path, SourceWriter, Statement, Type, TypeDefinition, Use,
+};
+use crate::codegen::{
+    BasicBlock, Builder, Codegen, LlvmFunc, LlvmI32, LlvmPtr, LlvmType, LlvmU64, LlvmVoid,
+    ResolvedType, TestFunction,
+};
+use crate::lexer::{AsKeyword, Identifier, Span, StringLiteral, SyntaxError, UnsignedLiteral};
+use crate::pkg::{
+    Abi, ConstValue, Extern, FunctionSignature, Representation, SignatureParam, TargetArch,
+    TypeDeclaration,
+};
 use std::borrow::Cow;
 use std::ffi::CString;
 
@@ -11,7 +22,7 @@ pub(super) struct Function {
     name: Identifier,
     params: Vec<FunctionParam>,
     ret: Option<Type>,
-    body: Option<Vec<Statement>>,
+    body: Option<Block>,
 }
 
 impl Function {
@@ -20,7 +31,7 @@ impl Function {
         name: Identifier,
         params: Vec<FunctionParam>,
         ret: Option<Type>,
-        body: Option<Vec<Statement>>,
+        body: Option<Block>,
     ) -> Self {
         Self {
             attrs,
@@ -35,6 +46,134 @@ impl Function {
         &self.attrs
     }
 
+    pub fn name(&self) -> &Identifier {
+        &self.name
+    }
+
+    /// Returns the type of all parameters followed by the return type.
+    pub fn types(&self) -> impl Iterator<Item = &Type> {
+        self.params.iter().map(|p| &p.ty).chain(self.ret.as_ref())
+    }
+
+    pub fn body(&self) -> Option<&[Statement]> {
+        self.body.as_deref()
+    }
+
+    /// Returns the signature of this function as written in the source. A function without a
+    /// return type is shown as returning `()` the same as [`crate::pkg::Function`].
+    pub fn signature(&self) -> FunctionSignature {
+        let ty = |t: &Type| {
+            let mut w = SourceWriter::new(Vec::new());
+            t.format(&mut w);
+            w.into_string()
+        };
+
+        let params = self
+            .params
+            .iter()
+            .map(|p| SignatureParam::new(p.name.value().to_owned(), ty(&p.ty)))
+            .collect();
+        let ret = self.ret.as_ref().map(ty).unwrap_or_else(|| "()".into());
+
+        FunctionSignature::new(self.name.value().to_owned(), params, ret)
+    }
+
+    /// Returns a warning for each `let` that shadows a binding from an outer scope. The parameters
+    /// are the outermost scope of the body.
+    pub fn shadows(&self) -> Vec<SyntaxError> {
+        let body = match &self.body {
+            Some(v) => v,
+            None => return Vec::new(),
+        };
+
+        let params = self.params.iter().map(|p| &p.name).collect();
+        let mut warnings = Vec::new();
+
+        Self::check_shadows(&[params], body, &mut warnings);
+
+        warnings
+    }
+
+    fn check_shadows<'a>(
+        outer: &[Vec<&'a Identifier>],
+        block: &'a [Statement],
+        warnings: &mut Vec<SyntaxError>,
+    ) {
+        let mut scope = Vec::new();
+
+        for stmt in block {
+            let (vars, exprs) = match stmt {
+                Statement::Let(v) => (v.vars(), v.val()),
+                Statement::Unit(v) | Statement::Value(v) => (Vec::new(), v.as_slice()),
+            };
+
+            // Walk the nested blocks (e.g. the body of if) with the bindings that are visible at
+            // this point. The variable being declared is not visible in its own initializer.
+            let mut visible = outer.to_vec();
+
+            visible.push(scope.clone());
+
+            for b in exprs.iter().flat_map(|e| e.blocks()) {
+                Self::check_shadows(&visible, b, warnings);
+            }
+
+            // Check the variables.
+            for var in vars.into_iter().filter(|v| v.value() != "_") {
+                // Redeclaring in the same block is not a shadow of an outer binding.
+                let prev = outer
+                    .iter()
+                    .flatten()
+                    .rev()
+                    .find(|&&v| v.value() == var.value());
+
+                if let Some(prev) = prev {
+                    warnings.push(SyntaxError::new(
+                        var.span().clone(),
+                        format!(
+                            "'{}' shadows the binding on line {}",
+                            var,
+                            prev.span().line()
+                        ),
+                    ));
+                }
+
+                scope.push(var);
+            }
+        }
+    }
+
+    pub fn format(&self, w: &mut SourceWriter) {
+        self.attrs.format(w);
+        w.mark(self.name.span());
+        w.write(format_args!("fn {}(", self.name));
+
+        for (i, p) in self.params.iter().enumerate() {
+            if i != 0 {
+                w.write(", ");
+            }
+
+            w.write(format_args!("{}: ", p.name));
+            p.ty.format(w);
+        }
+
+        w.write(")");
+
+        if let Some(v) = &self.ret {
+            w.write(": ");
+            v.format(w);
+        }
+
+        match &self.body {
+            Some(v) => {
+                w.write(" ");
+                v.format(w);
+            }
+            None => w.write(";"),
+        }
+
+        w.newline();
+    }
+
     pub fn build<'a, 'b: 'a, U: IntoIterator<Item = &'a Use> + Clone>(
         &self,
         cx: &mut Codegen<'b>,
@@ -46,7 +185,8 @@ impl Function {
             return Ok(None);
         }
 
-        // Get public type.
+        // Get public type. The C ABI is the default so the existing functions are not changed.
+        let abi = self.attrs.abi().map_or(Abi::C, |v| v.1);
         let ext = crate::pkg::Function::new(
             self.name.value().to_owned(),
             {
@@ -63,8 +203,20 @@ impl Function {
                 Some(v) => v.to_external(cx, uses.clone())?,
                 None => crate::pkg::Type::Unit { ptr: 0 },
             },
+            self.attrs.ext().map(|v| v.1),
+            abi,
         );
 
+        // Only the types that have an equivalent in C can be used on an extern function with the C
+        // ABI.
+        if self.attrs.ext().is_some() && abi == Abi::C {
+            for t in self.params.iter().map(|p| &p.ty).chain(self.ret.as_ref()) {
+                if let Some(reason) = t.check_ffi(cx, uses.clone())? {
+                    return Err(SyntaxError::new(t.name().span(), reason));
+                }
+            }
+        }
+
         // Build function name.
         let name = match self.attrs.ext() {
             Some((_, Extern::C)) => Cow::Borrowed(self.name.value()),
@@ -99,11 +251,23 @@ impl Function {
             None => LlvmType::Void(LlvmVoid::new(cx)),
         };
 
-        // Check if entry point.
-        let entry = self.attrs.entry().is_some();
+        // Check if entry point. The function from Nitro.yml takes precedence over @entry.
+        let entry = match cx.entry_function() {
+            _ if !cx.main() => false,
+            Some(v) => v
+                .strip_suffix(self.name.value())
+                .and_then(|v| v.strip_suffix('.'))
+                .is_some_and(|v| v == container),
+            None => self.attrs.entry().is_some(),
+        };
 
         if entry {
-            if !cx.entry().is_empty() {
+            if abi != Abi::C {
+                return Err(SyntaxError::new(
+                    self.name.span(),
+                    "the entry point must use the C ABI",
+                ));
+            } else if !cx.entry().is_empty() {
                 return Err(SyntaxError::new(
                     self.name.span(),
                     "more than one entry point has been defined",
@@ -121,6 +285,33 @@ impl Function {
             }
         }
 
+        // Check if test.
+        let test = self.attrs.test().is_some();
+
+        if test {
+            if abi != Abi::C {
+                return Err(SyntaxError::new(
+                    self.name.span(),
+                    "a test function must use the C ABI",
+                ));
+            } else if !self.params.is_empty() {
+                return Err(SyntaxError::new(
+                    self.name.span(),
+                    "a test function must have zero parameters",
+                ));
+            } else if never || !(ret.is_void() || ret.is_i32()) {
+                return Err(SyntaxError::new(
+                    self.name.span(),
+                    "a test function must have either unit or nitro.Int32 as a return type",
+                ));
+            } else if self.body.is_none() {
+                return Err(SyntaxError::new(
+                    self.name.span(),
+                    "a test function must have a body",
+                ));
+            }
+        }
+
         // Get params.
         let mut params = Vec::<LlvmType<'a, 'b>>::new();
 
@@ -139,10 +330,16 @@ impl Function {
         }
 
         // Create a function.
-        let mut func = LlvmFunc::new(cx, CString::new(name.as_ref()).unwrap(), &params, ret);
+        let status = ret.is_i32();
+        let mut func = LlvmFunc::new(cx, CString::new(name.as_ref()).unwrap(), &params, &ret);
+
+        match abi {
+            Abi::C => {}
+            Abi::Nitro => func.set_fastcc(),
+        }
 
         match &self.body {
-            Some(v) => Self::build_body(cx, &mut func, v),
+            Some(v) => self.build_body(cx, uses, &mut func, &ret, v)?,
             None => {
                 if self.attrs.ext().is_none() {
                     return Err(SyntaxError::new(
@@ -150,9 +347,40 @@ impl Function {
                         "a body is required for non-extern or non-abstract",
                     ));
                 }
+
+                // The optimizer hints are applied on the definition.
+                if let Some((n, _)) = self.attrs.inline() {
+                    return Err(SyntaxError::new(
+                        n.span(),
+                        "inline attribute cannot be used on a function without a body",
+                    ));
+                } else if let Some(n) = self.attrs.cold() {
+                    return Err(SyntaxError::new(
+                        n.span(),
+                        "cold attribute cannot be used on a function without a body",
+                    ));
+                }
             }
         }
 
+        // Apply optimizer hints.
+        match self.attrs.inline() {
+            Some((_, Inline::Hint)) => func.set_inline_hint(),
+            Some((_, Inline::Always)) => func.set_inline_always(),
+            Some((_, Inline::Never)) => func.set_inline_never(),
+            None => {}
+        }
+
+        if self.attrs.cold().is_some() {
+            func.set_cold();
+        }
+
+        // Register the test.
+        if test && cx.test() {
+            let display = format!("{}.{}", container, self.name.value());
+            cx.add_test(TestFunction::new(name.to_string(), display, status));
+        }
+
         // Set entry point.
         if entry {
             cx.set_entry(name.into_owned());
@@ -161,17 +389,655 @@ impl Function {
         Ok(Some(ext))
     }
 
-    fn build_body<'a, 'b: 'a>(
+    fn build_body<'a, 'b: 'a, 'c, U: IntoIterator<Item = &'c Use> + Clone>(
+        &self,
         cx: &'a Codegen<'b>,
+        uses: U,
         func: &mut LlvmFunc<'a, 'b>,
+        ret: &LlvmType<'a, 'b>,
         stmts: &[Statement],
-    ) {
+    ) -> Result<(), SyntaxError> {
+        // A match expression is parsed and checked but cannot be lowered yet. Reject it here since
+        // most statements are not lowered so it would otherwise be dropped without any error.
+        let mut matches = None;
+
+        for e in stmts.iter().flat_map(|s| s.exprs()) {
+            e.walk(&mut |e| {
+                if let (None, Expression::Match(v)) = (&matches, e) {
+                    matches = Some(v.span().clone());
+                }
+            });
+        }
+
+        if let Some(span) = matches {
+            return Err(SyntaxError::new(span, "'match' is not supported yet"));
+        }
+
         let mut bb = BasicBlock::new(cx);
         let mut b = Builder::new(cx, &mut bb);
+        let mut blocks = Vec::new();
 
-        b.ret_void();
+        // Check the initializer of annotated variables and lower the inline assemblies that are a
+        // statement on its own.
+        for stmt in stmts {
+            let asm = match stmt {
+                Statement::Let(v) => {
+                    self.build_let(cx, uses.clone(), &mut b, v)?;
+                    continue;
+                }
+                Statement::Unit(v) | Statement::Value(v) => match v.as_slice() {
+                    [Expression::Asm(v)] => v,
+                    _ => continue,
+                },
+            };
+
+            if Self::build_asm(cx, uses.clone(), &mut b, asm)? {
+                // Put the remaining code in a block that cannot be reached.
+                let mut next = BasicBlock::new(cx);
+
+                b.unreachable();
+                b.position(&mut next);
+                blocks.push(next);
+            }
+        }
+
+        // Lower the resulting value.
+        let value = match stmts.last() {
+            Some(Statement::Value(v)) => Self::build_value(
+                cx,
+                uses,
+                &mut b,
+                ret,
+                self.ret.as_ref(),
+                v,
+                "the type of the value does not match the return type",
+            )?,
+            _ => None,
+        };
+
+        match value {
+            Some(v) => b.ret(v),
+            None => b.ret_void(),
+        };
 
         func.append(bb);
+
+        for bb in blocks {
+            func.append(bb);
+        }
+
+        Ok(())
+    }
+
+    /// Lower `asm` as a call to an inline assembly. Returns `true` if the assembly never returns.
+    ///
+    /// An output cannot be assigned to a variable yet so only the discarded one is supported, which
+    /// is lowered as a clobber.
+    fn build_asm<'a, 'b: 'a, 'c, U: IntoIterator<Item = &'c Use> + Clone>(
+        cx: &'a Codegen<'b>,
+        uses: U,
+        b: &mut Builder<'a, 'b>,
+        asm: &Asm,
+    ) -> Result<bool, SyntaxError> {
+        let mut constraints = Vec::new();
+        let mut args = Vec::with_capacity(asm.inputs().len());
+
+        // An integer literal is an Int32 when its type cannot be inferred, the same as the other
+        // languages. An immediate can be any 64-bit value.
+        for (op, val) in asm.inputs() {
+            let (constraint, ty) = match op {
+                AsmIn::Register(v) => (
+                    format!("{{{}}}", Self::asm_register(v)?),
+                    LlvmType::I32(LlvmI32::new(cx)),
+                ),
+                AsmIn::Memory(_) => ("m".to_owned(), LlvmType::I32(LlvmI32::new(cx))),
+                AsmIn::Immediate(_) => ("i".to_owned(), LlvmType::U64(LlvmU64::new(cx))),
+            };
+
+            constraints.push(constraint);
+            args.push(Self::build_asm_operand(cx, uses.clone(), b, &ty, val)?);
+        }
+
+        let mut never = false;
+
+        for (op, var) in asm.outputs() {
+            match op {
+                AsmOut::Never(_) => never = true,
+                AsmOut::Register(v) => {
+                    if var.value() != "_" {
+                        return Err(SyntaxError::new(
+                            var.span().clone(),
+                            "an output to a variable is not supported yet",
+                        ));
+                    }
+
+                    constraints.push(format!("~{{{}}}", Self::asm_register(v)?));
+                }
+            }
+        }
+
+        // Build the call.
+        let inst = match CString::new(asm.inst().value()) {
+            Ok(v) => v,
+            Err(_) => {
+                return Err(SyntaxError::new(
+                    asm.inst().span().clone(),
+                    "an instruction cannot contain a NUL character",
+                ));
+            }
+        };
+
+        let constraints = CString::new(constraints.join(",")).unwrap();
+        let intel = cx.target().arch() == TargetArch::X86_64;
+
+        b.asm(&inst, &constraints, &args, intel);
+
+        Ok(never)
+    }
+
+    /// Returns the name of the register in `reg` if it is valid.
+    fn asm_register(reg: &StringLiteral) -> Result<&str, SyntaxError> {
+        let name = reg.value();
+
+        if name.is_empty() || !name.bytes().all(|b| b.is_ascii_alphanumeric()) {
+            return Err(SyntaxError::new(reg.span().clone(), "invalid register"));
+        }
+
+        Ok(name)
+    }
+
+    /// Build an operand of the inline assembly from `exprs`. An integer literal without `as` will
+    /// have the type `ty`.
+    fn build_asm_operand<'a, 'b: 'a, 'c, U: IntoIterator<Item = &'c Use> + Clone>(
+        cx: &'a Codegen<'b>,
+        uses: U,
+        b: &mut Builder<'a, 'b>,
+        ty: &LlvmType<'a, 'b>,
+        exprs: &[Expression],
+    ) -> Result<*mut crate::ffi::LlvmValue, SyntaxError> {
+        let value = match exprs {
+            [_, Expression::As(_, _), ..] => {
+                let ty = match exprs.last().unwrap() {
+                    Expression::As(_, t) => t.as_value(),
+                    e => return Err(SyntaxError::new(e.span(), "expect 'as'")),
+                };
+
+                Self::build_cast(cx, uses, b, exprs, ty, "")?
+            }
+            [Expression::Unsigned(v)] => Self::build_unsigned(ty, None, v)?,
+            [Expression::Null(_)] => {
+                LlvmType::Ptr(LlvmPtr::new(cx, LlvmType::Void(LlvmVoid::new(cx)))).get_zero()
+            }
+            [e, ..] => {
+                return Err(SyntaxError::new(
+                    e.span(),
+                    "only an integer literal, null or a conversion can be an operand of the inline assembly for now",
+                ));
+            }
+            [] => unreachable!(),
+        };
+
+        Ok(value)
+    }
+
+    /// Check the initializer of `var` against its type annotation. The value is not lowered
+    /// since there is no support for local variables yet.
+    fn build_let<'a, 'b: 'a, 'c, U: IntoIterator<Item = &'c Use> + Clone>(
+        &self,
+        cx: &'a Codegen<'b>,
+        uses: U,
+        b: &mut Builder<'a, 'b>,
+        var: &Let,
+    ) -> Result<(), SyntaxError> {
+        var.pattern().check_value(var.val())?;
+
+        let ann = match var.ty() {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+
+        var.pattern().check_type(ann)?;
+
+        let ty = match ann.build(cx, uses.clone())? {
+            Some(v) => v,
+            None => {
+                return Err(SyntaxError::new(
+                    ann.name().span(),
+                    "a variable cannot have a never type",
+                ));
+            }
+        };
+
+        let mismatch = format!(
+            "the type of the value does not match the type annotation on line {}",
+            ann.name().span().line()
+        );
+
+        Self::build_value(cx, uses, b, &ty, Some(ann), var.val(), &mismatch)?;
+
+        Ok(())
+    }
+
+    /// Build a value of `ty` from `exprs`. Only an integer literal, `null`, a constant, a conversion
+    /// of them, a zero-initialized struct, a tuple of them or an intrinsic call is supported for now.
+    /// Returns [`None`] if `exprs` is not lowered yet (e.g. a local variable or a call to other
+    /// function) or it does not produce a value. A string literal is an error since there is no type
+    /// that can hold it. An error with `mismatch` will be returned if the type of the value is not
+    /// `expected`.
+    fn build_value<'a, 'b: 'a, 'c, U: IntoIterator<Item = &'c Use> + Clone>(
+        cx: &'a Codegen<'b>,
+        uses: U,
+        b: &mut Builder<'a, 'b>,
+        ty: &LlvmType<'a, 'b>,
+        expected: Option<&Type>,
+        exprs: &[Expression],
+        mismatch: &str,
+    ) -> Result<Option<*mut crate::ffi::LlvmValue>, SyntaxError> {
+        let path = expected.and_then(|t| t.as_value());
+        let value = match exprs {
+            [_, Expression::As(_, _), ..] => {
+                Self::build_cast(cx, uses, b, exprs, path, mismatch.to_owned())?
+            }
+            [Expression::Unsigned(v)] => Self::build_unsigned(ty, path, v)?,
+            [Expression::Null(v)] => match ty {
+                LlvmType::Ptr(_) => ty.get_zero(),
+                _ => return Err(SyntaxError::new(v.span().clone(), mismatch.to_owned())),
+            },
+            [Expression::Const(v)] => Self::build_const(cx, uses, ty, path, v)?,
+            [Expression::Construct(v)] => {
+                Self::build_zero(cx, uses, ty, path, v, mismatch.to_owned())?
+            }
+            [Expression::Tuple(v)] => Self::build_tuple(cx, uses, b, ty, expected, v, mismatch)?,
+            [Expression::Call(v)] => return Self::build_intrinsic(cx, uses, b, ty, v, mismatch),
+            [Expression::String(v)] => {
+                return Err(SyntaxError::new(v.span().clone(), "unsupported expression"));
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some(value))
+    }
+
+    /// Lower `call` if it is an intrinsic. Returns [`None`] if `call` is not an intrinsic or the
+    /// intrinsic does not produce a value.
+    fn build_intrinsic<'a, 'b: 'a, 'c, U: IntoIterator<Item = &'c Use> + Clone>(
+        cx: &'a Codegen<'b>,
+        uses: U,
+        b: &mut Builder<'a, 'b>,
+        ty: &LlvmType<'a, 'b>,
+        call: &Call,
+        mismatch: &str,
+    ) -> Result<Option<*mut crate::ffi::LlvmValue>, SyntaxError> {
+        let intrin = match Intrinsic::resolve(cx, call.name())? {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+
+        // Check the signature.
+        let params = intrin.params(cx);
+
+        if call.args().len() != params.len() {
+            return Err(SyntaxError::new(
+                call.span(),
+                format!("the intrinsic expect {} arguments", params.len()),
+            ));
+        } else if !intrin.returns(cx, ty) {
+            return Err(SyntaxError::new(call.span(), mismatch.to_owned()));
+        }
+
+        // Build arguments.
+        let mut args = Vec::with_capacity(params.len());
+
+        for (exprs, t) in call.args().iter().zip(&params) {
+            let mismatch = "the type of the argument does not match the intrinsic";
+
+            match Self::build_value(cx, uses.clone(), b, t, None, exprs, mismatch)? {
+                Some(v) => args.push(v),
+                None => {
+                    return Err(SyntaxError::new(
+                        exprs.first().map_or_else(|| call.span(), |e| e.span()),
+                        "only an integer literal, null or an intrinsic call can be an argument of an intrinsic for now",
+                    ));
+                }
+            }
+        }
+
+        Ok(intrin.build(cx, b, &args))
+    }
+
+    fn build_tuple<'a, 'b: 'a, 'c, U: IntoIterator<Item = &'c Use> + Clone>(
+        cx: &'a Codegen<'b>,
+        uses: U,
+        b: &mut Builder<'a, 'b>,
+        ty: &LlvmType<'a, 'b>,
+        expected: Option<&Type>,
+        tuple: &Tuple,
+        mismatch: &str,
+    ) -> Result<*mut crate::ffi::LlvmValue, SyntaxError> {
+        let elems = tuple.elems();
+        let (ty, expected) = match (ty, expected.and_then(|t| t.as_tuple())) {
+            (LlvmType::Tuple(t), Some(e)) if e.len() == elems.len() => (t, e),
+            _ => return Err(SyntaxError::new(tuple.span(), mismatch.to_owned())),
+        };
+
+        // Build elements.
+        let mut values = Vec::with_capacity(elems.len());
+
+        for ((exprs, t), e) in elems.iter().zip(ty.elems()).zip(expected) {
+            match Self::build_value(cx, uses.clone(), b, t, Some(e), exprs, mismatch)? {
+                Some(v) => values.push(v),
+                None => {
+                    return Err(SyntaxError::new(
+                        tuple.span(),
+                        "only a literal, a constant or a struct can be an element of a tuple for now",
+                    ));
+                }
+            }
+        }
+
+        Ok(ty.get_const(&values))
+    }
+
+    /// Fold the value of the constant referenced by `cons` into a value of `ty`. The type of the
+    /// constant must be `expected`.
+    fn build_const<'a, 'b: 'a, 'c, U: IntoIterator<Item = &'c Use> + Clone>(
+        cx: &'a Codegen<'b>,
+        uses: U,
+        ty: &LlvmType<'a, 'b>,
+        expected: Option<&Path>,
+        cons: &ConstRef,
+    ) -> Result<*mut crate::ffi::LlvmValue, SyntaxError> {
+        let (cty, value) = Self::const_value(cx, uses.clone(), cons)?;
+
+        // Check the type.
+        let expected = match expected {
+            Some(v) => Type::resolve(cx, uses, v)?.0,
+            None => String::new(),
+        };
+
+        if cty != expected {
+            return Err(SyntaxError::new(
+                cons.span(),
+                format!("the constant has type {cty} which does not match the expected type"),
+            ));
+        }
+
+        // Fold the value.
+        Self::build_const_value(ty, &cty, value, cons)
+    }
+
+    /// Look up the constant referenced by `cons`. Returns the fully qualified name of its type
+    /// and its value.
+    fn const_value<'b, 'c, U: IntoIterator<Item = &'c Use> + Clone>(
+        cx: &Codegen<'b>,
+        uses: U,
+        cons: &ConstRef,
+    ) -> Result<(String, ConstValue), SyntaxError> {
+        let name = cons.name();
+        let undefined = || SyntaxError::new(name.span().clone(), "undefined constant");
+        let (owner, resolved) = Type::resolve(cx, uses.clone(), cons.ty())?;
+        let (cty, value) = match resolved {
+            ResolvedType::Internal(src) => {
+                let c = src.constant(cx, name.value())?.ok_or_else(undefined)?;
+
+                // Local names in the type of the constant are relative to its namespace.
+                let ns = owner["self.".len()..].rsplit_once('.').map_or("", |v| v.0);
+                let (cty, _) = Type::resolve_in(
+                    cx.resolver(),
+                    ns,
+                    src.prelude(),
+                    src.uses(),
+                    c.ty().as_value().unwrap(),
+                )?;
+                let value = match c.value() {
+                    ConstLiteral::Unsigned(v) => ConstValue::Unsigned(v.value()),
+                    ConstLiteral::Float(v) => ConstValue::Float(v.value()),
+                    ConstLiteral::String(v) => ConstValue::String(v.value().to_owned()),
+                };
+
+                (cty, value)
+            }
+            ResolvedType::External((pkg, t)) => {
+                let c = match t {
+                    TypeDeclaration::Basic(v) => v.constant(name.value()),
+                };
+
+                let c = c.ok_or_else(undefined)?;
+                let cty = match c.ty() {
+                    crate::pkg::Type::Struct { pkg: p, name, .. }
+                    | crate::pkg::Type::Class { pkg: p, name, .. } => match p {
+                        Some((p, _)) => format!("{p}.{name}"),
+                        None => format!("{}.{}", pkg.name(), name),
+                    },
+                    t => t.to_string(),
+                };
+
+                (cty, c.value().clone())
+            }
+        };
+
+        Ok((cty, value))
+    }
+
+    /// Build a value of `ty` from `value` of the constant referenced by `cons`. `name` is the name
+    /// of its type.
+    fn build_const_value<'a, 'b: 'a>(
+        ty: &LlvmType<'a, 'b>,
+        name: &str,
+        value: ConstValue,
+        cons: &ConstRef,
+    ) -> Result<*mut crate::ffi::LlvmValue, SyntaxError> {
+        match value {
+            ConstValue::Unsigned(v) => Self::build_integer(ty, Some(name), v, &cons.span()),
+            ConstValue::Float(_) | ConstValue::String(_) => Err(SyntaxError::new(
+                cons.span(),
+                "only an integer constant can be used here for now",
+            )),
+        }
+    }
+
+    /// Build a value from `exprs` that is converted with one or more `as` (e.g.
+    /// `Int32.MAX as UInt8`). An error with `mismatch` will be returned if the resulting type is
+    /// not `expected`.
+    fn build_cast<'a, 'b: 'a, 'c, U, M>(
+        cx: &'a Codegen<'b>,
+        uses: U,
+        b: &mut Builder<'a, 'b>,
+        exprs: &[Expression],
+        expected: Option<&Path>,
+        mismatch: M,
+    ) -> Result<*mut crate::ffi::LlvmValue, SyntaxError>
+    where
+        U: IntoIterator<Item = &'c Use> + Clone,
+        M: Into<Cow<'static, str>>,
+    {
+        let mut casts = exprs[1..].iter().map(|e| match e {
+            Expression::As(d, t) => Ok((d, t)),
+            e => Err(SyntaxError::new(e.span(), "expect 'as'")),
+        });
+
+        // Build the value to convert. Only an integer literal or a constant is supported for now.
+        let (def, mut last) = casts.next().unwrap()?;
+        let mut to = Self::build_cast_type(cx, uses.clone(), def, last)?;
+        let mut value = match &exprs[0] {
+            Expression::Unsigned(v) => Self::build_unsigned(&to, last.as_value(), v)?,
+            Expression::Const(c) => {
+                let (name, value) = Self::const_value(cx, uses.clone(), c)?;
+                let resolved = match cx.resolver().resolve(&name) {
+                    Some(v) => v,
+                    None => {
+                        return Err(SyntaxError::new(
+                            c.ty().span(),
+                            format!("cannot resolve type {name} of this constant"),
+                        ));
+                    }
+                };
+
+                let from = match Type::build_numeric(cx, resolved) {
+                    Some(v) => v,
+                    None => {
+                        return Err(SyntaxError::new(
+                            def.span().clone(),
+                            format!("a value of type {name} cannot be converted with 'as'"),
+                        ));
+                    }
+                };
+
+                let value = Self::build_const_value(&from, &name, value, c)?;
+
+                b.cast(value, &from, &to)
+            }
+            e => {
+                return Err(SyntaxError::new(
+                    e.span(),
+                    "only an integer literal or a constant can be converted for now",
+                ));
+            }
+        };
+
+        // Apply the remaining conversions.
+        for c in casts {
+            let (def, ty) = c?;
+            let from = to;
+
+            to = Self::build_cast_type(cx, uses.clone(), def, ty)?;
+            value = b.cast(value, &from, &to);
+            last = ty;
+        }
+
+        // Check if the type is the expected one.
+        let expected = match expected {
+            Some(v) => Some(Type::resolve(cx, uses.clone(), v)?.0),
+            None => None,
+        };
+
+        if expected != Some(Type::resolve(cx, uses, last.as_value().unwrap())?.0) {
+            return Err(SyntaxError::new(last.name().span(), mismatch));
+        }
+
+        Ok(value)
+    }
+
+    /// Build the target type of `def`.
+    fn build_cast_type<'a, 'b: 'a, 'c, U: IntoIterator<Item = &'c Use>>(
+        cx: &'a Codegen<'b>,
+        uses: U,
+        def: &AsKeyword,
+        ty: &Type,
+    ) -> Result<LlvmType<'a, 'b>, SyntaxError> {
+        let invalid = || {
+            SyntaxError::new(
+                def.span() + &ty.name().span(),
+                "only a numeric type can be the target of 'as'",
+            )
+        };
+
+        let name = ty.as_value().ok_or_else(invalid)?;
+        let (_, resolved) = Type::resolve(cx, uses, name)?;
+
+        Type::build_numeric(cx, resolved).ok_or_else(invalid)
+    }
+
+    /// Build a zero-initialized value of `ty` from `cons`. An error with `mismatch` will be returned
+    /// if the type of `cons` is not `expected`.
+    fn build_zero<'a, 'b: 'a, 'c, U, M>(
+        cx: &'a Codegen<'b>,
+        uses: U,
+        ty: &LlvmType<'a, 'b>,
+        expected: Option<&Path>,
+        cons: &Construct,
+        mismatch: M,
+    ) -> Result<*mut crate::ffi::LlvmValue, SyntaxError>
+    where
+        U: IntoIterator<Item = &'c Use> + Clone,
+        M: Into<Cow<'static, str>>,
+    {
+        if let Some((n, _)) = cons.fields().first() {
+            return Err(SyntaxError::new(
+                n.span().clone(),
+                "field initializers not yet supported",
+            ));
+        }
+
+        // Only a struct can be zero-initialized.
+        let (name, resolved) = Type::resolve(cx, uses.clone(), cons.name())?;
+        let class = match resolved {
+            ResolvedType::Internal(v) => match v.ty().unwrap() {
+                TypeDefinition::Basic(v) => v.is_ref(),
+            },
+            ResolvedType::External((_, t)) => match t {
+                TypeDeclaration::Basic(v) => v.is_class(),
+            },
+        };
+
+        if class {
+            return Err(SyntaxError::new(
+                cons.name().span(),
+                "zero-initialization is only allowed for a struct",
+            ));
+        }
+
+        // Check if the type is the expected one.
+        let expected = match expected {
+            Some(v) => Some(Type::resolve(cx, uses, v)?.0),
+            None => None,
+        };
+
+        if expected.as_ref() != Some(&name) {
+            return Err(SyntaxError::new(cons.name().span(), mismatch));
+        }
+
+        Ok(ty.get_zero())
+    }
+
+    /// Build a constant of `ty` from `lit`. This is where an integer literal take on the type of
+    /// `@repr` struct. `name` is the name of the type to report when `lit` is out of range.
+    pub fn build_unsigned<'a, 'b: 'a>(
+        ty: &LlvmType<'a, 'b>,
+        name: Option<&Path>,
+        lit: &UnsignedLiteral,
+    ) -> Result<*mut crate::ffi::LlvmValue, SyntaxError> {
+        let name = name.map(|v| v.to_string());
+
+        Self::build_integer(ty, name.as_deref(), lit.value(), lit.span())
+    }
+
+    /// Build an integer constant of `ty`. `span` is the location to report when `v` cannot be
+    /// represented by `ty`, which is reported with `name` or the name of the type in the standard
+    /// library if it is [`None`].
+    fn build_integer<'a, 'b: 'a>(
+        ty: &LlvmType<'a, 'b>,
+        name: Option<&str>,
+        v: u64,
+        span: &Span,
+    ) -> Result<*mut crate::ffi::LlvmValue, SyntaxError> {
+        let (repr, ptr) = match ty {
+            LlvmType::I32(_) => (Representation::I32, 0),
+            LlvmType::U8(_) => (Representation::U8, 0),
+            LlvmType::U32(_) => (Representation::Un, 4),
+            LlvmType::U64(_) => (Representation::Un, 8),
+            LlvmType::Void(_) | LlvmType::Ptr(_) | LlvmType::Tuple(_) => {
+                return Err(SyntaxError::new(
+                    span.clone(),
+                    "an integer literal can be used only with an integer type",
+                ));
+            }
+        };
+
+        if let Err(e) = repr.check_literal(name.unwrap_or(repr.std_name()), v, ptr) {
+            return Err(SyntaxError::new(span.clone(), e));
+        }
+
+        // The value is already checked so the conversions cannot fail.
+        let v = match ty {
+            LlvmType::I32(t) => t.get_const(v.try_into().unwrap()),
+            LlvmType::U8(t) => t.get_const(v.try_into().unwrap()),
+            LlvmType::U32(t) => t.get_const(v.try_into().unwrap()),
+            LlvmType::U64(t) => t.get_const(v),
+            LlvmType::Void(_) | LlvmType::Ptr(_) | LlvmType::Tuple(_) => unreachable!(),
+        };
+
+        Ok(v as _)
     }
 }
 
@@ -186,3 +1052,232 @@ impl FunctionParam {
         Self { name, ty }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::tests::build_ir;
+
+    #[test]
+    fn asm_constraints() {
+        let ir = build_ir(
+            "nitro",
+            "class Test;\n\nimpl Test {\n    fn Run() {\n        asm(\"nop\", in(\"ecx\") 7, in(mem) 1 as UInt8);\n        asm(\"int 0x29\", in(imm) 3, out(\"rax\") _, out(!) _);\n    }\n}\n",
+        )
+        .unwrap();
+
+        assert!(
+            ir.contains("call void asm sideeffect inteldialect \"nop\", \"{ecx},m\"(i32 7, i8 1)"),
+            "{ir}"
+        );
+        assert!(
+            ir.contains("call void asm sideeffect inteldialect \"int 0x29\", \"i,~{rax}\"(i64 3)"),
+            "{ir}"
+        );
+        assert!(ir.contains("unreachable"), "{ir}");
+    }
+
+    #[test]
+    fn asm_error() {
+        let e = build_ir(
+            "nitro",
+            "class Test;\n\nimpl Test {\n    fn Run() {\n        asm(\"nop\", out(\"rax\") v);\n    }\n}\n",
+        )
+        .unwrap_err();
+
+        assert_eq!(e.reason(), "an output to a variable is not supported yet");
+        assert_eq!(e.span().as_str(), "v");
+
+        let e = build_ir(
+            "nitro",
+            "class Test;\n\nimpl Test {\n    fn Run() {\n        asm(\"nop\", in(\"{rax}\") 1);\n    }\n}\n",
+        )
+        .unwrap_err();
+
+        assert_eq!(e.reason(), "invalid register");
+    }
+
+    #[test]
+    fn zero_initialized() {
+        let ir = build_ir(
+            "nitro",
+            "class Test;\n\nimpl Test {\n    fn Zero(): Int32 {\n        Int32 {}\n    }\n\n    fn Pair(): (Int32, UInt8) {\n        (Int32 {}, UInt8 {})\n    }\n}\n",
+        )
+        .unwrap();
+
+        assert!(ir.contains("ret i32 0"), "{ir}");
+        assert!(ir.contains("ret { i32, i8 } zeroinitializer"), "{ir}");
+    }
+
+    #[test]
+    fn zero_initialized_error() {
+        let e = build_ir(
+            "nitro",
+            "class Test;\n\nimpl Test {\n    fn Get(): Test {\n        Test {}\n    }\n}\n",
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            e.reason(),
+            "zero-initialization is only allowed for a struct"
+        );
+        assert_eq!(e.span().as_str(), "Test");
+
+        let e = build_ir(
+            "nitro",
+            "class Test;\n\nimpl Test {\n    fn Get(): Int32 {\n        Int32 { value = 1 }\n    }\n}\n",
+        )
+        .unwrap_err();
+
+        assert_eq!(e.reason(), "field initializers not yet supported");
+        assert_eq!(e.span().as_str(), "value");
+    }
+
+    #[test]
+    fn optimizer_hints() {
+        let ir = build_ir(
+            "nitro",
+            "class Test;\n\nimpl Test {\n    @inline\n    fn Hint() {}\n\n    @inline(always)\n    fn Always() {}\n\n    @cold\n    @inline(never)\n    fn Never() {}\n}\n",
+        )
+        .unwrap();
+
+        assert!(ir.contains("inlinehint"), "{ir}");
+        assert!(ir.contains("alwaysinline"), "{ir}");
+        assert!(ir.contains("noinline"), "{ir}");
+        assert!(ir.contains("cold"), "{ir}");
+    }
+
+    #[test]
+    fn optimizer_hints_without_body() {
+        let e = build_ir(
+            "nitro",
+            "class Test;\n\nimpl Test {\n    @ext(C)\n    @inline\n    fn foo();\n}\n",
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            e.reason(),
+            "inline attribute cannot be used on a function without a body"
+        );
+        assert_eq!(e.span().as_str(), "@inline");
+
+        let e = build_ir(
+            "nitro",
+            "class Test;\n\nimpl Test {\n    @ext(C)\n    @cold\n    fn foo();\n}\n",
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            e.reason(),
+            "cold attribute cannot be used on a function without a body"
+        );
+        assert_eq!(e.span().as_str(), "@cold");
+    }
+
+    #[test]
+    fn unlowered_value() {
+        // A local variable is not lowered yet but must not be an error since std use it.
+        build_ir(
+            "nitro",
+            "class Test;\n\nimpl Test {\n    fn Get(): Int32 {\n        value\n    }\n}\n",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn out_of_range() {
+        let e = build_ir(
+            "nitro",
+            "class Test;\n\nimpl Test {\n    fn Get(): UInt8 {\n        300\n    }\n}\n",
+        )
+        .unwrap_err();
+
+        assert_eq!(e.reason(), "300 is out of range for UInt8 (0..=255)");
+        assert_eq!(e.span().as_str(), "300");
+
+        let e = build_ir(
+            "nitro",
+            "class Test;\n\nimpl Test {\n    fn Get(): Int32 {\n        2147483648 as Int32\n    }\n}\n",
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            e.reason(),
+            "2147483648 is out of range for Int32 (-2147483648..=2147483647)"
+        );
+        assert_eq!(e.span().as_str(), "2147483648");
+    }
+
+    #[test]
+    fn unsupported_match() {
+        // The match in a statement that is not lowered.
+        let e = build_ir(
+            "nitro",
+            "class Test;\n\nimpl Test {\n    fn Get(): UInt8 {\n        match 1 {\n            1 => 2,\n            _ => 3,\n        };\n        1\n    }\n}\n",
+        )
+        .unwrap_err();
+
+        assert_eq!(e.reason(), "'match' is not supported yet");
+        assert_eq!(e.span().as_str(), "match");
+
+        // The match in the resulting value.
+        let e = build_ir(
+            "nitro",
+            "class Test;\n\nimpl Test {\n    fn Get(): UInt8 {\n        match 1 {\n            _ => 3,\n        }\n    }\n}\n",
+        )
+        .unwrap_err();
+
+        assert_eq!(e.reason(), "'match' is not supported yet");
+    }
+
+    #[test]
+    fn unlowered_call() {
+        build_ir(
+            "app",
+            "class Test;\n\nimpl Test {\n    fn Get(): UInt8 {\n        Foo.Bar(1)\n    }\n}\n",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn annotated_literal() {
+        // The same literal is valid or not depending on the width of the annotation.
+        build_ir(
+            "nitro",
+            "class Test;\n\nimpl Test {\n    fn Run() {\n        let v: UInt32 = 300;\n    }\n}\n",
+        )
+        .unwrap();
+
+        let e = build_ir(
+            "nitro",
+            "class Test;\n\nimpl Test {\n    fn Run() {\n        let v: UInt8 = 300;\n    }\n}\n",
+        )
+        .unwrap_err();
+
+        assert_eq!(e.reason(), "300 is out of range for UInt8 (0..=255)");
+        assert_eq!(e.span().as_str(), "300");
+
+        let e = build_ir(
+            "nitro",
+            "class Test;\n\nimpl Test {\n    fn Run() {\n        let v: *UInt8 = 1;\n    }\n}\n",
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            e.reason(),
+            "an integer literal can be used only with an integer type"
+        );
+        assert_eq!(e.span().as_str(), "1");
+    }
+
+    #[test]
+    fn unsupported_initializer() {
+        let e = build_ir(
+            "nitro",
+            "class Test;\n\nimpl Test {\n    fn Run() {\n        let v: Int32 = \"abc\";\n    }\n}\n",
+        )
+        .unwrap_err();
+
+        assert_eq!(e.reason(), "unsupported expression");
+        assert_eq!(e.span().as_str(), "\"abc\"");
+    }
+}
@@ -1,23 +1,70 @@
-use super::Function;
-use crate::lexer::{Identifier, ImplKeyword, Span};
+use super::{Constant, Function, SourceWriter};
+use crate::lexer::{CloseCurly, Identifier, ImplKeyword, Span};
 
 /// An implementation block for a type.
 pub(super) struct TypeImpl {
     def: ImplKeyword,
     ty: Identifier,
+    constants: Vec<Constant>,
     functions: Vec<Function>,
+    close: CloseCurly,
 }
 
 impl TypeImpl {
-    pub fn new(def: ImplKeyword, ty: Identifier, functions: Vec<Function>) -> Self {
-        Self { def, ty, functions }
+    pub fn new(
+        def: ImplKeyword,
+        ty: Identifier,
+        constants: Vec<Constant>,
+        functions: Vec<Function>,
+        close: CloseCurly,
+    ) -> Self {
+        Self {
+            def,
+            ty,
+            constants,
+            functions,
+            close,
+        }
     }
 
     pub fn span(&self) -> &Span {
         self.def.span()
     }
 
+    pub fn ty(&self) -> &Identifier {
+        &self.ty
+    }
+
+    pub fn constants(&self) -> &[Constant] {
+        self.constants.as_ref()
+    }
+
     pub fn functions(&self) -> &[Function] {
         self.functions.as_ref()
     }
+
+    pub fn format(&self, w: &mut SourceWriter) {
+        w.mark(self.def.span());
+        w.write(format_args!("{} {} {{", self.def, self.ty));
+        w.newline();
+        w.indent();
+
+        // Constants always come before the functions.
+        for c in &self.constants {
+            c.format(w);
+        }
+
+        for (i, f) in self.functions.iter().enumerate() {
+            if i != 0 || !self.constants.is_empty() {
+                w.newline();
+            }
+
+            f.format(w);
+        }
+
+        w.mark(self.close.span());
+        w.unindent();
+        w.write("}");
+        w.newline();
+    }
 }
@@ -0,0 +1,178 @@
+use super::{Path, Type};
+use crate::codegen::{Builder, Codegen, LlvmPtr, LlvmType, LlvmU8, LlvmVoid};
+use crate::lexer::SyntaxError;
+use crate::pkg::Representation;
+use std::mem::discriminant;
+
+/// A compiler intrinsic, which is called like a function in `nitro.intrin` but lowered directly
+/// into the instructions.
+///
+/// The intrinsics are not declared by any type so they never appear in a package. They are only
+/// available to the `nitro` package and the projects that set `allow-intrinsics` since misusing
+/// them is undefined behavior.
+#[derive(Clone, Copy)]
+pub(super) enum Intrinsic {
+    /// `PtrAdd(p: *UInt8, n: UInt): *UInt8`
+    Offset,
+    /// `PtrDiff(a: *UInt8, b: *UInt8): UInt`, which is the distance in bytes from `b` to `a`.
+    Distance,
+    /// `PtrReadI32(p: *UInt8): Int32`, `PtrReadU8(p: *UInt8): UInt8` or
+    /// `PtrReadUn(p: *UInt8): UInt`.
+    Load(Representation),
+    /// `PtrWriteI32(p: *UInt8, v: Int32)`, `PtrWriteU8(p: *UInt8, v: UInt8)` or
+    /// `PtrWriteUn(p: *UInt8, v: UInt)`.
+    Store(Representation),
+}
+
+impl Intrinsic {
+    const NAMESPACE: &'static str = "nitro.intrin.";
+
+    /// Returns the intrinsic that `name` refers to or [`None`] if `name` is not in the reserved
+    /// namespace. An error will be returned if `name` is not a known intrinsic or the intrinsics
+    /// are not allowed in the package that is being built.
+    pub fn resolve(cx: &Codegen, name: &Path) -> Result<Option<Self>, SyntaxError> {
+        let full = name.to_string();
+        let short = match full.strip_prefix(Self::NAMESPACE) {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+
+        if cx.pkg() != "nitro" && !cx.allow_intrinsics() {
+            return Err(SyntaxError::new(
+                name.span(),
+                "nitro.intrin is reserved for the nitro package unless allow-intrinsics is set in Nitro.yml",
+            ));
+        }
+
+        let v = match short {
+            "PtrAdd" => Self::Offset,
+            "PtrDiff" => Self::Distance,
+            "PtrReadI32" => Self::Load(Representation::I32),
+            "PtrReadU8" => Self::Load(Representation::U8),
+            "PtrReadUn" => Self::Load(Representation::Un),
+            "PtrWriteI32" => Self::Store(Representation::I32),
+            "PtrWriteU8" => Self::Store(Representation::U8),
+            "PtrWriteUn" => Self::Store(Representation::Un),
+            _ => return Err(SyntaxError::new(name.span(), "unknown intrinsic")),
+        };
+
+        Ok(Some(v))
+    }
+
+    pub fn params<'a, 'b: 'a>(self, cx: &'a Codegen<'b>) -> Vec<LlvmType<'a, 'b>> {
+        match self {
+            Self::Offset => vec![
+                ptr(cx),
+                Type::build_primitive_struct(cx, Representation::Un),
+            ],
+            Self::Distance => vec![ptr(cx), ptr(cx)],
+            Self::Load(_) => vec![ptr(cx)],
+            Self::Store(r) => vec![ptr(cx), Type::build_primitive_struct(cx, r)],
+        }
+    }
+
+    pub fn ret<'a, 'b: 'a>(self, cx: &'a Codegen<'b>) -> LlvmType<'a, 'b> {
+        match self {
+            Self::Offset => ptr(cx),
+            Self::Distance => Type::build_primitive_struct(cx, Representation::Un),
+            Self::Load(r) => Type::build_primitive_struct(cx, r),
+            Self::Store(_) => LlvmType::Void(LlvmVoid::new(cx)),
+        }
+    }
+
+    /// Returns `true` if the return type is `ty`.
+    pub fn returns(self, cx: &Codegen, ty: &LlvmType) -> bool {
+        let ret = self.ret(cx);
+
+        discriminant(&ret) == discriminant(ty) && ret.as_raw() == ty.as_raw()
+    }
+
+    /// Emit the instructions with `args`, which must match [`Intrinsic::params()`]. Returns
+    /// [`None`] if the intrinsic does not produce a value.
+    pub fn build<'a, 'b: 'a>(
+        self,
+        cx: &'a Codegen<'b>,
+        b: &mut Builder<'a, 'b>,
+        args: &[*mut crate::ffi::LlvmValue],
+    ) -> Option<*mut crate::ffi::LlvmValue> {
+        match self {
+            Self::Offset => Some(b.gep8(args[0], args[1])),
+            Self::Distance => {
+                let ty = Type::build_primitive_struct(cx, Representation::Un);
+
+                Some(b.ptr_diff(args[0], args[1], &ty))
+            }
+            Self::Load(r) => Some(b.load(&Type::build_primitive_struct(cx, r), args[0])),
+            Self::Store(_) => {
+                b.store(args[1], args[0]);
+                None
+            }
+        }
+    }
+}
+
+/// Returns the type of `*UInt8`.
+fn ptr<'a, 'b: 'a>(cx: &'a Codegen<'b>) -> LlvmType<'a, 'b> {
+    LlvmType::Ptr(LlvmPtr::new(cx, LlvmType::U8(LlvmU8::new(cx))))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::tests::build_ir;
+
+    #[test]
+    fn offset_and_load() {
+        let ir = build_ir(
+            "nitro",
+            "class Test;\n\nimpl Test {\n    fn Read(): UInt8 {\n        nitro.intrin.PtrReadU8(nitro.intrin.PtrAdd(null, 16))\n    }\n}\n",
+        )
+        .unwrap();
+
+        assert!(ir.contains("getelementptr"), "{ir}");
+        assert!(ir.contains("load i8"), "{ir}");
+    }
+
+    #[test]
+    fn distance() {
+        let ir = build_ir(
+            "nitro",
+            "class Test;\n\nimpl Test {\n    fn Distance(): UInt {\n        nitro.intrin.PtrDiff(nitro.intrin.PtrAdd(null, 8), null)\n    }\n}\n",
+        )
+        .unwrap();
+
+        assert!(ir.contains("ptrtoint"), "{ir}");
+    }
+
+    #[test]
+    fn store() {
+        let ir = build_ir(
+            "nitro",
+            "class Test;\n\nimpl Test {\n    fn Write() {\n        nitro.intrin.PtrWriteI32(null, 1)\n    }\n}\n",
+        )
+        .unwrap();
+
+        assert!(ir.contains("store i32 1, ptr null"), "{ir}");
+    }
+
+    #[test]
+    fn outside_nitro() {
+        let e = build_ir(
+            "app",
+            "class Test;\n\nimpl Test {\n    fn Write() {\n        nitro.intrin.PtrWriteI32(null, 1)\n    }\n}\n",
+        )
+        .unwrap_err();
+
+        assert!(e.reason().contains("reserved"), "{}", e.reason());
+    }
+
+    #[test]
+    fn unknown() {
+        let e = build_ir(
+            "nitro",
+            "class Test;\n\nimpl Test {\n    fn Write() {\n        nitro.intrin.Foo(null)\n    }\n}\n",
+        )
+        .unwrap_err();
+
+        assert_eq!(e.reason(), "unknown intrinsic");
+    }
+}
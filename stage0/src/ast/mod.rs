@@ -1,39 +1,55 @@
+pub use self::audit::*;
+
 use self::attr::Attributes;
 use self::bt::BasicType;
+use self::constant::{ConstLiteral, Constant};
+use self::expr::Expression;
+use self::fmt::SourceWriter;
 use self::func::{Function, FunctionParam};
 use self::imp::TypeImpl;
+use self::intrin::Intrinsic;
 use self::path::Path;
-use self::stmt::Statement;
+use self::stmt::{Block, Statement};
 use self::ty::{Type, TypeName};
 use self::using::Use;
-use crate::codegen::Codegen;
-use crate::lexer::{Identifier, ImplKeyword, Lexer, SyntaxError, Token};
-use crate::pkg::{Public, TypeDeclaration};
+use crate::codegen::{Codegen, ResolvedType};
+use crate::lexer::{Identifier, ImplKeyword, Lexer, Span, SyntaxError, Token};
+use crate::limit::MAX_PARAM_NAME;
+use crate::pkg::{FunctionSignature, Public, TargetOs, TypeDeclaration};
 use std::borrow::Cow;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use thiserror::Error;
 
 mod attr;
+mod audit;
 mod bt;
+mod constant;
 mod expr;
+mod fmt;
 mod func;
 mod imp;
+mod intrin;
 mod path;
 mod stmt;
 mod ty;
 mod using;
 
+/// Types in the `nitro` package that can be referred by its name without a `use` declaration.
+const PRELUDE: [&str; 3] = ["Int32", "UInt", "UInt8"];
+
 ///  A parsed source file.
 pub struct SourceFile {
     path: PathBuf,
     uses: Vec<Use>,
     ty: Option<TypeDefinition>,
     impls: Vec<TypeImpl>,
+    comments: Vec<Span>,
 }
 
 impl SourceFile {
-    pub fn parse<P: Into<PathBuf>>(path: P) -> Result<SourceFile, ParseError> {
+    /// `max_depth` is the maximum nesting level of the source (see [`Lexer::set_max_depth()`]).
+    pub fn parse<P: Into<PathBuf>>(path: P, max_depth: usize) -> Result<SourceFile, ParseError> {
         // Read the file.
         let path = path.into();
         let data = match std::fs::read_to_string(&path) {
@@ -42,16 +58,25 @@ impl SourceFile {
         };
 
         // Parse source file.
+        Self::parse_str(path, data, max_depth).map_err(ParseError::ParseFailed)
+    }
+
+    /// Parse a source file from `data` without touching the filesystem. `path` is used only for
+    /// identifying the source file (e.g. in the diagnostics).
+    pub fn parse_str<P: Into<PathBuf>>(
+        path: P,
+        data: String,
+        max_depth: usize,
+    ) -> Result<SourceFile, SyntaxError> {
         let mut file = Self {
-            path,
+            path: path.into(),
             ty: None,
             uses: Vec::new(),
             impls: Vec::new(),
+            comments: Vec::new(),
         };
 
-        if let Err(e) = file.parse_top(data) {
-            return Err(ParseError::ParseFailed(e));
-        }
+        file.parse_top(data, max_depth)?;
 
         Ok(file)
     }
@@ -60,20 +85,248 @@ impl SourceFile {
         &self.path
     }
 
+    /// Returns the source of this file with the canonical formatting. The comments are kept close to
+    /// the item they were next to in the source.
+    pub fn format(&self) -> String {
+        let mut w = SourceWriter::new(self.comments.clone());
+
+        // Uses.
+        for u in &self.uses {
+            u.format(&mut w);
+        }
+
+        // Type.
+        if let Some(ty) = &self.ty {
+            w.separator();
+
+            match ty {
+                TypeDefinition::Basic(v) => v.format(&mut w),
+            }
+        }
+
+        // Implementations.
+        for im in &self.impls {
+            w.separator();
+            im.format(&mut w);
+        }
+
+        w.flush();
+        w.into_string()
+    }
+
+    /// Returns the name of all `@entry` functions that will be built for `os` with `defines`.
+    pub fn entries(
+        &self,
+        os: TargetOs,
+        defines: &HashMap<String, String>,
+    ) -> Result<Vec<&Identifier>, SyntaxError> {
+        let mut list = Vec::new();
+
+        for im in &self.impls {
+            for func in im.functions() {
+                let attrs = func.attrs();
+
+                if attrs.entry().is_some() && attrs.eval_condition(os, defines)? {
+                    list.push(func.name());
+                }
+            }
+        }
+
+        Ok(list)
+    }
+
+    /// Returns the name of all functions with `@entry` regardless of `@if`.
+    pub fn annotated_entries(&self) -> impl Iterator<Item = &Identifier> {
+        self.impls
+            .iter()
+            .flat_map(|im| im.functions())
+            .filter(|f| f.attrs().entry().is_some())
+            .map(|f| f.name())
+    }
+
+    /// Returns `true` if this file has a function named `name` that will be built for `os`.
+    pub fn has_function(
+        &self,
+        name: &str,
+        os: TargetOs,
+        defines: &HashMap<String, String>,
+    ) -> Result<bool, SyntaxError> {
+        for im in &self.impls {
+            for func in im.functions() {
+                if func.name().value() == name && func.attrs().eval_condition(os, defines)? {
+                    return Ok(true);
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Returns the warnings of this file in the order they appear in the source.
+    pub fn lints(&self) -> Vec<SyntaxError> {
+        self.impls
+            .iter()
+            .flat_map(|im| im.functions())
+            .flat_map(|f| f.shadows())
+            .collect()
+    }
+
+    /// Returns the signature of all functions in this file in the order they are defined.
+    pub fn signatures(&self) -> Vec<FunctionSignature> {
+        self.impls
+            .iter()
+            .flat_map(|im| im.functions())
+            .map(|f| f.signature())
+            .collect()
+    }
+
+    /// Returns all extern functions, `asm` expressions and public functions with a pointer in its
+    /// signature in the order they appear in the source.
+    pub fn audit(&self) -> Vec<AuditItem> {
+        let mut items = Vec::new();
+
+        for im in &self.impls {
+            let ty = im.ty().value();
+
+            for func in im.functions() {
+                let span = func.name().span();
+
+                if func.attrs().ext().is_some() {
+                    items.push(AuditItem::Extern {
+                        line: span.line(),
+                        column: span.column(),
+                        ty: ty.to_owned(),
+                        signature: func.signature(),
+                    });
+                }
+
+                if func.attrs().public().is_some() && func.types().any(|t| t.has_pointer()) {
+                    items.push(AuditItem::Pointer {
+                        line: span.line(),
+                        column: span.column(),
+                        ty: ty.to_owned(),
+                        signature: func.signature(),
+                    });
+                }
+
+                for stmt in func.body().into_iter().flatten() {
+                    for expr in stmt.exprs() {
+                        expr.walk(&mut |e| {
+                            if let Expression::Asm(v) = e {
+                                let span = v.span();
+
+                                items.push(AuditItem::Asm {
+                                    line: span.line(),
+                                    column: span.column(),
+                                    ty: ty.to_owned(),
+                                    function: func.name().value().to_owned(),
+                                    instruction: v.inst().value().to_owned(),
+                                });
+                            }
+                        });
+                    }
+                }
+            }
+        }
+
+        items.sort_by_key(|i| (i.line(), i.column()));
+        items
+    }
+
     pub fn has_type(&self) -> bool {
         self.ty.is_some()
     }
 
+    /// Returns `true` if this file has at least one `@reexport` or `@pub` use declaration.
+    pub fn has_reexports(&self) -> bool {
+        self.uses.iter().any(|u| u.reexport().is_some())
+    }
+
+    /// Returns the types from the dependencies that was re-exported with `@reexport` or `@pub`
+    /// together with the location of its use declaration.
+    pub fn reexports(&self, cx: &Codegen) -> Result<Vec<(Span, TypeDeclaration)>, SyntaxError> {
+        let mut types = Vec::new();
+
+        for u in &self.uses {
+            let attr = match u.reexport() {
+                Some(v) => v,
+                None => continue,
+            };
+
+            if !u.attrs().run_condition(cx)? {
+                continue;
+            }
+
+            if cx.executable() {
+                return Err(SyntaxError::new(
+                    attr.span(),
+                    "re-export is only allowed in a library",
+                ));
+            }
+
+            if let Some(v) = u.rename() {
+                return Err(SyntaxError::new(
+                    v.span().clone(),
+                    "a re-exported type cannot be renamed",
+                ));
+            }
+
+            // Only a type from the dependency can be re-exported.
+            let ty = match Type::resolve(cx, std::iter::empty(), u.name())?.1 {
+                ResolvedType::Internal(_) => {
+                    return Err(SyntaxError::new(
+                        u.name().span(),
+                        "only a type from a dependency can be re-exported",
+                    ));
+                }
+                ResolvedType::External((pkg, ty)) => match ty {
+                    TypeDeclaration::Basic(v) => TypeDeclaration::Basic(
+                        v.reexport(pkg.name().as_str(), pkg.version().major()),
+                    ),
+                },
+            };
+
+            types.push((u.name().span(), ty));
+        }
+
+        Ok(types)
+    }
+
     fn ty(&self) -> Option<&TypeDefinition> {
         self.ty.as_ref()
     }
 
+    fn uses(&self) -> &[Use] {
+        &self.uses
+    }
+
+    /// Returns `false` if the type definition has `@no_prelude`.
+    pub fn prelude(&self) -> bool {
+        self.ty
+            .as_ref()
+            .is_none_or(|t| t.attrs().no_prelude().is_none())
+    }
+
+    /// Returns the constant with `name` that will be built for the current target.
+    fn constant(&self, cx: &Codegen, name: &str) -> Result<Option<&Constant>, SyntaxError> {
+        for c in self.impls.iter().flat_map(|im| im.constants()) {
+            if c.name().value() == name && c.attrs().run_condition(cx)? {
+                return Ok(Some(c));
+            }
+        }
+
+        Ok(None)
+    }
+
     pub fn build<'a, 'b: 'a>(
         &self,
         cg: &'a mut Codegen<'b>,
     ) -> Result<Option<TypeDeclaration>, SyntaxError> {
-        // Get fully qualified type name.
-        let ty = self.ty.as_ref().unwrap();
+        // Get fully qualified type name. A file without a type contains only re-exports.
+        let ty = match &self.ty {
+            Some(v) => v,
+            None => return Ok(None),
+        };
         let fqtn = if cg.namespace().is_empty() {
             Cow::Borrowed(ty.name().value())
         } else {
@@ -84,8 +337,24 @@ impl SourceFile {
         let pkg = match ty {
             TypeDefinition::Basic(ty) => {
                 let mut funcs = HashSet::new();
+                let mut consts = HashSet::new();
 
                 for im in &self.impls {
+                    for c in im.constants() {
+                        let exp = match c.build(cg, &fqtn, &self.uses)? {
+                            Some(v) => v,
+                            None => continue,
+                        };
+
+                        if c.attrs()
+                            .public()
+                            .filter(|v| v.1 == Public::External)
+                            .is_some()
+                        {
+                            consts.insert(exp);
+                        }
+                    }
+
                     for func in im.functions() {
                         let exp = match func.build(cg, &fqtn, &self.uses)? {
                             Some(v) => v,
@@ -108,6 +377,7 @@ impl SourceFile {
                     ty.attrs().to_external(),
                     fqtn.into_owned(),
                     funcs,
+                    consts,
                 ))
             }
         };
@@ -124,8 +394,11 @@ impl SourceFile {
         }
     }
 
-    fn parse_top(&mut self, data: String) -> Result<(), SyntaxError> {
+    fn parse_top(&mut self, data: String, max_depth: usize) -> Result<(), SyntaxError> {
         let mut lex = Lexer::new(data);
+
+        lex.set_max_depth(max_depth);
+
         let mut attrs = None;
 
         loop {
@@ -194,6 +467,7 @@ impl SourceFile {
                             }
 
                             self.impls.push(Self::parse_type_impl(&mut lex, def, ty)?);
+                            self.check_constants()?;
                         }
                         t => return Err(SyntaxError::new(t.span().clone(), "expect '{'")),
                     }
@@ -207,6 +481,8 @@ impl SourceFile {
             }
         }
 
+        self.comments = lex.take_comments();
+
         Ok(())
     }
 
@@ -265,9 +541,9 @@ impl SourceFile {
         ty: Identifier,
     ) -> Result<TypeImpl, SyntaxError> {
         let mut attrs = None;
+        let mut constants = Vec::new();
         let mut functions = Vec::new();
-
-        loop {
+        let close = loop {
             let tok = match lex.next()? {
                 Some(v) => v,
                 None => {
@@ -280,15 +556,20 @@ impl SourceFile {
 
             match tok {
                 Token::AttributeName(name) => attrs = Some(Attributes::parse(lex, name)?),
+                Token::ConstKeyword(def) => {
+                    let attrs = attrs.take().unwrap_or_default();
+
+                    constants.push(Constant::parse(lex, attrs, def)?);
+                }
                 Token::FnKeyword(_) => {
                     functions.push(Self::parse_fn(lex, attrs.take().unwrap_or_default())?);
                 }
-                Token::CloseCurly(_) => break,
+                Token::CloseCurly(v) => break v,
                 t => return Err(SyntaxError::new(t.span().clone(), "syntax error")),
             }
-        }
+        };
 
-        Ok(TypeImpl::new(def, ty, functions))
+        Ok(TypeImpl::new(def, ty, constants, functions, close))
     }
 
     fn parse_fn(lex: &mut Lexer, attrs: Attributes) -> Result<Function, SyntaxError> {
@@ -313,9 +594,16 @@ impl SourceFile {
             match tok {
                 Token::Identifier(name) => {
                     // Parse the parameter.
+                    if name.value().len() > MAX_PARAM_NAME {
+                        return Err(SyntaxError::new(
+                            name.span().clone(),
+                            format!("parameter name cannot be longer than {MAX_PARAM_NAME} bytes"),
+                        ));
+                    }
+
                     lex.next_colon()?;
 
-                    let ty = Self::parse_type(lex)?;
+                    let ty = Type::parse(lex)?;
 
                     match ty.name() {
                         TypeName::Unit(o, c) => {
@@ -330,7 +618,9 @@ impl SourceFile {
                                 "never type cannot be a function parameter",
                             ));
                         }
-                        TypeName::Ident(_) => params.push(FunctionParam::new(name, ty)),
+                        TypeName::Ident(_) | TypeName::Tuple(_, _, _) => {
+                            params.push(FunctionParam::new(name, ty))
+                        }
                     }
 
                     // Check for a ','.
@@ -370,7 +660,7 @@ impl SourceFile {
             Token::Semicolon(_) => return Ok(Function::new(attrs, name, params, None, None)),
             Token::OpenCurly(_) => None,
             Token::Colon(_) => {
-                let ret = Self::parse_type(lex)?;
+                let ret = Type::parse(lex)?;
                 let next = match lex.next()? {
                     Some(v) => v,
                     None => {
@@ -405,92 +695,38 @@ impl SourceFile {
         };
 
         // Parse body.
-        let body = Statement::parse_block(lex)?;
+        let body = Block::parse(lex)?;
 
         Ok(Function::new(attrs, name, params, ret, Some(body)))
     }
 
-    fn parse_type(lex: &mut Lexer) -> Result<Type, SyntaxError> {
-        // Parse pointer prefix.
-        let mut prefixes = Vec::new();
+    /// Check if the constants in the last implementation has the same name as the other constants.
+    fn check_constants(&self) -> Result<(), SyntaxError> {
+        let (last, prev) = self.impls.split_last().unwrap();
+        let mut seen: Vec<&Identifier> = prev
+            .iter()
+            .flat_map(|im| im.constants())
+            .map(|c| c.name())
+            .collect();
 
-        loop {
-            match lex.next()? {
-                Some(Token::Asterisk(v)) => prefixes.push(v),
-                Some(_) => {
-                    lex.undo();
-                    break;
-                }
-                None => {
-                    return Err(SyntaxError::new(
-                        lex.last().unwrap().clone(),
-                        "expect an identifier after this",
-                    ));
-                }
-            }
-        }
+        for c in last.constants() {
+            let name = c.name();
 
-        // Parse type.
-        let next = match lex.next()? {
-            Some(v) => v,
-            None => {
+            if let Some(v) = seen.iter().find(|v| v.value() == name.value()) {
                 return Err(SyntaxError::new(
-                    lex.last().unwrap().clone(),
-                    "expect an identifier after this",
+                    name.span().clone(),
+                    format!(
+                        "constant '{}' is already defined on line {}",
+                        name,
+                        v.span().line()
+                    ),
                 ));
             }
-        };
-
-        let name = match next {
-            Token::ExclamationMark(v) => {
-                if prefixes.is_empty() {
-                    TypeName::Never(v)
-                } else {
-                    return Err(SyntaxError::new(
-                        v.span().clone(),
-                        "never type cannot be a pointer",
-                    ));
-                }
-            }
-            Token::OpenParenthesis(o) => TypeName::Unit(o, lex.next_cp()?),
-            Token::Identifier(mut ident) => {
-                let mut fqtn = Vec::new();
-
-                loop {
-                    match lex.next()? {
-                        Some(Token::FullStop(v)) => {
-                            fqtn.push(Token::FullStop(v));
-                            fqtn.push(Token::Identifier(ident));
-                        }
-                        Some(_) => {
-                            lex.undo();
-                            break;
-                        }
-                        None => break,
-                    }
 
-                    ident = match lex.next()? {
-                        Some(Token::Identifier(v)) => v,
-                        Some(t) => {
-                            return Err(SyntaxError::new(t.span().clone(), "expect an identifier"))
-                        }
-                        None => {
-                            return Err(SyntaxError::new(
-                                lex.last().unwrap().clone(),
-                                "expect an identifier after this",
-                            ));
-                        }
-                    };
-                }
-
-                fqtn.push(Token::Identifier(ident));
-
-                TypeName::Ident(Path::new(fqtn))
-            }
-            t => return Err(SyntaxError::new(t.span().clone(), "invalid type")),
-        };
+            seen.push(name);
+        }
 
-        Ok(Type::new(prefixes, name))
+        Ok(())
     }
 
     fn can_define_type(&self, name: &Identifier) -> Result<(), SyntaxError> {
@@ -499,7 +735,12 @@ impl SourceFile {
                 name.span().clone(),
                 "multiple type definition in a source file",
             ));
-        } else if name.value() != self.path.file_stem().unwrap() {
+        } else if self
+            .path
+            .file_stem()
+            .filter(|&v| v == name.value())
+            .is_none()
+        {
             return Err(SyntaxError::new(
                 name.span().clone(),
                 "type name and file name must be matched",
@@ -538,3 +779,334 @@ pub enum ParseError {
     #[error("cannot parse source file")]
     ParseFailed(#[source] SyntaxError),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::SourceFile;
+    use crate::codegen::{Codegen, TargetMachine, TypeResolver};
+    use crate::ffi::llvm_init;
+    use crate::lexer::{Lexer, SyntaxError};
+    use crate::limit::MAX_PARAM_NAME;
+    use crate::pkg::{
+        Abi, Attributes, BasicType, Function, FunctionParam, PackageMeta, PackageName,
+        PackageVersion, PrimitiveTarget, Public, Representation, Type, TypeDeclaration,
+    };
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use proptest::sample::{select, Index};
+    use std::collections::{HashMap, HashSet};
+    use std::sync::Once;
+
+    /// Build `src` as the type `Test` in a library of `pkg` and returns the textual IR. The
+    /// primitive types from the standard library are included when `pkg` is `nitro`.
+    pub(super) fn build_ir(pkg: &str, src: &str) -> Result<String, SyntaxError> {
+        build_with(pkg, src, &[], |cg, _| Ok(cg.ir()))
+    }
+
+    /// Same as [`build_ir()`] but the library depends on `deps` and `f` will be called with the
+    /// codegen and `src` after it was built.
+    fn build_with<R>(
+        pkg: &str,
+        src: &str,
+        deps: &[(PackageMeta, Vec<TypeDeclaration>)],
+        f: impl FnOnce(&Codegen, &SourceFile) -> Result<R, SyntaxError>,
+    ) -> Result<R, SyntaxError> {
+        static INIT: Once = Once::new();
+
+        INIT.call_once(|| unsafe { llvm_init() });
+
+        // Parse the sources.
+        let mut sources = vec![("Test".to_owned(), src.to_owned())];
+
+        if pkg == "nitro" {
+            sources.push(("Int32".into(), include_str!("../../../std/Int32.nt").into()));
+            sources.push(("UInt".into(), include_str!("../../../std/UInt.nt").into()));
+            sources.push(("UInt8".into(), include_str!("../../../std/UInt8.nt").into()));
+            sources.push((
+                "Allocator".into(),
+                include_str!("../../../std/Allocator.nt").into(),
+            ));
+        }
+
+        let mut files = Vec::with_capacity(sources.len());
+
+        for (name, data) in sources {
+            let file = SourceFile::parse_str(format!("{name}.nt"), data, Lexer::DEFAULT_MAX_DEPTH)?;
+            files.push((name, file));
+        }
+
+        // Build the sources.
+        let name: PackageName = pkg.parse().unwrap();
+        let version: PackageVersion = "1.0.0".parse().unwrap();
+        let machine = TargetMachine::new(&PrimitiveTarget::ALL[0]).unwrap();
+        let defines = HashMap::new();
+        let mut resolver = TypeResolver::new();
+
+        resolver.populate_internal_types(files.iter().map(|(n, f)| (n, f)));
+
+        for (meta, types) in deps {
+            resolver.populate_external_types(meta, types);
+        }
+
+        let mut cg = Codegen::new(&name, &version, &machine, false, &resolver, &defines);
+
+        for (_, src) in &files {
+            cg.set_prelude(src.prelude());
+            src.build(&mut cg)?;
+        }
+
+        f(&cg, &files[0].1)
+    }
+
+    /// Sources to derive the inputs for the parser property tests.
+    const SEEDS: [&str; 4] = [
+        include_str!("../../../std/Allocator.nt"),
+        include_str!("../../../std/Int32.nt"),
+        include_str!("../../../std/UInt.nt"),
+        include_str!("../../../std/UInt8.nt"),
+    ];
+
+    #[test]
+    fn reexport_declaration() {
+        // Re-export a type that was re-exported by the other package.
+        let facade = PackageMeta::new("facade".parse().unwrap(), "1.0.0".parse().unwrap());
+        let deps = [(facade, vec![reexported()])];
+        let types = build_with("app", "@pub use facade.Bar;\n", &deps, |cg, src| {
+            src.reexports(cg)
+        })
+        .unwrap();
+
+        match types.as_slice() {
+            [(span, TypeDeclaration::Basic(t))] => {
+                assert_eq!(span.as_str(), "facade.Bar");
+                assert_eq!(t.name(), "Bar");
+                assert_eq!(t.origin(), Some(("core", 2)));
+            }
+            _ => panic!("expect a single re-export"),
+        }
+    }
+
+    #[test]
+    fn reexport_consumer() {
+        // The re-exported type is resolved from the facade but it belongs to the original package.
+        let facade = PackageMeta::new("facade".parse().unwrap(), "1.0.0".parse().unwrap());
+        let deps = [(facade, vec![reexported()])];
+        let ir = build_with(
+            "app",
+            "use facade.Bar;\n\nclass Test;\n\nimpl Test {\n    @pub\n    fn Take(v: Bar) {}\n}\n",
+            &deps,
+            |cg, _| Ok(cg.ir()),
+        )
+        .unwrap();
+
+        let sym = |pkg: &str, ver: u16| {
+            let ty = Type::Struct {
+                ptr: 0,
+                pkg: Some((pkg.into(), ver)),
+                name: "Bar".into(),
+            };
+            let params = vec![FunctionParam::new("v".into(), ty)];
+
+            Function::new("Take".into(), params, Type::Unit { ptr: 0 }, None, Abi::C)
+                .mangle(Some(("app", 1)), "Test")
+        };
+
+        assert!(ir.contains(&sym("core", 2)), "{ir}");
+        assert!(!ir.contains(&sym("facade", 1)), "{ir}");
+    }
+
+    /// Returns `Bar` from `core` v2 that was re-exported.
+    fn reexported() -> TypeDeclaration {
+        let attrs = Attributes::new(Some(Public::External), None, Some(Representation::I32));
+        let ty = BasicType::new(false, attrs, "Bar".into(), HashSet::new(), HashSet::new());
+
+        TypeDeclaration::Basic(ty.reexport("core", 2))
+    }
+
+    #[test]
+    fn format_comments() {
+        let src = "// The header.\nuse nitro.Int32; // Trailing.\n\n@pub\nclass Test;\n\nimpl Test {\n    /* Before\n       const. */\n    const MAX: Int32 = 1;\n\n    // Before attributes.\n    @pub\n    // Between attributes.\n    @entry\n    fn Main() { // After '{'.\n        // Before let.\n        let v = 1;   // After let.\n        foo(/* Inside. */ v);\n        // Before '}'.\n    }\n\n    fn Empty() {\n        // Nothing here.\n    }\n} // After impl.\n// The footer.\n";
+        let expect = "// The header.\nuse nitro.Int32; // Trailing.\n\n@pub\nclass Test;\n\nimpl Test {\n    /* Before\n       const. */\n    const MAX: Int32 = 1;\n\n    // Before attributes.\n    @pub\n    // Between attributes.\n    @entry\n    fn Main() { // After '{'.\n        // Before let.\n        let v = 1; // After let.\n        foo(v); /* Inside. */\n        // Before '}'.\n    }\n\n    fn Empty() {\n        // Nothing here.\n    }\n} // After impl.\n// The footer.\n";
+        let formatted = SourceFile::parse_str("Test.nt", src.into(), Lexer::DEFAULT_MAX_DEPTH)
+            .unwrap()
+            .format();
+
+        assert_eq!(formatted, expect);
+
+        // The output must be stable.
+        let again = SourceFile::parse_str("Test.nt", formatted, Lexer::DEFAULT_MAX_DEPTH)
+            .unwrap()
+            .format();
+
+        assert_eq!(again, expect);
+    }
+
+    #[test]
+    fn format_line_comments() {
+        // A comment must not be appended to a line that ends with a line comment.
+        let src = "class Test;\n\nimpl Test {\n    fn Run(\n        // Own line.\n        v: Int32, /* Trailing\n        block. */\n    ) {\n        v\n    }\n}\n";
+        let formatted = SourceFile::parse_str("Test.nt", src.into(), Lexer::DEFAULT_MAX_DEPTH)
+            .unwrap()
+            .format();
+
+        assert_eq!(
+            formatted,
+            "class Test;\n\nimpl Test {\n    fn Run(v: Int32) {\n        // Own line.\n        /* Trailing\n        block. */\n        v\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn format_seeds() {
+        for (i, seed) in SEEDS.into_iter().enumerate() {
+            let path = format!("{}.nt", ["Allocator", "Int32", "UInt", "UInt8"][i]);
+            let once = SourceFile::parse_str(&path, seed.into(), Lexer::DEFAULT_MAX_DEPTH)
+                .unwrap()
+                .format();
+            let twice = SourceFile::parse_str(&path, once.clone(), Lexer::DEFAULT_MAX_DEPTH)
+                .unwrap()
+                .format();
+
+            assert_eq!(once, twice, "{path}");
+        }
+    }
+
+    /// Inputs that used to panic the parser.
+    #[test]
+    fn parse_malformed() {
+        let inputs = [
+            "",
+            " \n\t",
+            "$",
+            "@",
+            "@pub(",
+            "@pub(foo",
+            "@7",
+            "@Foo",
+            "@Foo struct",
+            "@if(os == \"linux\") @if(os == \"linux\") struct Test;",
+            "use",
+            "use foo.",
+            "use .foo;",
+            "use foo..bar;",
+            "impl",
+            "impl Test {",
+            "impl Test { fn",
+            "struct Test",
+            "class Test {",
+            "fn",
+            "impl Test { fn Foo(): self.. {} }",
+        ];
+
+        for data in inputs {
+            let _ = SourceFile::parse_str("Test.nt", data.into(), Lexer::DEFAULT_MAX_DEPTH);
+            let _ = SourceFile::parse_str("Test", data.into(), Lexer::DEFAULT_MAX_DEPTH);
+        }
+    }
+
+    #[test]
+    fn param_limit() {
+        let parse = |len| {
+            let src = format!(
+                "class Test;\n\nimpl Test {{\n    fn Run({}: Int32) {{\n    }}\n}}\n",
+                "a".repeat(len)
+            );
+
+            SourceFile::parse_str("Test.nt", src, Lexer::DEFAULT_MAX_DEPTH)
+        };
+
+        parse(MAX_PARAM_NAME).unwrap();
+
+        // The name is a valid identifier so it is rejected by the parser.
+        let e = parse(MAX_PARAM_NAME + 1).err().unwrap();
+
+        assert_eq!(e.reason(), "parameter name cannot be longer than 255 bytes");
+        assert_eq!(e.span().as_str().len(), MAX_PARAM_NAME + 1);
+    }
+
+    #[test]
+    fn parse_nested() {
+        // Too deep.
+        let data = nested_if(1000);
+        let e = match SourceFile::parse_str("Test.nt", data, Lexer::DEFAULT_MAX_DEPTH) {
+            Ok(_) => panic!("the source should be too deep"),
+            Err(e) => e,
+        };
+
+        assert_eq!(e.reason(), "expression nesting too deep");
+        assert_eq!(e.span().as_str(), "if");
+
+        // Deep but still within the limit.
+        SourceFile::parse_str("Test.nt", nested_if(200), Lexer::DEFAULT_MAX_DEPTH).unwrap();
+    }
+
+    // Run with more cases with `PROPTEST_CASES=100000 cargo test parse_`.
+    proptest! {
+        #[test]
+        fn parse_arbitrary(data in any::<String>()) {
+            let _ = SourceFile::parse_str("Test.nt", data, Lexer::DEFAULT_MAX_DEPTH);
+        }
+
+        #[test]
+        fn parse_truncated(seed in 0..SEEDS.len(), end in any::<Index>()) {
+            let seed = SEEDS[seed];
+            let end = end.index(seed.len() + 1);
+
+            if seed.is_char_boundary(end) {
+                let data = seed[..end].to_owned();
+                let _ = SourceFile::parse_str("Test.nt", data, Lexer::DEFAULT_MAX_DEPTH);
+            }
+        }
+
+        #[test]
+        fn parse_tokens(tokens in vec(select(tokens()), 0..64)) {
+            let _ = SourceFile::parse_str("Test.nt", tokens.join(" "), Lexer::DEFAULT_MAX_DEPTH);
+        }
+
+        #[test]
+        fn tokenize_arbitrary(data in any::<String>()) {
+            let (tokens, errors) = Lexer::new(data.as_str()).tokenize();
+
+            for (_, s) in tokens {
+                prop_assert!(data.contains(s.as_str()));
+            }
+
+            for e in errors {
+                prop_assert!(e.span().offset() <= data.len());
+            }
+        }
+    }
+
+    /// Returns a source with `depth` nested `if` in a function body.
+    fn nested_if(depth: usize) -> String {
+        let mut data = String::from("class Test;\n\nimpl Test {\n    fn Run() {\n");
+
+        for _ in 0..depth {
+            data.push_str("if a {\n");
+        }
+
+        data.push_str("b;\n");
+
+        for _ in 0..depth {
+            data.push_str("};\n");
+        }
+
+        data.push_str("    }\n}\n");
+        data
+    }
+
+    /// Returns the tokens of the seeds so [`parse_tokens()`] produces something that resembles a
+    /// source file.
+    fn tokens() -> Vec<&'static str> {
+        let mut tokens: Vec<&str> = SEEDS
+            .iter()
+            .flat_map(|s| s.split_whitespace())
+            .chain([
+                "(", ")", "{", "}", ".", ",", ";", ":", "=", "@", "\"", "/*", "self",
+            ])
+            .collect();
+
+        tokens.sort_unstable();
+        tokens.dedup();
+        tokens
+    }
+}
@@ -1,4 +1,4 @@
-use crate::lexer::{Identifier, Span, Token};
+use crate::lexer::{Identifier, Span, SyntaxError, Token};
 use std::fmt::{Display, Formatter};
 
 /// A path of identifier (e.g. `foo.bar.Foo`).
@@ -7,31 +7,45 @@ pub(super) struct Path {
 }
 
 impl Path {
-    pub fn new(components: Vec<Token>) -> Self {
-        // Requires at least one component and the last one must be identifier.
-        assert!(!components.is_empty());
-        assert!(components.last().unwrap().is_identifier());
-
+    /// `components` must not be empty.
+    pub fn new(components: Vec<Token>) -> Result<Self, SyntaxError> {
         // Check the first component.
         let mut iter = components.iter();
-        let mut next = iter.next().unwrap();
+        let first = iter.next().unwrap();
 
-        assert!(next.is_self() || next.is_identifier());
+        if !first.is_self() && !first.is_identifier() {
+            return Err(SyntaxError::new(
+                first.span().clone(),
+                "expect an identifier or self keyword",
+            ));
+        }
 
         // Check the remaining components.
         let mut ident = false;
 
         for c in iter {
             if ident {
-                assert!(c.is_identifier());
-            } else {
-                assert!(c.is_full_stop());
+                if !c.is_identifier() {
+                    return Err(SyntaxError::new(c.span().clone(), "expect an identifier"));
+                }
+            } else if !c.is_full_stop() {
+                return Err(SyntaxError::new(c.span().clone(), "expect '.'"));
             }
 
             ident = !ident;
         }
 
-        Self { components }
+        // The last one must be identifier.
+        let last = components.last().unwrap();
+
+        if !last.is_identifier() {
+            return Err(SyntaxError::new(
+                last.span().clone(),
+                "expect an identifier after this",
+            ));
+        }
+
+        Ok(Self { components })
     }
 
     pub fn span(&self) -> Span {
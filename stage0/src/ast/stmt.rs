@@ -1,26 +1,156 @@
 use super::expr::Expression;
-use super::Attributes;
-use crate::lexer::{Identifier, LetKeyword, Lexer, SyntaxError, Token};
+use super::{Attributes, SourceWriter, Type};
+use crate::lexer::{
+    CloseCurly, CloseParenthesis, Identifier, LetKeyword, Lexer, OpenParenthesis, SyntaxError,
+    Token,
+};
+use std::fmt::{Display, Formatter};
+use std::ops::Deref;
+
+/// A block of statements (e.g. the body of a function).
+pub(super) struct Block {
+    stmts: Vec<Statement>,
+    close: Option<CloseCurly>,
+}
+
+impl Block {
+    /// Parse the statements up to and including the closing curly brace. The opening one must
+    /// already be consumed.
+    pub fn parse(lex: &mut Lexer) -> Result<Self, SyntaxError> {
+        // The nesting level is not increased here since a block is always a part of the expression
+        // (e.g. `if`), which already increased it.
+        let mut stmts = Vec::new();
+
+        while let Some(stmt) = Statement::parse(lex)? {
+            stmts.push(stmt);
+        }
+
+        Ok(Self {
+            stmts,
+            close: Some(CloseCurly::new(lex.last().unwrap().clone())),
+        })
+    }
+
+    /// Create a block from a statement without any curly brace (e.g. the body of a match arm).
+    pub fn bare(stmt: Statement) -> Self {
+        Self {
+            stmts: vec![stmt],
+            close: None,
+        }
+    }
+
+    /// Write this block including the curly braces.
+    pub fn format(&self, w: &mut SourceWriter) {
+        let close = self.close.as_ref().map(|v| v.span());
+
+        if self.stmts.is_empty() && !close.is_some_and(|v| w.has_comments(v)) {
+            w.write("{}");
+            return;
+        }
+
+        w.write("{");
+        w.newline();
+        w.indent();
+
+        for stmt in &self.stmts {
+            stmt.format(w);
+            w.newline();
+        }
+
+        if let Some(v) = close {
+            w.mark(v);
+        }
+
+        w.unindent();
+        w.write("}");
+    }
+}
+
+impl Deref for Block {
+    type Target = [Statement];
+
+    fn deref(&self) -> &Self::Target {
+        &self.stmts
+    }
+}
 
 /// A statement.
 pub(super) enum Statement {
-    Let(Let),
+    Let(Box<Let>),
     Unit(Vec<Expression>),
     Value(Vec<Expression>),
 }
 
 impl Statement {
-    pub fn parse_block(lex: &mut Lexer) -> Result<Vec<Self>, SyntaxError> {
-        let mut block = Vec::new();
-
-        while let Some(stmt) = Self::parse(lex)? {
-            block.push(stmt);
+    /// Returns the top-level expressions of this statement.
+    pub fn exprs(&self) -> &[Expression] {
+        match self {
+            Self::Let(v) => v.val(),
+            Self::Unit(v) | Self::Value(v) => v,
         }
+    }
+
+    pub fn format(&self, w: &mut SourceWriter) {
+        match self {
+            Self::Let(v) => {
+                v.attrs.format(w);
+                w.mark(v.def.span());
+                w.write(&v.def);
+                w.write(format_args!(" {}", v.pattern));
 
-        Ok(block)
+                if let Some(t) = &v.ty {
+                    w.write(": ");
+                    t.format(w);
+                }
+
+                w.write(" = ");
+                w.write_exprs(&v.val);
+                w.write(";");
+            }
+            Self::Unit(v) => {
+                if let Some(e) = v.first() {
+                    w.mark(&e.span());
+                }
+
+                w.write_exprs(v);
+                w.write(";");
+            }
+            Self::Value(v) => {
+                if let Some(e) = v.first() {
+                    w.mark(&e.span());
+                }
+
+                w.write_exprs(v);
+            }
+        }
     }
 
     fn parse(lex: &mut Lexer) -> Result<Option<Self>, SyntaxError> {
+        // This function is on the recursion path of the nested blocks so a statement that can have
+        // a large stack usage is parsed in a separate function.
+        let stmt = match lex.next()? {
+            Some(Token::CloseCurly(_)) => return Ok(None),
+            Some(Token::AttributeName(_) | Token::LetKeyword(_)) => {
+                lex.undo();
+                Self::parse_let(lex)?
+            }
+            Some(_) => {
+                lex.undo();
+                Self::parse_exprs(lex)?
+            }
+            None => {
+                return Err(SyntaxError::new(
+                    lex.last().unwrap().clone(),
+                    "expect an '}' after this",
+                ));
+            }
+        };
+
+        Ok(Some(stmt))
+    }
+
+    /// Parse a statement that starts with attributes or `let`.
+    fn parse_let(lex: &mut Lexer) -> Result<Self, SyntaxError> {
         // Parse attributes.
         let attrs = match lex.next()? {
             Some(Token::AttributeName(name)) => {
@@ -42,50 +172,34 @@ impl Statement {
 
                 attrs
             }
-            Some(v) => {
+            _ => {
                 lex.undo();
                 Attributes::default()
             }
-            None => {
-                return Err(SyntaxError::new(
-                    lex.last().unwrap().clone(),
-                    "expect an '}' after this",
-                ));
-            }
         };
 
         // Parse statement.
-        let stmt = match lex.next()? {
+        match lex.next()? {
             Some(Token::LetKeyword(def)) => {
-                let name = lex.next_ident()?;
-                lex.next_equals()?;
-
-                let exprs = Expression::parse(lex)?;
-                lex.next_semicolon()?;
-
-                Statement::Let(Let::new(attrs, def, name, exprs))
+                Ok(Statement::Let(Box::new(Let::parse(lex, attrs, def)?)))
             }
-            Some(Token::CloseCurly(_)) => return Ok(None),
-            Some(_) => {
+            _ => {
                 lex.undo();
+                Self::parse_exprs(lex)
+            }
+        }
+    }
 
-                let exprs = Expression::parse(lex)?;
-
-                match lex.next()? {
-                    Some(Token::Semicolon(_)) => Statement::Unit(exprs),
-                    Some(Token::CloseCurly(_)) => {
-                        lex.undo();
-                        Statement::Value(exprs)
-                    }
-                    Some(t) => return Err(SyntaxError::new(t.span().clone(), "expect ';'")),
-                    None => {
-                        return Err(SyntaxError::new(
-                            lex.last().unwrap().clone(),
-                            "expect an '}' after this",
-                        ));
-                    }
-                }
+    /// Parse a statement that is not `let`.
+    fn parse_exprs(lex: &mut Lexer) -> Result<Self, SyntaxError> {
+        let exprs = Expression::parse(lex)?;
+        let stmt = match lex.next()? {
+            Some(Token::Semicolon(_)) => Statement::Unit(exprs),
+            Some(Token::CloseCurly(_)) => {
+                lex.undo();
+                Statement::Value(exprs)
             }
+            Some(t) => return Err(SyntaxError::new(t.span().clone(), "expect ';'")),
             None => {
                 return Err(SyntaxError::new(
                     lex.last().unwrap().clone(),
@@ -94,7 +208,7 @@ impl Statement {
             }
         };
 
-        Ok(Some(stmt))
+        Ok(stmt)
     }
 }
 
@@ -102,17 +216,290 @@ impl Statement {
 pub(super) struct Let {
     attrs: Attributes,
     def: LetKeyword,
-    var: Identifier,
+    pattern: Pattern,
+    ty: Option<Type>,
     val: Vec<Expression>,
 }
 
 impl Let {
-    pub fn new(attrs: Attributes, def: LetKeyword, var: Identifier, val: Vec<Expression>) -> Self {
+    pub fn new(
+        attrs: Attributes,
+        def: LetKeyword,
+        pattern: Pattern,
+        ty: Option<Type>,
+        val: Vec<Expression>,
+    ) -> Self {
         Self {
             attrs,
             def,
-            var,
+            pattern,
+            ty,
             val,
         }
     }
+
+    /// Parse a let statement after `def`.
+    fn parse(lex: &mut Lexer, attrs: Attributes, def: LetKeyword) -> Result<Self, SyntaxError> {
+        let pattern = Pattern::parse(lex)?;
+        let ty = match lex.next()? {
+            Some(Token::Colon(_)) => Some(Type::parse(lex)?),
+            Some(_) => {
+                lex.undo();
+                None
+            }
+            None => None,
+        };
+
+        lex.next_equals()?;
+
+        let val = Expression::parse(lex)?;
+
+        lex.next_semicolon()?;
+
+        Ok(Self::new(attrs, def, pattern, ty, val))
+    }
+
+    pub fn pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+
+    /// Returns all variables that are introduced by this statement.
+    pub fn vars(&self) -> Vec<&Identifier> {
+        let mut list = Vec::new();
+        self.pattern.collect_vars(&mut list);
+        list
+    }
+
+    /// Returns the type annotation (e.g. `Int32` in `let x: Int32 = 0;`).
+    pub fn ty(&self) -> Option<&Type> {
+        self.ty.as_ref()
+    }
+
+    pub fn val(&self) -> &[Expression] {
+        &self.val
+    }
+}
+
+/// A pattern on the left side of [`Let`].
+pub(super) enum Pattern {
+    Var(Identifier),
+    Tuple(OpenParenthesis, Vec<Pattern>, CloseParenthesis),
+}
+
+impl Pattern {
+    fn parse(lex: &mut Lexer) -> Result<Self, SyntaxError> {
+        let open = match lex.next()? {
+            Some(Token::Identifier(v)) => return Ok(Self::Var(v)),
+            Some(Token::OpenParenthesis(v)) => v,
+            Some(t) => {
+                return Err(SyntaxError::new(
+                    t.span().clone(),
+                    "expect an identifier or '('",
+                ));
+            }
+            None => {
+                return Err(SyntaxError::new(
+                    lex.last().unwrap().clone(),
+                    "expect an identifier after this",
+                ));
+            }
+        };
+
+        // Parse a tuple.
+        let mut elems = Vec::new();
+
+        lex.enter()?;
+
+        let close = loop {
+            elems.push(Self::parse(lex)?);
+
+            match lex.next()? {
+                Some(Token::Comma(_)) => {}
+                Some(Token::CloseParenthesis(v)) => break v,
+                Some(t) => return Err(SyntaxError::new(t.span().clone(), "expect an ')'")),
+                None => {
+                    return Err(SyntaxError::new(
+                        lex.last().unwrap().clone(),
+                        "expect an ')' after this",
+                    ));
+                }
+            }
+        };
+
+        lex.leave();
+
+        if elems.len() < 2 {
+            return Err(SyntaxError::new(
+                open.span() + close.span(),
+                "a tuple must have at least two elements",
+            ));
+        }
+
+        Ok(Self::Tuple(open, elems, close))
+    }
+
+    /// Check if `val` can be destructured by this pattern. Only a tuple construction is checked
+    /// since the type of other expressions is not known here.
+    pub fn check_value(&self, val: &[Expression]) -> Result<(), SyntaxError> {
+        let (pats, tuple) = match (self, val) {
+            (Self::Tuple(_, p, _), [Expression::Tuple(t)]) => (p, t),
+            _ => return Ok(()),
+        };
+
+        if pats.len() != tuple.elems().len() {
+            return Err(SyntaxError::new(
+                tuple.span(),
+                format!("expect a tuple with {} elements", pats.len()),
+            ));
+        }
+
+        for (p, v) in pats.iter().zip(tuple.elems()) {
+            p.check_value(v)?;
+        }
+
+        Ok(())
+    }
+
+    /// Check if a value of `ty` can be destructured by this pattern.
+    pub fn check_type(&self, ty: &Type) -> Result<(), SyntaxError> {
+        let (o, pats, c) = match self {
+            Self::Var(_) => return Ok(()),
+            Self::Tuple(o, p, c) => (o, p, c),
+        };
+
+        match ty.as_tuple() {
+            Some(t) if t.len() == pats.len() => {
+                for (p, t) in pats.iter().zip(t) {
+                    p.check_type(t)?;
+                }
+
+                Ok(())
+            }
+            _ => Err(SyntaxError::new(
+                o.span() + c.span(),
+                format!(
+                    "the pattern does not match the type annotation on line {}",
+                    ty.name().span().line()
+                ),
+            )),
+        }
+    }
+
+    fn collect_vars<'a>(&'a self, list: &mut Vec<&'a Identifier>) {
+        match self {
+            Self::Var(v) => list.push(v),
+            Self::Tuple(_, v, _) => {
+                for p in v {
+                    p.collect_vars(list);
+                }
+            }
+        }
+    }
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Var(v) => v.fmt(f),
+            Self::Tuple(o, v, c) => {
+                o.fmt(f)?;
+
+                for (i, p) in v.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(", ")?;
+                    }
+
+                    p.fmt(f)?;
+                }
+
+                c.fmt(f)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Let, Statement};
+    use crate::ast::expr::Expression;
+    use crate::ast::SourceWriter;
+    use crate::lexer::Lexer;
+
+    fn parse(src: &str) -> Box<Let> {
+        match Statement::parse(&mut Lexer::new(src)).unwrap() {
+            Some(Statement::Let(v)) => v,
+            _ => panic!("expect a let statement"),
+        }
+    }
+
+    fn format(stmt: Statement) -> String {
+        let mut w = SourceWriter::new(Vec::new());
+        stmt.format(&mut w);
+        w.into_string()
+    }
+
+    #[test]
+    fn let_annotation() {
+        let stmt = parse("let v: Int32 = 1;");
+        let ty = stmt.ty().unwrap();
+
+        assert_eq!(stmt.pattern().to_string(), "v");
+        assert_eq!(ty.as_value().unwrap().to_string(), "Int32");
+        assert!(!ty.has_pointer());
+        assert!(matches!(stmt.val(), [Expression::Unsigned(_)]));
+        assert_eq!(format(Statement::Let(stmt)), "let v: Int32 = 1;");
+
+        let stmt = parse("let v = 1;");
+
+        assert!(stmt.ty().is_none());
+        assert_eq!(format(Statement::Let(stmt)), "let v = 1;");
+    }
+
+    #[test]
+    fn let_pointer() {
+        let stmt = parse("let p: **nitro.UInt8 = null;");
+        let ty = stmt.ty().unwrap();
+
+        assert!(ty.has_pointer());
+        assert!(ty.as_value().is_none());
+        assert_eq!(format(Statement::Let(stmt)), "let p: **nitro.UInt8 = null;");
+
+        let stmt = parse("let (p, v): (*Int32, UInt8) = (null, 0);");
+        let ty = stmt.ty().unwrap();
+
+        assert!(ty.has_pointer());
+        assert_eq!(ty.as_tuple().unwrap().len(), 2);
+        assert_eq!(
+            format(Statement::Let(stmt)),
+            "let (p, v): (*Int32, UInt8) = (null, 0);"
+        );
+    }
+
+    #[test]
+    fn let_reserved_attribute() {
+        let e = Statement::parse(&mut Lexer::new("@stack let v = 1;"))
+            .err()
+            .unwrap();
+
+        assert_eq!(e.span().as_str(), "@stack");
+        assert_eq!(
+            e.reason(),
+            "an attribute begin with a lower case is a reserved name"
+        );
+    }
+
+    #[test]
+    fn let_error() {
+        let e = Statement::parse(&mut Lexer::new("let v: = 1;"))
+            .err()
+            .unwrap();
+
+        assert_eq!(e.span().as_str(), "=");
+
+        let e = Statement::parse(&mut Lexer::new("let v: Int32 1;"))
+            .err()
+            .unwrap();
+
+        assert_eq!(e.span().as_str(), "1");
+    }
 }
@@ -1,10 +1,11 @@
 use super::bt::BasicType;
-use super::{Path, SourceFile, TypeDefinition, Use};
+use super::{Path, SourceFile, SourceWriter, TypeDefinition, Use, PRELUDE};
 use crate::codegen::{
-    Codegen, LlvmI32, LlvmPtr, LlvmType, LlvmU64, LlvmU8, LlvmVoid, ResolvedType,
+    Codegen, LlvmI32, LlvmPtr, LlvmTuple, LlvmType, LlvmU64, LlvmU8, LlvmVoid, ResolvedType,
+    TypeResolver,
 };
 use crate::lexer::{
-    Asterisk, CloseParenthesis, ExclamationMark, OpenParenthesis, Span, SyntaxError,
+    Asterisk, CloseParenthesis, ExclamationMark, Lexer, OpenParenthesis, Span, SyntaxError, Token,
 };
 use crate::pkg::{Representation, TypeDeclaration};
 
@@ -19,11 +20,249 @@ impl Type {
         Self { prefixes, name }
     }
 
+    pub fn parse(lex: &mut Lexer) -> Result<Self, SyntaxError> {
+        // Parse pointer prefix.
+        let mut prefixes = Vec::new();
+
+        loop {
+            match lex.next()? {
+                Some(Token::Asterisk(v)) => {
+                    lex.enter()?;
+                    prefixes.push(v);
+                }
+                Some(_) => {
+                    lex.undo();
+                    break;
+                }
+                None => {
+                    return Err(SyntaxError::new(
+                        lex.last().unwrap().clone(),
+                        "expect an identifier after this",
+                    ));
+                }
+            }
+        }
+
+        for _ in &prefixes {
+            lex.leave();
+        }
+
+        // Parse type.
+        let next = match lex.next()? {
+            Some(v) => v,
+            None => {
+                return Err(SyntaxError::new(
+                    lex.last().unwrap().clone(),
+                    "expect an identifier after this",
+                ));
+            }
+        };
+
+        let name = match next {
+            Token::ExclamationMark(v) => {
+                if prefixes.is_empty() {
+                    TypeName::Never(v)
+                } else {
+                    return Err(SyntaxError::new(
+                        v.span().clone(),
+                        "never type cannot be a pointer",
+                    ));
+                }
+            }
+            Token::OpenParenthesis(o) => match lex.next()? {
+                Some(Token::CloseParenthesis(c)) => TypeName::Unit(o, c),
+                Some(_) => {
+                    lex.undo();
+                    Self::parse_tuple(lex, o)?
+                }
+                None => {
+                    return Err(SyntaxError::new(
+                        o.span().clone(),
+                        "expect an ')' after this",
+                    ));
+                }
+            },
+            Token::Identifier(mut ident) => {
+                let mut fqtn = Vec::new();
+
+                loop {
+                    match lex.next()? {
+                        Some(Token::FullStop(v)) => {
+                            fqtn.push(Token::Identifier(ident));
+                            fqtn.push(Token::FullStop(v));
+                        }
+                        Some(_) => {
+                            lex.undo();
+                            break;
+                        }
+                        None => break,
+                    }
+
+                    ident = match lex.next()? {
+                        Some(Token::Identifier(v)) => v,
+                        Some(t) => {
+                            return Err(SyntaxError::new(t.span().clone(), "expect an identifier"))
+                        }
+                        None => {
+                            return Err(SyntaxError::new(
+                                lex.last().unwrap().clone(),
+                                "expect an identifier after this",
+                            ));
+                        }
+                    };
+                }
+
+                fqtn.push(Token::Identifier(ident));
+
+                TypeName::Ident(Path::new(fqtn)?)
+            }
+            t => return Err(SyntaxError::new(t.span().clone(), "invalid type")),
+        };
+
+        Ok(Self::new(prefixes, name))
+    }
+
+    /// Parse the elements of a tuple (e.g. `Int32, Int32)` in `(Int32, Int32)`).
+    fn parse_tuple(lex: &mut Lexer, open: OpenParenthesis) -> Result<TypeName, SyntaxError> {
+        let mut elems = Vec::new();
+
+        lex.enter()?;
+
+        let close = loop {
+            let ty = Self::parse(lex)?;
+
+            match &ty.name {
+                TypeName::Unit(o, c) if ty.prefixes.is_empty() => {
+                    return Err(SyntaxError::new(
+                        o.span() + c.span(),
+                        "unit type cannot be an element of a tuple",
+                    ));
+                }
+                TypeName::Never(v) => {
+                    return Err(SyntaxError::new(
+                        v.span().clone(),
+                        "never type cannot be an element of a tuple",
+                    ));
+                }
+                _ => elems.push(ty),
+            }
+
+            match lex.next()? {
+                Some(Token::Comma(_)) => {}
+                Some(Token::CloseParenthesis(v)) => break v,
+                Some(t) => return Err(SyntaxError::new(t.span().clone(), "expect an ')'")),
+                None => {
+                    return Err(SyntaxError::new(
+                        lex.last().unwrap().clone(),
+                        "expect an ')' after this",
+                    ));
+                }
+            }
+        };
+
+        lex.leave();
+
+        if elems.len() < 2 {
+            return Err(SyntaxError::new(
+                open.span() + close.span(),
+                "a tuple must have at least two elements",
+            ));
+        }
+
+        Ok(TypeName::Tuple(open, elems, close))
+    }
+
     pub fn name(&self) -> &TypeName {
         &self.name
     }
 
-    pub fn build<'a, 'b: 'a, 'c, U: IntoIterator<Item = &'c Use>>(
+    /// Returns the elements if this type is a tuple that is not a pointer.
+    pub fn as_tuple(&self) -> Option<&[Type]> {
+        match &self.name {
+            TypeName::Tuple(_, v, _) if self.prefixes.is_empty() => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if this type is a pointer or a tuple that contains a pointer.
+    pub fn has_pointer(&self) -> bool {
+        !self.prefixes.is_empty()
+            || self
+                .as_tuple()
+                .is_some_and(|v| v.iter().any(Self::has_pointer))
+    }
+
+    /// Returns the name of the type if it is not a pointer or a built-in type.
+    pub fn as_value(&self) -> Option<&Path> {
+        match &self.name {
+            TypeName::Ident(v) if self.prefixes.is_empty() => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the reason if this type cannot be used on an extern function. A pointer, the unit,
+    /// the never type and a struct with `@repr` can be used as-is in C.
+    pub fn check_ffi<'a, U: IntoIterator<Item = &'a Use>>(
+        &self,
+        cx: &Codegen,
+        uses: U,
+    ) -> Result<Option<&'static str>, SyntaxError> {
+        if !self.prefixes.is_empty() {
+            return Ok(None);
+        }
+
+        let n = match &self.name {
+            TypeName::Unit(_, _) | TypeName::Never(_) => return Ok(None),
+            TypeName::Ident(v) => v,
+            TypeName::Tuple(_, _, _) => {
+                return Ok(Some("a tuple cannot be used on an extern function"));
+            }
+        };
+
+        let (class, repr) = match Self::resolve(cx, uses, n)?.1 {
+            ResolvedType::Internal(s) => match s.ty().unwrap() {
+                TypeDefinition::Basic(t) => (t.is_ref(), t.attrs().repr().is_some()),
+            },
+            ResolvedType::External((_, t)) => match t {
+                TypeDeclaration::Basic(t) => (t.is_class(), t.attrs().repr().is_some()),
+            },
+        };
+
+        Ok(if class {
+            Some("a class cannot be passed by value to an extern function")
+        } else if !repr {
+            Some("a struct without @repr cannot be used on an extern function")
+        } else {
+            None
+        })
+    }
+
+    pub fn format(&self, w: &mut SourceWriter) {
+        for p in &self.prefixes {
+            w.write(p);
+        }
+
+        match &self.name {
+            TypeName::Unit(o, c) => w.write(format_args!("{o}{c}")),
+            TypeName::Never(v) => w.write(v),
+            TypeName::Ident(v) => w.write(v),
+            TypeName::Tuple(o, v, c) => {
+                w.write(o);
+
+                for (i, t) in v.iter().enumerate() {
+                    if i != 0 {
+                        w.write(", ");
+                    }
+
+                    t.format(w);
+                }
+
+                w.write(c);
+            }
+        }
+    }
+
+    pub fn build<'a, 'b: 'a, 'c, U: IntoIterator<Item = &'c Use> + Clone>(
         &self,
         cx: &'a Codegen<'b>,
         uses: U,
@@ -40,6 +279,15 @@ impl Type {
                     ResolvedType::External((_, t)) => Self::build_external_type(cx, &n, t),
                 }
             }
+            TypeName::Tuple(_, v, _) => {
+                let mut elems = Vec::with_capacity(v.len());
+
+                for t in v {
+                    elems.push(t.build(cx, uses.clone())?.unwrap());
+                }
+
+                LlvmType::Tuple(LlvmTuple::new(cx, elems))
+            }
         };
 
         // Resolve pointers.
@@ -50,7 +298,7 @@ impl Type {
         Ok(Some(ty))
     }
 
-    pub fn to_external<'a, 'b, U: IntoIterator<Item = &'a Use>>(
+    pub fn to_external<'a, 'b, U: IntoIterator<Item = &'a Use> + Clone>(
         &self,
         cx: &Codegen<'b>,
         uses: U,
@@ -61,6 +309,15 @@ impl Type {
         let ty = match &self.name {
             TypeName::Unit(_, _) => Type::Unit { ptr },
             TypeName::Never(_) => Type::Never,
+            TypeName::Tuple(_, v, _) => {
+                let mut elems = Vec::with_capacity(v.len());
+
+                for t in v {
+                    elems.push(t.to_external(cx, uses.clone())?);
+                }
+
+                Type::Tuple { ptr, elems }
+            }
             TypeName::Ident(n) => {
                 let (n, t) = Self::resolve(cx, uses, n)?;
 
@@ -81,11 +338,18 @@ impl Type {
                         }
                     }
                     ResolvedType::External((p, t)) => {
-                        let pkg = Some((p.name().as_str().to_owned(), p.version().major()));
                         let name = t.name().to_owned();
 
                         match t {
                             TypeDeclaration::Basic(t) => {
+                                // A re-exported type is still belong to its original package.
+                                let pkg = match t.origin() {
+                                    Some((n, v)) => Some((n.to_owned(), v)),
+                                    None => {
+                                        Some((p.name().as_str().to_owned(), p.version().major()))
+                                    }
+                                };
+
                                 if t.is_class() {
                                     Type::Class { ptr, pkg, name }
                                 } else {
@@ -159,7 +423,27 @@ impl Type {
         }
     }
 
-    fn build_primitive_struct<'a, 'b: 'a>(
+    /// Build the LLVM type of `ty` if it is a numeric type (a struct with `@repr`).
+    pub fn build_numeric<'a, 'b: 'a>(
+        cx: &'a Codegen<'b>,
+        ty: &ResolvedType,
+    ) -> Option<LlvmType<'a, 'b>> {
+        let repr = match ty {
+            ResolvedType::Internal(v) => match v.ty().unwrap() {
+                TypeDefinition::Basic(v) if !v.is_ref() => v.attrs().repr().map(|v| v.1),
+                _ => None,
+            },
+            ResolvedType::External((_, t)) => match t {
+                TypeDeclaration::Basic(v) if !v.is_class() => v.attrs().repr(),
+                _ => None,
+            },
+        };
+
+        repr.map(|v| Self::build_primitive_struct(cx, v))
+            .filter(|t| t.is_numeric())
+    }
+
+    pub fn build_primitive_struct<'a, 'b: 'a>(
         cg: &'a Codegen<'b>,
         repr: Representation,
     ) -> LlvmType<'a, 'b> {
@@ -173,10 +457,25 @@ impl Type {
         }
     }
 
-    fn resolve<'a, 'b, U: IntoIterator<Item = &'a Use>>(
+    pub fn resolve<'a, 'b, U: IntoIterator<Item = &'a Use>>(
         cg: &Codegen<'b>,
         uses: U,
         name: &Path,
+    ) -> Result<(String, &'b ResolvedType<'b>), SyntaxError> {
+        Self::resolve_in(cg.resolver(), cg.namespace(), cg.prelude(), uses, name)
+    }
+
+    /// Same as [`Self::resolve()`] but resolve the local name in `ns` instead of the namespace of
+    /// the source file that is being built. `prelude` is `false` if that source file has
+    /// `@no_prelude`.
+    ///
+    /// A local name is searched from the use declarations, the types in `ns` then [`PRELUDE`].
+    pub fn resolve_in<'a, 'b, U: IntoIterator<Item = &'a Use>>(
+        resolver: &'b TypeResolver<'b>,
+        ns: &str,
+        prelude: bool,
+        uses: U,
+        name: &Path,
     ) -> Result<(String, &'b ResolvedType<'b>), SyntaxError> {
         // Resolve full name.
         let (name, span) = match name.as_local() {
@@ -202,12 +501,25 @@ impl Type {
                 match found {
                     Some(v) => (v.name().to_string(), v.name().span()),
                     None => {
-                        let fqtn = if cg.namespace().is_empty() {
+                        let fqtn = if ns.is_empty() {
                             format!("self.{}", name)
                         } else {
-                            format!("self.{}.{}", cg.namespace(), name)
+                            format!("self.{}.{}", ns, name)
                         };
 
+                        // The prelude is used only when the name is not a local type and the
+                        // standard library is available.
+                        if resolver.resolve(&fqtn).is_none()
+                            && prelude
+                            && PRELUDE.contains(&name.value())
+                        {
+                            let fqtn = format!("nitro.{}", name);
+
+                            if let Some(ty) = resolver.resolve(&fqtn) {
+                                return Ok((fqtn, ty));
+                            }
+                        }
+
                         (fqtn, name.span().clone())
                     }
                 }
@@ -216,7 +528,7 @@ impl Type {
         };
 
         // Resolve type.
-        match cg.resolver().resolve(&name) {
+        match resolver.resolve(&name) {
             Some(ty) => Ok((name, ty)),
             None => Err(SyntaxError::new(span, "undefined type")),
         }
@@ -228,6 +540,7 @@ pub(super) enum TypeName {
     Unit(OpenParenthesis, CloseParenthesis),
     Never(ExclamationMark),
     Ident(Path),
+    Tuple(OpenParenthesis, Vec<Type>, CloseParenthesis),
 }
 
 impl TypeName {
@@ -236,6 +549,112 @@ impl TypeName {
             TypeName::Unit(o, c) => o.span() + c.span(),
             TypeName::Never(v) => v.span().clone(),
             TypeName::Ident(v) => v.span(),
+            TypeName::Tuple(o, _, c) => o.span() + c.span(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Type;
+    use crate::ast::SourceFile;
+    use crate::codegen::TypeResolver;
+    use crate::lexer::{Lexer, SyntaxError};
+    use crate::pkg::{Attributes, BasicType, PackageMeta, TypeDeclaration};
+    use std::collections::HashSet;
+
+    #[test]
+    fn prelude() {
+        let test = "class Test;\n";
+
+        assert_eq!(resolve(&[("Test", test)], "Int32").unwrap(), "nitro.Int32");
+        assert_eq!(resolve(&[("Test", test)], "UInt8").unwrap(), "nitro.UInt8");
+
+        // A type that is not in the prelude.
+        let e = resolve(&[("Test", test)], "Foo").unwrap_err();
+
+        assert_eq!(e.reason(), "undefined type");
+    }
+
+    #[test]
+    fn prelude_explicit_use() {
+        let test = "use foo.Int32;\n\nclass Test;\n";
+
+        assert_eq!(resolve(&[("Test", test)], "Int32").unwrap(), "foo.Int32");
+
+        // A rename to the name in the prelude.
+        let test = "use foo.Int32 as UInt8;\n\nclass Test;\n";
+
+        assert_eq!(resolve(&[("Test", test)], "UInt8").unwrap(), "foo.Int32");
+    }
+
+    #[test]
+    fn prelude_local_type() {
+        let files = [("Test", "class Test;\n"), ("Int32", "class Int32;\n")];
+
+        assert_eq!(resolve(&files, "Int32").unwrap(), "self.Int32");
+        assert_eq!(resolve(&files, "UInt8").unwrap(), "nitro.UInt8");
+    }
+
+    #[test]
+    fn no_prelude() {
+        let test = "@no_prelude\nclass Test;\n";
+        let e = resolve(&[("Test", test)], "Int32").unwrap_err();
+
+        assert_eq!(e.reason(), "undefined type");
+        assert_eq!(e.span().as_str(), "Int32");
+
+        // An explicit use still works.
+        let test = "use nitro.Int32;\n\n@no_prelude\nclass Test;\n";
+
+        assert_eq!(resolve(&[("Test", test)], "Int32").unwrap(), "nitro.Int32");
+    }
+
+    /// Resolve `name` from the source file `Test` in `files`. The dependencies are `nitro` with the
+    /// types in the prelude and `foo` with `Int32`.
+    fn resolve(files: &[(&str, &str)], name: &str) -> Result<String, SyntaxError> {
+        let files: Vec<(String, SourceFile)> = files
+            .iter()
+            .map(|&(n, d)| {
+                let path = format!("{n}.nt");
+                let file = SourceFile::parse_str(path, d.into(), Lexer::DEFAULT_MAX_DEPTH).unwrap();
+
+                (n.to_owned(), file)
+            })
+            .collect();
+        let nitro = PackageMeta::new("nitro".parse().unwrap(), "1.0.0".parse().unwrap());
+        let foo = PackageMeta::new("foo".parse().unwrap(), "1.0.0".parse().unwrap());
+        let nitro_types = ["Int32", "UInt", "UInt8"].map(declaration);
+        let foo_types = [declaration("Int32")];
+        let mut resolver = TypeResolver::new();
+
+        resolver.populate_internal_types(files.iter().map(|(n, f)| (n, f)));
+        resolver.populate_external_types(&nitro, &nitro_types);
+        resolver.populate_external_types(&foo, &foo_types);
+
+        // Resolve.
+        let src = &files.iter().find(|(n, _)| n == "Test").unwrap().1;
+        let ty = Type::parse(&mut Lexer::new(name)).unwrap();
+
+        Type::resolve_in(
+            &resolver,
+            "",
+            src.prelude(),
+            src.uses(),
+            ty.as_value().unwrap(),
+        )
+        .map(|v| v.0)
+    }
+
+    fn declaration(name: &str) -> TypeDeclaration {
+        let attrs = Attributes::new(None, None, None);
+
+        TypeDeclaration::Basic(BasicType::new(
+            false,
+            attrs,
+            name.into(),
+            HashSet::new(),
+            HashSet::new(),
+        ))
+    }
+}
@@ -1,5 +1,5 @@
-use super::{Attributes, Path};
-use crate::lexer::{Identifier, Lexer, SyntaxError, Token, UseKeyword};
+use super::{Attributes, Path, SourceWriter};
+use crate::lexer::{AttributeName, Identifier, Lexer, SyntaxError, Token, UseKeyword};
 
 /// A `use` declaration.
 pub(super) struct Use {
@@ -55,6 +55,8 @@ impl Use {
         }
 
         // Get remaining path.
+        let mut rename = None;
+
         loop {
             let next = match lex.next()? {
                 Some(v) => v,
@@ -81,6 +83,11 @@ impl Use {
                         }
                     }
                 }
+                Token::AsKeyword(_) => {
+                    rename = Some(lex.next_ident()?);
+                    lex.next_semicolon()?;
+                    break;
+                }
                 Token::Semicolon(_) => break,
                 t => return Err(SyntaxError::new(t.span(), "expect ';'")),
             }
@@ -89,16 +96,74 @@ impl Use {
         Ok(Self {
             attrs,
             def,
-            name: Path::new(name),
-            rename: None,
+            name: Path::new(name)?,
+            rename,
         })
     }
 
+    pub fn attrs(&self) -> &Attributes {
+        &self.attrs
+    }
+
     pub fn name(&self) -> &Path {
         &self.name
     }
 
+    /// Returns the attribute that make this declaration a re-export (either `@reexport` or `@pub`).
+    pub fn reexport(&self) -> Option<&AttributeName> {
+        self.attrs
+            .reexport()
+            .or_else(|| self.attrs.public().map(|(n, _)| n))
+    }
+
+    pub fn format(&self, w: &mut SourceWriter) {
+        self.attrs.format(w);
+        w.mark(self.def.span());
+        w.write(format_args!("{} {}", self.def, self.name));
+
+        if let Some(v) = &self.rename {
+            w.write(format_args!(" as {v}"));
+        }
+
+        w.write(";");
+        w.newline();
+    }
+
     pub fn rename(&self) -> Option<&Identifier> {
         self.rename.as_ref()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::SourceFile;
+    use crate::lexer::Lexer;
+
+    #[test]
+    fn reexport() {
+        let src = "@pub\nuse foo.Bar;\n@reexport\nuse foo.baz.Qux;\nuse foo.Quux;\n";
+        let file = SourceFile::parse_str("Test.nt", src.into(), Lexer::DEFAULT_MAX_DEPTH).unwrap();
+        let uses: Vec<(String, Option<&str>)> = file
+            .uses()
+            .iter()
+            .map(|u| {
+                (
+                    u.name().to_string(),
+                    u.reexport().map(|v| v.span().as_str()),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            uses,
+            [
+                ("foo.Bar".into(), Some("@pub")),
+                ("foo.baz.Qux".into(), Some("@reexport")),
+                ("foo.Quux".into(), None)
+            ]
+        );
+        assert!(file.has_reexports());
+        assert!(!file.has_type());
+        assert_eq!(file.format(), src);
+    }
+}
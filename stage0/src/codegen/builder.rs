@@ -1,8 +1,11 @@
-use super::{BasicBlock, Codegen};
+use super::{BasicBlock, Codegen, LlvmType};
 use crate::ffi::{
-    llvm_builder_append_block, llvm_builder_call, llvm_builder_dispose, llvm_builder_new,
-    llvm_builder_ret, llvm_builder_ret_void,
+    llvm_builder_append_block, llvm_builder_asm, llvm_builder_call, llvm_builder_cast,
+    llvm_builder_cond_br, llvm_builder_dispose, llvm_builder_gep8, llvm_builder_icmp_ne,
+    llvm_builder_load, llvm_builder_new, llvm_builder_ptr_diff, llvm_builder_ret,
+    llvm_builder_ret_void, llvm_builder_store, llvm_builder_unreachable,
 };
+use std::ffi::CStr;
 use std::marker::PhantomData;
 
 /// Encapsulate an LLVM IR builder.
@@ -23,6 +26,11 @@ impl<'a, 'b: 'a> Builder<'a, 'b> {
         }
     }
 
+    /// Move the insertion point to the end of `block`.
+    pub fn position(&mut self, block: &mut BasicBlock<'a, 'b>) {
+        unsafe { llvm_builder_append_block(self.raw, block.as_raw()) };
+    }
+
     pub fn call(
         &mut self,
         func: *mut crate::ffi::LlvmFunction,
@@ -31,6 +39,27 @@ impl<'a, 'b: 'a> Builder<'a, 'b> {
         unsafe { llvm_builder_call(self.raw, func, args.as_ptr(), args.len()) }
     }
 
+    /// Call an inline assembly that does not produce any value. `constraints` is in the LLVM
+    /// format and `intel` indicates whether `inst` is in the Intel syntax.
+    pub fn asm(
+        &mut self,
+        inst: &CStr,
+        constraints: &CStr,
+        args: &[*mut crate::ffi::LlvmValue],
+        intel: bool,
+    ) -> *mut crate::ffi::LlvmCall {
+        unsafe {
+            llvm_builder_asm(
+                self.raw,
+                inst.as_ptr(),
+                constraints.as_ptr(),
+                args.as_ptr(),
+                args.len(),
+                intel,
+            )
+        }
+    }
+
     pub fn ret_void(&mut self) -> *mut crate::ffi::LlvmReturn {
         unsafe { llvm_builder_ret_void(self.raw) }
     }
@@ -38,6 +67,73 @@ impl<'a, 'b: 'a> Builder<'a, 'b> {
     pub fn ret(&mut self, v: *mut crate::ffi::LlvmValue) -> *mut crate::ffi::LlvmReturn {
         unsafe { llvm_builder_ret(self.raw, v) }
     }
+
+    pub fn cond_br(
+        &mut self,
+        cond: *mut crate::ffi::LlvmValue,
+        then: &BasicBlock<'a, 'b>,
+        otherwise: &BasicBlock<'a, 'b>,
+    ) -> *mut crate::ffi::LlvmBranch {
+        unsafe { llvm_builder_cond_br(self.raw, cond, then.as_raw(), otherwise.as_raw()) }
+    }
+
+    pub fn unreachable(&mut self) -> *mut crate::ffi::LlvmUnreachable {
+        unsafe { llvm_builder_unreachable(self.raw) }
+    }
+
+    pub fn icmp_ne(
+        &mut self,
+        lhs: *mut crate::ffi::LlvmValue,
+        rhs: *mut crate::ffi::LlvmValue,
+    ) -> *mut crate::ffi::LlvmValue {
+        unsafe { llvm_builder_icmp_ne(self.raw, lhs, rhs) }
+    }
+
+    /// Convert `v` from `from` to `to`. The instruction (e.g. `trunc` or `sext`) is chosen from
+    /// the size and signedness of both types.
+    pub fn cast(
+        &mut self,
+        v: *mut crate::ffi::LlvmValue,
+        from: &LlvmType<'a, 'b>,
+        to: &LlvmType<'a, 'b>,
+    ) -> *mut crate::ffi::LlvmValue {
+        unsafe { llvm_builder_cast(self.raw, v, from.is_signed(), to.as_raw(), to.is_signed()) }
+    }
+
+    /// Offset `ptr` by `off` bytes.
+    pub fn gep8(
+        &mut self,
+        ptr: *mut crate::ffi::LlvmValue,
+        off: *mut crate::ffi::LlvmValue,
+    ) -> *mut crate::ffi::LlvmValue {
+        unsafe { llvm_builder_gep8(self.raw, ptr, off) }
+    }
+
+    /// Returns the distance in bytes from `rhs` to `lhs` as an integer of `ty`.
+    pub fn ptr_diff(
+        &mut self,
+        lhs: *mut crate::ffi::LlvmValue,
+        rhs: *mut crate::ffi::LlvmValue,
+        ty: &LlvmType<'a, 'b>,
+    ) -> *mut crate::ffi::LlvmValue {
+        unsafe { llvm_builder_ptr_diff(self.raw, lhs, rhs, ty.as_raw()) }
+    }
+
+    pub fn load(
+        &mut self,
+        ty: &LlvmType<'a, 'b>,
+        ptr: *mut crate::ffi::LlvmValue,
+    ) -> *mut crate::ffi::LlvmValue {
+        unsafe { llvm_builder_load(self.raw, ty.as_raw(), ptr) }
+    }
+
+    pub fn store(
+        &mut self,
+        v: *mut crate::ffi::LlvmValue,
+        ptr: *mut crate::ffi::LlvmValue,
+    ) -> *mut crate::ffi::LlvmStore {
+        unsafe { llvm_builder_store(self.raw, v, ptr) }
+    }
 }
 
 impl<'a, 'b: 'a> Drop for Builder<'a, 'b> {
@@ -1,7 +1,9 @@
 use super::{BasicBlock, Codegen, LlvmType};
 use crate::ffi::{
-    llvm_function_append, llvm_function_new, llvm_function_set_noreturn, llvm_function_set_stdcall,
-    llvm_module_get_function, llvm_type_func,
+    llvm_function_append, llvm_function_new, llvm_function_set_cold, llvm_function_set_fastcc,
+    llvm_function_set_inline_always, llvm_function_set_inline_hint, llvm_function_set_inline_never,
+    llvm_function_set_noreturn, llvm_function_set_stdcall, llvm_module_get_function,
+    llvm_type_func,
 };
 use std::ffi::CStr;
 use std::marker::PhantomData;
@@ -32,7 +34,7 @@ impl<'a, 'b: 'a> LlvmFunc<'a, 'b> {
         cx: &'a Codegen<'b>,
         name: N,
         params: &[LlvmType<'a, 'b>],
-        ret: LlvmType<'a, 'b>,
+        ret: &LlvmType<'a, 'b>,
     ) -> Self {
         let name = name.as_ref();
         let params: Vec<*mut crate::ffi::LlvmType> = params.iter().map(|p| p.as_raw()).collect();
@@ -53,10 +55,30 @@ impl<'a, 'b: 'a> LlvmFunc<'a, 'b> {
         unsafe { llvm_function_set_stdcall(self.value) };
     }
 
+    pub fn set_fastcc(&mut self) {
+        unsafe { llvm_function_set_fastcc(self.value) };
+    }
+
     pub fn set_noreturn(&mut self) {
         unsafe { llvm_function_set_noreturn(self.value) };
     }
 
+    pub fn set_inline_hint(&mut self) {
+        unsafe { llvm_function_set_inline_hint(self.value) };
+    }
+
+    pub fn set_inline_always(&mut self) {
+        unsafe { llvm_function_set_inline_always(self.value) };
+    }
+
+    pub fn set_inline_never(&mut self) {
+        unsafe { llvm_function_set_inline_never(self.value) };
+    }
+
+    pub fn set_cold(&mut self) {
+        unsafe { llvm_function_set_cold(self.value) };
+    }
+
     pub fn as_raw(&self) -> *mut crate::ffi::LlvmFunction {
         self.value
     }
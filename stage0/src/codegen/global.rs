@@ -0,0 +1,40 @@
+use super::{Codegen, LlvmType};
+use crate::ffi::{llvm_global_new_const, llvm_module_get_global};
+use std::ffi::CStr;
+use std::marker::PhantomData;
+
+/// A global variable. The value is owned by the module so there is nothing to keep here.
+pub struct LlvmGlobal<'a, 'b: 'a> {
+    phantom: PhantomData<&'a Codegen<'b>>,
+}
+
+impl<'a, 'b: 'a> LlvmGlobal<'a, 'b> {
+    pub fn get<N: AsRef<CStr>>(cx: &'a Codegen<'b>, name: N) -> Option<Self> {
+        let name = name.as_ref();
+        let value = unsafe { llvm_module_get_global(cx.module, name.as_ptr()) };
+
+        if value.is_null() {
+            None
+        } else {
+            Some(Self {
+                phantom: PhantomData,
+            })
+        }
+    }
+
+    /// Create a read-only global variable with `init` as its value.
+    pub fn new_const<N: AsRef<CStr>>(
+        cx: &'a Codegen<'b>,
+        name: N,
+        ty: &LlvmType<'a, 'b>,
+        init: *mut crate::ffi::LlvmValue,
+    ) -> Self {
+        let name = name.as_ref();
+
+        unsafe { llvm_global_new_const(cx.module, ty.as_raw(), init, name.as_ptr()) };
+
+        Self {
+            phantom: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use super::BuildError;
+use crate::ffi::{
+    llvm_layout_dispose, llvm_layout_new, llvm_target_create_machine, llvm_target_dispose_machine,
+    llvm_target_lookup,
+};
+use crate::pkg::PrimitiveTarget;
+use std::cell::OnceCell;
+use std::ffi::CString;
+use std::ptr::null;
+
+/// An LLVM target machine together with its data layout.
+///
+/// Creating the target machine is relatively expensive so it should be shared between all
+/// [`super::Codegen`] for the same target (see [`MachineCache`]).
+pub struct TargetMachine {
+    target: &'static PrimitiveTarget,
+    machine: *mut crate::ffi::LlvmMachine,
+    layout: *mut crate::ffi::LlvmLayout,
+}
+
+impl TargetMachine {
+    pub fn new(target: &'static PrimitiveTarget) -> Result<Self, BuildError> {
+        // Get LLVM target. This can fail if LLVM was built without the backend for the target.
+        let triple = CString::new(target.to_string()).unwrap();
+        let llvm = {
+            let mut err = String::new();
+            let ptr = unsafe { llvm_target_lookup(triple.as_ptr(), &mut err) };
+
+            if ptr.is_null() {
+                return Err(BuildError::TargetNotSupported(target, err));
+            }
+
+            ptr
+        };
+
+        // Create LLVM target machine.
+        let machine = unsafe { llvm_target_create_machine(llvm, triple.as_ptr(), null(), null()) };
+
+        // Create LLVM layout.
+        let layout = unsafe { llvm_layout_new(machine) };
+
+        Ok(Self {
+            target,
+            machine,
+            layout,
+        })
+    }
+
+    pub fn target(&self) -> &'static PrimitiveTarget {
+        self.target
+    }
+
+    pub(super) fn machine(&self) -> *mut crate::ffi::LlvmMachine {
+        self.machine
+    }
+
+    pub(super) fn layout(&self) -> *mut crate::ffi::LlvmLayout {
+        self.layout
+    }
+}
+
+impl Drop for TargetMachine {
+    fn drop(&mut self) {
+        unsafe { llvm_layout_dispose(self.layout) };
+        unsafe { llvm_target_dispose_machine(self.machine) };
+    }
+}
+
+/// A lazily populated [`TargetMachine`] for each [`PrimitiveTarget`].
+///
+/// There is no optimization level yet so the target is the only key.
+#[derive(Default)]
+pub struct MachineCache {
+    machines: [OnceCell<TargetMachine>; PrimitiveTarget::ALL.len()],
+}
+
+impl MachineCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, target: &'static PrimitiveTarget) -> Result<&TargetMachine, BuildError> {
+        let i = PrimitiveTarget::ALL
+            .iter()
+            .position(|t| t.id() == target.id())
+            .unwrap();
+        let cell = &self.machines[i];
+
+        if let Some(v) = cell.get() {
+            return Ok(v);
+        }
+
+        let machine = TargetMachine::new(target)?;
+
+        Ok(cell.get_or_init(|| machine))
+    }
+}
@@ -1,23 +1,26 @@
 pub use self::block::*;
 pub use self::builder::*;
 pub use self::func::*;
+pub use self::global::*;
+pub use self::machine::*;
 pub use self::resolver::*;
 pub use self::ty::*;
 
 use crate::ffi::{
-    llvm_context_dispose, llvm_context_new, llvm_layout_dispose, llvm_layout_new,
-    llvm_layout_pointer_size, llvm_module_dispose, llvm_module_new, llvm_module_set_layout,
-    llvm_target_create_machine, llvm_target_dispose_machine, llvm_target_emit_object,
-    llvm_target_lookup,
+    llvm_context_dispose, llvm_context_new, llvm_layout_pointer_size, llvm_module_dispose,
+    llvm_module_new, llvm_module_set_layout, llvm_target_emit_object,
 };
 use crate::pkg::{PackageName, PackageVersion, PrimitiveTarget, TargetOs};
+use std::collections::HashMap;
 use std::ffi::{CStr, CString};
-use std::ptr::null;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 mod block;
 mod builder;
 mod func;
+mod global;
+mod machine;
 mod resolver;
 mod ty;
 
@@ -27,59 +30,62 @@ mod ty;
 pub struct Codegen<'a> {
     module: *mut crate::ffi::LlvmModule,
     llvm: *mut crate::ffi::LlvmContext,
-    layout: *mut crate::ffi::LlvmLayout,
-    machine: *mut crate::ffi::LlvmMachine,
+    machine: &'a TargetMachine,
     pkg: &'a PackageName,
     version: &'a PackageVersion,
-    target: &'static PrimitiveTarget,
     executable: bool,
     namespace: &'a str,
+    prelude: bool,
     entry: String,
+    entry_function: Option<&'a str>,
+    main: bool,
+    symbol: &'a str,
+    allow_no_entry: bool,
+    allow_intrinsics: bool,
+    test: bool,
+    tests: Vec<TestFunction>,
     resolver: &'a TypeResolver<'a>,
+    defines: &'a HashMap<String, String>,
 }
 
 impl<'a> Codegen<'a> {
+    /// The default name of the symbol the process start at.
+    pub const ENTRY_SYMBOL: &'static str = "main";
+
     pub fn new(
         pkg: &'a PackageName,
         version: &'a PackageVersion,
-        target: &'static PrimitiveTarget,
+        machine: &'a TargetMachine,
         executable: bool,
         resolver: &'a TypeResolver<'a>,
+        defines: &'a HashMap<String, String>,
     ) -> Self {
-        // Get LLVM target.
-        let triple = CString::new(target.to_string()).unwrap();
-        let llvm = {
-            let mut err = String::new();
-            let ptr = unsafe { llvm_target_lookup(triple.as_ptr(), &mut err) };
-            assert!(!ptr.is_null());
-            ptr
-        };
-
-        // Create LLVM target machine.
-        let machine = unsafe { llvm_target_create_machine(llvm, triple.as_ptr(), null(), null()) };
-
-        // Create LLVM layout.
-        let layout = unsafe { llvm_layout_new(machine) };
-
         // Create LLVM module.
         let llvm = unsafe { llvm_context_new() };
         let name = CString::new(pkg.as_str()).unwrap();
         let module = unsafe { llvm_module_new(llvm, name.as_ptr()) };
 
-        unsafe { llvm_module_set_layout(module, layout) };
+        unsafe { llvm_module_set_layout(module, machine.layout()) };
 
         Self {
             module,
             llvm,
-            layout,
             machine,
             pkg,
             version,
-            target,
             executable,
             namespace: "",
+            prelude: true,
             entry: String::new(),
+            entry_function: None,
+            main: true,
+            symbol: Self::ENTRY_SYMBOL,
+            allow_no_entry: false,
+            allow_intrinsics: false,
+            test: false,
+            tests: Vec::new(),
             resolver,
+            defines,
         }
     }
 
@@ -92,13 +98,18 @@ impl<'a> Codegen<'a> {
     }
 
     pub fn target(&self) -> &'static PrimitiveTarget {
-        self.target
+        self.machine.target()
     }
 
     pub fn executable(&self) -> bool {
         self.executable
     }
 
+    /// Returns the user-defined keys that can be tested with `@if`.
+    pub fn defines(&self) -> &'a HashMap<String, String> {
+        self.defines
+    }
+
     pub fn namespace(&self) -> &'a str {
         self.namespace
     }
@@ -107,6 +118,15 @@ impl<'a> Codegen<'a> {
         self.namespace = v;
     }
 
+    /// Returns `false` if the source file that is being built has `@no_prelude`.
+    pub fn prelude(&self) -> bool {
+        self.prelude
+    }
+
+    pub fn set_prelude(&mut self, v: bool) {
+        self.prelude = v;
+    }
+
     pub fn entry(&self) -> &str {
         &self.entry
     }
@@ -115,43 +135,148 @@ impl<'a> Codegen<'a> {
         self.entry = v;
     }
 
+    /// Returns the full name of the function (e.g. `App.Main`) to use as the entry point instead of
+    /// the one with `@entry`.
+    pub fn entry_function(&self) -> Option<&'a str> {
+        self.entry_function
+    }
+
+    pub fn set_entry_function(&mut self, v: Option<&'a str>) {
+        self.entry_function = v;
+    }
+
+    /// Returns `false` if the executable does not have the generated entry point, in which case
+    /// `@entry` has no effect.
+    pub fn main(&self) -> bool {
+        self.main
+    }
+
+    pub fn set_main(&mut self, v: bool) {
+        self.main = v;
+    }
+
+    /// Set the name of the symbol for the generated entry point. The default is
+    /// [`Codegen::ENTRY_SYMBOL`].
+    pub fn set_entry_symbol(&mut self, v: &'a str) {
+        self.symbol = v;
+    }
+
+    /// Set to `true` to generate an entry point that do nothing when there is no `@entry` function
+    /// instead of failing with [`BuildError::NoEntryPoint`].
+    pub fn set_allow_no_entry(&mut self, v: bool) {
+        self.allow_no_entry = v;
+    }
+
+    /// Returns `true` if the functions in `nitro.intrin` can be called outside the `nitro` package.
+    pub fn allow_intrinsics(&self) -> bool {
+        self.allow_intrinsics
+    }
+
+    pub fn set_allow_intrinsics(&mut self, v: bool) {
+        self.allow_intrinsics = v;
+    }
+
+    /// Returns `true` if this [`Codegen`] is building a test harness instead of a normal
+    /// executable.
+    pub fn test(&self) -> bool {
+        self.test
+    }
+
+    pub fn set_test(&mut self, v: bool) {
+        assert!(!v || self.executable);
+        self.test = v;
+    }
+
+    pub fn tests(&self) -> &[TestFunction] {
+        &self.tests
+    }
+
+    pub fn add_test(&mut self, v: TestFunction) {
+        self.tests.push(v);
+    }
+
     pub fn resolver(&self) -> &'a TypeResolver<'a> {
         self.resolver
     }
 
     /// Returns the pointer size, in bytes.
     pub fn pointer_size(&self) -> u32 {
-        unsafe { llvm_layout_pointer_size(self.layout) }
+        unsafe { llvm_layout_pointer_size(self.machine.layout()) }
     }
 
-    pub fn build<F: AsRef<std::path::Path>>(self, file: F) -> Result<(), BuildError> {
+    /// Returns the textual IR of the module that has been built so far.
+    #[cfg(test)]
+    pub fn ir(&self) -> String {
+        let mut ir = String::new();
+        unsafe { crate::ffi::llvm_module_print(self.module, &mut ir) };
+        ir
+    }
+
+    pub fn build<F: AsRef<Path>>(self, file: F) -> Result<(), BuildError> {
         // Generate entry point.
         match self.executable {
-            true => self.build_main()?,
-            false => match self.target.os() {
+            true => {
+                if self.main {
+                    self.build_main()?;
+                }
+            }
+            false => match self.target().os() {
                 TargetOs::Darwin => {}
                 TargetOs::Linux => {}
                 TargetOs::Win32 => self.build_dll_main()?,
             },
         }
 
+        // Emit to a temporary file so a failure will not leave a partial object at the output
+        // path. The previous object is also removed since it was built from the other sources.
         // TODO: Invoke LLVMVerifyModule.
+        let file = file.as_ref();
+        let target = self.target();
+        let mut tmp = file.as_os_str().to_owned();
+
+        tmp.push(".tmp");
+
+        let tmp = PathBuf::from(tmp);
+        let path = CString::new(tmp.to_str().unwrap()).unwrap();
         let mut err = String::new();
-        let file = file.as_ref().to_str().unwrap();
-        let file = CString::new(file).unwrap();
 
-        if !unsafe { llvm_target_emit_object(self.machine, self.module, file.as_ptr(), &mut err) } {
-            Err(BuildError::EmitObjectFailed(err))
-        } else {
-            Ok(())
+        if !unsafe {
+            llvm_target_emit_object(self.machine.machine(), self.module, path.as_ptr(), &mut err)
+        } {
+            std::fs::remove_file(&tmp).ok();
+            std::fs::remove_file(file).ok();
+
+            return Err(BuildError::EmitObjectFailed(target, file.to_owned(), err));
+        }
+
+        if let Err(e) = std::fs::rename(&tmp, file) {
+            std::fs::remove_file(&tmp).ok();
+
+            return Err(BuildError::EmitObjectFailed(
+                target,
+                file.to_owned(),
+                e.to_string(),
+            ));
         }
+
+        Ok(())
     }
 
     fn build_main(&self) -> Result<(), BuildError> {
-        if self.entry.is_empty() {
+        // The test harness does not need an entry point.
+        if self.test {
+            return self.build_runner(&self.tests);
+        } else if self.entry.is_empty() && !self.allow_no_entry {
             return Err(BuildError::NoEntryPoint);
         }
 
+        self.build_runner(&[])
+    }
+
+    /// Build an entry point that call each function in `funcs` in sequence. If a function return a
+    /// non-zero status the process will print a zero-based index of that function on a line then
+    /// exit with 1. The index is not used as the exit status since it will be truncated to 8 bits.
+    fn build_runner(&self, funcs: &[TestFunction]) -> Result<(), BuildError> {
         // Get exit function.
         let name = CStr::from_bytes_with_nul(b"exit\0").unwrap();
         let exit = match LlvmFunc::get(self, name) {
@@ -159,26 +284,71 @@ impl<'a> Codegen<'a> {
             None => {
                 let params = [LlvmType::I32(LlvmI32::new(self))];
                 let ret = LlvmType::Void(LlvmVoid::new(self));
-                let mut func = LlvmFunc::new(self, name, &params, ret);
+                let mut func = LlvmFunc::new(self, name, &params, &ret);
 
                 func.set_noreturn();
                 func
             }
         };
 
+        // Get putchar function.
+        let name = c"putchar";
+        let putchar = match LlvmFunc::get(self, name) {
+            Some(_) => todo!(),
+            None => {
+                let params = [LlvmType::I32(LlvmI32::new(self))];
+                let ret = LlvmType::I32(LlvmI32::new(self));
+
+                LlvmFunc::new(self, name, &params, &ret)
+            }
+        };
+
         // Create a function.
-        let name = CStr::from_bytes_with_nul(b"main\0").unwrap();
+        let name = CString::new(self.symbol).unwrap();
         let ret = LlvmType::Void(LlvmVoid::new(self));
-        let mut func = LlvmFunc::new(self, name, &[], ret);
+        let mut func = LlvmFunc::new(self, &name, &[], &ret);
 
         // Build body.
+        let i32 = LlvmI32::new(self);
         let mut body = BasicBlock::new(self);
         let mut b = Builder::new(self, &mut body);
+        let mut blocks = vec![body];
+
+        for (i, f) in funcs.iter().enumerate() {
+            let name = CString::new(f.symbol.as_str()).unwrap();
+            let callee = LlvmFunc::get(self, name).unwrap();
+            let status = b.call(callee.as_raw(), &[]);
+
+            if !f.status {
+                continue;
+            }
 
-        b.call(exit.as_raw(), &[LlvmI32::new(self).get_const(0) as _]);
+            // Exit if the function was failed.
+            let failed = b.icmp_ne(status as _, i32.get_const(0) as _);
+            let mut fail = BasicBlock::new(self);
+            let mut next = BasicBlock::new(self);
+
+            b.cond_br(failed, &fail, &next);
+            b.position(&mut fail);
+
+            for ch in format!("{i}\n").bytes() {
+                b.call(putchar.as_raw(), &[i32.get_const(ch.into()) as _]);
+            }
+
+            b.call(exit.as_raw(), &[i32.get_const(1) as _]);
+            b.unreachable();
+            b.position(&mut next);
+
+            blocks.push(fail);
+            blocks.push(next);
+        }
+
+        b.call(exit.as_raw(), &[i32.get_const(0) as _]);
         b.ret_void(); // TODO: Is it possible to remove this?
 
-        func.append(body);
+        for bb in blocks {
+            func.append(bb);
+        }
 
         Ok(())
     }
@@ -194,7 +364,7 @@ impl<'a> Codegen<'a> {
         // Create a function.
         let name = CStr::from_bytes_with_nul(b"_DllMainCRTStartup\0").unwrap();
         let ret = LlvmType::I32(LlvmI32::new(self));
-        let mut func = LlvmFunc::new(self, name, &params, ret);
+        let mut func = LlvmFunc::new(self, name, &params, &ret);
 
         func.set_stdcall();
 
@@ -214,8 +384,29 @@ impl<'a> Drop for Codegen<'a> {
     fn drop(&mut self) {
         unsafe { llvm_module_dispose(self.module) };
         unsafe { llvm_context_dispose(self.llvm) };
-        unsafe { llvm_layout_dispose(self.layout) };
-        unsafe { llvm_target_dispose_machine(self.machine) };
+    }
+}
+
+/// A function that was marked with `@test`.
+pub struct TestFunction {
+    symbol: String,
+    name: String,
+    status: bool,
+}
+
+impl TestFunction {
+    /// `status` indicated if the function returns `nitro.Int32` as a result of the test.
+    pub fn new(symbol: String, name: String, status: bool) -> Self {
+        Self {
+            symbol,
+            name,
+            status,
+        }
+    }
+
+    /// Returns a human-readable name (e.g. `foo.Bar.Baz`).
+    pub fn name(&self) -> &str {
+        &self.name
     }
 }
 
@@ -225,6 +416,9 @@ pub enum BuildError {
     #[error("no entry point has been defined")]
     NoEntryPoint,
 
-    #[error("{0}")]
-    EmitObjectFailed(String),
+    #[error("LLVM backend for {0} is not available ({1})")]
+    TargetNotSupported(&'static PrimitiveTarget, String),
+
+    #[error("cannot emit object for {0} to {1} ({2})")]
+    EmitObjectFailed(&'static PrimitiveTarget, PathBuf, String),
 }
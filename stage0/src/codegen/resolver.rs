@@ -23,6 +23,11 @@ impl<'a> TypeResolver<'a> {
         S: IntoIterator<Item = (&'a String, &'a SourceFile)>,
     {
         for (name, ty) in set {
+            // A file that contains only re-exports does not define a type.
+            if !ty.has_type() {
+                continue;
+            }
+
             let mut key = String::from("self.");
 
             key.push_str(&name);
@@ -1,7 +1,7 @@
 use super::Codegen;
 use crate::ffi::{
-    llvm_integer_const, llvm_type_int32, llvm_type_int64, llvm_type_int8, llvm_type_ptr,
-    llvm_type_void,
+    llvm_const_struct, llvm_const_zero, llvm_integer_const, llvm_type_int32, llvm_type_int64,
+    llvm_type_int8, llvm_type_ptr, llvm_type_struct, llvm_type_void,
 };
 use std::marker::PhantomData;
 
@@ -13,6 +13,7 @@ pub enum LlvmType<'a, 'b: 'a> {
     U32(LlvmU32<'a, 'b>),
     U64(LlvmU64<'a, 'b>),
     Ptr(LlvmPtr<'a, 'b>),
+    Tuple(LlvmTuple<'a, 'b>),
 }
 
 impl<'a, 'b: 'a> LlvmType<'a, 'b> {
@@ -24,6 +25,19 @@ impl<'a, 'b: 'a> LlvmType<'a, 'b> {
             Self::U32(v) => v.raw as _,
             Self::U64(v) => v.raw as _,
             Self::Ptr(v) => v.raw as _,
+            Self::Tuple(v) => v.raw as _,
+        }
+    }
+
+    /// Get a constant where all bits is zero.
+    pub fn get_zero(&self) -> *mut crate::ffi::LlvmValue {
+        unsafe { llvm_const_zero(self.as_raw()) }
+    }
+
+    pub fn is_void(&self) -> bool {
+        match self {
+            Self::Void(_) => true,
+            _ => false,
         }
     }
 
@@ -33,6 +47,18 @@ impl<'a, 'b: 'a> LlvmType<'a, 'b> {
             _ => false,
         }
     }
+
+    /// Returns `true` if this type can be converted to another numeric type with `as`.
+    pub fn is_numeric(&self) -> bool {
+        match self {
+            Self::I32(_) | Self::U8(_) | Self::U32(_) | Self::U64(_) => true,
+            Self::Void(_) | Self::Ptr(_) | Self::Tuple(_) => false,
+        }
+    }
+
+    pub fn is_signed(&self) -> bool {
+        matches!(self, Self::I32(_))
+    }
 }
 
 /// An unit type.
@@ -82,6 +108,10 @@ impl<'a, 'b: 'a> LlvmU8<'a, 'b> {
             phantom: PhantomData,
         }
     }
+
+    pub fn get_const(&self, v: u8) -> *mut crate::ffi::LlvmConstInt {
+        unsafe { llvm_integer_const(self.raw, Into::<u64>::into(v), false) }
+    }
 }
 
 /// A 32-bits unsigned integer.
@@ -97,6 +127,10 @@ impl<'a, 'b: 'a> LlvmU32<'a, 'b> {
             phantom: PhantomData,
         }
     }
+
+    pub fn get_const(&self, v: u32) -> *mut crate::ffi::LlvmConstInt {
+        unsafe { llvm_integer_const(self.raw, Into::<u64>::into(v), false) }
+    }
 }
 
 /// A 64-bits unsigned integer.
@@ -112,6 +146,10 @@ impl<'a, 'b: 'a> LlvmU64<'a, 'b> {
             phantom: PhantomData,
         }
     }
+
+    pub fn get_const(&self, v: u64) -> *mut crate::ffi::LlvmConstInt {
+        unsafe { llvm_integer_const(self.raw, v, false) }
+    }
 }
 
 /// A pointer to something.
@@ -130,3 +168,33 @@ impl<'a, 'b: 'a> LlvmPtr<'a, 'b> {
         }
     }
 }
+
+/// An anonymous struct that hold the elements of a tuple.
+pub struct LlvmTuple<'a, 'b: 'a> {
+    raw: *mut crate::ffi::LlvmStruct,
+    elems: Vec<LlvmType<'a, 'b>>,
+    phantom: PhantomData<&'a Codegen<'b>>,
+}
+
+impl<'a, 'b: 'a> LlvmTuple<'a, 'b> {
+    pub fn new(cx: &'a Codegen<'b>, elems: Vec<LlvmType<'a, 'b>>) -> Self {
+        let raw: Vec<*mut crate::ffi::LlvmType> = elems.iter().map(|t| t.as_raw()).collect();
+
+        Self {
+            raw: unsafe { llvm_type_struct(cx.llvm, raw.as_ptr(), raw.len()) },
+            elems,
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn elems(&self) -> &[LlvmType<'a, 'b>] {
+        &self.elems
+    }
+
+    /// Get a constant tuple. Each value must be a constant with the same type as the element.
+    pub fn get_const(&self, values: &[*mut crate::ffi::LlvmValue]) -> *mut crate::ffi::LlvmValue {
+        assert_eq!(values.len(), self.elems.len());
+
+        unsafe { llvm_const_struct(self.raw, values.as_ptr(), values.len()) }
+    }
+}
@@ -1,9 +1,10 @@
-use std::ffi::c_char;
+use std::ffi::{c_char, c_void};
 
 #[allow(improper_ctypes)]
 extern "C" {
     pub fn llvm_init();
     pub fn llvm_process_triple(t: &mut String);
+    pub fn llvm_version(v: &mut String);
     pub fn llvm_target_lookup(triple: *const c_char, err: &mut String) -> *const LlvmTarget;
     pub fn llvm_target_create_machine(
         target: *const LlvmTarget,
@@ -18,6 +19,12 @@ extern "C" {
         file: *const c_char,
         err: &mut String,
     ) -> bool;
+    pub fn llvm_object_symbol_sizes(
+        file: *const c_char,
+        cx: *mut c_void,
+        cb: unsafe extern "C" fn(*mut c_void, *const c_char, u64),
+        err: &mut String,
+    ) -> bool;
     pub fn llvm_layout_new(mc: *const LlvmMachine) -> *mut LlvmLayout;
     pub fn llvm_layout_dispose(dl: *mut LlvmLayout);
     pub fn llvm_layout_pointer_size(dl: *const LlvmLayout) -> u32;
@@ -30,6 +37,9 @@ extern "C" {
         md: *const LlvmModule,
         name: *const c_char,
     ) -> *mut LlvmFunction;
+    pub fn llvm_module_get_global(md: *const LlvmModule, name: *const c_char) -> *mut LlvmGlobal;
+    #[cfg(test)]
+    pub fn llvm_module_print(md: *const LlvmModule, s: &mut String);
     pub fn llvm_type_void(cx: *mut LlvmContext) -> *mut LlvmType;
     pub fn llvm_type_int8(cx: *mut LlvmContext) -> *mut LlvmInteger;
     pub fn llvm_type_int32(cx: *mut LlvmContext) -> *mut LlvmInteger;
@@ -41,14 +51,36 @@ extern "C" {
         count: usize,
         va: bool,
     ) -> *mut LlvmPrototype;
+    pub fn llvm_type_struct(
+        cx: *mut LlvmContext,
+        elems: *const *mut LlvmType,
+        count: usize,
+    ) -> *mut LlvmStruct;
+    pub fn llvm_const_struct(
+        ty: *mut LlvmStruct,
+        values: *const *mut LlvmValue,
+        count: usize,
+    ) -> *mut LlvmValue;
+    pub fn llvm_const_zero(ty: *mut LlvmType) -> *mut LlvmValue;
     pub fn llvm_function_new(
         md: *mut LlvmModule,
         ty: *mut LlvmPrototype,
         name: *const c_char,
     ) -> *mut LlvmFunction;
+    pub fn llvm_global_new_const(
+        md: *mut LlvmModule,
+        ty: *mut LlvmType,
+        init: *mut LlvmValue,
+        name: *const c_char,
+    ) -> *mut LlvmGlobal;
     pub fn llvm_function_append(f: *mut LlvmFunction, bb: *mut LlvmBlock);
     pub fn llvm_function_set_stdcall(f: *mut LlvmFunction);
+    pub fn llvm_function_set_fastcc(f: *mut LlvmFunction);
     pub fn llvm_function_set_noreturn(f: *mut LlvmFunction);
+    pub fn llvm_function_set_inline_hint(f: *mut LlvmFunction);
+    pub fn llvm_function_set_inline_always(f: *mut LlvmFunction);
+    pub fn llvm_function_set_inline_never(f: *mut LlvmFunction);
+    pub fn llvm_function_set_cold(f: *mut LlvmFunction);
     pub fn llvm_integer_const(ty: *mut LlvmInteger, val: u64, sign: bool) -> *mut LlvmConstInt;
     pub fn llvm_block_new(cx: *mut LlvmContext) -> *mut LlvmBlock;
     pub fn llvm_block_dispose(bb: *mut LlvmBlock);
@@ -61,8 +93,56 @@ extern "C" {
         args: *const *mut LlvmValue,
         nargs: usize,
     ) -> *mut LlvmCall;
+    pub fn llvm_builder_asm(
+        ib: *mut LlvmBuilder,
+        inst: *const c_char,
+        constraints: *const c_char,
+        args: *const *mut LlvmValue,
+        nargs: usize,
+        intel: bool,
+    ) -> *mut LlvmCall;
     pub fn llvm_builder_ret_void(ib: *mut LlvmBuilder) -> *mut LlvmReturn;
     pub fn llvm_builder_ret(ib: *mut LlvmBuilder, v: *mut LlvmValue) -> *mut LlvmReturn;
+    pub fn llvm_builder_cond_br(
+        ib: *mut LlvmBuilder,
+        cond: *mut LlvmValue,
+        then: *mut LlvmBlock,
+        otherwise: *mut LlvmBlock,
+    ) -> *mut LlvmBranch;
+    pub fn llvm_builder_unreachable(ib: *mut LlvmBuilder) -> *mut LlvmUnreachable;
+    pub fn llvm_builder_icmp_ne(
+        ib: *mut LlvmBuilder,
+        lhs: *mut LlvmValue,
+        rhs: *mut LlvmValue,
+    ) -> *mut LlvmValue;
+    pub fn llvm_builder_cast(
+        ib: *mut LlvmBuilder,
+        v: *mut LlvmValue,
+        from_signed: bool,
+        ty: *mut LlvmType,
+        to_signed: bool,
+    ) -> *mut LlvmValue;
+    pub fn llvm_builder_gep8(
+        ib: *mut LlvmBuilder,
+        ptr: *mut LlvmValue,
+        off: *mut LlvmValue,
+    ) -> *mut LlvmValue;
+    pub fn llvm_builder_ptr_diff(
+        ib: *mut LlvmBuilder,
+        lhs: *mut LlvmValue,
+        rhs: *mut LlvmValue,
+        ty: *mut LlvmType,
+    ) -> *mut LlvmValue;
+    pub fn llvm_builder_load(
+        ib: *mut LlvmBuilder,
+        ty: *mut LlvmType,
+        ptr: *mut LlvmValue,
+    ) -> *mut LlvmValue;
+    pub fn llvm_builder_store(
+        ib: *mut LlvmBuilder,
+        v: *mut LlvmValue,
+        ptr: *mut LlvmValue,
+    ) -> *mut LlvmStore;
     pub fn ZSTD_createCStream() -> *mut ZSTD_CCtx;
     pub fn ZSTD_freeCStream(zcs: *mut ZSTD_CCtx) -> usize;
     pub fn ZSTD_compressStream2(
@@ -71,6 +151,7 @@ extern "C" {
         input: *mut ZSTD_inBuffer,
         endOp: ZSTD_EndDirective,
     ) -> usize;
+    pub fn ZSTD_CCtx_loadDictionary(cctx: *mut ZSTD_CCtx, dict: *const u8, size: usize) -> usize;
     pub fn ZSTD_CStreamInSize() -> usize;
     pub fn ZSTD_CStreamOutSize() -> usize;
     pub fn ZSTD_createDStream() -> *mut ZSTD_DCtx;
@@ -80,9 +161,12 @@ extern "C" {
         output: *mut ZSTD_outBuffer,
         input: *mut ZSTD_inBuffer,
     ) -> usize;
+    pub fn ZSTD_DCtx_loadDictionary(dctx: *mut ZSTD_DCtx, dict: *const u8, size: usize) -> usize;
     pub fn ZSTD_DStreamInSize() -> usize;
+    pub fn ZSTD_getDictID_fromDict(dict: *const u8, size: usize) -> u32;
     pub fn ZSTD_isError(code: usize) -> u32;
     pub fn ZSTD_getErrorName(code: usize) -> *const c_char;
+    pub fn ZSTD_versionString() -> *const c_char;
 }
 
 pub struct LlvmTarget(());
@@ -93,14 +177,19 @@ pub struct LlvmModule(());
 pub struct LlvmType(());
 pub struct LlvmInteger(());
 pub struct LlvmPointer(());
+pub struct LlvmStruct(());
 pub struct LlvmPrototype(());
 pub struct LlvmValue(());
 pub struct LlvmFunction(());
+pub struct LlvmGlobal(());
 pub struct LlvmConstInt(());
 pub struct LlvmBlock(());
 pub struct LlvmBuilder(());
 pub struct LlvmCall(());
 pub struct LlvmReturn(());
+pub struct LlvmBranch(());
+pub struct LlvmUnreachable(());
+pub struct LlvmStore(());
 
 #[repr(C)]
 #[allow(non_camel_case_types)]
@@ -0,0 +1,71 @@
+use std::fs::File;
+use std::hash::Hasher;
+use std::io::{ErrorKind, Read};
+use std::path::Path;
+
+/// A 64-bit FNV-1a hasher.
+///
+/// Unlike [`std::collections::hash_map::DefaultHasher`] the output is stable across Rust versions
+/// so it can be written to a file and compared later.
+pub struct Fnv64(u64);
+
+impl Fnv64 {
+    pub fn new() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for Fnv64 {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= u64::from(b);
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+/// Feed the content of the file at `path` to `h`.
+pub fn hash_file<H: Hasher>(path: &Path, h: &mut H) -> Result<(), std::io::Error> {
+    let mut file = File::open(path)?;
+    let mut buf = vec![0; 32768];
+
+    loop {
+        let n = match file.read(&mut buf) {
+            Ok(0) => break Ok(()),
+            Ok(v) => v,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => break Err(e),
+        };
+
+        h.write(&buf[..n]);
+    }
+}
+
+/// Feed the name and the content of all files under `path` to `h` in a deterministic order.
+pub fn hash_dir<H: Hasher>(path: &Path, h: &mut H) -> Result<(), std::io::Error> {
+    let mut items = Vec::new();
+
+    for item in std::fs::read_dir(path)? {
+        items.push(item?.path());
+    }
+
+    items.sort_unstable();
+
+    for item in items {
+        let name = item.file_name().unwrap();
+
+        h.write(name.as_encoded_bytes());
+
+        if item.is_dir() {
+            hash_dir(&item, h)?;
+        } else {
+            hash_file(&item, h)?;
+        }
+    }
+
+    Ok(())
+}
@@ -1,6 +1,7 @@
 pub use self::span::*;
 pub use self::token::*;
 
+use crate::limit::MAX_IDENT;
 use std::borrow::Cow;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
@@ -14,21 +15,65 @@ pub struct Lexer {
     data: Rc<String>,
     next: usize,
     last: Option<Span>,
+    prev: Option<Span>,
+    depth: usize,
+    max_depth: usize,
+    comments: Vec<Span>,
 }
 
 impl Lexer {
+    /// Default value for [`Self::set_max_depth()`].
+    pub const DEFAULT_MAX_DEPTH: usize = 256;
+
     pub fn new<D: Into<String>>(data: D) -> Self {
         Self {
             data: Rc::new(data.into()),
             next: 0,
             last: None,
+            prev: None,
+            depth: 0,
+            max_depth: Self::DEFAULT_MAX_DEPTH,
+            comments: Vec::new(),
         }
     }
 
+    /// Set the maximum nesting level that [`Self::enter()`] will allow. `v` must not be zero.
+    pub fn set_max_depth(&mut self, v: usize) {
+        assert_ne!(v, 0);
+        self.max_depth = v;
+    }
+
+    /// Increase the nesting level. This must be called by the parser before each recursion so a
+    /// deeply nested source produces an error instead of overflowing the stack. The error will be
+    /// reported at the last token.
+    pub fn enter(&mut self) -> Result<(), SyntaxError> {
+        if self.depth == self.max_depth {
+            return Err(SyntaxError::new(
+                self.last.clone().unwrap(),
+                "expression nesting too deep",
+            ));
+        }
+
+        self.depth += 1;
+
+        Ok(())
+    }
+
+    /// Decrease the nesting level that was increased by [`Self::enter()`].
+    pub fn leave(&mut self) {
+        self.depth -= 1;
+    }
+
     pub fn last(&self) -> Option<&Span> {
         self.last.as_ref()
     }
 
+    /// Move the comments that have been skipped out of the lexer. The comments are in the same order
+    /// as the source.
+    pub fn take_comments(&mut self) -> Vec<Span> {
+        std::mem::take(&mut self.comments)
+    }
+
     pub fn next_equals(&mut self) -> Result<Equals, SyntaxError> {
         let tok = match self.next()? {
             Some(v) => v,
@@ -149,26 +194,52 @@ impl Lexer {
     }
 
     pub fn next(&mut self) -> Result<Option<Token>, SyntaxError> {
-        // Find a non-whitespace.
-        let mut iter = self.data[self.next..].chars();
+        // Find a non-whitespace. Comments are skipped here so the span of the previous token stays
+        // valid for undo().
         let ch = loop {
-            let ch = match iter.next() {
+            let rest = &self.data[self.next..];
+            let ch = match rest.chars().next() {
                 Some(v) => v,
                 None => return Ok(None),
             };
 
-            self.next += ch.len_utf8();
+            if rest.starts_with("//") {
+                let len = rest.find('\n').unwrap_or(rest.len());
 
-            if !ch.is_whitespace() {
-                break ch;
+                self.push_comment(rest[..len].trim_end().len());
+                self.next += len;
+            } else if let Some(body) = rest.strip_prefix("/*") {
+                match body.find("*/") {
+                    Some(i) => {
+                        self.push_comment(i + 4);
+                        self.next += i + 4;
+                    }
+                    None => {
+                        let span = Span::new(self.data.clone(), self.next, 2);
+
+                        // Consume the remaining so tokenize() does not report the content.
+                        self.next = self.data.len();
+
+                        return Err(SyntaxError::new(span, "unterminated block comment"));
+                    }
+                }
+            } else {
+                self.next += ch.len_utf8();
+
+                if !ch.is_whitespace() {
+                    break ch;
+                }
             }
         };
 
+        let mut iter = self.data[self.next..].chars();
+
         // Check if a punctuation.
         let span = Span::new(self.data.clone(), self.next - ch.len_utf8(), ch.len_utf8());
         let tok: Option<Token> = match ch {
             '!' => Some(ExclamationMark::new(span).into()),
             '=' => Some(Equals::new(span).into()),
+            '>' => Some(GreaterThan::new(span).into()),
             '*' => Some(Asterisk::new(span).into()),
             '.' => Some(FullStop::new(span).into()),
             ',' => Some(Comma::new(span).into()),
@@ -182,7 +253,7 @@ impl Lexer {
         };
 
         if let Some(t) = tok {
-            self.last = Some(t.span().clone());
+            self.prev = self.last.replace(t.span().clone());
             return Ok(Some(t));
         }
 
@@ -219,19 +290,23 @@ impl Lexer {
 
                     self.next += ch.len_utf8();
 
+                    // We don't support multi-line string literal because new line is different on
+                    // each platform. CRLF is treated as a single line break so the CR does not end
+                    // up in the span.
+                    let eol = match ch {
+                        '\n' => true,
+                        '\r' => self.data[self.next..].starts_with('\n'),
+                        _ => false,
+                    };
+
+                    if eol {
+                        return Err(SyntaxError::new(
+                            Span::new(self.data.clone(), start, self.next - ch.len_utf8() - start),
+                            "incomplete string (a line break is not allowed in a string literal)",
+                        ));
+                    }
+
                     match ch {
-                        '\n' => {
-                            // We don't support multi-line string literal because new line is
-                            // different on each platform.
-                            return Err(SyntaxError::new(
-                                Span::new(
-                                    self.data.clone(),
-                                    start,
-                                    self.next - ch.len_utf8() - start,
-                                ),
-                                "incomplete string",
-                            ));
-                        }
                         '"' => break,
                         v => value.push(v),
                     }
@@ -255,25 +330,84 @@ impl Lexer {
                     let span = Span::new(self.data.clone(), self.next - ident.len(), ident.len());
                     Self::parse_ident(ident, span)?
                 } else {
-                    todo!()
+                    let span = Span::new(self.data.clone(), self.next, ch.len_utf8());
+
+                    self.next += ch.len_utf8();
+
+                    return Err(SyntaxError::new(
+                        span,
+                        if ch.is_alphanumeric() {
+                            format!("identifier cannot contain '{ch}' (only ASCII letters, digits and '_')")
+                        } else {
+                            format!("unexpected character '{ch}'")
+                        },
+                    ));
                 }
             }
         };
 
-        self.last = Some(tok.span().clone());
+        self.prev = self.last.replace(tok.span().clone());
 
         Ok(Some(tok))
     }
 
+    /// Read all tokens until the end of data. Unlike [`Self::next()`] this does not stop on the
+    /// first error but record it and continue from the next character so the caller still get all
+    /// valid tokens (e.g. for syntax highlighting).
+    pub fn tokenize(mut self) -> (Vec<(Token, Span)>, Vec<SyntaxError>) {
+        let mut tokens = Vec::new();
+        let mut errors = Vec::new();
+
+        loop {
+            let start = self.next;
+
+            match self.next() {
+                Ok(Some(v)) => {
+                    let span = v.span().clone();
+                    tokens.push((v, span));
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    // Skip the offending character if the lexer did not move.
+                    if self.next == start {
+                        self.next += self.data[start..].chars().next().unwrap().len_utf8();
+                    }
+
+                    errors.push(e);
+                }
+            }
+        }
+
+        (tokens, errors)
+    }
+
+    /// Push back the token returned from the last [`Self::next()`]. This can be called only once
+    /// after each call to [`Self::next()`] that returns a token.
     pub fn undo(&mut self) {
         let last = self.last.take().unwrap();
         self.next = last.offset();
+        self.last = self.prev.take();
+    }
+
+    /// Record a comment with `len` bytes at the current position. The comment may be scanned again
+    /// after [`Self::undo()`] so it is ignored if it was already recorded.
+    fn push_comment(&mut self, len: usize) {
+        if self.comments.last().is_none_or(|c| c.offset() < self.next) {
+            self.comments
+                .push(Span::new(self.data.clone(), self.next, len));
+        }
     }
 
     fn parse_num(lit: String, span: Span) -> Result<Token, SyntaxError> {
         let tok = if lit.contains('.') {
-            match lit.parse() {
-                Ok(v) => FloatLiteral::new(span, v).into(),
+            match lit.parse::<f64>() {
+                Ok(v) if v.is_finite() => FloatLiteral::new(span, v).into(),
+                Ok(_) => {
+                    return Err(SyntaxError::new(
+                        span,
+                        "floating point literal is too large",
+                    ));
+                }
                 Err(_) => return Err(SyntaxError::new(span, "invalid floating point literal")),
             }
         } else {
@@ -288,25 +422,38 @@ impl Lexer {
 
     fn parse_ident(ident: String, span: Span) -> Result<Token, SyntaxError> {
         let tok = match ident.as_str() {
+            "as" => AsKeyword::new(span).into(),
             "asm" => AsmKeyword::new(span).into(),
             "class" => ClassKeyword::new(span).into(),
+            "const" => ConstKeyword::new(span).into(),
             "fn" => FnKeyword::new(span).into(),
             "if" => IfKeyword::new(span).into(),
             "is" => IsKeyword::new(span).into(),
             "impl" => ImplKeyword::new(span).into(),
             "let" => LetKeyword::new(span).into(),
+            "match" => MatchKeyword::new(span).into(),
             "null" => NullKeyword::new(span).into(),
             "self" => SelfKeyword::new(span).into(),
             "struct" => StructKeyword::new(span).into(),
             "use" => UseKeyword::new(span).into(),
-            _ => Identifier::new(span, ident).into(),
+            _ => {
+                if ident.len() > MAX_IDENT {
+                    return Err(SyntaxError::new(
+                        span,
+                        format!("identifier cannot be longer than {MAX_IDENT} bytes"),
+                    ));
+                }
+
+                Identifier::new(span, ident).into()
+            }
         };
 
         Ok(tok)
     }
 
+    /// Identifiers and attribute names are restricted to ASCII so there are no confusable names.
     fn is_ident(ch: char) -> bool {
-        ch.is_alphanumeric() || ch == '_'
+        ch.is_ascii_alphanumeric() || ch == '_'
     }
 
     fn read<P>(&mut self, p: P) -> String
@@ -345,6 +492,14 @@ impl SyntaxError {
             reason: reason.into(),
         }
     }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
 }
 
 impl Error for SyntaxError {}
@@ -357,3 +512,277 @@ impl Display for SyntaxError {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenize() {
+        let (tokens, errors) = Lexer::new("@pub fn Foo(): UInt8 { 12 } // end").tokenize();
+        let tokens: Vec<(&str, &str)> =
+            tokens.iter().map(|(t, s)| (t.kind(), s.as_str())).collect();
+
+        assert!(errors.is_empty());
+        assert_eq!(
+            tokens,
+            [
+                ("attribute", "@pub"),
+                ("keyword", "fn"),
+                ("identifier", "Foo"),
+                ("punctuation", "("),
+                ("punctuation", ")"),
+                ("punctuation", ":"),
+                ("identifier", "UInt8"),
+                ("punctuation", "{"),
+                ("integer", "12"),
+                ("punctuation", "}"),
+            ]
+        );
+    }
+
+    #[test]
+    fn undo() {
+        let mut lex = Lexer::new("foo bar");
+
+        lex.next().unwrap().unwrap();
+        lex.next().unwrap().unwrap();
+        lex.undo();
+
+        // The lexer must be in the same state as before the undone token.
+        assert_eq!(lex.last().unwrap().as_str(), "foo");
+        assert_eq!(lex.next().unwrap().unwrap().span().as_str(), "bar");
+        assert!(lex.next().unwrap().is_none());
+        assert_eq!(lex.last().unwrap().as_str(), "bar");
+
+        // Undo the first token.
+        let mut lex = Lexer::new("foo");
+
+        lex.next().unwrap().unwrap();
+        lex.undo();
+
+        assert!(lex.last().is_none());
+        assert_eq!(lex.next().unwrap().unwrap().span().as_str(), "foo");
+    }
+
+    #[test]
+    fn ident_limit() {
+        let name = "a".repeat(MAX_IDENT);
+        let mut lex = Lexer::new(name.as_str());
+
+        match lex.next().unwrap() {
+            Some(Token::Identifier(v)) => assert_eq!(v.value(), name),
+            _ => panic!("the identifier is not accepted"),
+        }
+
+        let name = "a".repeat(MAX_IDENT + 1);
+        let e = Lexer::new(name.as_str()).next().err().unwrap();
+
+        assert_eq!(e.reason(), "identifier cannot be longer than 512 bytes");
+        assert_eq!(e.span().as_str().len(), MAX_IDENT + 1);
+    }
+
+    #[test]
+    fn comments() {
+        // A line comment at the end without a newline.
+        let mut lex = Lexer::new("foo // end");
+
+        assert_eq!(lex.next().unwrap().unwrap().span().as_str(), "foo");
+        assert!(lex.next().unwrap().is_none());
+        assert_eq!(lex.last().unwrap().as_str(), "foo");
+
+        // A multi-line block comment inside a function body.
+        let mut lex = Lexer::new("fn Run() {\n    /* first\n       second */\n    foo();\n}");
+        let mut tokens = Vec::new();
+
+        while let Some(t) = lex.next().unwrap() {
+            let s = t.span();
+
+            tokens.push((s.as_str().to_owned(), s.line(), s.column()));
+        }
+
+        let comments = lex.take_comments();
+
+        assert_eq!(
+            tokens,
+            [
+                ("fn".into(), 1, 1),
+                ("Run".into(), 1, 4),
+                ("(".into(), 1, 7),
+                (")".into(), 1, 8),
+                ("{".into(), 1, 10),
+                ("foo".into(), 4, 5),
+                ("(".into(), 4, 8),
+                (")".into(), 4, 9),
+                (";".into(), 4, 10),
+                ("}".into(), 5, 1),
+            ]
+        );
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].as_str(), "/* first\n       second */");
+
+        // Undo across a comment.
+        let mut lex = Lexer::new("foo /* a */ bar");
+
+        lex.next().unwrap().unwrap();
+        lex.next().unwrap().unwrap();
+        lex.undo();
+
+        assert_eq!(lex.last().unwrap().as_str(), "foo");
+        assert_eq!(lex.next().unwrap().unwrap().span().as_str(), "bar");
+    }
+
+    #[test]
+    fn unterminated_comment() {
+        let mut lex = Lexer::new("foo\n  /* bar\nbaz");
+
+        lex.next().unwrap().unwrap();
+
+        let e = lex.next().err().unwrap();
+
+        assert_eq!(e.reason(), "unterminated block comment");
+        assert_eq!(e.span().as_str(), "/*");
+        assert_eq!((e.span().line(), e.span().column()), (2, 3));
+    }
+
+    #[test]
+    fn crlf_caret() {
+        let mut lex = Lexer::new("fn Foo() {\r\n    /* \u{e9} */ $bar;\r\n}\r\n");
+        let e = loop {
+            if let Err(e) = lex.next() {
+                break e;
+            }
+        };
+
+        assert_eq!((e.span().line(), e.span().column()), (2, 13));
+        assert_eq!(
+            e.to_string(),
+            format!(
+                "{}\n    2 |     /* \u{e9} */ $bar;\n      |             ^",
+                e.reason()
+            )
+        );
+
+        // A span that covers multiple lines.
+        let mut lex = Lexer::new("foo\r\n  bar\r\n");
+        let foo = lex.next().unwrap().unwrap();
+        let bar = lex.next().unwrap().unwrap();
+        let span = foo.span() + bar.span();
+
+        assert_eq!(span.as_str(), "foo\r\n  bar");
+        assert_eq!(
+            span.to_string(),
+            "    1 | foo\n      | ^^^\n    2 |   bar\n      |   ^^^"
+        );
+    }
+
+    #[test]
+    fn crlf_string() {
+        let mut lex = Lexer::new("let v = \"abc\r\ndef\";\r\n");
+
+        lex.next().unwrap().unwrap();
+        lex.next().unwrap().unwrap();
+        lex.next().unwrap().unwrap();
+
+        let e = lex.next().err().unwrap();
+
+        assert_eq!(e.span().as_str(), "\"abc");
+        assert_eq!(
+            e.to_string(),
+            "incomplete string (a line break is not allowed in a string literal)\n    1 | let v = \"abc\n      |         ^^^^"
+        );
+    }
+
+    #[test]
+    fn snippet() {
+        // A short span that covers multiple lines.
+        let mut lex = Lexer::new("if a {\n    b\n}");
+        let first = lex.next().unwrap().unwrap();
+        let mut last = first.span().clone();
+
+        while let Some(t) = lex.next().unwrap() {
+            last = t.span().clone();
+        }
+
+        let e = SyntaxError::new(first.span() + &last, "short");
+
+        assert_eq!(
+            e.to_string(),
+            "short\n    1 | if a {\n      | ^^ ^ ^\n    2 |     b\n      |     ^\n    3 | }\n      | ^"
+        );
+
+        // A long span.
+        let data: String = (1..=1010).map(|i| format!("a{i}\n")).collect();
+        let mut lex = Lexer::new(data);
+        let first = lex.next().unwrap().unwrap();
+        let mut last = first.span().clone();
+
+        while let Some(t) = lex.next().unwrap() {
+            last = t.span().clone();
+        }
+
+        let e = SyntaxError::new(first.span() + &last, "long");
+
+        assert_eq!(
+            e.to_string(),
+            [
+                "long",
+                "    1 | a1",
+                "      | ^^",
+                "    2 | a2",
+                "      | ^^",
+                "    3 | a3",
+                "      | ^^",
+                "    4 | a4",
+                "      | ^^",
+                "      | ... 1,004 lines omitted ...",
+                " 1009 | a1009",
+                "      | ^^^^^",
+                " 1010 | a1010",
+                "      | ^^^^^",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn snippet_string() {
+        let mut lex = Lexer::new("let v = \"abc");
+
+        lex.next().unwrap();
+        lex.next().unwrap();
+        lex.next().unwrap();
+
+        let e = lex.next().err().unwrap();
+
+        assert_eq!(
+            e.to_string(),
+            "incomplete string\n    1 | let v = \"abc\n      |         ^^^^"
+        );
+    }
+
+    #[test]
+    fn tokenize_recovery() {
+        let (tokens, errors) = Lexer::new("let $x = \"abc\nfoo; /* bar").tokenize();
+        let tokens: Vec<(&str, usize, usize)> = tokens
+            .iter()
+            .map(|(_, s)| (s.as_str(), s.line(), s.column()))
+            .collect();
+        let errors: Vec<(&str, usize, usize)> = errors
+            .iter()
+            .map(|e| (e.span().as_str(), e.span().line(), e.span().column()))
+            .collect();
+
+        assert_eq!(
+            tokens,
+            [
+                ("let", 1, 1),
+                ("x", 1, 6),
+                ("=", 1, 8),
+                ("foo", 2, 1),
+                (";", 2, 4)
+            ]
+        );
+        assert_eq!(errors, [("$", 1, 5), ("\"abc", 1, 10), ("/*", 2, 6)]);
+    }
+}
@@ -12,6 +12,12 @@ pub struct Span {
 }
 
 impl Span {
+    /// Maximum number of source lines to show from the beginning of a multi-line span.
+    const SNIPPET_HEAD: usize = 4;
+
+    /// Maximum number of source lines to show from the end of a multi-line span.
+    const SNIPPET_TAIL: usize = 2;
+
     pub fn new(source: Rc<String>, offset: usize, length: usize) -> Self {
         assert_ne!(*source.as_bytes().get(offset).unwrap(), b'\n');
         assert_ne!(length, 0);
@@ -31,6 +37,24 @@ impl Span {
         self.begin
     }
 
+    /// Returns the source text that is covered by this span.
+    pub fn as_str(&self) -> &str {
+        &self.source[self.begin..self.end]
+    }
+
+    /// Returns a one-based line number where this span begin.
+    pub fn line(&self) -> usize {
+        self.source[..self.begin].matches('\n').count() + 1
+    }
+
+    /// Returns a one-based column number, in characters, where this span begin. Only LF is a line
+    /// terminator so the CR of CRLF is never counted since it is always at the end of the line.
+    pub fn column(&self) -> usize {
+        let line = self.source[..self.begin].rfind('\n').map_or(0, |i| i + 1);
+
+        self.source[line..self.begin].chars().count() + 1
+    }
+
     fn create_indicator_line(target: &str, start: usize, end: usize) -> String {
         let mut target = target.chars();
         let mut line = String::new();
@@ -50,6 +74,22 @@ impl Span {
 
         line
     }
+
+    /// Format `n` with `,` as a thousands separator regardless of the locale.
+    fn group_digits(n: usize) -> String {
+        let digits = n.to_string();
+        let mut out = String::new();
+
+        for (i, c) in digits.chars().enumerate() {
+            if i != 0 && (digits.len() - i).is_multiple_of(3) {
+                out.push(',');
+            }
+
+            out.push(c);
+        }
+
+        out
+    }
 }
 
 impl From<&Self> for Span {
@@ -92,12 +132,14 @@ impl Display for Span {
             }
 
             match ch {
+                // The CR of CRLF is not a part of the line and does not take a column. The offset
+                // still need to include it so the span boundaries are matched in bytes.
                 '\r' => {}
                 '\n' => {
                     if let Some(c) = start {
                         // Add an indicator line.
                         let l = lines.last().unwrap().0.as_str();
-                        let e = end.unwrap_or_else(|| l.len());
+                        let e = end.unwrap_or_else(|| l.chars().count());
                         let l = Self::create_indicator_line(l, c, e);
 
                         if l.chars().any(|c| !c.is_whitespace()) {
@@ -130,7 +172,7 @@ impl Display for Span {
 
         if let Some(c) = start {
             let l = lines.last().unwrap().0.as_str();
-            let e = l.len();
+            let e = end.unwrap_or_else(|| l.chars().count());
             let l = Self::create_indicator_line(l, c, e);
 
             if l.chars().any(|c| !c.is_whitespace()) {
@@ -139,23 +181,51 @@ impl Display for Span {
             }
         }
 
+        // Group each source line with its indicator line.
+        let lines = &lines[first.unwrap()..=last.unwrap()];
+        let mut groups: Vec<&[(String, Option<usize>)]> = Vec::new();
+        let mut begin = 0;
+
+        for i in 1..=lines.len() {
+            if i == lines.len() || lines[i].1.is_some() {
+                groups.push(&lines[begin..i]);
+                begin = i;
+            }
+        }
+
+        // Elide the middle of a long span so a span that covers the whole file does not flood the
+        // terminal. The first group is always shown since it contains the beginning of the span.
+        let (head, tail, omitted) = if groups.len() > Self::SNIPPET_HEAD + Self::SNIPPET_TAIL {
+            let omitted = groups.len() - Self::SNIPPET_HEAD - Self::SNIPPET_TAIL;
+
+            (Self::SNIPPET_HEAD, Self::SNIPPET_TAIL, omitted)
+        } else {
+            (groups.len(), 0, 0)
+        };
+
         // Write.
-        let first = first.unwrap();
-        let last = last.unwrap();
+        let mut out = Vec::new();
+
+        for (i, group) in groups.iter().enumerate() {
+            if i == head && omitted != 0 {
+                out.push(format!(
+                    "      | ... {} lines omitted ...",
+                    Self::group_digits(omitted)
+                ));
+            }
 
-        for i in first..=last {
-            let l = &lines[i];
+            if i >= head && i < groups.len() - tail {
+                continue;
+            }
 
-            if let Some(n) = l.1 {
-                // Line from the source is never be the last line.
-                writeln!(f, "{:>5} | {}", n + 1, l.0)?;
-            } else if i == last {
-                write!(f, "      | {}", l.0)?;
-            } else {
-                writeln!(f, "      | {}", l.0)?;
+            for (l, n) in group.iter() {
+                match n {
+                    Some(n) => out.push(format!("{:>5} | {}", n + 1, l)),
+                    None => out.push(format!("      | {}", l)),
+                }
             }
         }
 
-        Ok(())
+        f.write_str(&out.join("\n"))
     }
 }
@@ -5,6 +5,7 @@ use std::fmt::{Display, Formatter};
 pub enum Token {
     ExclamationMark(ExclamationMark),
     Equals(Equals),
+    GreaterThan(GreaterThan),
     Asterisk(Asterisk),
     FullStop(FullStop),
     Comma(Comma),
@@ -25,8 +26,11 @@ pub enum Token {
     FnKeyword(FnKeyword),
     SelfKeyword(SelfKeyword),
     LetKeyword(LetKeyword),
+    ConstKeyword(ConstKeyword),
+    AsKeyword(AsKeyword),
     IfKeyword(IfKeyword),
     IsKeyword(IsKeyword),
+    MatchKeyword(MatchKeyword),
     AsmKeyword(AsmKeyword),
     NullKeyword(NullKeyword),
     Identifier(Identifier),
@@ -34,23 +38,52 @@ pub enum Token {
 
 impl Token {
     pub fn is_full_stop(&self) -> bool {
-        match self {
-            Self::FullStop(_) => true,
-            _ => false,
-        }
+        matches!(self, Self::FullStop(_))
     }
 
     pub fn is_self(&self) -> bool {
-        match self {
-            Self::SelfKeyword(_) => true,
-            _ => false,
-        }
+        matches!(self, Self::SelfKeyword(_))
     }
 
     pub fn is_identifier(&self) -> bool {
+        matches!(self, Self::Identifier(_))
+    }
+
+    /// Returns the category of this token for the tools that does not need the exact token (e.g.
+    /// syntax highlighting).
+    pub fn kind(&self) -> &'static str {
         match self {
-            Self::Identifier(_) => true,
-            _ => false,
+            Self::ExclamationMark(_)
+            | Self::Equals(_)
+            | Self::GreaterThan(_)
+            | Self::Asterisk(_)
+            | Self::FullStop(_)
+            | Self::Comma(_)
+            | Self::Colon(_)
+            | Self::Semicolon(_)
+            | Self::OpenParenthesis(_)
+            | Self::CloseParenthesis(_)
+            | Self::OpenCurly(_)
+            | Self::CloseCurly(_) => "punctuation",
+            Self::AttributeName(_) => "attribute",
+            Self::UnsignedLiteral(_) => "integer",
+            Self::FloatLiteral(_) => "float",
+            Self::StringLiteral(_) => "string",
+            Self::UseKeyword(_)
+            | Self::StructKeyword(_)
+            | Self::ClassKeyword(_)
+            | Self::ImplKeyword(_)
+            | Self::FnKeyword(_)
+            | Self::SelfKeyword(_)
+            | Self::LetKeyword(_)
+            | Self::ConstKeyword(_)
+            | Self::AsKeyword(_)
+            | Self::IfKeyword(_)
+            | Self::IsKeyword(_)
+            | Self::MatchKeyword(_)
+            | Self::AsmKeyword(_)
+            | Self::NullKeyword(_) => "keyword",
+            Self::Identifier(_) => "identifier",
         }
     }
 
@@ -58,6 +91,7 @@ impl Token {
         match self {
             Self::ExclamationMark(v) => &v.0,
             Self::Equals(v) => &v.0,
+            Self::GreaterThan(v) => &v.0,
             Self::Asterisk(v) => &v.0,
             Self::FullStop(v) => &v.0,
             Self::Comma(v) => &v.0,
@@ -78,8 +112,11 @@ impl Token {
             Self::FnKeyword(v) => &v.0,
             Self::SelfKeyword(v) => &v.0,
             Self::LetKeyword(v) => &v.0,
+            Self::ConstKeyword(v) => &v.0,
+            Self::AsKeyword(v) => &v.0,
             Self::IfKeyword(v) => &v.0,
             Self::IsKeyword(v) => &v.0,
+            Self::MatchKeyword(v) => &v.0,
             Self::AsmKeyword(v) => &v.0,
             Self::NullKeyword(v) => &v.0,
             Self::Identifier(v) => &v.span,
@@ -99,6 +136,12 @@ impl From<Equals> for Token {
     }
 }
 
+impl From<GreaterThan> for Token {
+    fn from(value: GreaterThan) -> Self {
+        Self::GreaterThan(value)
+    }
+}
+
 impl From<Asterisk> for Token {
     fn from(value: Asterisk) -> Self {
         Self::Asterisk(value)
@@ -219,6 +262,18 @@ impl From<LetKeyword> for Token {
     }
 }
 
+impl From<ConstKeyword> for Token {
+    fn from(value: ConstKeyword) -> Self {
+        Self::ConstKeyword(value)
+    }
+}
+
+impl From<AsKeyword> for Token {
+    fn from(value: AsKeyword) -> Self {
+        Self::AsKeyword(value)
+    }
+}
+
 impl From<IfKeyword> for Token {
     fn from(value: IfKeyword) -> Self {
         Self::IfKeyword(value)
@@ -231,6 +286,12 @@ impl From<IsKeyword> for Token {
     }
 }
 
+impl From<MatchKeyword> for Token {
+    fn from(value: MatchKeyword) -> Self {
+        Self::MatchKeyword(value)
+    }
+}
+
 impl From<AsmKeyword> for Token {
     fn from(value: AsmKeyword) -> Self {
         Self::AsmKeyword(value)
@@ -254,6 +315,7 @@ impl Display for Token {
         let t: &dyn Display = match self {
             Self::ExclamationMark(v) => v,
             Self::Equals(v) => v,
+            Self::GreaterThan(v) => v,
             Self::Asterisk(v) => v,
             Self::FullStop(v) => v,
             Self::Comma(v) => v,
@@ -274,8 +336,11 @@ impl Display for Token {
             Self::FnKeyword(v) => v,
             Self::SelfKeyword(v) => v,
             Self::LetKeyword(v) => v,
+            Self::ConstKeyword(v) => v,
+            Self::AsKeyword(v) => v,
             Self::IfKeyword(v) => v,
             Self::IsKeyword(v) => v,
+            Self::MatchKeyword(v) => v,
             Self::AsmKeyword(v) => v,
             Self::NullKeyword(v) => v,
             Self::Identifier(v) => v,
@@ -323,6 +388,21 @@ impl Display for Equals {
     }
 }
 
+/// An `>` token.
+pub struct GreaterThan(Span);
+
+impl GreaterThan {
+    pub fn new(span: Span) -> Self {
+        Self(span)
+    }
+}
+
+impl Display for GreaterThan {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(">")
+    }
+}
+
 /// An `*` token.
 pub struct Asterisk(Span);
 
@@ -516,6 +596,10 @@ impl UnsignedLiteral {
     pub fn span(&self) -> &Span {
         &self.span
     }
+
+    pub fn value(&self) -> u64 {
+        self.value
+    }
 }
 
 impl Display for UnsignedLiteral {
@@ -534,11 +618,26 @@ impl FloatLiteral {
     pub fn new(span: Span, value: f64) -> Self {
         Self { span, value }
     }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    pub fn value(&self) -> f64 {
+        self.value
+    }
 }
 
 impl Display for FloatLiteral {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        self.value.fmt(f)
+        self.value.fmt(f)?;
+
+        // Keep the literal as a float when it is formatted back to the source.
+        if self.value.fract() == 0.0 {
+            f.write_str(".0")?;
+        }
+
+        Ok(())
     }
 }
 
@@ -683,6 +782,10 @@ impl LetKeyword {
     pub fn new(span: Span) -> Self {
         Self(span)
     }
+
+    pub fn span(&self) -> &Span {
+        &self.0
+    }
 }
 
 impl Display for LetKeyword {
@@ -691,6 +794,44 @@ impl Display for LetKeyword {
     }
 }
 
+/// An `const` keyword.
+pub struct ConstKeyword(Span);
+
+impl ConstKeyword {
+    pub fn new(span: Span) -> Self {
+        Self(span)
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.0
+    }
+}
+
+impl Display for ConstKeyword {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("const")
+    }
+}
+
+/// An `as` keyword.
+pub struct AsKeyword(Span);
+
+impl AsKeyword {
+    pub fn new(span: Span) -> Self {
+        Self(span)
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.0
+    }
+}
+
+impl Display for AsKeyword {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("as")
+    }
+}
+
 /// An `if` keyword.
 pub struct IfKeyword(Span);
 
@@ -725,6 +866,25 @@ impl Display for IsKeyword {
     }
 }
 
+/// An `match` keyword.
+pub struct MatchKeyword(Span);
+
+impl MatchKeyword {
+    pub fn new(span: Span) -> Self {
+        Self(span)
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.0
+    }
+}
+
+impl Display for MatchKeyword {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("match")
+    }
+}
+
 /// An `asm` keyword.
 pub struct AsmKeyword(Span);
 
@@ -0,0 +1,33 @@
+use thiserror::Error;
+
+/// Maximum length of an identifier in bytes.
+pub const MAX_IDENT: usize = 512;
+
+/// Maximum length of a parameter name in bytes. The length is stored as `u8` in the package.
+pub const MAX_PARAM_NAME: usize = u8::MAX as usize;
+
+/// Maximum length of a fully qualified type name in bytes. The length is stored as `u16` in the
+/// package.
+pub const MAX_TYPE_NAME: usize = u16::MAX as usize;
+
+/// Convert `len` to the integer that is used to store it. `what` is the name of the item to
+/// report when `len` does not fit.
+///
+/// The front end should already reject the names that exceed the limits so this is the last line
+/// of defense for the serializers.
+pub fn encode_len<T: TryFrom<usize>>(len: usize, what: &'static str) -> std::io::Result<T> {
+    T::try_from(len).map_err(|_| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            LengthOverflowError { what, len },
+        )
+    })
+}
+
+/// Represents an error when the length of an item is too large to be stored.
+#[derive(Debug, Error)]
+#[error("{what} is too long ({len} bytes)")]
+pub struct LengthOverflowError {
+    what: &'static str,
+    len: usize,
+}
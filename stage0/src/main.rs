@@ -1,34 +1,160 @@
-use crate::ast::ParseError;
-use crate::ffi::llvm_init;
+use crate::ast::{AuditFile, ParseError, SourceFile};
+use crate::codegen::BuildError;
+use crate::ffi::{llvm_init, llvm_version, ZSTD_versionString};
+use crate::lexer::Lexer;
 use crate::pkg::{
-    DependencyResolver, Package, PackageName, PrimitiveTarget, Target, TargetResolver,
+    validate_for_pack, Dependency, DependencyKind, DependencyResolveError, DependencyResolver,
+    Function, LibraryBinary, Package, PackageName, PrimitiveTarget, PrimitiveTargetError, Target,
+    TargetResolver,
 };
-use crate::project::{Project, ProjectBuildError, ProjectLoadError};
+use crate::project::{
+    is_define_key, stubs_subdir, Fingerprint, Project, ProjectBuildError, ProjectLoadError,
+    ProjectOpenError,
+};
+use crate::zstd::ZstdDictionary;
 use clap::{command, value_parser, Arg, ArgAction, ArgMatches, Command};
 use dirs::home_dir;
 use std::borrow::Cow;
+use std::collections::BTreeSet;
 use std::error::Error;
-use std::fs::OpenOptions;
-use std::io::Write;
+use std::ffi::{CStr, OsString};
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, Read, Write};
+use std::panic::catch_unwind;
 use std::path::{Path, PathBuf};
-use std::process::ExitCode;
+use std::process::{ExitCode, ExitStatus};
 
 mod ast;
 mod codegen;
 mod ffi;
+mod hash;
 mod lexer;
+mod limit;
 mod pkg;
 mod project;
 mod zstd;
 
+/// The help text of the exit status. This is a contract with the scripts so the existing codes must
+/// not be changed.
+const EXIT_STATUS: &str = "\
+Exit status:
+  0    Success
+  1    Errors in the project (e.g. syntax errors or failed tests)
+  2    Invalid arguments (e.g. unknown target)
+  3    Errors in the environment (e.g. missing stubs or the cache is not writable)
+  4    Link errors
+  5    Invalid package (e.g. a corrupted package or packages that cannot be merged)
+  6    The project cannot be loaded (e.g. invalid Nitro.yml or unreadable sources)
+  7    The package cannot be packed (e.g. invalid metadata for publishing)
+  8    The binaries cannot be exported
+  101  Internal compiler error";
+
 fn main() -> ExitCode {
+    match catch_unwind(run) {
+        Ok(v) => v,
+        Err(e) => {
+            let msg = match e.downcast_ref::<&str>() {
+                Some(&v) => v,
+                None => match e.downcast_ref::<String>() {
+                    Some(v) => v.as_str(),
+                    None => "unknown panic",
+                },
+            };
+
+            eprintln!(
+                "Internal compiler error: {msg}. Please report this to the Nitro developers."
+            );
+
+            ExitCode::from(Failure::INTERNAL)
+        }
+    }
+}
+
+fn run() -> ExitCode {
     // Parse arguments.
     let project = Arg::new("project")
         .help("Path to the project (default to current directory)")
         .value_name("PROJECT")
         .value_parser(value_parser!(PathBuf));
+    let no_strip = Arg::new("no-strip")
+        .help("Keep symbols in the executables")
+        .long("no-strip")
+        .action(ArgAction::SetTrue);
+    let strict = Arg::new("strict")
+        .help("Treat warnings as errors")
+        .long("strict")
+        .action(ArgAction::SetTrue);
+    let emit = Arg::new("emit")
+        .help("Write additional outputs next to the object files")
+        .long("emit")
+        .value_name("KIND")
+        .value_parser(["deps"]);
+    let out_dir = Arg::new("out-dir")
+        .help("Directory to place the build artifacts (default to .build in the sources directory)")
+        .long("out-dir")
+        .value_name("DIR")
+        .value_parser(value_parser!(PathBuf));
+    let dry_run = Arg::new("dry-run")
+        .help("Build the project and print the outputs without writing them")
+        .long("dry-run")
+        .action(ArgAction::SetTrue);
+    let define = Arg::new("define")
+        .help("Set a key that can be tested with @if (override defines in Nitro.yml)")
+        .long("define")
+        .value_name("KEY=VALUE")
+        .value_parser(parse_define)
+        .action(ArgAction::Append);
+    let dictionary = Arg::new("dictionary")
+        .help("Path to a zstd dictionary the package was compressed with")
+        .long("dictionary")
+        .value_name("FILE")
+        .value_parser(value_parser!(PathBuf))
+        .action(ArgAction::Append);
     let args = command!()
+        .long_version(long_version())
         .subcommand_required(true)
+        .after_help(EXIT_STATUS)
+        .arg(
+            Arg::new("host")
+                .help(
+                    "Triple of the machine to run the outputs (default to NITRO_HOST or detected)",
+                )
+                .long("host")
+                .value_name("TRIPLE")
+                .global(true),
+        )
+        .arg(
+            Arg::new("max-parse-depth")
+                .long("max-parse-depth")
+                .value_name("DEPTH")
+                .value_parser(value_parser!(u64).range(1..))
+                .global(true)
+                .hide(true),
+        )
+        .arg(
+            Arg::new("repair-cache")
+                .help("Unpack a cached dependency again if it does not match the requested one")
+                .long("repair-cache")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("verbose")
+                .help("Print more information")
+                .short('v')
+                .long("verbose")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("quiet")
+                .help("Do not print progress information")
+                .short('q')
+                .long("quiet")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("verbose")
+                .global(true),
+        )
         .subcommand(
             Command::new("init")
                 .about("Create a Nitro project in an existing directory")
@@ -48,6 +174,88 @@ fn main() -> ExitCode {
         .subcommand(
             Command::new("build")
                 .about("Build a Nitro project")
+                .arg(
+                    Arg::new("print-stubs-dir")
+                        .help("Print the stubs directory for each target then exit")
+                        .long("print-stubs-dir")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("allow-no-entry")
+                        .help("Generate an entry point that exit with zero if no @entry function")
+                        .long("allow-no-entry")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("size-report")
+                        .help("Write the size of each function to size-report.yml in the workspace")
+                        .long("size-report")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("emit-deps")
+                        .help("Write a Makefile-style dependency file for the whole build")
+                        .long("emit-deps")
+                        .value_name("FILE")
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(no_strip.clone())
+                .arg(strict.clone())
+                .arg(emit.clone())
+                .arg(out_dir.clone())
+                .arg(define.clone())
+                .arg(project.clone()),
+        )
+        .subcommand(
+            Command::new("check")
+                .about("Check a Nitro project for syntax errors")
+                .arg(
+                    Arg::new("stdin")
+                        .help("Read a single source file from stdin and report it as PATH")
+                        .long("stdin")
+                        .value_name("PATH")
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("signatures")
+                        .help("Print the signature of all functions in the source as YAML")
+                        .long("signatures")
+                        .action(ArgAction::SetTrue)
+                        .requires("stdin"),
+                )
+                .arg(
+                    Arg::new("tokens")
+                        .help("Print all tokens in the source instead of parsing it")
+                        .long("tokens")
+                        .action(ArgAction::SetTrue)
+                        .requires("stdin")
+                        .conflicts_with("signatures"),
+                )
+                .arg(define.clone())
+                .arg(project.clone()),
+        )
+        .subcommand(
+            Command::new("audit")
+                .about("Report extern functions, asm expressions and public pointers in a project")
+                .arg(
+                    Arg::new("format")
+                        .help("Format of the report")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .value_parser(["text", "json"])
+                        .default_value("text"),
+                )
+                .arg(project.clone()),
+        )
+        .subcommand(
+            Command::new("fmt")
+                .about("Format all source files in a Nitro project")
+                .arg(
+                    Arg::new("check")
+                        .help("Report the unformatted files instead of rewriting it")
+                        .long("check")
+                        .action(ArgAction::SetTrue),
+                )
                 .arg(project.clone()),
         )
         .subcommand(
@@ -61,11 +269,276 @@ fn main() -> ExitCode {
                         .value_name("FILE")
                         .value_parser(value_parser!(PathBuf)),
                 )
+                .arg(
+                    dictionary
+                        .clone()
+                        .help("Path to a zstd dictionary to compress the package with")
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("previous")
+                        .help("Path to the previous release to check the version against")
+                        .long("previous")
+                        .value_name("FILE")
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("allow-inconsistent")
+                        .help("Pack the package even if its binaries do not agree with each other")
+                        .long("allow-inconsistent")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(dry_run.clone())
+                .arg(no_strip.clone())
+                .arg(strict.clone())
+                .arg(emit.clone())
+                .arg(out_dir.clone())
+                .arg(define.clone())
+                .arg(project.clone()),
+        )
+        .subcommand(
+            Command::new("inspect")
+                .about("Show information of a Nitro package")
+                .arg(
+                    Arg::new("readme")
+                        .help("Print the readme of the package")
+                        .long("readme")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("verify")
+                        .help("Check if the binaries of the package agree with each other")
+                        .long("verify")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(dictionary.clone())
+                .arg(
+                    Arg::new("package")
+                        .help("Path to the package")
+                        .value_name("PACKAGE")
+                        .value_parser(value_parser!(PathBuf))
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("merge")
+                .about("Combine the binaries of two Nitro packages into a single package")
+                .arg(
+                    Arg::new("output")
+                        .help("Path of the output file")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .value_parser(value_parser!(PathBuf))
+                        .required(true),
+                )
+                .arg(dictionary.clone().help(
+                    "Path to a zstd dictionary the packages were compressed with (the first one \
+                    will be used to compress the output)",
+                ))
+                .arg(
+                    Arg::new("packages")
+                        .help("Path to the packages to merge")
+                        .value_name("PACKAGE")
+                        .value_parser(value_parser!(PathBuf))
+                        .num_args(2)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("extract")
+                .about("Extract a binary from a Nitro package")
+                .arg(
+                    Arg::new("target")
+                        .help("Target of the binary")
+                        .long("target")
+                        .value_name("TRIPLE")
+                        .value_parser(|v: &str| v.parse::<&'static PrimitiveTarget>())
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("kind")
+                        .help("Kind of the binary")
+                        .long("kind")
+                        .value_name("KIND")
+                        .value_parser(["exe", "lib"])
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("output")
+                        .help("Path of the output file")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .value_parser(value_parser!(PathBuf))
+                        .required(true),
+                )
+                .arg(dictionary.clone())
+                .arg(
+                    Arg::new("package")
+                        .help("Path to the package")
+                        .value_name("PACKAGE")
+                        .value_parser(value_parser!(PathBuf))
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("unpack")
+                .about("Unpack a Nitro package to a directory")
+                .arg(dictionary.clone())
+                .arg(
+                    Arg::new("package")
+                        .help("Path to the package")
+                        .value_name("FILE")
+                        .value_parser(value_parser!(PathBuf))
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("output")
+                        .help("Path to the directory to unpack the package (created if missing)")
+                        .value_name("DIR")
+                        .value_parser(value_parser!(PathBuf))
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("deps")
+                .about("List the run-time dependencies of all libraries in a Nitro package")
+                .arg(dictionary.clone())
+                .arg(
+                    Arg::new("package")
+                        .help("Path to the package")
+                        .value_name("PACKAGE")
+                        .value_parser(value_parser!(PathBuf))
+                        .required(true),
+                ),
+        )
+        .subcommand(Command::new("targets").about("List all supported targets"))
+        .subcommand(
+            Command::new("demangle")
+                .about("Convert the symbols of Nitro functions to readable signatures")
+                .arg(
+                    Arg::new("symbols")
+                        .help("Symbols to convert (read the text from stdin if not specified)")
+                        .value_name("SYMBOL")
+                        .num_args(1..),
+                ),
+        )
+        .subcommand(
+            Command::new("graph")
+                .about("Print the dependencies of a project")
+                .arg(
+                    Arg::new("format")
+                        .help("Format of the output")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .value_parser(["text", "dot"])
+                        .default_value("text"),
+                )
+                .arg(project.clone()),
+        )
+        .subcommand(
+            Command::new("fingerprint")
+                .about("Print the inputs of the build")
+                .arg(
+                    Arg::new("target")
+                        .help("Target of the build (default to the current target)")
+                        .long("target")
+                        .value_name("TRIPLE")
+                        .value_parser(|v: &str| v.parse::<&'static PrimitiveTarget>()),
+                )
+                .arg(
+                    Arg::new("compare")
+                        .help("Report the first difference with the specified fingerprint instead")
+                        .long("compare")
+                        .value_name("FILE")
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(project.clone()),
+        )
+        .subcommand(
+            Command::new("test")
+                .about("Build and run the tests")
+                .arg(
+                    Arg::new("target")
+                        .help("Target to build the tests (default to the current target)")
+                        .long("target")
+                        .value_name("TRIPLE")
+                        .value_parser(|v: &str| v.parse::<&'static PrimitiveTarget>()),
+                )
+                .arg(out_dir.clone())
+                .arg(project.clone()),
+        )
+        .subcommand(
+            Command::new("cheader")
+                .about("Generate a C header for the @ext(C) functions in the library")
+                .arg(
+                    Arg::new("output")
+                        .help("Path of the output file (default to NAME.h)")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("target")
+                        .help("Target of the library to read (default to the current target)")
+                        .long("target")
+                        .value_name("TRIPLE")
+                        .value_parser(|v: &str| v.parse::<&'static PrimitiveTarget>()),
+                )
+                .arg(no_strip.clone())
+                .arg(strict.clone())
+                .arg(emit.clone())
+                .arg(out_dir.clone())
+                .arg(define.clone())
+                .arg(project.clone()),
+        )
+        .subcommand(
+            Command::new("clean")
+                .about("Remove the build artifacts in the sources directories")
+                .arg(project.clone()),
+        )
+        .subcommand(
+            Command::new("run")
+                .about("Build the project and run its executable")
+                .arg(
+                    Arg::new("args")
+                        .help("Arguments to pass to the executable")
+                        .value_name("ARGS")
+                        .value_parser(value_parser!(OsString))
+                        .num_args(0..)
+                        .last(true),
+                )
+                .arg(no_strip.clone())
+                .arg(strict.clone())
+                .arg(emit.clone())
+                .arg(out_dir.clone())
+                .arg(define.clone())
                 .arg(project.clone()),
         )
         .subcommand(
             Command::new("export")
                 .about("Export binaries")
+                .arg(
+                    Arg::new("assets")
+                        .help("Copy the package assets next to the binaries")
+                        .long("assets")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("with-deps")
+                        .help("Copy the shared libraries of runtime dependencies next to the binaries")
+                        .long("with-deps")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("target")
+                        .help("Target of the binaries to export (default to the current target)")
+                        .long("target")
+                        .value_name("TRIPLE")
+                        .value_parser(|v: &str| v.parse::<&'static PrimitiveTarget>()),
+                )
                 .arg(
                     Arg::new("outputs")
                         .help("Path to the directory to place the binaries")
@@ -73,6 +546,12 @@ fn main() -> ExitCode {
                         .value_parser(value_parser!(PathBuf))
                         .required(true),
                 )
+                .arg(dry_run)
+                .arg(no_strip)
+                .arg(strict)
+                .arg(emit)
+                .arg(out_dir)
+                .arg(define)
                 .arg(project),
         )
         .get_matches();
@@ -82,7 +561,7 @@ fn main() -> ExitCode {
         Ok(v) => v,
         Err(e) => {
             eprintln!("Cannot get path of the executable: {}.", join_nested(&e));
-            return ExitCode::FAILURE;
+            return Failure::Environment.into();
         }
     };
 
@@ -94,7 +573,7 @@ fn main() -> ExitCode {
                 exe.display(),
                 join_nested(&e)
             );
-            return ExitCode::FAILURE;
+            return Failure::Environment.into();
         }
     };
 
@@ -109,7 +588,7 @@ fn main() -> ExitCode {
                     exe.display(),
                     join_nested(&e)
                 );
-                return ExitCode::FAILURE;
+                return Failure::Environment.into();
             }
         }
     };
@@ -119,18 +598,64 @@ fn main() -> ExitCode {
 
     home.push(".nitro");
 
-    // Get std path.
-    let prefix = exe.parent().unwrap().parent().unwrap();
-    let mut std = prefix.join("share");
+    // Get the stubs directory. We look for share/nitro/stub in the parents of the executable so
+    // it does not depends on the layout of the installation. The standard library is always next to
+    // the stubs directory.
+    let stubs = match find_stubs(&exe) {
+        Some(v) => v,
+        None => match std::env::var_os("NITRO_STUBS").filter(|v| !v.is_empty()) {
+            Some(v) => PathBuf::from(v),
+            None => {
+                eprintln!(
+                    "Cannot find share/nitro/stub in any parent of {}. Set NITRO_STUBS to the \
+                    location of the stubs to use a different layout.",
+                    exe.display()
+                );
+                return Failure::Environment.into();
+            }
+        },
+    };
+
+    let std = match stubs.parent() {
+        Some(v) => v.join("nitro.npk"),
+        None => {
+            eprintln!("{} cannot be used as a stubs directory.", stubs.display());
+            return Failure::Environment.into();
+        }
+    };
+
+    // Get host target.
+    let flag = args.get_one::<String>("host").map(|v| v.as_str());
+    let env = std::env::var("NITRO_HOST").ok().filter(|v| !v.is_empty());
+    let host = match resolve_host(flag, env.as_deref()) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Cannot determine the host target: {}.", join_nested(&e));
+
+            // The detection can only fail when the host is not supported.
+            return if flag.is_some() || env.is_some() {
+                Failure::Usage.into()
+            } else {
+                Failure::Environment.into()
+            };
+        }
+    };
+
+    if args.get_flag("verbose") {
+        eprintln!("Host: {host}");
+    }
+
+    // Setup dependency resolver.
+    let mut deps = DependencyResolver::new(home.join("packages"), std);
 
-    std.push("nitro");
-    std.push("nitro.npk");
+    deps.set_repair_cache(args.get_flag("repair-cache"));
 
     // Execute the command.
     let cx = Context {
-        prefix,
+        stubs,
+        host,
         targets: TargetResolver::new(),
-        deps: DependencyResolver::new(home.join("packages"), std),
+        deps,
     };
 
     match args.subcommand().unwrap() {
@@ -138,12 +663,29 @@ fn main() -> ExitCode {
             Ok(_) => ExitCode::SUCCESS,
             Err(v) => v,
         },
+        ("build", args) if args.get_flag("print-stubs-dir") => print_stubs_dir(&cx),
         ("build", args) => match build(args, &cx) {
             Ok(_) => ExitCode::SUCCESS,
             Err(v) => v,
         },
+        ("check", args) => check(args, &cx),
+        ("audit", args) => audit(args, &cx),
+        ("fmt", args) => fmt(args, &cx),
         ("pack", args) => pack(args, &cx),
         ("export", args) => export(args, &cx),
+        ("inspect", args) => inspect(args, &cx),
+        ("merge", args) => merge(args, &cx),
+        ("extract", args) => extract(args, &cx),
+        ("unpack", args) => unpack(args),
+        ("deps", args) => list_deps(args, &cx),
+        ("targets", _) => targets(&cx),
+        ("demangle", args) => demangle(args),
+        ("graph", args) => graph(args, &cx),
+        ("fingerprint", args) => fingerprint(args, &cx),
+        ("test", args) => test(args, &cx),
+        ("run", args) => run_project(args, &cx),
+        ("cheader", args) => cheader(args, &cx),
+        ("clean", args) => clean(args, &cx),
         _ => todo!(),
     }
 }
@@ -164,12 +706,12 @@ fn init(args: &ArgMatches) -> Result<(), ExitCode> {
                     "The name of project directory is not a valid project name: {}.",
                     join_nested(&e)
                 );
-                return Err(ExitCode::FAILURE);
+                return Err(Failure::Usage.into());
             }
         },
         None => {
             eprintln!("The location th create the project cannot be a root of filesystem.");
-            return Err(ExitCode::FAILURE);
+            return Err(Failure::Usage.into());
         }
     };
 
@@ -180,7 +722,7 @@ fn init(args: &ArgMatches) -> Result<(), ExitCode> {
         Ok(v) => v,
         Err(e) => {
             eprintln!("Cannot create {}: {}.", proj.display(), join_nested(&e));
-            return Err(ExitCode::FAILURE);
+            return Err(Failure::Environment.into());
         }
     };
 
@@ -208,7 +750,7 @@ fn init(args: &ArgMatches) -> Result<(), ExitCode> {
         Ok(v) => v,
         Err(e) => {
             eprintln!("Cannot create {}: {}.", ignore.display(), join_nested(&e));
-            return Err(ExitCode::FAILURE);
+            return Err(Failure::Environment.into());
         }
     };
 
@@ -222,7 +764,7 @@ fn init_exe(src: PathBuf) -> Result<(), ExitCode> {
     if let Err(e) = std::fs::create_dir(&src) {
         if e.kind() != std::io::ErrorKind::AlreadyExists {
             eprintln!("Cannot create {}: {}.", src.display(), join_nested(&e));
-            return Err(ExitCode::FAILURE);
+            return Err(Failure::Environment.into());
         }
     }
 
@@ -232,12 +774,10 @@ fn init_exe(src: PathBuf) -> Result<(), ExitCode> {
         Ok(v) => v,
         Err(e) => {
             eprintln!("Cannot create {}: {}.", path.display(), join_nested(&e));
-            return Err(ExitCode::FAILURE);
+            return Err(Failure::Environment.into());
         }
     };
 
-    writeln!(app, "use nitro.Int32;").unwrap();
-    writeln!(app).unwrap();
     writeln!(app, "class App;").unwrap();
     writeln!(app).unwrap();
     writeln!(app, "impl App {{").unwrap();
@@ -255,7 +795,7 @@ fn init_lib(src: PathBuf) -> Result<(), ExitCode> {
     if let Err(e) = std::fs::create_dir(&src) {
         if e.kind() != std::io::ErrorKind::AlreadyExists {
             eprintln!("Cannot create {}: {}.", src.display(), join_nested(&e));
-            return Err(ExitCode::FAILURE);
+            return Err(Failure::Environment.into());
         }
     }
 
@@ -265,12 +805,10 @@ fn init_lib(src: PathBuf) -> Result<(), ExitCode> {
         Ok(v) => v,
         Err(e) => {
             eprintln!("Cannot create {}: {}.", path.display(), join_nested(&e));
-            return Err(ExitCode::FAILURE);
+            return Err(Failure::Environment.into());
         }
     };
 
-    writeln!(class, "use nitro.Int32;").unwrap();
-    writeln!(class).unwrap();
     writeln!(class, "@pub").unwrap();
     writeln!(class, "class SampleClass;").unwrap();
     writeln!(class).unwrap();
@@ -285,87 +823,705 @@ fn init_lib(src: PathBuf) -> Result<(), ExitCode> {
 }
 
 fn build(args: &ArgMatches, cx: &Context) -> Result<Package, ExitCode> {
-    // Initialize LLVM.
-    unsafe { llvm_init() };
+    let stubs = stubs(cx);
+    let project = load_for_build(args, cx, &stubs)?;
 
-    // Get path to the project.
-    let path = match args.get_one::<PathBuf>("project") {
-        Some(v) => Cow::Borrowed(v.as_path()),
-        None => Cow::Owned(std::env::current_dir().unwrap()),
-    };
+    build_project(args, &project)
+}
 
-    // Get path to stubs.
-    let mut stubs = cx.prefix.join("share");
+/// Open the project and apply the build options in `args`.
+fn load_for_build<'a>(
+    args: &ArgMatches,
+    cx: &'a Context,
+    stubs: &'a Path,
+) -> Result<Project<'a>, ExitCode> {
+    let mut project = load(args, cx, stubs)?;
 
-    stubs.push("nitro");
-    stubs.push("stub");
+    project.set_strip(!args.get_flag("no-strip"));
+    project.set_strict(args.get_flag("strict"));
+    project.set_emit_deps(args.get_one::<String>("emit").is_some_and(|v| v == "deps"));
 
-    // Open the project.
-    let mut project = match Project::open(path.as_ref(), &cx.targets, &stubs, &cx.deps) {
-        Ok(v) => v,
-        Err(e) => {
-            eprintln!("Cannot open {}: {}.", path.display(), join_nested(&e));
-            return Err(ExitCode::FAILURE);
-        }
-    };
+    if let Some(v) = args.get_one::<PathBuf>("out-dir") {
+        project.set_out_dir(v.clone());
+    }
 
-    // Load the project.
-    if let Err(e) = project.load() {
-        match e {
-            ProjectLoadError::ParseSourceFailed(p, ParseError::ParseFailed(e)) => {
-                eprintln!("{}: {}", p.display(), e);
-            }
-            e => eprintln!(
-                "Cannot load {}: {}.",
-                project.path().display(),
-                join_nested(&e)
-            ),
-        }
+    // Only the build command accepts --allow-no-entry so a package will always have a real entry.
+    if let Ok(Some(&v)) = args.try_get_one::<bool>("allow-no-entry") {
+        project.set_allow_no_entry(v);
+    }
 
-        return Err(ExitCode::FAILURE);
+    if let Ok(Some(&v)) = args.try_get_one::<bool>("size-report") {
+        project.set_size_report(v);
     }
 
-    // Build the project.
-    project.build().map_err(|e| {
-        match e {
-            ProjectBuildError::InvalidSyntax(p, e) => {
-                eprintln!("{}: {}", p.display(), e);
-            }
-            ProjectBuildError::BuildFailed(p, e) => {
-                eprintln!("Cannot build {}: {}", p.display(), e);
-            }
-            e => eprintln!("{}: {}", project.path().display(), join_nested(&e)),
-        }
+    if let Ok(Some(v)) = args.try_get_one::<PathBuf>("emit-deps") {
+        project.set_deps_file(v.clone());
+    }
 
-        ExitCode::FAILURE
-    })
+    Ok(project)
 }
 
-fn pack(args: &ArgMatches, cx: &Context) -> ExitCode {
-    // Build.
-    let pkg = match build(args, cx) {
-        Ok(v) => v,
-        Err(e) => return e,
-    };
+fn build_project(args: &ArgMatches, project: &Project) -> Result<Package, ExitCode> {
+    // Initialize LLVM.
+    unsafe { llvm_init() };
 
-    // Get output path.
-    let path = match args.get_one::<PathBuf>("output") {
-        Some(v) => Cow::Borrowed(v.as_path()),
-        None => {
-            let mut p = std::env::current_dir().unwrap();
-            p.push(format!("{}.npk", pkg.meta().name()));
-            Cow::Owned(p)
-        }
-    };
+    // Build the project.
+    let pkg = project
+        .build()
+        .map_err(|e| report_build_error(project, e))?;
 
-    // Pack.
-    if let Err(e) = pkg.pack(path.as_ref()) {
-        eprintln!("Cannot pack {}: {}.", path.display(), join_nested(&e));
-        return ExitCode::FAILURE;
-    }
+    // Report the targets that was built.
+    if !args.get_flag("quiet") {
+        let mut built: Vec<(String, &str)> = pkg
+            .libs()
+            .keys()
+            .map(|t| (t.to_string(), "library"))
+            .chain(pkg.exes().keys().map(|t| (t.to_string(), "executable")))
+            .collect();
 
-    ExitCode::SUCCESS
-}
+        built.sort();
+
+        for (target, kind) in built {
+            eprintln!("Built {kind} for {target}.");
+        }
+    }
+
+    Ok(pkg)
+}
+
+fn print_stubs_dir(cx: &Context) -> ExitCode {
+    let stubs = stubs(cx);
+
+    println!("{}", stubs.display());
+
+    for t in &PrimitiveTarget::ALL {
+        println!("{}: {}", t, stubs.join(stubs_subdir(t)).display());
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn targets(cx: &Context) -> ExitCode {
+    let stubs = stubs(cx);
+
+    if let Err(e) = write_targets(std::io::stdout().lock(), &stubs) {
+        eprintln!("Cannot write the targets: {}.", join_nested(&e));
+        return Failure::Environment.into();
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Write each primitive target as `TRIPLE ID SUBDIR MARK` where `MARK` tells whether the stubs
+/// directory for it exists in `stubs`.
+fn write_targets<W: Write>(mut w: W, stubs: &Path) -> Result<(), std::io::Error> {
+    for t in &PrimitiveTarget::ALL {
+        let dir = stubs_subdir(t);
+        let mark = if stubs.join(dir).is_dir() {
+            '\u{2713}'
+        } else {
+            '\u{2717}'
+        };
+
+        writeln!(w, "{} {} {} {}", t, t.id(), dir, mark)?;
+    }
+
+    Ok(())
+}
+
+fn demangle(args: &ArgMatches) -> ExitCode {
+    // Convert the symbols from the arguments.
+    if let Some(syms) = args.get_many::<String>("symbols") {
+        for s in syms {
+            match Function::demangle(s) {
+                Some(v) => println!("{v}"),
+                None => println!("{s}"),
+            }
+        }
+
+        return ExitCode::SUCCESS;
+    }
+
+    // Convert all symbols in the text from stdin (e.g. a crash dump or a linker error).
+    for line in std::io::stdin().lines() {
+        let line = match line {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("Cannot read stdin: {}.", join_nested(&e));
+                return Failure::Environment.into();
+            }
+        };
+
+        let mut out = String::new();
+        let mut rest = line.as_str();
+
+        while let Some(i) = rest.find(|c: char| c == '_' || c.is_ascii_alphanumeric()) {
+            let len = rest[i..]
+                .find(|c: char| c != '_' && !c.is_ascii_alphanumeric())
+                .unwrap_or(rest.len() - i);
+            let word = &rest[i..(i + len)];
+
+            out.push_str(&rest[..i]);
+
+            match Function::demangle(word) {
+                Some(v) => out.push_str(&v),
+                None => out.push_str(word),
+            }
+
+            rest = &rest[(i + len)..];
+        }
+
+        out.push_str(rest);
+
+        println!("{out}");
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn fingerprint(args: &ArgMatches, cx: &Context) -> ExitCode {
+    // Open the project.
+    let stubs = stubs(cx);
+    let project = match load(args, cx, &stubs) {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+
+    // Get the fingerprint.
+    let target = args
+        .get_one::<&'static PrimitiveTarget>("target")
+        .copied()
+        .unwrap_or(cx.host);
+    let fp = match project.fingerprint(target) {
+        Ok(v) => v,
+        Err(e) => return report_build_error(&project, e),
+    };
+
+    // Print the fingerprint.
+    let path = match args.get_one::<PathBuf>("compare") {
+        Some(v) => v,
+        None => {
+            if let Err(e) = serde_yaml::to_writer(std::io::stdout(), &fp) {
+                eprintln!("Cannot write the fingerprint: {}.", join_nested(&e));
+                return Failure::Environment.into();
+            }
+
+            return ExitCode::SUCCESS;
+        }
+    };
+
+    // Compare with the other fingerprint.
+    let other: Fingerprint = match File::open(path).map(serde_yaml::from_reader) {
+        Ok(Ok(v)) => v,
+        Ok(Err(e)) => {
+            eprintln!("Cannot parse {}: {}.", path.display(), join_nested(&e));
+            return Failure::Usage.into();
+        }
+        Err(e) => {
+            eprintln!("Cannot open {}: {}.", path.display(), join_nested(&e));
+            return Failure::Usage.into();
+        }
+    };
+
+    match fp.diff(&other) {
+        Some(v) => {
+            println!("{v}");
+            Failure::Diagnostic.into()
+        }
+        None => ExitCode::SUCCESS,
+    }
+}
+
+fn test(args: &ArgMatches, cx: &Context) -> ExitCode {
+    // Initialize LLVM.
+    unsafe { llvm_init() };
+
+    // Get target.
+    let host = cx.host;
+    let target = args
+        .get_one::<&'static PrimitiveTarget>("target")
+        .copied()
+        .unwrap_or(host);
+
+    // Open the project.
+    let stubs = stubs(cx);
+    let mut project = match load(args, cx, &stubs) {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+
+    if let Some(v) = args.get_one::<PathBuf>("out-dir") {
+        project.set_out_dir(v.clone());
+    }
+
+    // Build the test harnesses.
+    let bins = match project.build_tests(&Target::Primitive(target)) {
+        Ok(v) => v,
+        Err(e) => return report_build_error(&project, e),
+    };
+
+    if !std::ptr::eq(target, host) {
+        println!("The tests were compiled for {target} but cannot be run on {host}.");
+        return ExitCode::SUCCESS;
+    }
+
+    // Run the tests.
+    let mut failed = false;
+
+    for bin in bins {
+        let tests = bin.tests();
+
+        if tests.is_empty() {
+            continue;
+        }
+
+        let passed = match run_harness(bin.path(), tests.len()) {
+            Ok(Some(v)) => v,
+            Ok(None) => {
+                eprintln!("{} was terminated abnormally.", bin.path().display());
+                return Failure::Diagnostic.into();
+            }
+            Err(e) => {
+                eprintln!("Cannot run {}: {}.", bin.path().display(), join_nested(&e));
+                return Failure::Environment.into();
+            }
+        };
+
+        for name in &tests[..passed] {
+            println!("test {name} ... ok");
+        }
+
+        if let Some(name) = tests.get(passed) {
+            println!("test {name} ... FAILED");
+            failed = true;
+        }
+    }
+
+    if failed {
+        Failure::Diagnostic.into()
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn run_project(args: &ArgMatches, cx: &Context) -> ExitCode {
+    // Build.
+    let pkg = match build(args, cx) {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+
+    // Get the executable for the host.
+    let host = cx.host;
+    let exe = match pkg.exes().get(&Target::Primitive(host)) {
+        Some(v) => v.bin(),
+        None if pkg.exes().is_empty() => {
+            eprintln!("The project does not have an executable to run.");
+            return Failure::Usage.into();
+        }
+        None => {
+            eprintln!("No executable was built for {host}.");
+            return Failure::Environment.into();
+        }
+    };
+
+    // Run the executable.
+    let argv: Vec<OsString> = args
+        .get_many::<OsString>("args")
+        .map(|v| v.cloned().collect())
+        .unwrap_or_default();
+    let status = match spawn(exe, &argv) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Cannot run {}: {}.", exe.display(), join_nested(&e));
+            return Failure::Environment.into();
+        }
+    };
+
+    match status.code() {
+        Some(v) => ExitCode::from(v as u8),
+        None => {
+            eprintln!("{} was terminated abnormally.", exe.display());
+            Failure::Diagnostic.into()
+        }
+    }
+}
+
+fn clean(args: &ArgMatches, cx: &Context) -> ExitCode {
+    // Open the project. The sources are not loaded so a project with a broken source can still be
+    // cleaned.
+    let path = match args.get_one::<PathBuf>("project") {
+        Some(v) => Cow::Borrowed(v.as_path()),
+        None => Cow::Owned(std::env::current_dir().unwrap()),
+    };
+
+    let stubs = stubs(cx);
+    let project = match Project::open(path.as_ref(), &cx.targets, &stubs, &cx.deps) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Cannot open {}: {}.", path.display(), join_nested(&e));
+
+            return Failure::of_open(&e).into();
+        }
+    };
+
+    // Remove the directories.
+    for dir in project.build_dirs() {
+        match std::fs::remove_dir_all(&dir) {
+            Ok(_) => println!("Removed {}", dir.display()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                eprintln!("Cannot remove {}: {}.", dir.display(), join_nested(&e));
+                return Failure::Environment.into();
+            }
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn cheader(args: &ArgMatches, cx: &Context) -> ExitCode {
+    // Build.
+    let stubs = stubs(cx);
+    let project = match load_for_build(args, cx, &stubs) {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+
+    let pkg = match build_project(args, &project) {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+
+    // Generate the header.
+    let target = args
+        .get_one::<&'static PrimitiveTarget>("target")
+        .copied()
+        .unwrap_or(cx.host);
+    let header = match project.c_header(&pkg, &Target::Primitive(target)) {
+        Ok(Some(v)) => v,
+        Ok(None) => {
+            eprintln!("The project does not have a library.");
+            return Failure::Usage.into();
+        }
+        Err(e) => return report_build_error(&project, e),
+    };
+
+    // Write the header.
+    let path = match args.get_one::<PathBuf>("output") {
+        Some(v) => Cow::Borrowed(v.as_path()),
+        None => Cow::Owned(
+            std::env::current_dir()
+                .unwrap()
+                .join(format!("{}.h", pkg.meta().name())),
+        ),
+    };
+
+    if let Err(e) = std::fs::write(&path, header) {
+        eprintln!("Cannot write {}: {}.", path.display(), join_nested(&e));
+        return Failure::Environment.into();
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn check(args: &ArgMatches, cx: &Context) -> ExitCode {
+    // Check if the source is from stdin.
+    let path = match args.get_one::<PathBuf>("stdin") {
+        Some(v) => v,
+        None => {
+            let stubs = stubs(cx);
+
+            return match load(args, cx, &stubs) {
+                Ok(_) => ExitCode::SUCCESS,
+                Err(e) => e,
+            };
+        }
+    };
+
+    // Read the source.
+    let mut data = String::new();
+
+    if let Err(e) = std::io::stdin().read_to_string(&mut data) {
+        eprintln!("Cannot read the source from stdin: {}.", join_nested(&e));
+        return Failure::Environment.into();
+    }
+
+    // Print the tokens.
+    if args.get_flag("tokens") {
+        return dump_tokens(path, data);
+    }
+
+    // Parse the source.
+    let src = match SourceFile::parse_str(path, data, max_depth(args)) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("{}: {}", path.display(), e);
+            return Failure::Diagnostic.into();
+        }
+    };
+
+    // Print the signatures.
+    if args.get_flag("signatures") {
+        if let Err(e) = serde_yaml::to_writer(std::io::stdout(), &src.signatures()) {
+            eprintln!("Cannot write the signatures: {}.", join_nested(&e));
+            return Failure::Environment.into();
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Print each token in `data` as `LINE:COLUMN KIND TEXT`. The errors are reported and skipped so
+/// the tokens after them are still printed.
+fn dump_tokens(path: &Path, data: String) -> ExitCode {
+    let (tokens, errors) = Lexer::new(data).tokenize();
+    let mut out = std::io::stdout().lock();
+
+    for (tok, span) in tokens {
+        let line = span.line();
+        let col = span.column();
+        let kind = tok.kind();
+
+        if let Err(e) = writeln!(out, "{line}:{col} {kind} {}", span.as_str()) {
+            eprintln!("Cannot write the tokens: {}.", join_nested(&e));
+            return Failure::Environment.into();
+        }
+    }
+
+    for e in &errors {
+        let span = e.span();
+
+        eprintln!(
+            "{}:{}:{}: {}",
+            path.display(),
+            span.line(),
+            span.column(),
+            e.reason()
+        );
+    }
+
+    if errors.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        Failure::Diagnostic.into()
+    }
+}
+
+fn audit(args: &ArgMatches, cx: &Context) -> ExitCode {
+    // Load the project.
+    let stubs = stubs(cx);
+    let project = match load(args, cx, &stubs) {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+
+    // Collect the items.
+    let mut files: Vec<AuditFile> = project
+        .sources()
+        .map(|s| {
+            let path = s.path();
+            let path = path.strip_prefix(project.path()).unwrap_or(path);
+
+            AuditFile::new(path, s.audit())
+        })
+        .filter(|f| !f.is_empty())
+        .collect();
+
+    files.sort_unstable_by(|a, b| a.file().cmp(b.file()));
+
+    // Print the report.
+    if args.get_one::<String>("format").unwrap() == "json" {
+        if let Err(e) = serde_json::to_writer_pretty(std::io::stdout(), &files) {
+            eprintln!("Cannot write the report: {}.", join_nested(&e));
+            return Failure::Environment.into();
+        }
+
+        println!();
+    } else {
+        for (i, file) in files.into_iter().enumerate() {
+            if i != 0 {
+                println!();
+            }
+
+            print!("{file}");
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn graph(args: &ArgMatches, cx: &Context) -> ExitCode {
+    // Load the project.
+    let stubs = stubs(cx);
+    let project = match load(args, cx, &stubs) {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+
+    // Resolve the dependencies.
+    let graph = match project.dependency_graph() {
+        Ok(v) => v,
+        Err(e) => return report_build_error(&project, e),
+    };
+
+    if args.get_one::<String>("format").unwrap() == "dot" {
+        print!("{}", graph.to_dot());
+    } else {
+        print!("{}", graph.to_text());
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn fmt(args: &ArgMatches, cx: &Context) -> ExitCode {
+    // Load the project.
+    let stubs = stubs(cx);
+    let project = match load(args, cx, &stubs) {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+
+    // Format the sources.
+    let check = args.get_flag("check");
+    let mut unformatted = false;
+
+    for src in project.sources() {
+        let path = src.path();
+
+        let formatted = src.format();
+        let current = match std::fs::read_to_string(path) {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("Cannot read {}: {}.", path.display(), join_nested(&e));
+                return Failure::Environment.into();
+            }
+        };
+
+        if current == formatted {
+            continue;
+        }
+
+        if check {
+            println!("{}", path.display());
+            unformatted = true;
+        } else if let Err(e) = std::fs::write(path, formatted) {
+            eprintln!("Cannot write {}: {}.", path.display(), join_nested(&e));
+            return Failure::Environment.into();
+        }
+    }
+
+    if unformatted {
+        Failure::Diagnostic.into()
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn pack(args: &ArgMatches, cx: &Context) -> ExitCode {
+    // Build.
+    let pkg = match build(args, cx) {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+
+    // Get output path.
+    let path = match args.get_one::<PathBuf>("output") {
+        Some(v) => Cow::Borrowed(v.as_path()),
+        None => {
+            let mut p = std::env::current_dir().unwrap();
+            p.push(format!("{}.npk", pkg.meta().name()));
+            Cow::Owned(p)
+        }
+    };
+
+    // Check if the package can be published.
+    let dicts = match dictionaries(args) {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+
+    let baseline = match args.get_one::<PathBuf>("previous") {
+        Some(path) => {
+            let file = match File::open(path) {
+                Ok(v) => v,
+                Err(e) => {
+                    eprintln!("Cannot open {}: {}.", path.display(), join_nested(&e));
+                    return Failure::Usage.into();
+                }
+            };
+
+            match Package::inspect(BufReader::new(file), &dicts) {
+                Ok(v) => Some(v),
+                Err(e) => {
+                    eprintln!("Cannot read {}: {}.", path.display(), join_nested(&e));
+                    return Failure::Package.into();
+                }
+            }
+        }
+        None => None,
+    };
+
+    let lib = !pkg.libs().is_empty();
+    let warnings = match validate_for_pack(pkg.meta(), lib, baseline.as_ref().map(|v| v.meta())) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Cannot pack {}: {}.", pkg.meta().name(), join_nested(&e));
+            return Failure::Pack.into();
+        }
+    };
+
+    for w in warnings {
+        if args.get_flag("strict") {
+            eprintln!("Cannot pack {}: {}.", pkg.meta().name(), w);
+            return Failure::Pack.into();
+        }
+
+        eprintln!("Warning: {w}.");
+    }
+
+    let issues = pkg.verify(&cx.targets);
+
+    if args.get_flag("allow-inconsistent") {
+        for i in issues {
+            eprintln!("Warning: {}.", join_nested(&i));
+        }
+    } else if !issues.is_empty() {
+        for i in issues {
+            eprintln!("{}: {}.", pkg.meta().name(), join_nested(&i));
+        }
+
+        eprintln!(
+            "Cannot pack {}: the package is not consistent (use --allow-inconsistent to pack it anyway).",
+            pkg.meta().name()
+        );
+
+        return Failure::Pack.into();
+    }
+
+    // Pack.
+
+    if args.get_flag("dry-run") {
+        let size = match pkg.packed_size(dicts.first()) {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("Cannot pack {}: {}.", path.display(), join_nested(&e));
+                return Failure::Pack.into();
+            }
+        };
+
+        println!("Would write {} ({size} bytes)", path.display());
+
+        return ExitCode::SUCCESS;
+    }
+
+    if let Err(e) = pkg.pack(path.as_ref(), dicts.first()) {
+        eprintln!("Cannot pack {}: {}.", path.display(), join_nested(&e));
+        return Failure::Pack.into();
+    }
+
+    ExitCode::SUCCESS
+}
 
 fn export(args: &ArgMatches, cx: &Context) -> ExitCode {
     // Build.
@@ -374,22 +1530,547 @@ fn export(args: &ArgMatches, cx: &Context) -> ExitCode {
         Err(e) => return e,
     };
 
-    // Export the binaries.
-    let tartet = Target::Primitive(PrimitiveTarget::current());
-    let path = args.get_one::<PathBuf>("outputs").unwrap();
+    // Export the binaries.
+    let target = args
+        .get_one::<&'static PrimitiveTarget>("target")
+        .copied()
+        .unwrap_or(cx.host);
+    let target = Target::Primitive(target);
+    let path = args.get_one::<PathBuf>("outputs").unwrap();
+    let assets = args.get_flag("assets");
+    let with_deps = args.get_flag("with-deps");
+
+    if args.get_flag("dry-run") {
+        let files = match pkg.export_files(path, &target, assets, with_deps, &cx.targets, &cx.deps)
+        {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!(
+                    "Cannot export the binaries to {}: {}.",
+                    path.display(),
+                    join_nested(&e)
+                );
+                return Failure::Export.into();
+            }
+        };
+
+        for (from, to, _) in files {
+            let size = match from.metadata() {
+                Ok(v) => v.len(),
+                Err(e) => {
+                    eprintln!(
+                        "Cannot get metadata of {}: {}.",
+                        from.display(),
+                        join_nested(&e)
+                    );
+                    return Failure::Export.into();
+                }
+            };
+
+            println!(
+                "Would copy {} to {} ({size} bytes)",
+                from.display(),
+                to.display()
+            );
+        }
+
+        return ExitCode::SUCCESS;
+    }
+
+    if let Err(e) = pkg.export(path, &target, assets, with_deps, &cx.targets, &cx.deps) {
+        eprintln!(
+            "Cannot export the binaries to {}: {}.",
+            path.display(),
+            join_nested(&e)
+        );
+        return Failure::Export.into();
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn inspect(args: &ArgMatches, cx: &Context) -> ExitCode {
+    // Open the package.
+    let path = args.get_one::<PathBuf>("package").unwrap();
+    let file = match File::open(path) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Cannot open {}: {}.", path.display(), join_nested(&e));
+            return Failure::Usage.into();
+        }
+    };
+
+    // Read the package.
+    let dicts = match dictionaries(args) {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+
+    let info = match Package::inspect(BufReader::new(file), &dicts) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Cannot read {}: {}.", path.display(), join_nested(&e));
+            return Failure::Package.into();
+        }
+    };
+
+    // Print the information.
+    if !args.get_flag("readme") {
+        let meta = info.meta();
+
+        println!("{} {}", meta.name(), meta.version());
+
+        if let Some(v) = info.tool() {
+            println!("Built with {v}.");
+        }
+    } else if let Some(v) = info.readme() {
+        std::io::stdout().write_all(v).unwrap();
+    } else {
+        eprintln!("{} does not have a readme.", path.display());
+        return Failure::Usage.into();
+    }
+
+    // Verify the binaries, which need the package to be unpacked.
+    if !args.get_flag("verify") {
+        return ExitCode::SUCCESS;
+    }
+
+    with_temp_dir("inspect", |temp| {
+        let pkg = open_packed(path, temp, cx, &dicts)?;
+        let issues = pkg.verify(&cx.targets);
+
+        for i in &issues {
+            eprintln!("{}: {}.", path.display(), join_nested(i));
+        }
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(Failure::Package.into())
+        }
+    })
+}
+
+fn merge(args: &ArgMatches, cx: &Context) -> ExitCode {
+    with_temp_dir("merge", |temp| merge_packages(args, cx, temp))
+}
+
+fn merge_packages(args: &ArgMatches, cx: &Context, temp: &Path) -> Result<(), ExitCode> {
+    let dicts = dictionaries(args)?;
+    let mut merged: Option<Package> = None;
+
+    for (i, path) in args.get_many::<PathBuf>("packages").unwrap().enumerate() {
+        let pkg = open_packed(path, &temp.join(i.to_string()), cx, &dicts)?;
+
+        match &mut merged {
+            Some(v) => {
+                if let Err(e) = v.merge(pkg) {
+                    eprintln!("Cannot merge {}: {}.", path.display(), join_nested(&e));
+                    return Err(Failure::Package.into());
+                }
+            }
+            None => merged = Some(pkg),
+        }
+    }
+
+    // Pack.
+    let path = args.get_one::<PathBuf>("output").unwrap();
+
+    if let Err(e) = merged.unwrap().pack(path, dicts.first()) {
+        eprintln!("Cannot pack {}: {}.", path.display(), join_nested(&e));
+        return Err(Failure::Environment.into());
+    }
+
+    Ok(())
+}
+
+fn unpack(args: &ArgMatches) -> ExitCode {
+    // Open the package.
+    let path = args.get_one::<PathBuf>("package").unwrap();
+    let file = match File::open(path) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Cannot open {}: {}.", path.display(), join_nested(&e));
+            return Failure::Usage.into();
+        }
+    };
+
+    let dicts = match dictionaries(args) {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+
+    // Unpack.
+    let dir = args.get_one::<PathBuf>("output").unwrap();
+
+    if let Err(e) = Package::unpack(BufReader::new(file), dir, &dicts) {
+        eprintln!("Cannot unpack {}: {}.", path.display(), join_nested(&e));
+        return Failure::Package.into();
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn extract(args: &ArgMatches, cx: &Context) -> ExitCode {
+    with_temp_dir("extract", |temp| extract_binary(args, cx, temp))
+}
+
+fn extract_binary(args: &ArgMatches, cx: &Context, temp: &Path) -> Result<(), ExitCode> {
+    // Open the package.
+    let path = args.get_one::<PathBuf>("package").unwrap();
+    let dicts = dictionaries(args)?;
+    let pkg = open_packed(path, temp, cx, &dicts)?;
+
+    // Get the binary.
+    let pt = *args.get_one::<&'static PrimitiveTarget>("target").unwrap();
+    let target = Target::Primitive(pt);
+    let kind = args.get_one::<String>("kind").unwrap();
+    let from = match kind.as_str() {
+        "exe" => pkg.exes().get(&target).map(|b| b.bin()),
+        "lib" => match pkg.libs().get(&target).map(|b| b.bin().bin()) {
+            Some(LibraryBinary::Bundle(v)) => Some(v),
+            Some(LibraryBinary::System(n)) => {
+                eprintln!("The library for {pt} is a system library '{n}'.");
+                return Err(Failure::Usage.into());
+            }
+            Some(LibraryBinary::Memory(_)) => unreachable!(),
+            None => None,
+        },
+        _ => unreachable!(),
+    };
+
+    let from = match from {
+        Some(v) => v,
+        None => {
+            let mut list: Vec<String> = pkg
+                .exes()
+                .keys()
+                .map(|t| (t, "exe"))
+                .chain(pkg.libs().keys().map(|t| (t, "lib")))
+                .map(|(t, k)| match cx.targets.primitive(t) {
+                    Ok(v) => format!("{v} ({k})"),
+                    Err(_) => format!("{t} ({k})"),
+                })
+                .collect();
 
-    if let Err(e) = pkg.export(path, &tartet, &cx.targets, &cx.deps) {
+            list.sort();
+
+            eprintln!(
+                "{} does not contain {} for {}. Available binaries:",
+                path.display(),
+                kind,
+                pt
+            );
+
+            for v in list {
+                eprintln!("  {v}");
+            }
+
+            return Err(Failure::Usage.into());
+        }
+    };
+
+    // Write the binary.
+    let to = args.get_one::<PathBuf>("output").unwrap();
+
+    if let Err(e) = std::fs::copy(from, to) {
         eprintln!(
-            "Cannot export the binaries to {}: {}.",
-            path.display(),
+            "Cannot copy {} to {}: {}.",
+            from.display(),
+            to.display(),
             join_nested(&e)
         );
-        return ExitCode::FAILURE;
+        return Err(Failure::Environment.into());
+    }
+
+    Ok(())
+}
+
+fn list_deps(args: &ArgMatches, cx: &Context) -> ExitCode {
+    // Open the package. Only the metadata is needed so there is no need to unpack it.
+    let path = args.get_one::<PathBuf>("package").unwrap();
+    let file = match File::open(path) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Cannot open {}: {}.", path.display(), join_nested(&e));
+            return Failure::Usage.into();
+        }
+    };
+
+    let dicts = match dictionaries(args) {
+        Ok(v) => v,
+        Err(e) => return e,
+    };
+
+    let pkg = match Package::open_reader(BufReader::new(file), &dicts, &cx.targets) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Cannot read {}: {}.", path.display(), join_nested(&e));
+            return Failure::Package.into();
+        }
+    };
+
+    // Collect the dependencies that are required at run time from all targets.
+    let deps: BTreeSet<&Dependency> = pkg
+        .libs()
+        .values()
+        .flat_map(|b| b.deps())
+        .filter(|d| d.kind() == DependencyKind::Runtime)
+        .map(|d| d.dep())
+        .collect();
+
+    for dep in deps {
+        println!("{dep}");
     }
 
     ExitCode::SUCCESS
 }
 
+/// Run `f` with a temporary directory that will be removed when `f` returns.
+fn with_temp_dir<F>(name: &str, f: F) -> ExitCode
+where
+    F: FnOnce(&Path) -> Result<(), ExitCode>,
+{
+    let temp = std::env::temp_dir().join(format!("nitro-{}-{}", name, std::process::id()));
+    let res = f(&temp);
+
+    if let Err(e) = std::fs::remove_dir_all(&temp) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            eprintln!("Cannot remove {}: {}.", temp.display(), join_nested(&e));
+        }
+    }
+
+    match res {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(v) => v,
+    }
+}
+
+/// Unpack the package at `path` into `dir` and open it.
+fn open_packed(
+    path: &Path,
+    dir: &Path,
+    cx: &Context,
+    dicts: &[ZstdDictionary],
+) -> Result<Package, ExitCode> {
+    let file = match File::open(path) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Cannot open {}: {}.", path.display(), join_nested(&e));
+            return Err(Failure::Usage.into());
+        }
+    };
+
+    if let Err(e) = Package::unpack(BufReader::new(file), dir, dicts) {
+        eprintln!("Cannot unpack {}: {}.", path.display(), join_nested(&e));
+        return Err(Failure::Package.into());
+    }
+
+    match Package::open(dir, &cx.targets) {
+        Ok(v) => Ok(v),
+        Err(e) => {
+            eprintln!("Cannot open {}: {}.", path.display(), join_nested(&e));
+            Err(Failure::Package.into())
+        }
+    }
+}
+
+/// Load the dictionaries specified with `--dictionary`.
+fn dictionaries(args: &ArgMatches) -> Result<Vec<ZstdDictionary>, ExitCode> {
+    let mut dicts = Vec::new();
+
+    for path in args.get_many::<PathBuf>("dictionary").into_iter().flatten() {
+        let data = match std::fs::read(path) {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("Cannot read {}: {}.", path.display(), join_nested(&e));
+                return Err(Failure::Usage.into());
+            }
+        };
+
+        match ZstdDictionary::new(data) {
+            Some(v) => dicts.push(v),
+            None => {
+                eprintln!("{} is not a zstd dictionary.", path.display());
+                return Err(Failure::Usage.into());
+            }
+        }
+    }
+
+    Ok(dicts)
+}
+
+fn stubs(cx: &Context) -> PathBuf {
+    cx.stubs.clone()
+}
+
+/// Returns `share/nitro/stub` in the nearest parent of `exe` that has it.
+fn find_stubs(exe: &Path) -> Option<PathBuf> {
+    exe.ancestors()
+        .skip(1)
+        .map(|p| p.join("share").join("nitro").join("stub"))
+        .find(|p| p.is_dir())
+}
+
+fn load<'a>(args: &ArgMatches, cx: &'a Context, stubs: &'a Path) -> Result<Project<'a>, ExitCode> {
+    // Get path to the project.
+    let path = match args.get_one::<PathBuf>("project") {
+        Some(v) => Cow::Borrowed(v.as_path()),
+        None => Cow::Owned(std::env::current_dir().unwrap()),
+    };
+
+    // Open the project.
+    let mut project = match Project::open(path.as_ref(), &cx.targets, stubs, &cx.deps) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Cannot open {}: {}.", path.display(), join_nested(&e));
+
+            return Err(Failure::of_open(&e).into());
+        }
+    };
+
+    // Only the commands that build the project accept --define.
+    if let Ok(Some(v)) = args.try_get_many::<(String, String)>("define") {
+        for (k, v) in v {
+            project.set_define(k.clone(), v.clone());
+        }
+    }
+
+    // Load the project.
+    project.set_max_depth(max_depth(args));
+
+    if let Err(e) = project.load() {
+        match &e {
+            ProjectLoadError::ParseSourceFailed(p, ParseError::ParseFailed(e)) => {
+                eprintln!("{}: {}", p.display(), e);
+            }
+            e => eprintln!(
+                "Cannot load {}: {}.",
+                project.path().display(),
+                join_nested(e)
+            ),
+        }
+
+        return Err(Failure::of_load(&e).into());
+    }
+
+    Ok(project)
+}
+
+fn parse_define(v: &str) -> Result<(String, String), String> {
+    let (k, v) = v
+        .split_once('=')
+        .ok_or_else(|| String::from("expect KEY=VALUE"))?;
+
+    if !is_define_key(k) {
+        return Err(format!("'{k}' is not a valid key"));
+    }
+
+    Ok((k.to_owned(), v.to_owned()))
+}
+
+fn max_depth(args: &ArgMatches) -> usize {
+    match args.get_one::<u64>("max-parse-depth") {
+        Some(&v) => v.try_into().unwrap_or(usize::MAX),
+        None => Lexer::DEFAULT_MAX_DEPTH,
+    }
+}
+
+fn report_build_error(project: &Project, e: ProjectBuildError) -> ExitCode {
+    let code = Failure::of_build(&e);
+
+    match e {
+        ProjectBuildError::InvalidSyntax(p, e) => {
+            eprintln!("{}: {}", p.display(), e);
+        }
+        ProjectBuildError::BuildFailed(p, e) => {
+            eprintln!("Cannot build {}: {}", p.display(), e);
+        }
+        e => eprintln!("{}: {}", project.path().display(), join_nested(&e)),
+    }
+
+    code.into()
+}
+
+/// Returns the text for `--version`, which includes the version of the libraries we are linked
+/// with. The text is leaked since clap only accept a static string and this is called once.
+fn long_version() -> &'static str {
+    let mut llvm = String::new();
+    let zstd = unsafe { CStr::from_ptr(ZSTD_versionString()) };
+
+    unsafe { llvm_version(&mut llvm) };
+
+    format!(
+        "{}\nLLVM {}\nzstd {}",
+        env!("CARGO_PKG_VERSION"),
+        llvm,
+        zstd.to_string_lossy()
+    )
+    .leak()
+}
+
+/// Run `program` with `args` and wait for it to exit.
+fn spawn(program: &Path, args: &[OsString]) -> Result<ExitStatus, std::io::Error> {
+    std::process::Command::new(program).args(args).status()
+}
+
+/// Run the test harness `bin` with `tests` tests and returns the number of tests that were passed
+/// or [`None`] if the harness was terminated abnormally. The output of the harness is forwarded to
+/// the standard output except the line that report the failed test.
+fn run_harness(bin: &Path, tests: usize) -> Result<Option<usize>, std::io::Error> {
+    let output = std::process::Command::new(bin)
+        .stderr(std::process::Stdio::inherit())
+        .output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (passed, rest) = match harness_result(output.status.code(), &stdout, tests) {
+        Some(v) => v,
+        None => {
+            print!("{stdout}");
+            return Ok(None);
+        }
+    };
+
+    print!("{rest}");
+
+    Ok(Some(passed))
+}
+
+/// Returns the number of tests that were passed and the output of the harness without the line
+/// that report the failed test. The harness exit with 1 after printing the zero-based index of the
+/// first failed test on the last line (see `Codegen::build_runner()`).
+fn harness_result(code: Option<i32>, stdout: &str, tests: usize) -> Option<(usize, &str)> {
+    match code? {
+        0 => Some((tests, stdout)),
+        1 => {
+            let line = stdout.strip_suffix('\n')?;
+            let (rest, index) = match line.rfind('\n') {
+                Some(i) => (&stdout[..(i + 1)], &line[(i + 1)..]),
+                None => ("", line),
+            };
+
+            match index.parse() {
+                Ok(v) if v < tests => Some((v, rest)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Returns the host target from `flag`, `env` or LLVM detection, in that order.
+fn resolve_host(
+    flag: Option<&str>,
+    env: Option<&str>,
+) -> Result<&'static PrimitiveTarget, PrimitiveTargetError> {
+    if let Some(v) = flag {
+        v.parse()
+    } else if let Some(v) = env {
+        v.parse()
+    } else {
+        PrimitiveTarget::current()
+    }
+}
+
 fn join_nested(mut e: &dyn Error) -> String {
     let mut m = e.to_string();
 
@@ -402,8 +2083,263 @@ fn join_nested(mut e: &dyn Error) -> String {
     m
 }
 
-struct Context<'a> {
-    prefix: &'a Path,
+/// Category of the failure. The value is the exit code (see [`EXIT_STATUS`]).
+#[derive(Clone, Copy)]
+enum Failure {
+    Diagnostic = 1,
+    Usage = 2,
+    Environment = 3,
+    Link = 4,
+    Package = 5,
+    Load = 6,
+    Pack = 7,
+    Export = 8,
+}
+
+impl Failure {
+    /// Exit code when the compiler was panic.
+    const INTERNAL: u8 = 101;
+
+    fn of_open(e: &ProjectOpenError) -> Self {
+        match e {
+            // Nitro.yml does not exists mean the path is not a project.
+            ProjectOpenError::OpenFileFailed(_, _) => Self::Usage,
+            _ => Self::Load,
+        }
+    }
+
+    fn of_load(e: &ProjectLoadError) -> Self {
+        match e {
+            ProjectLoadError::ParseSourceFailed(_, ParseError::ParseFailed(_)) => Self::Diagnostic,
+            _ => Self::Load,
+        }
+    }
+
+    fn of_build(e: &ProjectBuildError) -> Self {
+        use ProjectBuildError::*;
+
+        match e {
+            ResolveDependencyFailed(_, e) => match e {
+                DependencyResolveError::OpenPackageFailed(_, _)
+                | DependencyResolveError::CacheMismatch { .. } => Self::Diagnostic,
+                DependencyResolveError::CheckCacheFailed(_, _)
+                | DependencyResolveError::OpenStdFailed(_, _)
+                | DependencyResolveError::UnpackPackageFailed(_)
+                | DependencyResolveError::RemoveCacheFailed(_, _) => Self::Environment,
+            },
+            ResolvePrimitiveTargetFailed(_, _) | ResolveParentTargetFailed(_, _) => Self::Usage,
+            BuildFailed(_, BuildError::TargetNotSupported(_, _)) => Self::Environment,
+            InvalidSyntax(_, _)
+            | BuildFailed(_, _)
+            | InvalidAssetPath(_)
+            | AssetDirectoryLink(_)
+            | AssetOutsideProject(_, _)
+            | NoEntryPoint
+            | EntryNotFound(_, _)
+            | MultipleEntryPoints(_, _, _, _)
+            | ConflictingSources(_, _, _)
+            | GenerateHeaderFailed(_) => Self::Diagnostic,
+            LinkFailed(_, _) => Self::Link,
+            CreateDirectoryFailed(_, _)
+            | CreateModuleDefinitionFailed(_, _)
+            | GetAssetMetadataFailed(_, _)
+            | EnumerateAssetsFailed(_, _)
+            | WriteDepsFailed(_, _)
+            | EnumerateStubsFailed(_, _)
+            | ReadSymbolsFailed(_, _)
+            | WriteSizeReportFailed(_, _)
+            | HashFileFailed(_, _)
+            | WriteFingerprintFailed(_, _)
+            | StubMismatch(_, _, _) => Self::Environment,
+        }
+    }
+}
+
+impl From<Failure> for ExitCode {
+    fn from(v: Failure) -> Self {
+        Self::from(v as u8)
+    }
+}
+
+struct Context {
+    stubs: PathBuf,
+    host: &'static PrimitiveTarget,
     targets: TargetResolver,
     deps: DependencyResolver,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_status() {
+        // The codes are a contract with the scripts so it must not be changed.
+        let codes = [
+            (Failure::Diagnostic, 1),
+            (Failure::Usage, 2),
+            (Failure::Environment, 3),
+            (Failure::Link, 4),
+            (Failure::Package, 5),
+            (Failure::Load, 6),
+            (Failure::Pack, 7),
+            (Failure::Export, 8),
+        ];
+
+        for (f, code) in codes {
+            let line = format!("\n  {code:<5}");
+
+            assert_eq!(f as u8, code);
+            assert!(EXIT_STATUS.contains(&line), "{code} is not documented");
+        }
+
+        assert!(EXIT_STATUS.contains(&format!("\n  {} ", Failure::INTERNAL)));
+    }
+
+    #[test]
+    fn harness_result() {
+        assert_eq!(super::harness_result(Some(0), "", 2), Some((2, "")));
+        assert_eq!(
+            super::harness_result(Some(0), "foo\n", 2),
+            Some((2, "foo\n"))
+        );
+        assert_eq!(super::harness_result(Some(1), "0\n", 2), Some((0, "")));
+        assert_eq!(
+            super::harness_result(Some(1), "foo\n1\n", 2),
+            Some((1, "foo\n"))
+        );
+
+        // The index must not be truncated like the exit status.
+        assert_eq!(
+            super::harness_result(Some(1), "255\n", 300),
+            Some((255, ""))
+        );
+        assert_eq!(
+            super::harness_result(Some(1), "299\n", 300),
+            Some((299, ""))
+        );
+
+        // Abnormal termination.
+        assert_eq!(super::harness_result(None, "", 2), None);
+        assert_eq!(super::harness_result(Some(2), "0\n", 2), None);
+        assert_eq!(super::harness_result(Some(1), "", 2), None);
+        assert_eq!(super::harness_result(Some(1), "2\n", 2), None);
+        assert_eq!(super::harness_result(Some(1), "0", 2), None);
+        assert_eq!(super::harness_result(Some(1), "foo\n", 2), None);
+    }
+
+    #[test]
+    fn host_flag() {
+        let flag = PrimitiveTarget::ALL[1].to_string();
+        let env = PrimitiveTarget::ALL[2].to_string();
+        let host = resolve_host(Some(&flag), Some(&env)).unwrap();
+
+        assert_eq!(host.id(), PrimitiveTarget::ALL[1].id());
+    }
+
+    #[test]
+    fn host_env() {
+        let env = PrimitiveTarget::ALL[2].to_string();
+        let host = resolve_host(None, Some(&env)).unwrap();
+
+        assert_eq!(host.id(), PrimitiveTarget::ALL[2].id());
+    }
+
+    #[test]
+    fn host_detection() {
+        unsafe { llvm_init() };
+
+        let host = resolve_host(None, None).unwrap();
+
+        assert_eq!(host.id(), PrimitiveTarget::current().unwrap().id());
+    }
+
+    #[test]
+    fn host_invalid() {
+        let env = PrimitiveTarget::ALL[2].to_string();
+
+        // The invalid flag must not fall back to the environment.
+        assert!(matches!(
+            resolve_host(Some("foo"), Some(&env)),
+            Err(PrimitiveTargetError::UnknownArch(v)) if v == "foo"
+        ));
+        assert!(matches!(
+            resolve_host(None, Some("x86_64-unknown-foo-gnu")),
+            Err(PrimitiveTargetError::UnknownOs(v)) if v == "foo"
+        ));
+    }
+
+    #[test]
+    fn targets_marker() {
+        // Only the stubs for Darwin exist.
+        let stubs = std::env::temp_dir().join(format!("nitro-stubs-{}", std::process::id()));
+
+        std::fs::create_dir_all(stubs.join("darwin")).unwrap();
+
+        let mut out = Vec::new();
+        let r = write_targets(&mut out, &stubs);
+
+        std::fs::remove_dir_all(&stubs).unwrap();
+        r.unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        let expected: Vec<String> = PrimitiveTarget::ALL
+            .iter()
+            .map(|t| {
+                let dir = stubs_subdir(t);
+                let mark = if dir == "darwin" {
+                    '\u{2713}'
+                } else {
+                    '\u{2717}'
+                };
+
+                format!("{} {} {} {}", t, t.id(), dir, mark)
+            })
+            .collect();
+
+        assert_eq!(lines, expected);
+        assert_eq!(out.matches('\u{2713}').count(), 2);
+    }
+
+    #[test]
+    fn run_tests() {
+        // Create a project with one passing and one failing test.
+        let path = std::env::temp_dir().join(format!("nitro-test-{}", std::process::id()));
+        let src = path.join("src");
+
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::write(
+            path.join("Nitro.yml"),
+            "package:\n  name: app\n  version: 1.0.0\nexecutable:\n  sources: src\n",
+        )
+        .unwrap();
+        std::fs::write(src.join("Status.nt"), "@repr(i32)\nstruct Status;\n").unwrap();
+        std::fs::write(
+            src.join("Test.nt"),
+            "class Test;\n\nimpl Test {\n    @test\n    fn Pass(): Status {\n        0\n    }\n\n    @test\n    fn Fail(): Status {\n        1\n    }\n}\n",
+        )
+        .unwrap();
+
+        // Build and run the harness.
+        unsafe { llvm_init() };
+
+        let host = PrimitiveTarget::current().unwrap();
+        let stubs = Path::new(env!("CARGO_MANIFEST_DIR")).join("../stub");
+        let targets = TargetResolver::new();
+        let deps = DependencyResolver::new(path.join("cache"), path.join("std.npk"));
+        let mut project = Project::open(&path, &targets, &stubs, &deps).unwrap();
+
+        project.load().unwrap();
+
+        let bins = project.build_tests(&Target::Primitive(host)).unwrap();
+        let bin = &bins[0];
+        let tests = bin.tests();
+        let passed = run_harness(bin.path(), tests.len()).unwrap();
+
+        std::fs::remove_dir_all(&path).unwrap();
+
+        assert_eq!(tests, ["Test.Pass", "Test.Fail"]);
+        assert_eq!(passed, Some(1));
+    }
+}
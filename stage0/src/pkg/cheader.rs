@@ -0,0 +1,119 @@
+use super::{
+    sorted_by_name, Abi, Extern, Library, PackageName, Representation, Type, TypeDeclaration,
+};
+use std::fmt::Write;
+use thiserror::Error;
+
+/// Generate a C header that declares all `@ext(C)` functions with the C ABI in `lib`, which belong
+/// to `pkg`.
+///
+/// `repr` returns the representation of a struct from its package name ([`None`] for `pkg` itself)
+/// and its name. A struct without a representation cannot be used in C. The lines are terminated
+/// with LF regardless of the host.
+pub fn c_header<F>(pkg: &PackageName, lib: &Library, repr: F) -> Result<String, CHeaderError>
+where
+    F: Fn(Option<&str>, &str) -> Option<Representation>,
+{
+    let guard = format!("{}_H", pkg.as_str().to_ascii_uppercase());
+    let mut decls = String::new();
+
+    for ty in lib.sorted_types() {
+        let funcs = match ty {
+            TypeDeclaration::Basic(v) => v.funcs(),
+        };
+
+        for f in sorted_by_name(funcs) {
+            if f.ext() != Some(Extern::C) || f.abi() != Abi::C {
+                continue;
+            }
+
+            // Return type.
+            let err = |t: &Type| CHeaderError::UnsupportedType(f.name().to_owned(), t.to_string());
+            let ret = match f.ret() {
+                Type::Never => "_Noreturn void".to_owned(),
+                t => c_type(t, &repr).ok_or_else(|| err(t))?,
+            };
+
+            // Parameters.
+            let mut params = String::new();
+
+            for (i, p) in f.params().iter().enumerate() {
+                let t = match p.ty() {
+                    Type::Unit { ptr: 0 } | Type::Never => None,
+                    t => c_type(t, &repr),
+                };
+
+                if i != 0 {
+                    params.push_str(", ");
+                }
+
+                params.push_str(&declarator(&t.ok_or_else(|| err(p.ty()))?, p.name()));
+            }
+
+            if params.is_empty() {
+                params.push_str("void");
+            }
+
+            writeln!(decls, "{}({});", declarator(&ret, f.name()), params).unwrap();
+        }
+    }
+
+    // Assemble the header.
+    let mut h = String::new();
+
+    writeln!(h, "/* Generated from {pkg}. Do not edit. */").unwrap();
+    writeln!(h, "#ifndef {guard}").unwrap();
+    writeln!(h, "#define {guard}").unwrap();
+    h.push_str("\n#include <stddef.h>\n#include <stdint.h>\n");
+    h.push_str("\n#ifdef __cplusplus\nextern \"C\" {\n#endif\n\n");
+    h.push_str(&decls);
+    h.push_str("\n#ifdef __cplusplus\n}\n#endif\n");
+    writeln!(h, "\n#endif /* {guard} */").unwrap();
+
+    Ok(h)
+}
+
+/// Returns the C type of `ty` or [`None`] if it has no equivalent in C.
+fn c_type<F>(ty: &Type, repr: &F) -> Option<String>
+where
+    F: Fn(Option<&str>, &str) -> Option<Representation>,
+{
+    let (base, ptr) = match ty {
+        Type::Unit { ptr } => ("void", *ptr),
+        Type::Struct { ptr, pkg, name } => {
+            let base = match repr(pkg.as_ref().map(|v| v.0.as_str()), name)? {
+                Representation::I32 => "int32_t",
+                Representation::U8 => "uint8_t",
+                Representation::Un => "size_t",
+            };
+
+            (base, *ptr)
+        }
+        Type::Never | Type::Class { .. } | Type::Tuple { .. } => return None,
+    };
+
+    let mut s = base.to_owned();
+
+    if ptr != 0 {
+        s.push(' ');
+        s.extend(std::iter::repeat_n('*', ptr));
+    }
+
+    Some(s)
+}
+
+/// Returns a declaration of `name` with type `ty` (e.g. `uint8_t *ptr`).
+fn declarator(ty: &str, name: &str) -> String {
+    if ty.ends_with('*') {
+        format!("{ty}{name}")
+    } else {
+        format!("{ty} {name}")
+    }
+}
+
+/// Represents an error when [`c_header()`] fails.
+#[derive(Debug, Error)]
+pub enum CHeaderError {
+    #[error("type {1} on function {0} has no equivalent in C")]
+    UnsupportedType(String, String),
+}
@@ -1,23 +1,26 @@
 use super::{
-    Package, PackageName, PackageNameError, PackageOpenError, PackageUnpackError, PackageVersion,
-    TargetResolver,
+    Named, Package, PackageName, PackageNameError, PackageOpenError, PackageUnpackError,
+    PackageVersion, TargetResolver,
 };
 use serde::{Deserialize, Serialize};
-use std::cell::RefCell;
 use std::collections::BTreeMap;
 use std::fmt::{Display, Formatter};
 use std::fs::File;
-use std::hash::Hash;
+use std::hash::{Hash, Hasher};
 use std::io::{Read, Write};
-use std::path::PathBuf;
-use std::rc::Rc;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use thiserror::Error;
 
 /// An object for resolving package dependencies.
+///
+/// This can be shared between threads. The resolved packages are shared so the same dependency is
+/// loaded only once.
 pub struct DependencyResolver {
     cache: PathBuf,
-    loaded: RefCell<BTreeMap<Dependency, Rc<Package>>>,
+    loaded: Mutex<BTreeMap<(PackageName, u16), PackageSlot>>,
     std: PathBuf,
+    repair: bool,
 }
 
 impl DependencyResolver {
@@ -28,36 +31,60 @@ impl DependencyResolver {
     {
         Self {
             cache: cache.into(),
-            loaded: RefCell::default(),
+            loaded: Mutex::default(),
             std: std.into(),
+            repair: false,
         }
     }
 
+    /// Set to `true` to remove a cached package that does not match with the requested dependency
+    /// and unpack it again instead of failing with [`DependencyResolveError::CacheMismatch`].
+    pub fn set_repair_cache(&mut self, v: bool) {
+        self.repair = v;
+    }
+
     pub fn resolve(
         &self,
         id: &Dependency,
         targets: &TargetResolver,
-    ) -> Result<Rc<Package>, DependencyResolveError> {
-        // Check if already loaded.
-        let mut loaded = self.loaded.borrow_mut();
+    ) -> Result<Arc<Package>, DependencyResolveError> {
+        // Get the slot for the package. Each major version has its own lock so the other threads
+        // that resolve the same dependency will wait for it instead of loading it again while the
+        // other dependencies can be loaded at the same time.
+        let key = (id.name.clone(), id.version.major());
+        let slot = self.loaded.lock().unwrap().entry(key).or_default().clone();
+        let mut slot = slot.lock().unwrap();
 
-        if let Some((_, loaded)) = loaded.range(id..).next() {
-            if loaded.meta.version().major() == id.version.major() {
-                return Ok(loaded.clone());
-            }
+        // Check if already loaded.
+        if let Some(pkg) = slot.as_ref() {
+            return Ok(pkg.clone());
         }
 
+        let pkg = Arc::new(self.load(id, targets)?);
+
+        *slot = Some(pkg.clone());
+
+        Ok(pkg)
+    }
+
+    /// Open the package for `id` from the cache, unpacking it first if it is not cached.
+    fn load(
+        &self,
+        id: &Dependency,
+        targets: &TargetResolver,
+    ) -> Result<Package, DependencyResolveError> {
         // Check for cache.
         let cache = self.cache.join(format!("{}-{}", id.name, id.version));
 
         match cache.symlink_metadata() {
-            Ok(_) => match Package::open(&cache, targets) {
-                Ok(v) => {
-                    let pkg = Rc::new(v);
-                    assert!(loaded.insert(id.clone(), pkg.clone()).is_none());
-                    return Ok(pkg);
+            Ok(_) => match Self::open_cache(id, &cache, targets) {
+                Ok(v) => return Ok(v),
+                Err(DependencyResolveError::CacheMismatch { .. }) if self.repair => {
+                    if let Err(e) = std::fs::remove_dir_all(&cache) {
+                        return Err(DependencyResolveError::RemoveCacheFailed(cache, e));
+                    }
                 }
-                Err(e) => return Err(DependencyResolveError::OpenPackageFailed(cache, e)),
+                Err(e) => return Err(e),
             },
             Err(e) => {
                 if e.kind() != std::io::ErrorKind::NotFound {
@@ -77,20 +104,56 @@ impl DependencyResolver {
         };
 
         // Unpack the package.
-        Package::unpack(pkg, &cache).map_err(|e| DependencyResolveError::UnpackPackageFailed(e))?;
+        Package::unpack(pkg, &cache, &[])
+            .map_err(|e| DependencyResolveError::UnpackPackageFailed(e))?;
 
         // Open the package.
-        match Package::open(&cache, targets) {
-            Ok(v) => {
-                let pkg = Rc::new(v);
-                assert!(loaded.insert(id.clone(), pkg.clone()).is_none());
-                return Ok(pkg);
+        Self::open_cache(id, &cache, targets)
+    }
+
+    /// Open the cached package at `path` and make sure it is the package for `id`.
+    fn open_cache(
+        id: &Dependency,
+        path: &Path,
+        targets: &TargetResolver,
+    ) -> Result<Package, DependencyResolveError> {
+        let pkg = match Package::open(path, targets) {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(DependencyResolveError::OpenPackageFailed(
+                    path.to_owned(),
+                    e,
+                ))
             }
-            Err(e) => return Err(DependencyResolveError::OpenPackageFailed(cache, e)),
+        };
+
+        // The directory may be renamed or copied by the user.
+        let meta = pkg.meta();
+
+        if *meta.name() != id.name || meta.version().major() != id.version.major() {
+            return Err(DependencyResolveError::CacheMismatch {
+                expected: id.clone(),
+                found: Dependency::new(meta.name().clone(), meta.version().clone()),
+                path: path.to_owned(),
+            });
         }
+
+        Ok(pkg)
     }
 }
 
+/// A package that was resolved by [`DependencyResolver`]. [`None`] if the package is being loaded
+/// or was failed to load.
+type PackageSlot = Arc<Mutex<Option<Arc<Package>>>>;
+
+// The resolver is shared between the threads that build each target.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send_sync::<DependencyResolver>();
+    assert_send_sync::<Package>();
+};
+
 /// A package dependency.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Dependency {
@@ -103,6 +166,14 @@ impl Dependency {
         Self { name, version }
     }
 
+    pub fn name(&self) -> &PackageName {
+        &self.name
+    }
+
+    pub fn version(&self) -> &PackageVersion {
+        &self.version
+    }
+
     pub fn deserialize<R: Read>(mut r: R) -> Result<Self, DependencyError> {
         // Read name.
         let mut data = [0; 32];
@@ -129,6 +200,92 @@ impl Display for Dependency {
     }
 }
 
+/// A dependency of a [`Binary`](super::Binary).
+///
+/// Two values are equal if the dependency are the same regardless of its kind.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BinaryDependency {
+    #[serde(flatten)]
+    dep: Dependency,
+    #[serde(default)]
+    kind: DependencyKind,
+}
+
+impl BinaryDependency {
+    pub fn new(dep: Dependency, kind: DependencyKind) -> Self {
+        Self { dep, kind }
+    }
+
+    pub fn deserialize<R: Read>(mut r: R) -> Result<Self, DependencyError> {
+        let dep = Dependency::deserialize(&mut r)?;
+        let mut kind = 0;
+
+        r.read_exact(std::slice::from_mut(&mut kind))?;
+
+        let kind = match kind {
+            0 => DependencyKind::Runtime,
+            1 => DependencyKind::TypesOnly,
+            v => return Err(DependencyError::InvalidKind(v)),
+        };
+
+        Ok(Self { dep, kind })
+    }
+
+    pub fn dep(&self) -> &Dependency {
+        &self.dep
+    }
+
+    pub fn kind(&self) -> DependencyKind {
+        self.kind
+    }
+
+    pub fn serialize<W: Write>(&self, mut w: W) -> Result<(), std::io::Error> {
+        self.dep.serialize(&mut w)?;
+
+        w.write_all(&[match self.kind {
+            DependencyKind::Runtime => 0,
+            DependencyKind::TypesOnly => 1,
+        }])
+    }
+}
+
+impl PartialEq for BinaryDependency {
+    fn eq(&self, other: &Self) -> bool {
+        self.dep == other.dep
+    }
+}
+
+impl Eq for BinaryDependency {}
+
+impl Hash for BinaryDependency {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.dep.hash(state);
+    }
+}
+
+impl Named for BinaryDependency {
+    fn name(&self) -> &str {
+        self.dep.name.as_str()
+    }
+}
+
+impl Display for BinaryDependency {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.dep.fmt(f)
+    }
+}
+
+/// How a [`Binary`](super::Binary) depends on the other package.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DependencyKind {
+    /// The shared library of the dependency must be present at run time.
+    #[default]
+    Runtime,
+    /// Only the exported types of the dependency was used so nothing is required at run time.
+    TypesOnly,
+}
+
 /// Represents an error when [`DependencyResolver::resolve()`] is failed.
 #[derive(Debug, Error)]
 pub enum DependencyResolveError {
@@ -143,6 +300,16 @@ pub enum DependencyResolveError {
 
     #[error("cannot unpack the package")]
     UnpackPackageFailed(#[source] PackageUnpackError),
+
+    #[error("{path} contains {found} instead of {expected} (delete it or use --repair-cache)")]
+    CacheMismatch {
+        expected: Dependency,
+        found: Dependency,
+        path: PathBuf,
+    },
+
+    #[error("cannot remove {0}")]
+    RemoveCacheFailed(PathBuf, #[source] std::io::Error),
 }
 
 /// Represents an error when [`Dependency`] is failed to construct.
@@ -153,6 +320,9 @@ pub enum DependencyError {
 
     #[error("invalid package name")]
     InvalidName(#[source] PackageNameError),
+
+    #[error("unknown dependency kind {0}")]
+    InvalidKind(u8),
 }
 
 impl From<std::io::Error> for DependencyError {
@@ -160,3 +330,211 @@ impl From<std::io::Error> for DependencyError {
         Self::ReadDataFailed(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pkg::{PackageMeta, PrimitiveTarget, Target};
+    use std::collections::HashSet;
+    use std::sync::mpsc::channel;
+    use std::sync::Barrier;
+    use std::time::Duration;
+
+    #[test]
+    fn resolve_concurrently() {
+        // Warm the cache.
+        let root = std::env::temp_dir().join(format!("nitro-dep-{}", std::process::id()));
+        let cache = root.join("cache");
+        let deps: Vec<Dependency> = ["foo", "bar"]
+            .into_iter()
+            .map(|n| Dependency::new(n.parse().unwrap(), "1.0.0".parse().unwrap()))
+            .collect();
+
+        for dep in &deps {
+            let path = cache.join(format!("{}-{}", dep.name(), dep.version()));
+            let meta = PackageMeta::new(dep.name().clone(), dep.version().clone());
+
+            write_cache(&path, &meta);
+        }
+
+        // Resolve the same dependencies from multiple threads. This is done on another thread so a
+        // deadlock fails the test instead of hanging it.
+        let resolver = Arc::new(DependencyResolver::new(&cache, root.join("std.npk")));
+        let (tx, rx) = channel();
+
+        std::thread::spawn({
+            let resolver = resolver.clone();
+            let deps = deps.clone();
+
+            move || {
+                let targets = TargetResolver::new();
+                let barrier = Barrier::new(8);
+                let pkgs: Vec<Vec<Arc<Package>>> = std::thread::scope(|s| {
+                    let threads: Vec<_> = (0..8)
+                        .map(|_| {
+                            s.spawn(|| {
+                                barrier.wait();
+
+                                deps.iter()
+                                    .map(|d| resolver.resolve(d, &targets).unwrap())
+                                    .collect()
+                            })
+                        })
+                        .collect();
+
+                    threads.into_iter().map(|t| t.join().unwrap()).collect()
+                });
+
+                tx.send(pkgs).unwrap();
+            }
+        });
+
+        let pkgs = rx.recv_timeout(Duration::from_secs(60)).unwrap();
+
+        std::fs::remove_dir_all(&root).unwrap();
+
+        // Each dependency must be loaded only once.
+        for (i, dep) in deps.iter().enumerate() {
+            assert_eq!(pkgs[0][i].meta().name(), dep.name());
+
+            for p in &pkgs {
+                assert!(Arc::ptr_eq(&p[i], &pkgs[0][i]));
+            }
+        }
+
+        assert!(!Arc::ptr_eq(&pkgs[0][0], &pkgs[0][1]));
+    }
+
+    #[test]
+    fn cache_mismatch() {
+        // Put the package for another name in the cache entry of std.
+        let root = std::env::temp_dir().join(format!("nitro-dep-mismatch-{}", std::process::id()));
+        let cache = root.join("cache");
+        let dep = Dependency::new("nitro".parse().unwrap(), "1.0.0".parse().unwrap());
+        let path = cache.join("nitro-1.0.0");
+        let meta = PackageMeta::new("foo".parse().unwrap(), "1.0.0".parse().unwrap());
+
+        write_cache(&path, &meta);
+
+        // Resolve without repairing.
+        let targets = TargetResolver::new();
+        let resolver = DependencyResolver::new(&cache, root.join("std.npk"));
+        let r = resolver.resolve(&dep, &targets);
+        let exists = path.join("meta.yml").exists();
+
+        std::fs::remove_dir_all(&root).unwrap();
+
+        match r {
+            Err(DependencyResolveError::CacheMismatch {
+                expected,
+                found,
+                path: p,
+            }) => {
+                assert_eq!(expected, dep);
+                assert_eq!(found.name().as_str(), "foo");
+                assert_eq!(p, path);
+            }
+            r => panic!("unexpected result {:?}", r.map(|_| ())),
+        }
+
+        assert!(exists);
+    }
+
+    #[test]
+    fn repair_cache() {
+        // Put the package for another name in the cache entry of std.
+        let root = std::env::temp_dir().join(format!("nitro-dep-repair-{}", std::process::id()));
+        let cache = root.join("cache");
+        let std = root.join("std.npk");
+        let dep = Dependency::new("nitro".parse().unwrap(), "1.0.0".parse().unwrap());
+        let path = cache.join("nitro-1.0.0");
+        let meta = PackageMeta::new("foo".parse().unwrap(), "1.0.0".parse().unwrap());
+
+        write_cache(&path, &meta);
+
+        // Pack std.
+        let bin = root.join("bin");
+        let meta = PackageMeta::new(dep.name().clone(), dep.version().clone());
+        let mut pkg = Package::builder(meta);
+
+        std::fs::write(&bin, b"std").unwrap();
+
+        pkg.add_executable(
+            Target::Primitive(&PrimitiveTarget::ALL[0]),
+            bin,
+            HashSet::new(),
+            None,
+        )
+        .unwrap();
+
+        pkg.build().unwrap().pack(&std, None).unwrap();
+
+        // Resolve with repairing.
+        let targets = TargetResolver::new();
+        let mut resolver = DependencyResolver::new(&cache, &std);
+
+        resolver.set_repair_cache(true);
+
+        let r = resolver.resolve(&dep, &targets);
+        let meta = std::fs::read_to_string(path.join("meta.yml"));
+
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(r.unwrap().meta().name(), dep.name());
+        assert!(meta.unwrap().contains("nitro"));
+    }
+
+    #[test]
+    fn binary_dependency_round_trip() {
+        let dep = Dependency::new("foo".parse().unwrap(), "1.2.3".parse().unwrap());
+
+        for kind in [DependencyKind::Runtime, DependencyKind::TypesOnly] {
+            let bin = BinaryDependency::new(dep.clone(), kind);
+            let mut data = Vec::new();
+
+            bin.serialize(&mut data).unwrap();
+
+            let read = BinaryDependency::deserialize(data.as_slice()).unwrap();
+
+            assert_eq!(*read.dep(), dep);
+            assert_eq!(read.kind(), kind);
+
+            // YAML.
+            let yaml = serde_yaml::to_string(&bin).unwrap();
+            let read: BinaryDependency = serde_yaml::from_str(&yaml).unwrap();
+
+            assert_eq!(*read.dep(), dep);
+            assert_eq!(read.kind(), kind);
+        }
+
+        // The dependencies that was written before the kind is a runtime one.
+        let read: BinaryDependency = serde_yaml::from_str("name: foo\nversion: 1.2.3\n").unwrap();
+
+        assert_eq!(*read.dep(), dep);
+        assert_eq!(read.kind(), DependencyKind::Runtime);
+
+        // Unknown kind.
+        let mut data = Vec::new();
+
+        dep.serialize(&mut data).unwrap();
+        data.push(2);
+
+        assert!(matches!(
+            BinaryDependency::deserialize(data.as_slice()),
+            Err(DependencyError::InvalidKind(2))
+        ));
+    }
+
+    /// Write a cached package with an empty executable for the first primitive target to `path`.
+    fn write_cache(path: &Path, meta: &PackageMeta) {
+        let exe = path
+            .join("exes")
+            .join(PrimitiveTarget::ALL[0].id().to_string());
+
+        std::fs::create_dir_all(&exe).unwrap();
+        std::fs::create_dir(path.join("libs")).unwrap();
+        std::fs::write(exe.join("bin"), []).unwrap();
+        std::fs::write(exe.join("deps.yml"), "[]").unwrap();
+        serde_yaml::to_writer(File::create(path.join("meta.yml")).unwrap(), meta).unwrap();
+    }
+}
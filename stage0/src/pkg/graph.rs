@@ -0,0 +1,150 @@
+use super::Dependency;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::Write;
+
+/// A graph of the packages that a project depends on.
+pub struct DependencyGraph {
+    root: Dependency,
+    edges: BTreeMap<Dependency, BTreeSet<Dependency>>,
+}
+
+impl DependencyGraph {
+    pub fn new(root: Dependency) -> Self {
+        Self {
+            root,
+            edges: BTreeMap::new(),
+        }
+    }
+
+    /// Add an edge from `from` to its dependency `to`. Returns `false` if the edge already exists.
+    pub fn add(&mut self, from: Dependency, to: Dependency) -> bool {
+        self.edges.entry(from).or_default().insert(to)
+    }
+
+    /// Render this graph as a Graphviz digraph. The edges go from the dependent to the dependency.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        let node = |d: &Dependency| {
+            let mut s = String::from("\"");
+
+            for c in d.to_string().chars() {
+                if c == '"' || c == '\\' {
+                    s.push('\\');
+                }
+
+                s.push(c);
+            }
+
+            s.push('"');
+            s
+        };
+
+        dot.push_str("digraph dependencies {\n");
+        writeln!(dot, "    {} [style=bold];", node(&self.root)).unwrap();
+
+        for (from, deps) in &self.edges {
+            for to in deps {
+                writeln!(dot, "    {} -> {};", node(from), node(to)).unwrap();
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Render this graph as an indented tree. A package that has dependencies is expanded only on
+    /// the first time it is shown and marked with `(*)` on the others.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{}\n", self.root);
+        let mut shown = HashSet::from([&self.root]);
+
+        self.write_children(&mut text, &self.root, "", &mut shown);
+
+        text
+    }
+
+    fn write_children<'a>(
+        &'a self,
+        text: &mut String,
+        parent: &Dependency,
+        prefix: &str,
+        shown: &mut HashSet<&'a Dependency>,
+    ) {
+        let deps = match self.edges.get(parent) {
+            Some(v) => v,
+            None => return,
+        };
+
+        for (i, dep) in deps.iter().enumerate() {
+            let last = i == deps.len() - 1;
+            let (branch, indent) = if last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+
+            if !shown.insert(dep) && self.edges.contains_key(dep) {
+                writeln!(text, "{prefix}{branch}{dep} (*)").unwrap();
+                continue;
+            }
+
+            writeln!(text, "{prefix}{branch}{dep}").unwrap();
+
+            self.write_children(text, dep, &format!("{prefix}{indent}"), shown);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dot() {
+        assert_eq!(
+            synthetic_graph().to_dot(),
+            "digraph dependencies {
+    \"app v1.0.0\" [style=bold];
+    \"app v1.0.0\" -> \"bar v1.0.0\";
+    \"app v1.0.0\" -> \"foo v2.0.0\";
+    \"bar v1.0.0\" -> \"baz v0.1.0\";
+    \"foo v2.0.0\" -> \"bar v1.0.0\";
+}
+"
+        );
+    }
+
+    #[test]
+    fn text() {
+        assert_eq!(
+            synthetic_graph().to_text(),
+            "app v1.0.0
+├── bar v1.0.0
+│   └── baz v0.1.0
+└── foo v2.0.0
+    └── bar v1.0.0 (*)
+"
+        );
+    }
+
+    /// `bar` is shared by `app` and `foo`.
+    fn synthetic_graph() -> DependencyGraph {
+        let app = dep("app", "1.0.0");
+        let foo = dep("foo", "2.0.0");
+        let bar = dep("bar", "1.0.0");
+        let baz = dep("baz", "0.1.0");
+        let mut graph = DependencyGraph::new(app.clone());
+
+        assert!(graph.add(app.clone(), foo.clone()));
+        assert!(graph.add(app, bar.clone()));
+        assert!(graph.add(foo, bar.clone()));
+        assert!(graph.add(bar.clone(), baz.clone()));
+        assert!(!graph.add(bar, baz));
+
+        graph
+    }
+
+    fn dep(name: &str, version: &str) -> Dependency {
+        Dependency::new(name.parse().unwrap(), version.parse().unwrap())
+    }
+}
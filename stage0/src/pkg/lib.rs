@@ -1,7 +1,10 @@
-use super::{TypeDeclaration, TypeDeserializeError};
+use super::{
+    sorted_by_name, StringTable, TargetArch, TargetOs, TypeDeclaration, TypeDeserializeError,
+};
+use crate::limit::encode_len;
 use std::collections::HashSet;
 use std::fs::File;
-use std::io::{Read, Write};
+use std::io::{Cursor, ErrorKind, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 
@@ -27,9 +30,22 @@ impl Library {
     const ENTRY_END: u8 = 0;
     const ENTRY_TYPES: u8 = 1;
     const ENTRY_SYSTEM: u8 = 2;
+    const ENTRY_STRINGS: u8 = 3;
+    const ENTRY_FORMAT: u8 = 4;
 
-    pub fn new(bin: LibraryBinary, types: HashSet<TypeDeclaration>) -> Self {
-        Self { bin, types }
+    /// Version of the serialized types. This must be bumped each time the serialized format of
+    /// [`TypeDeclaration`] is changed in a way that the older compiler cannot read. Version 1 is the
+    /// format before the string table was introduced, which has no version entry.
+    pub const TYPES_FORMAT: u16 = 2;
+
+    /// Magic of the types file that was produced by [`Self::unpack()`].
+    const TYPES_MAGIC: &'static [u8; 4] = b"\x7FNTY";
+
+    pub fn builder(bin: LibraryBinary) -> LibraryBuilder {
+        LibraryBuilder {
+            bin,
+            types: HashSet::new(),
+        }
     }
 
     pub fn open<B, T>(bin: B, types: T) -> Result<Self, LibraryError>
@@ -60,10 +76,23 @@ impl Library {
         let path = types.as_ref();
         let mut file =
             File::open(path).map_err(|e| LibraryError::OpenFileFailed(path.to_owned(), e))?;
+        let mut hdr = [0u8; 6];
+
+        file.read_exact(&mut hdr)
+            .map_err(|e| LibraryError::ReadFileFailed(path.to_owned(), e))?;
+
+        if &hdr[..4] != Self::TYPES_MAGIC
+            || u16::from_be_bytes([hdr[4], hdr[5]]) != Self::TYPES_FORMAT
+        {
+            return Err(LibraryError::UnsupportedTypes(path.to_owned()));
+        }
+
+        let strings = StringTable::deserialize(&mut file)
+            .map_err(|e| LibraryError::ReadTypeFailed(path.to_owned(), e))?;
         let mut types = HashSet::new();
 
         loop {
-            let ty = match TypeDeclaration::deserialize(&mut file) {
+            let ty = match TypeDeclaration::deserialize(&mut file, &strings) {
                 Ok(v) => v,
                 Err(TypeDeserializeError::EmptyData) => break,
                 Err(e) => return Err(LibraryError::ReadTypeFailed(path.to_owned(), e)),
@@ -85,19 +114,37 @@ impl Library {
         &self.types
     }
 
+    /// Returns the types sorted by its name. Use this instead of [`Library::types()`] when the
+    /// order is observable.
+    pub fn sorted_types(&self) -> Vec<&TypeDeclaration> {
+        sorted_by_name(&self.types)
+    }
+
     pub(super) fn serialize<W: Write>(&self, mut w: W) -> Result<(), std::io::Error> {
-        // Write magic.
+        // Write magic and format. The format must be the first entry so the reader can reject the
+        // data before interpreting the other entries.
         w.write_all(b"\x7FNLM")?;
+        w.write_all(&[Self::ENTRY_FORMAT])?;
+        w.write_all(&Self::TYPES_FORMAT.to_be_bytes())?;
+
+        // Serialize types.
+        let mut strings = StringTable::default();
+        let mut types = Vec::new();
+
+        for ty in self.sorted_types() {
+            ty.serialize(&mut types, &mut strings)?;
+        }
+
+        // Write strings. This must come before the types.
+        w.write_all(&[Self::ENTRY_STRINGS])?;
+        strings.serialize(&mut w)?;
 
         // Write types.
-        let types: u32 = self.types.len().try_into().unwrap();
+        let count: u32 = self.types.len().try_into().unwrap();
 
         w.write_all(&[Self::ENTRY_TYPES])?;
-        w.write_all(&types.to_be_bytes())?;
-
-        for ty in &self.types {
-            ty.serialize(&mut w)?;
-        }
+        w.write_all(&count.to_be_bytes())?;
+        w.write_all(&types)?;
 
         // Write binary.
         match &self.bin {
@@ -109,8 +156,12 @@ impl Library {
 
                 Ok(())
             }
+            LibraryBinary::Memory(data) => {
+                w.write_all(&[Self::ENTRY_END])?;
+                w.write_all(data)
+            }
             LibraryBinary::System(name) => {
-                let len: u16 = name.len().try_into().unwrap();
+                let len: u16 = encode_len(name.len(), "system library name")?;
 
                 w.write_all(&[Self::ENTRY_SYSTEM])?;
                 w.write_all(&len.to_be_bytes())?;
@@ -120,6 +171,94 @@ impl Library {
         }
     }
 
+    /// Read a library in the same format as [`Self::unpack()`] into memory. The bundled binary
+    /// will be [`LibraryBinary::Memory`].
+    pub(super) fn read<R: Read>(mut data: R) -> Result<Self, LibraryUnpackError> {
+        // Check magic.
+        let mut magic = [0u8; 4];
+
+        data.read_exact(&mut magic)?;
+
+        if magic.ne(b"\x7FNLM") {
+            return Err(LibraryUnpackError::NotNitroLibrary);
+        }
+
+        Self::read_format(&mut data)?;
+
+        // Iterate over the entries.
+        let mut strings = None;
+        let mut types = HashSet::new();
+        let mut sys = None;
+
+        loop {
+            let mut ty = 0;
+
+            data.read_exact(std::slice::from_mut(&mut ty))?;
+
+            match ty {
+                Self::ENTRY_END => break,
+                Self::ENTRY_STRINGS => {
+                    if strings.is_some() {
+                        return Err(LibraryUnpackError::DuplicatedStrings);
+                    }
+
+                    strings = Some(
+                        StringTable::deserialize(&mut data)
+                            .map_err(LibraryUnpackError::ReadStringsFailed)?,
+                    );
+                }
+                Self::ENTRY_TYPES => {
+                    let strings = strings
+                        .as_ref()
+                        .ok_or(LibraryUnpackError::StringsNotFound)?;
+
+                    // Read types count.
+                    let mut buf = [0u8; 4];
+                    data.read_exact(&mut buf)?;
+                    let ntype: usize = u32::from_be_bytes(buf).try_into().unwrap();
+
+                    // Read types.
+                    for i in 0..ntype {
+                        let ty = TypeDeclaration::deserialize(&mut data, strings)
+                            .map_err(|e| LibraryUnpackError::ReadTypeFailed(i, e))?;
+
+                        if !types.insert(ty) {
+                            return Err(LibraryUnpackError::DuplicatedType(i));
+                        }
+                    }
+                }
+                Self::ENTRY_SYSTEM => {
+                    // Read name length.
+                    let mut buf = [0u8; 2];
+                    data.read_exact(&mut buf)?;
+                    let len: usize = u16::from_be_bytes(buf).into();
+
+                    // Read name.
+                    let mut buf = vec![0u8; len];
+                    data.read_exact(&mut buf)?;
+
+                    match String::from_utf8(buf) {
+                        Ok(v) => sys = Some(v),
+                        Err(_) => return Err(LibraryUnpackError::InvalidSystemName),
+                    }
+                }
+                v => return Err(LibraryUnpackError::UnknownEntry(v)),
+            }
+        }
+
+        // Read binary.
+        let bin = match sys {
+            Some(name) => LibraryBinary::System(name),
+            None => {
+                let mut buf = Vec::new();
+                data.read_to_end(&mut buf)?;
+                LibraryBinary::Memory(buf)
+            }
+        };
+
+        Ok(Self { bin, types })
+    }
+
     pub(super) fn unpack<R, B, T>(mut data: R, bin: B, types: T) -> Result<(), LibraryUnpackError>
     where
         R: Read,
@@ -135,9 +274,17 @@ impl Library {
             return Err(LibraryUnpackError::NotNitroLibrary);
         }
 
+        Self::read_format(&mut data)?;
+
         // Iterate over the entries.
         let mut bin = File::create(bin).map_err(LibraryUnpackError::WriteBinaryFailed)?;
         let mut types = File::create(types).map_err(LibraryUnpackError::WriteTypeFailed)?;
+
+        types
+            .write_all(Self::TYPES_MAGIC)
+            .and_then(|_| types.write_all(&Self::TYPES_FORMAT.to_be_bytes()))
+            .map_err(LibraryUnpackError::WriteTypeFailed)?;
+        let mut strings = None;
         let mut sys = None;
 
         loop {
@@ -149,17 +296,36 @@ impl Library {
             // Process the entry.
             match ty {
                 Self::ENTRY_END => break,
+                Self::ENTRY_STRINGS => {
+                    if strings.is_some() {
+                        return Err(LibraryUnpackError::DuplicatedStrings);
+                    }
+
+                    // Read strings and write it as a header of the types file.
+                    let v = StringTable::deserialize(&mut data)
+                        .map_err(LibraryUnpackError::ReadStringsFailed)?;
+
+                    v.serialize(&mut types)
+                        .map_err(LibraryUnpackError::WriteTypeFailed)?;
+
+                    strings = Some(v);
+                }
                 Self::ENTRY_TYPES => {
+                    let strings = strings
+                        .as_mut()
+                        .ok_or(LibraryUnpackError::StringsNotFound)?;
+
                     // Read types count.
                     let mut buf = [0u8; 4];
                     data.read_exact(&mut buf)?;
                     let ntype: usize = u32::from_be_bytes(buf).try_into().unwrap();
 
-                    // Read types.
+                    // Read types. The string table is already complete so no new strings will be
+                    // added here.
                     for i in 0..ntype {
-                        let ty = TypeDeclaration::deserialize(&mut data)
+                        let ty = TypeDeclaration::deserialize(&mut data, strings)
                             .map_err(|e| LibraryUnpackError::ReadTypeFailed(i, e))?;
-                        ty.serialize(&mut types)
+                        ty.serialize(&mut types, strings)
                             .map_err(LibraryUnpackError::WriteTypeFailed)?;
                     }
                 }
@@ -198,12 +364,134 @@ impl Library {
 
         Ok(())
     }
+
+    /// Read the format entry, which must be the first entry of the serialized library.
+    fn read_format<R: Read>(mut data: R) -> Result<(), LibraryUnpackError> {
+        let mut buf = [0u8; 3];
+
+        data.read_exact(&mut buf)?;
+
+        // The library that was serialized before the format entry was introduced always start with
+        // the types entry.
+        let ver = match buf[0] {
+            Self::ENTRY_FORMAT => u16::from_be_bytes([buf[1], buf[2]]),
+            _ => 1,
+        };
+
+        if ver != Self::TYPES_FORMAT {
+            return Err(LibraryUnpackError::UnsupportedFormat(ver));
+        }
+
+        Ok(())
+    }
+}
+
+/// A builder for [`Library`].
+pub struct LibraryBuilder {
+    bin: LibraryBinary,
+    types: HashSet<TypeDeclaration>,
+}
+
+impl LibraryBuilder {
+    pub fn add_type(&mut self, ty: TypeDeclaration) -> Result<&mut Self, DuplicateType> {
+        let name = ty.name().to_owned();
+
+        if !self.types.insert(ty) {
+            return Err(DuplicateType(name));
+        }
+
+        Ok(self)
+    }
+
+    pub fn build(self) -> Library {
+        Library {
+            bin: self.bin,
+            types: self.types,
+        }
+    }
 }
 
 /// A library's binary.
 pub enum LibraryBinary {
     Bundle(PathBuf),
     System(String),
+    /// A bundled binary that was read from a packed package without unpacking it.
+    Memory(Vec<u8>),
+}
+
+impl LibraryBinary {
+    /// Returns the OS and the architecture that the bundled binary was built for by looking at its
+    /// header. Returns [`None`] if the binary is not a bundle or it has unknown format.
+    pub fn detect_target(&self) -> Result<Option<(TargetOs, TargetArch)>, std::io::Error> {
+        match self {
+            Self::Bundle(path) => Self::detect(File::open(path)?),
+            Self::System(_) => Ok(None),
+            Self::Memory(data) => Self::detect(Cursor::new(data)),
+        }
+    }
+
+    fn detect<R: Read + Seek>(
+        mut bin: R,
+    ) -> Result<Option<(TargetOs, TargetArch)>, std::io::Error> {
+        // Read the header.
+        let mut hdr = [0u8; 64];
+        let mut len = 0;
+
+        while len < hdr.len() {
+            match bin.read(&mut hdr[len..]) {
+                Ok(0) => break,
+                Ok(n) => len += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        let hdr = &hdr[..len];
+
+        // ELF.
+        if hdr.len() >= 20 && hdr.starts_with(b"\x7FELF") {
+            let machine = match hdr[5] {
+                1 => u16::from_le_bytes([hdr[18], hdr[19]]),
+                _ => u16::from_be_bytes([hdr[18], hdr[19]]),
+            };
+
+            return Ok(match machine {
+                62 => Some((TargetOs::Linux, TargetArch::X86_64)),
+                183 => Some((TargetOs::Linux, TargetArch::AArch64)),
+                _ => None,
+            });
+        }
+
+        // Mach-O 64.
+        if hdr.len() >= 8 && hdr.starts_with(&[0xCF, 0xFA, 0xED, 0xFE]) {
+            return Ok(match u32::from_le_bytes(hdr[4..8].try_into().unwrap()) {
+                0x01000007 => Some((TargetOs::Darwin, TargetArch::X86_64)),
+                0x0100000C => Some((TargetOs::Darwin, TargetArch::AArch64)),
+                _ => None,
+            });
+        }
+
+        // PE.
+        if hdr.len() >= 64 && hdr.starts_with(b"MZ") {
+            let off = u32::from_le_bytes(hdr[0x3C..0x40].try_into().unwrap());
+            let mut pe = [0u8; 6];
+
+            bin.seek(SeekFrom::Start(off.into()))?;
+            bin.read_exact(&mut pe)?;
+
+            if &pe[..4] != b"PE\0\0" {
+                return Ok(None);
+            }
+
+            return Ok(match u16::from_le_bytes([pe[4], pe[5]]) {
+                0x8664 => Some((TargetOs::Win32, TargetArch::X86_64)),
+                0xAA64 => Some((TargetOs::Win32, TargetArch::AArch64)),
+                _ => None,
+            });
+        }
+
+        Ok(None)
+    }
 }
 
 /// Represents an error when [`Library`] is failed to construct.
@@ -220,6 +508,9 @@ pub enum LibraryError {
 
     #[error("duplicated type declaration in {0}")]
     DuplicatedType(PathBuf),
+
+    #[error("{0} was unpacked by a different version of the compiler (delete the package cache to unpack it again)")]
+    UnsupportedTypes(PathBuf),
 }
 
 /// Represents an error when [`Library`] is failed to unpack from a serialized data.
@@ -231,6 +522,9 @@ pub enum LibraryUnpackError {
     #[error("the data is not a Nitro library")]
     NotNitroLibrary,
 
+    #[error("the library has types format {0}, which is not supported by this compiler (rebuild the package with this compiler)")]
+    UnsupportedFormat(u16),
+
     #[error("cannot write binary")]
     WriteBinaryFailed(#[source] std::io::Error),
 
@@ -240,11 +534,23 @@ pub enum LibraryUnpackError {
     #[error("cannot read type #{0}")]
     ReadTypeFailed(usize, #[source] TypeDeserializeError),
 
+    #[error("cannot read string table")]
+    ReadStringsFailed(#[source] TypeDeserializeError),
+
+    #[error("multiple string tables")]
+    DuplicatedStrings,
+
+    #[error("types appear before the string table")]
+    StringsNotFound,
+
     #[error("invalid name for system library")]
     InvalidSystemName,
 
     #[error("unknown entry {0}")]
     UnknownEntry(u8),
+
+    #[error("type #{0} already exists")]
+    DuplicatedType(usize),
 }
 
 impl From<std::io::Error> for LibraryUnpackError {
@@ -252,3 +558,336 @@ impl From<std::io::Error> for LibraryUnpackError {
         Self::ReadDataFailed(value)
     }
 }
+
+/// Represents an error when a type with the same name was added to [`LibraryBuilder`].
+#[derive(Debug, Error)]
+#[error("type {0} already exists")]
+pub struct DuplicateType(String);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pkg::{Abi, Attributes, BasicType, Function, FunctionParam, Public, Type};
+
+    const COLLECTIONS: &str = "example-collections";
+    const LIST: &str = "Collections.Generic.List";
+
+    #[test]
+    fn string_table_size() {
+        // Serialize.
+        let lib = synthetic_library();
+        let mut data = Vec::new();
+
+        lib.serialize(&mut data).unwrap();
+
+        // Each name must be stored only once.
+        for name in ["nitro", "Int32", "UInt", COLLECTIONS, LIST] {
+            let count = data
+                .windows(name.len())
+                .filter(|w| *w == name.as_bytes())
+                .count();
+
+            assert_eq!(count, 1, "{name}");
+        }
+
+        // Compute the size of the format before the string table (version 1), which has no format
+        // entry and no string table but the names are inlined on every reference.
+        let mut strings = 4;
+        let mut inlined = 0;
+        let mut interned = 0;
+
+        for name in ["nitro", "Int32", "UInt", COLLECTIONS, LIST] {
+            strings += 2 + name.len();
+        }
+
+        for ty in lib.sorted_types() {
+            let TypeDeclaration::Basic(ty) = ty;
+
+            for f in ty.funcs() {
+                for t in std::iter::once(f.ret()).chain(f.params().iter().map(|p| p.ty())) {
+                    inlined += inlined_len(t);
+                    interned += interned_len(t);
+                }
+            }
+        }
+
+        let before = data.len() - 3 - strings - interned + inlined;
+
+        // The names are the most of the data so it should be at least 40% smaller.
+        assert!(data.len() * 5 < before * 3, "{} -> {}", before, data.len());
+    }
+
+    #[test]
+    fn read_round_trip() {
+        let lib = synthetic_library();
+        let mut data = Vec::new();
+
+        lib.serialize(&mut data).unwrap();
+
+        let read = Library::read(data.as_slice()).unwrap();
+
+        assert!(matches!(read.bin(), LibraryBinary::Memory(v) if v == b"binary"));
+        assert_same_types(&lib, &read);
+    }
+
+    #[test]
+    fn reexport_round_trip() {
+        // The origin of a re-exported type is kept when it is re-exported again.
+        let attrs = Attributes::new(Some(Public::External), None, None);
+        let ty = BasicType::new(false, attrs, "Bar".into(), HashSet::new(), HashSet::new());
+        let ty = ty.reexport("core", 2).reexport("facade", 1);
+        let mut lib = Library::builder(LibraryBinary::Memory(b"binary".to_vec()));
+
+        lib.add_type(TypeDeclaration::Basic(ty)).unwrap();
+
+        let lib = lib.build();
+        let mut data = Vec::new();
+
+        lib.serialize(&mut data).unwrap();
+
+        let read = Library::read(data.as_slice()).unwrap();
+
+        assert_same_types(&lib, &read);
+
+        let TypeDeclaration::Basic(t) = read.sorted_types()[0];
+
+        assert_eq!(t.origin(), Some(("core", 2)));
+    }
+
+    #[test]
+    fn unpack_round_trip() {
+        let lib = synthetic_library();
+        let mut data = Vec::new();
+
+        lib.serialize(&mut data).unwrap();
+
+        // Unpack.
+        let dir = temp_dir("unpack");
+        let bin = dir.join("bin");
+        let types = dir.join("types");
+
+        Library::unpack(data.as_slice(), &bin, &types).unwrap();
+
+        // Open.
+        let opened = Library::open(&bin, &types).unwrap();
+
+        assert!(matches!(opened.bin(), LibraryBinary::Bundle(p) if *p == bin));
+        assert_eq!(std::fs::read(&bin).unwrap(), b"binary");
+        assert_same_types(&lib, &opened);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reject_old_format() {
+        // Version 1 start with the types entry right after the magic.
+        let mut data = b"\x7FNLM".to_vec();
+
+        data.push(Library::ENTRY_TYPES);
+        data.extend_from_slice(&0u32.to_be_bytes());
+        data.push(Library::ENTRY_END);
+
+        assert!(matches!(
+            Library::read(data.as_slice()),
+            Err(LibraryUnpackError::UnsupportedFormat(1))
+        ));
+
+        // Future version.
+        let mut data = b"\x7FNLM".to_vec();
+
+        data.push(Library::ENTRY_FORMAT);
+        data.extend_from_slice(&(Library::TYPES_FORMAT + 1).to_be_bytes());
+
+        assert!(matches!(
+            Library::read(data.as_slice()),
+            Err(LibraryUnpackError::UnsupportedFormat(v)) if v == Library::TYPES_FORMAT + 1
+        ));
+    }
+
+    #[test]
+    fn reject_old_types_file() {
+        // Version 1 of the types file start with the first type without the header.
+        let dir = temp_dir("types");
+        let bin = dir.join("bin");
+        let types = dir.join("types");
+
+        std::fs::write(&bin, b"binary").unwrap();
+        std::fs::write(&types, b"\x01\x00\x04Test\x00").unwrap();
+
+        assert!(matches!(
+            Library::open(&bin, &types),
+            Err(LibraryError::UnsupportedTypes(p)) if p == types
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Returns a library with 500 functions that reference the same few types.
+    #[test]
+    fn detect_interrupted() {
+        // ELF.
+        let mut elf = vec![0u8; 64];
+
+        elf[..6].copy_from_slice(b"\x7FELF\x02\x01");
+        elf[18..20].copy_from_slice(&62u16.to_le_bytes());
+
+        let bin = Interrupted::new(elf.clone());
+
+        assert_eq!(
+            LibraryBinary::detect(bin).unwrap(),
+            Some((TargetOs::Linux, TargetArch::X86_64))
+        );
+        assert_eq!(
+            LibraryBinary::Memory(elf).detect_target().unwrap(),
+            Some((TargetOs::Linux, TargetArch::X86_64))
+        );
+
+        // PE, which is read again after seeking to the PE header.
+        let mut pe = vec![0u8; 0x46];
+
+        pe[..2].copy_from_slice(b"MZ");
+        pe[0x3C..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+        pe[0x40..].copy_from_slice(b"PE\0\0\x64\xAA");
+
+        let bin = Interrupted::new(pe);
+
+        assert_eq!(
+            LibraryBinary::detect(bin).unwrap(),
+            Some((TargetOs::Win32, TargetArch::AArch64))
+        );
+    }
+
+    fn synthetic_library() -> Library {
+        let mut lib = Library::builder(LibraryBinary::Memory(b"binary".to_vec()));
+
+        for t in 0..10 {
+            let mut funcs = HashSet::new();
+
+            for f in 0..50 {
+                let params = vec![
+                    FunctionParam::new("index".into(), external(false, "nitro", 0, "UInt")),
+                    FunctionParam::new("value".into(), external(false, "nitro", 0, "Int32")),
+                    FunctionParam::new("list".into(), external(true, COLLECTIONS, 1, LIST)),
+                ];
+
+                funcs.insert(Function::new(
+                    format!("Function{f}"),
+                    params,
+                    external(true, COLLECTIONS, 1, LIST),
+                    None,
+                    Abi::Nitro,
+                ));
+            }
+
+            let ty = BasicType::new(
+                true,
+                Attributes::new(None, None, None),
+                format!("Synthetic.Type{t}"),
+                funcs,
+                HashSet::new(),
+            );
+
+            lib.add_type(TypeDeclaration::Basic(ty)).unwrap();
+        }
+
+        lib.build()
+    }
+
+    fn external(class: bool, pkg: &str, ver: u16, name: &str) -> Type {
+        let pkg = Some((pkg.to_owned(), ver));
+        let name = name.to_owned();
+
+        if class {
+            Type::Class { ptr: 0, pkg, name }
+        } else {
+            Type::Struct { ptr: 0, pkg, name }
+        }
+    }
+
+    /// Returns the serialized length of `ty` in the format version 1.
+    fn inlined_len(ty: &Type) -> usize {
+        match ty {
+            Type::Struct { pkg, name, .. } | Type::Class { pkg, name, .. } => {
+                let pkg = pkg.as_ref().map_or(1, |(p, _)| 1 + p.len() + 2);
+
+                2 + pkg + 2 + name.len()
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the serialized length of `ty` in the current format.
+    fn interned_len(ty: &Type) -> usize {
+        match ty {
+            Type::Struct { pkg, .. } | Type::Class { pkg, .. } => {
+                let pkg = if pkg.is_some() { 1 + 4 + 2 } else { 1 };
+
+                2 + pkg + 4
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn assert_same_types(expected: &Library, actual: &Library) {
+        let expected = expected.sorted_types();
+        let actual = actual.sorted_types();
+
+        assert_eq!(expected.len(), actual.len());
+
+        for (e, a) in expected.into_iter().zip(actual) {
+            let (TypeDeclaration::Basic(e), TypeDeclaration::Basic(a)) = (e, a);
+
+            assert_eq!(e.name(), a.name());
+            assert_eq!(e.is_class(), a.is_class());
+            assert_eq!(e.origin(), a.origin());
+            assert_eq!(debug_funcs(e), debug_funcs(a));
+        }
+    }
+
+    /// [`Function`] is compared by its name only so we need to compare its debug output instead.
+    fn debug_funcs(ty: &BasicType) -> Vec<String> {
+        let mut funcs: Vec<String> = ty.funcs().map(|f| format!("{f:?}")).collect();
+        funcs.sort_unstable();
+        funcs
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nitro-lib-{}-{}", name, std::process::id()));
+
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A reader that fails with [`ErrorKind::Interrupted`] on the first read after it was created
+    /// or seeked.
+    struct Interrupted {
+        data: Cursor<Vec<u8>>,
+        interrupt: bool,
+    }
+
+    impl Interrupted {
+        fn new(data: Vec<u8>) -> Self {
+            Self {
+                data: Cursor::new(data),
+                interrupt: true,
+            }
+        }
+    }
+
+    impl Read for Interrupted {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if std::mem::take(&mut self.interrupt) {
+                return Err(ErrorKind::Interrupted.into());
+            }
+
+            self.data.read(buf)
+        }
+    }
+
+    impl Seek for Interrupted {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.interrupt = true;
+            self.data.seek(pos)
+        }
+    }
+}
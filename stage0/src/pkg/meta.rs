@@ -2,6 +2,7 @@ use serde::de::{Error, Unexpected, Visitor};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt::{Display, Formatter};
 use std::num::ParseIntError;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use thiserror::Error;
 
@@ -10,11 +11,20 @@ use thiserror::Error;
 pub struct PackageMeta {
     name: PackageName,
     version: PackageVersion,
+    #[serde(default, skip_serializing)]
+    readme: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mangling: Option<u8>,
 }
 
 impl PackageMeta {
     pub fn new(name: PackageName, version: PackageVersion) -> Self {
-        Self { name, version }
+        Self {
+            name,
+            version,
+            readme: None,
+            mangling: None,
+        }
     }
 
     pub fn name(&self) -> &PackageName {
@@ -24,6 +34,58 @@ impl PackageMeta {
     pub fn version(&self) -> &PackageVersion {
         &self.version
     }
+
+    /// Returns a project-relative path to the Markdown file that describe the package. This is
+    /// only available when the metadata was loaded from `Nitro.yml`.
+    pub fn readme(&self) -> Option<&Path> {
+        self.readme.as_deref()
+    }
+
+    /// Returns the version of the mangling scheme that was used to build the package. This is
+    /// only available when the metadata was loaded from a package.
+    pub fn mangling(&self) -> Option<u8> {
+        self.mangling
+    }
+
+    pub fn set_mangling(&mut self, v: u8) {
+        self.mangling = Some(v);
+    }
+}
+
+/// Check if a package with `meta` can be packed. `lib` indicates whether the package contains a
+/// library and `baseline` is the metadata of the previous release to compare with.
+///
+/// On success the returned list contains the problems that do not prevent the package from being
+/// packed.
+pub fn validate_for_pack(
+    meta: &PackageMeta,
+    lib: bool,
+    baseline: Option<&PackageMeta>,
+) -> Result<Vec<PackWarning>, PackValidationError> {
+    let version = meta.version();
+    let mut warnings = Vec::new();
+
+    if version.to_bin() == 0 {
+        return Err(PackValidationError::ZeroVersion);
+    }
+
+    if let Some(b) = baseline {
+        if b.name() != meta.name() {
+            return Err(PackValidationError::BaselineMismatch(b.name().clone()));
+        }
+
+        if version < b.version() {
+            return Err(PackValidationError::VersionDowngrade(b.version().clone()));
+        }
+    }
+
+    // The symbols of a library are mangled without the major version when it is zero so all of
+    // them will be changed on 1.0.
+    if lib && version.major() == 0 {
+        warnings.push(PackWarning::UnstableAbi(version.clone()));
+    }
+
+    Ok(warnings)
 }
 
 /// Name of a Nitro package.
@@ -280,3 +342,82 @@ pub enum PackageVersionError {
     #[error("invalid patch number")]
     InvalidPatch(#[source] ParseIntError),
 }
+
+/// A problem in [`PackageMeta`] that does not prevent the package from being packed.
+#[derive(Debug, Error)]
+pub enum PackWarning {
+    #[error(
+        "version {0} has major 0 so every symbol of the library will change when it reach 1.0"
+    )]
+    UnstableAbi(PackageVersion),
+}
+
+/// Represents an error when [`validate_for_pack()`] fails.
+#[derive(Debug, Error)]
+pub enum PackValidationError {
+    #[error("version 0.0.0 cannot be packed")]
+    ZeroVersion,
+
+    #[error("the previous package is {0}")]
+    BaselineMismatch(PackageName),
+
+    #[error("the version is lower than the previous version {0}")]
+    VersionDowngrade(PackageVersion),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pack_zero_version() {
+        let r = validate_for_pack(&meta("foo", "0.0.0"), false, None);
+
+        assert!(matches!(r, Err(PackValidationError::ZeroVersion)));
+    }
+
+    #[test]
+    fn pack_baseline_mismatch() {
+        let baseline = meta("bar", "1.0.0");
+        let r = validate_for_pack(&meta("foo", "1.0.0"), false, Some(&baseline));
+
+        assert!(matches!(r, Err(PackValidationError::BaselineMismatch(n)) if n == *"bar"));
+    }
+
+    #[test]
+    fn pack_downgrade() {
+        let baseline = meta("foo", "1.2.0");
+        let r = validate_for_pack(&meta("foo", "1.1.9"), false, Some(&baseline));
+
+        assert!(
+            matches!(r, Err(PackValidationError::VersionDowngrade(v)) if v == "1.2.0".parse().unwrap())
+        );
+
+        // The same version is allowed so the package can be repacked.
+        let r = validate_for_pack(&meta("foo", "1.2.0"), false, Some(&baseline));
+
+        assert!(r.unwrap().is_empty());
+    }
+
+    #[test]
+    fn pack_unstable_abi() {
+        let r = validate_for_pack(&meta("foo", "0.1.0"), true, None).unwrap();
+
+        assert!(
+            matches!(r.as_slice(), [PackWarning::UnstableAbi(v)] if *v == "0.1.0".parse().unwrap())
+        );
+
+        // Only the library has the ABI.
+        let r = validate_for_pack(&meta("foo", "0.1.0"), false, None).unwrap();
+
+        assert!(r.is_empty());
+
+        let r = validate_for_pack(&meta("foo", "1.0.0"), true, None).unwrap();
+
+        assert!(r.is_empty());
+    }
+
+    fn meta(name: &str, version: &str) -> PackageMeta {
+        PackageMeta::new(name.parse().unwrap(), version.parse().unwrap())
+    }
+}
@@ -1,20 +1,28 @@
+pub use self::cheader::*;
 pub use self::dep::*;
+pub use self::graph::*;
 pub use self::lib::*;
 pub use self::meta::*;
+use self::scan::EntryScanner;
 pub use self::target::*;
 pub use self::ty::*;
-use crate::zstd::{ZstdReader, ZstdWriter};
-use std::collections::{HashMap, HashSet};
-use std::fs::{read_dir, File};
+use crate::hash::{hash_file, Fnv64};
+use crate::zstd::{ZstdDictionary, ZstdReader, ZstdWriter};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fs::{read_dir, File, OpenOptions};
+use std::hash::Hasher;
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 use thiserror::Error;
 use uuid::Uuid;
 
+mod cheader;
 mod dep;
+mod graph;
 mod lib;
 mod meta;
+mod scan;
 mod target;
 mod ty;
 
@@ -25,6 +33,8 @@ pub struct Package {
     meta: PackageMeta,
     exes: HashMap<Target, Binary<PathBuf>>,
     libs: HashMap<Target, Binary<Library>>,
+    assets: BTreeMap<String, PathBuf>,
+    readme: Option<PathBuf>,
 }
 
 impl Package {
@@ -34,33 +44,172 @@ impl Package {
     const ENTRY_DATE: u8 = 3;
     const ENTRY_EXE: u8 = 4;
     const ENTRY_LIB: u8 = 5;
-
-    pub fn new(
-        meta: PackageMeta,
-        exes: HashMap<Target, Binary<PathBuf>>,
-        libs: HashMap<Target, Binary<Library>>,
-    ) -> Self {
-        assert!(!exes.is_empty() || !libs.is_empty());
-
-        Self { meta, exes, libs }
+    const ENTRY_ASSET: u8 = 6;
+    const ENTRY_README: u8 = 7;
+    const ENTRY_MANGLING: u8 = 8;
+    const ENTRY_DICTIONARY: u8 = 9;
+    const ENTRY_TOOL: u8 = 10;
+    const ENTRY_CHECKSUM: u8 = 11;
+
+    /// Maximum size of the readme, in bytes.
+    pub const README_MAX: u64 = 256 * 1024;
+
+    pub fn builder(meta: PackageMeta) -> PackageBuilder {
+        PackageBuilder {
+            meta,
+            exes: HashMap::new(),
+            libs: HashMap::new(),
+            assets: BTreeMap::new(),
+        }
     }
 
     pub fn meta(&self) -> &PackageMeta {
         &self.meta
     }
 
+    pub fn exes(&self) -> &HashMap<Target, Binary<PathBuf>> {
+        &self.exes
+    }
+
     pub fn libs(&self) -> &HashMap<Target, Binary<Library>> {
         &self.libs
     }
 
-    pub fn pack<F: AsRef<Path>>(&self, file: F) -> Result<(), PackagePackError> {
+    /// Set a Markdown file on the local filesystem to describe the package. The file will not be
+    /// read until the package is packed.
+    pub fn set_readme(&mut self, path: PathBuf) {
+        self.readme = Some(path);
+    }
+
+    /// If `dict` is not [`None`] all entries will be compressed with it. The same dictionary must
+    /// be supplied to [`Package::unpack()`] to unpack the package.
+    pub fn pack<F: AsRef<Path>>(
+        &self,
+        file: F,
+        dict: Option<&ZstdDictionary>,
+    ) -> Result<(), PackagePackError> {
+        // Make sure each library was built for its target before writing anything.
+        for (target, lib) in &self.libs {
+            Self::check_lib_target(target, lib.bin.bin())?;
+        }
+
         // Create a package file.
         let path = file.as_ref();
-        let mut file = match File::create(path) {
+        let file = match File::create(path) {
             Ok(v) => v,
             Err(e) => return Err(PackagePackError::CreateFileFailed(e)),
         };
 
+        self.pack_to(file, dict)
+    }
+
+    /// Returns the size of the file that [`Self::pack()`] will write without writing it.
+    pub fn packed_size(&self, dict: Option<&ZstdDictionary>) -> Result<u64, PackagePackError> {
+        for (target, lib) in &self.libs {
+            Self::check_lib_target(target, lib.bin.bin())?;
+        }
+
+        let mut sink = SizeCounter::default();
+
+        self.pack_to(&mut sink, dict)?;
+
+        Ok(sink.len)
+    }
+
+    /// Cross-check the binaries of this package against each other. A package that has any issue
+    /// can still be unpacked but will fail when it is used.
+    pub fn verify(&self, targets: &TargetResolver) -> Vec<VerifyIssue> {
+        let mut issues = Vec::new();
+
+        // Check targets.
+        for target in self.exes.keys().chain(self.libs.keys()) {
+            if let Err(e) = targets.resolve(target.id()) {
+                issues.push(VerifyIssue::UnresolvedTarget(target.clone(), e));
+            }
+        }
+
+        // Check libraries.
+        let meta = &self.meta;
+        let major = meta.version().major();
+
+        for (target, lib) in &self.libs {
+            let mut syms = HashMap::new();
+
+            for ty in lib.bin.sorted_types() {
+                let TypeDeclaration::Basic(ty) = ty;
+
+                let owner = ty.origin().unwrap_or((meta.name().as_str(), major));
+
+                for f in sorted_by_name(ty.funcs()) {
+                    let name = format!("{}.{}", ty.name(), f.name());
+
+                    // Check if the packages that are referenced by the function are dependencies.
+                    let mut refs = Vec::new();
+
+                    for p in f.params() {
+                        p.ty().external_packages(&mut refs);
+                    }
+
+                    f.ret().external_packages(&mut refs);
+
+                    for (pkg, ver) in refs {
+                        let found = lib.deps.iter().any(|d| {
+                            let d = d.dep();
+                            d.name().as_str() == pkg && d.version().major() == ver
+                        });
+
+                        if !found {
+                            issues.push(VerifyIssue::MissingDependency(
+                                target.clone(),
+                                name.clone(),
+                                pkg.to_owned(),
+                                ver,
+                            ));
+                        }
+                    }
+
+                    // Check if the symbol is unique.
+                    if let Some(prev) = syms.insert(f.mangle(Some(owner), ty.name()), name.clone())
+                    {
+                        issues.push(VerifyIssue::DuplicatedSymbol(target.clone(), prev, name));
+                    }
+                }
+            }
+        }
+
+        // Check if the executable and the library for the same target agree on the dependencies.
+        for (target, exe) in &self.exes {
+            let lib = match self.libs.get(target) {
+                Some(v) => v,
+                None => continue,
+            };
+
+            for dep in &exe.deps {
+                let dep = dep.dep();
+                let other = lib
+                    .deps
+                    .iter()
+                    .map(|d| d.dep())
+                    .find(|d| d.name() == dep.name() && d.version() != dep.version());
+
+                if let Some(other) = other {
+                    issues.push(VerifyIssue::ConflictingDependency(
+                        target.clone(),
+                        dep.clone(),
+                        other.clone(),
+                    ));
+                }
+            }
+        }
+
+        issues
+    }
+
+    fn pack_to<W: Write + Seek>(
+        &self,
+        mut file: W,
+        dict: Option<&ZstdDictionary>,
+    ) -> Result<(), PackagePackError> {
         // Write file magic.
         file.write_all(b"\x7FNPK")?;
 
@@ -86,6 +235,74 @@ impl Package {
                 .to_be_bytes(),
         )?;
 
+        // Write mangling version. A package without this entry was built with the first version.
+        file.write_all(&[Self::ENTRY_MANGLING])?;
+        file.write_all(&[meta.mangling().unwrap_or(MANGLING_VERSION)])?;
+
+        // Write the compiler that produced this package.
+        let tool = Self::tool();
+
+        file.write_all(&[Self::ENTRY_TOOL, tool.len().try_into().unwrap()])?;
+        file.write_all(tool.as_bytes())?;
+
+        // Write dictionary ID. This must precede all compressed entries.
+        if let Some(dict) = dict {
+            file.write_all(&[Self::ENTRY_DICTIONARY])?;
+            file.write_all(&dict.id().to_be_bytes())?;
+        }
+
+        // Write readme.
+        if let Some(path) = &self.readme {
+            self.pack_readme(&mut file, path, dict)?;
+        }
+
+        // Write executables.
+        for (target, exe) in &self.exes {
+            let mut bin = match File::open(&exe.bin) {
+                Ok(v) => v,
+                Err(e) => return Err(PackagePackError::OpenExecutableFailed(exe.bin.clone(), e)),
+            };
+
+            // Write the target.
+            file.write_all(&[Self::ENTRY_EXE])?;
+            file.write_all(target.id().as_bytes())?;
+
+            // Write dependencies.
+            let count = TryInto::<u16>::try_into(exe.deps.len())
+                .unwrap()
+                .to_be_bytes();
+
+            file.write_all(&count)?;
+
+            for dep in &exe.deps {
+                dep.serialize(&mut file)?;
+            }
+
+            // Create a placeholder for binary length.
+            let lenoff = file.stream_position().unwrap();
+
+            file.write_all(&[0; 4])?;
+
+            // Write the executable.
+            let mut writer = Self::compress(&mut file, dict)?;
+
+            if let Err(e) = std::io::copy(&mut bin, &mut writer) {
+                return Err(PackagePackError::ReadExecutableFailed(exe.bin.clone(), e));
+            }
+
+            writer.flush()?;
+
+            drop(writer);
+
+            // Write executable length.
+            let cur = file.stream_position().unwrap();
+            let len: u32 = (cur - lenoff - 4).try_into().unwrap();
+
+            file.seek(SeekFrom::Start(lenoff)).unwrap();
+            file.write_all(&len.to_be_bytes())?;
+            file.seek(SeekFrom::Start(cur)).unwrap();
+        }
+
         // Write libraries
         for (target, lib) in &self.libs {
             // Write the target.
@@ -109,7 +326,7 @@ impl Package {
             file.write_all(&[0; 4])?;
 
             // Write the library.
-            let mut writer = ZstdWriter::new(&mut file);
+            let mut writer = Self::compress(&mut file, dict)?;
 
             lib.bin.serialize(&mut writer)?;
             writer.flush()?;
@@ -125,30 +342,206 @@ impl Package {
             file.seek(SeekFrom::Start(cur)).unwrap();
         }
 
+        // Write assets.
+        for (name, path) in &self.assets {
+            let mut asset = match File::open(path) {
+                Ok(v) => v,
+                Err(e) => return Err(PackagePackError::OpenAssetFailed(path.clone(), e)),
+            };
+
+            // Write the path.
+            let len = TryInto::<u16>::try_into(name.len()).unwrap().to_be_bytes();
+
+            file.write_all(&[Self::ENTRY_ASSET])?;
+            file.write_all(&len)?;
+            file.write_all(name.as_bytes())?;
+
+            // Create a placeholder for data length.
+            let lenoff = file.stream_position().unwrap();
+
+            file.write_all(&[0; 4])?;
+
+            // Write the data.
+            let mut writer = Self::compress(&mut file, dict)?;
+
+            if let Err(e) = std::io::copy(&mut asset, &mut writer) {
+                return Err(PackagePackError::ReadAssetFailed(path.clone(), e));
+            }
+
+            writer.flush()?;
+
+            drop(writer);
+
+            // Write data length.
+            let cur = file.stream_position().unwrap();
+            let len: u32 = (cur - lenoff - 4).try_into().unwrap();
+
+            file.seek(SeekFrom::Start(lenoff)).unwrap();
+            file.write_all(&len.to_be_bytes())?;
+            file.seek(SeekFrom::Start(cur)).unwrap();
+        }
+
         // End of entries.
         file.write_all(&[Self::ENTRY_END])?;
 
         Ok(())
     }
 
+    fn check_lib_target(target: &Target, bin: &LibraryBinary) -> Result<(), PackagePackError> {
+        let (pt, path) = match (target, bin) {
+            (Target::Primitive(t), LibraryBinary::Bundle(p)) => (*t, p),
+            _ => return Ok(()),
+        };
+
+        match bin.detect_target() {
+            Ok(Some((os, arch))) if os == pt.os() && arch == pt.arch() => Ok(()),
+            Ok(Some(_)) => Err(PackagePackError::LibraryTargetMismatch(path.clone(), pt)),
+            Ok(None) => Err(PackagePackError::UnknownLibraryFormat(path.clone())),
+            Err(e) => Err(PackagePackError::ReadLibraryFailed(path.clone(), e)),
+        }
+    }
+
+    fn pack_readme<W: Write + Seek>(
+        &self,
+        file: &mut W,
+        path: &Path,
+        dict: Option<&ZstdDictionary>,
+    ) -> Result<(), PackagePackError> {
+        let mut readme = match File::open(path) {
+            Ok(v) => v,
+            Err(e) => return Err(PackagePackError::OpenReadmeFailed(path.to_owned(), e)),
+        };
+
+        // Check the size.
+        let size = match readme.metadata() {
+            Ok(v) => v.len(),
+            Err(e) => return Err(PackagePackError::ReadReadmeFailed(path.to_owned(), e)),
+        };
+
+        if size > Self::README_MAX {
+            return Err(PackagePackError::ReadmeTooLarge(path.to_owned()));
+        }
+
+        // Create a placeholder for data length.
+        file.write_all(&[Self::ENTRY_README])?;
+
+        let lenoff = file.stream_position().unwrap();
+
+        file.write_all(&[0; 4])?;
+
+        // Write the data.
+        let mut writer = Self::compress(&mut *file, dict)?;
+
+        if let Err(e) = std::io::copy(
+            &mut Read::by_ref(&mut readme).take(Self::README_MAX),
+            &mut writer,
+        ) {
+            return Err(PackagePackError::ReadReadmeFailed(path.to_owned(), e));
+        }
+
+        writer.flush()?;
+
+        drop(writer);
+
+        // Write data length.
+        let cur = file.stream_position().unwrap();
+        let len: u32 = (cur - lenoff - 4).try_into().unwrap();
+
+        file.seek(SeekFrom::Start(lenoff)).unwrap();
+        file.write_all(&len.to_be_bytes())?;
+        file.seek(SeekFrom::Start(cur)).unwrap();
+
+        Ok(())
+    }
+
+    fn compress<W>(dest: W, dict: Option<&ZstdDictionary>) -> std::io::Result<ZstdWriter<W>> {
+        let mut writer = ZstdWriter::new(dest);
+
+        if let Some(dict) = dict {
+            writer.load_dictionary(dict)?;
+        }
+
+        Ok(writer)
+    }
+
+    fn decompress<R>(from: R, dict: Option<&ZstdDictionary>) -> std::io::Result<ZstdReader<R>> {
+        let mut reader = ZstdReader::new(from);
+
+        if let Some(dict) = dict {
+            reader.load_dictionary(dict)?;
+        }
+
+        Ok(reader)
+    }
+
+    /// If `assets` is `true` all assets in the package will be copied to `to` with the same
+    /// structure as they are in the package. If `with_deps` is `true` the shared libraries of all
+    /// runtime dependencies will be copied next to the binary.
     pub fn export<T>(
         &self,
         to: T,
         target: &Target,
+        assets: bool,
+        with_deps: bool,
         targets: &TargetResolver,
         deps: &DependencyResolver,
     ) -> Result<(), PackageExportError>
     where
         T: AsRef<Path>,
     {
-        // Create destination directory.
-        let to = to.as_ref();
+        let files = self.export_files(to, target, assets, with_deps, targets, deps)?;
 
-        if let Err(e) = std::fs::create_dir_all(to) {
-            return Err(PackageExportError::CreateDirectoryFailed(to.to_owned(), e));
+        for (from, to, hash) in files {
+            // Make sure the file is the same one that was built. It can be replaced by the other
+            // build or removed by the user since the build.
+            if let Some(expected) = hash {
+                let mut h = Fnv64::new();
+
+                if let Err(e) = hash_file(&from, &mut h) {
+                    return Err(PackageExportError::ReadArtifactFailed(from, e));
+                }
+
+                let found = h.finish();
+
+                if found != expected {
+                    return Err(PackageExportError::StaleArtifact {
+                        path: from,
+                        expected,
+                        found,
+                    });
+                }
+            }
+
+            let dir = to.parent().unwrap();
+
+            if let Err(e) = std::fs::create_dir_all(dir) {
+                return Err(PackageExportError::CreateDirectoryFailed(dir.to_owned(), e));
+            }
+
+            if let Err(e) = std::fs::copy(&from, &to) {
+                return Err(PackageExportError::CopyFailed(from, to, e));
+            }
         }
 
+        Ok(())
+    }
+
+    /// Returns the files that [`Self::export()`] will copy as a tuple of source, destination and
+    /// the expected hash of the source, if any. The binary is always the first one.
+    pub fn export_files<T>(
+        &self,
+        to: T,
+        target: &Target,
+        assets: bool,
+        with_deps: bool,
+        targets: &TargetResolver,
+        deps: &DependencyResolver,
+    ) -> Result<Vec<(PathBuf, PathBuf, Option<u64>)>, PackageExportError>
+    where
+        T: AsRef<Path>,
+    {
         // Resolve primitive target.
+        let to = to.as_ref();
         let pt = match targets.primitive(target) {
             Ok(v) => v,
             Err(e) => return Err(PackageExportError::ResolvePrimitiveTargetFailed(e)),
@@ -156,50 +549,40 @@ impl Package {
 
         // If there is an executable, export it otherwise export a library instead.
         let base = self.meta.name();
-        let (from, to) = if self.exes.is_empty() {
-            let lib = self
-                .libs
-                .get(target)
-                .ok_or(PackageExportError::TargetNotFound)?;
+        let (from, to, bin_deps, hash) = if self.exes.is_empty() {
+            let lib = match targets.find_nearest(target, &self.libs) {
+                Ok(Some((_, v))) => v,
+                Ok(None) => return Err(PackageExportError::TargetNotFound),
+                Err(e) => {
+                    return Err(PackageExportError::ResolveParentTargetFailed(
+                        target.clone(),
+                        e,
+                    ));
+                }
+            };
 
             // Get binary path.
             let from = match lib.bin.bin() {
                 LibraryBinary::Bundle(v) => v,
                 LibraryBinary::System(_) => return Err(PackageExportError::SystemLibrary),
+                LibraryBinary::Memory(_) => return Err(PackageExportError::NotUnpacked),
             };
 
             // Get destination path.
-            let ver = self.meta.version().major();
-            let to = to.join(match pt.os() {
-                TargetOs::Darwin => {
-                    if ver == 0 {
-                        format!("lib{base}.dylib")
-                    } else {
-                        format!("lib{base}-v{ver}.dylib")
-                    }
-                }
-                TargetOs::Linux => {
-                    if ver == 0 {
-                        format!("lib{base}.so")
-                    } else {
-                        format!("lib{base}-v{ver}.so")
-                    }
-                }
-                TargetOs::Win32 => {
-                    if ver == 0 {
-                        format!("{base}.dll")
-                    } else {
-                        format!("{base}-v{ver}.dll")
-                    }
-                }
-            });
+            let to = to.join(Self::library_file(pt, base, self.meta.version().major()));
 
-            (from, to)
+            (from, to, &lib.deps, lib.hash)
         } else {
-            let exe = self
-                .exes
-                .get(target)
-                .ok_or(PackageExportError::TargetNotFound)?;
+            let exe = match targets.find_nearest(target, &self.exes) {
+                Ok(Some((_, v))) => v,
+                Ok(None) => return Err(PackageExportError::TargetNotFound),
+                Err(e) => {
+                    return Err(PackageExportError::ResolveParentTargetFailed(
+                        target.clone(),
+                        e,
+                    ));
+                }
+            };
 
             // Get destination path.
             let to = to.join(match pt.os() {
@@ -207,30 +590,100 @@ impl Package {
                 TargetOs::Win32 => format!("{base}.exe"),
             });
 
-            (&exe.bin, to)
+            (&exe.bin, to, &exe.deps, exe.hash)
         };
 
         // Export.
-        if let Err(e) = std::fs::copy(from, &to) {
-            return Err(PackageExportError::CopyFailed(from.clone(), to, e));
+        let root = to.parent().unwrap().to_owned();
+        let mut files = vec![(from.clone(), to, hash)];
+
+        // Export dependencies.
+        if with_deps {
+            for dep in sorted_by_name(bin_deps) {
+                if dep.kind() != DependencyKind::Runtime {
+                    continue;
+                }
+
+                // Resolve the package.
+                let id = dep.dep();
+                let pkg = match deps.resolve(id, targets) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        return Err(PackageExportError::ResolveDependencyFailed(id.clone(), e));
+                    }
+                };
+
+                // Get the library.
+                let lib = match targets.find_nearest(target, &pkg.libs) {
+                    Ok(Some((_, v))) => v,
+                    Ok(None) => return Err(PackageExportError::DependencyNotFound(id.clone())),
+                    Err(e) => {
+                        return Err(PackageExportError::ResolveParentTargetFailed(
+                            target.clone(),
+                            e,
+                        ));
+                    }
+                };
+
+                // System library is already present on the target.
+                let from = match lib.bin.bin() {
+                    LibraryBinary::Bundle(v) => v,
+                    LibraryBinary::System(_) => continue,
+                    LibraryBinary::Memory(_) => return Err(PackageExportError::NotUnpacked),
+                };
+
+                // Copy the library.
+                let meta = pkg.meta();
+                let to = root.join(Self::library_file(pt, meta.name(), meta.version().major()));
+
+                files.push((from.clone(), to, None));
+            }
         }
 
-        Ok(())
+        // Export assets.
+        if assets {
+            for (name, from) in &self.assets {
+                files.push((from.clone(), root.join(name), None));
+            }
+        }
+
+        Ok(files)
+    }
+
+    fn library_file(target: &PrimitiveTarget, name: &PackageName, ver: u16) -> String {
+        match target.os() {
+            TargetOs::Darwin => {
+                if ver == 0 {
+                    format!("lib{name}.dylib")
+                } else {
+                    format!("lib{name}-v{ver}.dylib")
+                }
+            }
+            TargetOs::Linux => {
+                if ver == 0 {
+                    format!("lib{name}.so")
+                } else {
+                    format!("lib{name}-v{ver}.so")
+                }
+            }
+            TargetOs::Win32 => {
+                if ver == 0 {
+                    format!("{name}.dll")
+                } else {
+                    format!("{name}-v{ver}.dll")
+                }
+            }
+        }
     }
 
-    pub fn unpack<P, T>(mut pkg: P, to: T) -> Result<(), PackageUnpackError>
+    /// `dicts` is a list of dictionaries to select from if the package was packed with a
+    /// dictionary.
+    pub fn unpack<P, T>(pkg: P, to: T, dicts: &[ZstdDictionary]) -> Result<(), PackageUnpackError>
     where
         P: Read,
         T: AsRef<Path>,
     {
-        // Check magic.
-        let mut magic = [0u8; 4];
-
-        pkg.read_exact(&mut magic)?;
-
-        if magic.ne(b"\x7FNPK") {
-            return Err(PackageUnpackError::NotNitroPackage);
-        }
+        let mut scanner = EntryScanner::new(pkg, dicts)?;
 
         // Create destination directory.
         let to = to.as_ref();
@@ -239,6 +692,13 @@ impl Package {
             return Err(PackageUnpackError::CreateDirectoryFailed(to.to_owned(), e));
         }
 
+        // Create a directory for executables.
+        let exes = to.join("exes");
+
+        if let Err(e) = std::fs::create_dir(&exes) {
+            return Err(PackageUnpackError::CreateDirectoryFailed(exes, e));
+        }
+
         // Create a directory for libraries.
         let libs = to.join("libs");
 
@@ -246,45 +706,41 @@ impl Package {
             return Err(PackageUnpackError::CreateDirectoryFailed(libs, e));
         }
 
-        // Iterate over the entries.
-        let mut name = None;
-        let mut version = None;
-        let mut nlib = 0;
+        // Create a directory for assets.
+        let assets = to.join("assets");
 
-        loop {
-            // Read entry type.
-            let mut ty = 0;
+        if let Err(e) = std::fs::create_dir(&assets) {
+            return Err(PackageUnpackError::CreateDirectoryFailed(assets, e));
+        }
 
-            pkg.read_exact(std::slice::from_mut(&mut ty))?;
+        // Iterate over the entries.
+        let mut nexe = 0;
+        let mut nlib = 0;
 
-            // Process the entry.
+        while let Some(ty) = scanner.next()? {
             match ty {
-                Self::ENTRY_END => break,
-                Self::ENTRY_NAME => {
-                    let mut data = [0u8; 32];
-                    pkg.read_exact(&mut data)?;
-                    name = Some(
-                        PackageName::from_bin(&data)
-                            .map_err(|e| PackageUnpackError::InvalidNameEntry(e))?,
-                    );
-                }
-                Self::ENTRY_VERSION => {
-                    let mut data = [0u8; 8];
-                    pkg.read_exact(&mut data)?;
-                    version = Some(PackageVersion::from_bin(u64::from_be_bytes(data)));
-                }
-                Self::ENTRY_DATE => {
-                    let mut data = [0u8; 8];
-                    pkg.read_exact(&mut data)?;
+                Self::ENTRY_README => {
+                    // Write the readme.
+                    let path = to.join("README.md");
+                    let mut reader = scanner.data()?;
+                    let mut file = match File::create(&path) {
+                        Ok(v) => v,
+                        Err(e) => return Err(PackageUnpackError::WriteFileFailed(path, e)),
+                    };
+
+                    if let Err(e) = std::io::copy(&mut reader, &mut file) {
+                        return Err(PackageUnpackError::WriteFileFailed(path, e));
+                    }
                 }
-                Self::ENTRY_LIB => {
+                Self::ENTRY_EXE => {
                     // Read target.
+                    let pkg = scanner.pkg();
                     let mut data = [0u8; 16];
                     pkg.read_exact(&mut data)?;
 
-                    // Create a directory to unpack the library.
+                    // Create a directory to unpack the executable.
                     let target = Uuid::from_bytes(data);
-                    let dir = libs.join(target.to_string());
+                    let dir = exes.join(target.to_string());
 
                     if let Err(e) = std::fs::create_dir(&dir) {
                         return Err(PackageUnpackError::CreateDirectoryFailed(dir, e));
@@ -299,26 +755,26 @@ impl Package {
                     let mut deps = Vec::with_capacity(ndep);
 
                     for i in 0..ndep {
-                        match Dependency::deserialize(&mut pkg) {
+                        match BinaryDependency::deserialize(&mut *pkg) {
                             Ok(v) => deps.push(v),
                             Err(e) => {
-                                return Err(PackageUnpackError::InvalidLibraryDependency(
-                                    nlib, i, e,
+                                return Err(PackageUnpackError::InvalidExecutableDependency(
+                                    nexe, i, e,
                                 ));
                             }
                         };
                     }
 
-                    // Read binary length.
-                    let mut data = [0; 4];
-                    pkg.read_exact(&mut data)?;
-                    let len: u64 = u32::from_be_bytes(data).into();
-
-                    // Read the binary.
-                    let reader = ZstdReader::new(pkg.by_ref().take(len));
+                    // Write the binary.
+                    let mut reader = scanner.data()?;
+                    let path = dir.join("bin");
+                    let mut file = match Self::create_executable(&path) {
+                        Ok(v) => v,
+                        Err(e) => return Err(PackageUnpackError::WriteFileFailed(path, e)),
+                    };
 
-                    if let Err(e) = Library::unpack(reader, dir.join("bin"), dir.join("types")) {
-                        return Err(PackageUnpackError::UnpackLibraryFailed(dir, e));
+                    if let Err(e) = std::io::copy(&mut reader, &mut file) {
+                        return Err(PackageUnpackError::WriteFileFailed(path, e));
                     }
 
                     // Write dependencies.
@@ -330,18 +786,104 @@ impl Package {
 
                     serde_yaml::to_writer(file, &deps).unwrap();
 
-                    nlib += 1;
+                    nexe += 1;
                 }
-                v => return Err(PackageUnpackError::UnknownEntry(v)),
-            }
-        }
+                Self::ENTRY_LIB => {
+                    // Read target.
+                    let pkg = scanner.pkg();
+                    let mut data = [0u8; 16];
+                    pkg.read_exact(&mut data)?;
 
-        // Write metadata.
-        let name = name.ok_or(PackageUnpackError::NoNameEntry)?;
-        let version = version.ok_or(PackageUnpackError::NoVersionEntry)?;
-        let meta = PackageMeta::new(name, version);
-        let path = to.join("meta.yml");
-        let file = match File::create(&path) {
+                    // Create a directory to unpack the library.
+                    let target = Uuid::from_bytes(data);
+                    let dir = libs.join(target.to_string());
+
+                    if let Err(e) = std::fs::create_dir(&dir) {
+                        return Err(PackageUnpackError::CreateDirectoryFailed(dir, e));
+                    }
+
+                    // Read dependency count.
+                    let mut data = [0u8; 2];
+                    pkg.read_exact(&mut data)?;
+                    let ndep: usize = u16::from_be_bytes(data).into();
+
+                    // Read dependencies.
+                    let mut deps = Vec::with_capacity(ndep);
+
+                    for i in 0..ndep {
+                        match BinaryDependency::deserialize(&mut *pkg) {
+                            Ok(v) => deps.push(v),
+                            Err(e) => {
+                                return Err(PackageUnpackError::InvalidLibraryDependency(
+                                    nlib, i, e,
+                                ));
+                            }
+                        };
+                    }
+
+                    // Read the binary.
+                    let reader = scanner.data()?;
+
+                    if let Err(e) = Library::unpack(reader, dir.join("bin"), dir.join("types")) {
+                        return Err(PackageUnpackError::UnpackLibraryFailed(dir, e));
+                    }
+
+                    // Write dependencies.
+                    let path = dir.join("deps.yml");
+                    let file = match File::create(&path) {
+                        Ok(v) => v,
+                        Err(e) => return Err(PackageUnpackError::WriteFileFailed(path, e)),
+                    };
+
+                    serde_yaml::to_writer(file, &deps).unwrap();
+
+                    nlib += 1;
+                }
+                Self::ENTRY_ASSET => {
+                    // Read the path.
+                    let pkg = scanner.pkg();
+                    let mut data = [0u8; 2];
+                    pkg.read_exact(&mut data)?;
+                    let mut name = vec![0u8; u16::from_be_bytes(data).into()];
+                    pkg.read_exact(&mut name)?;
+
+                    // Make sure the path cannot escape from the assets directory.
+                    let name = match String::from_utf8(name) {
+                        Ok(v) if Self::is_valid_asset(&v) => v,
+                        Ok(v) => return Err(PackageUnpackError::InvalidAssetPath(v)),
+                        Err(e) => {
+                            let v = String::from_utf8_lossy(e.as_bytes()).into_owned();
+                            return Err(PackageUnpackError::InvalidAssetPath(v));
+                        }
+                    };
+
+                    // Create a parent directory.
+                    let path = assets.join(&name);
+                    let dir = path.parent().unwrap();
+
+                    if let Err(e) = std::fs::create_dir_all(dir) {
+                        return Err(PackageUnpackError::CreateDirectoryFailed(dir.to_owned(), e));
+                    }
+
+                    // Write the asset.
+                    let mut reader = scanner.data()?;
+                    let mut file = match File::create(&path) {
+                        Ok(v) => v,
+                        Err(e) => return Err(PackageUnpackError::WriteFileFailed(path, e)),
+                    };
+
+                    if let Err(e) = std::io::copy(&mut reader, &mut file) {
+                        return Err(PackageUnpackError::WriteFileFailed(path, e));
+                    }
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        // Write metadata.
+        let meta = scanner.meta()?;
+        let path = to.join("meta.yml");
+        let file = match File::create(&path) {
             Ok(v) => v,
             Err(e) => return Err(PackageUnpackError::WriteFileFailed(path, e)),
         };
@@ -351,6 +893,322 @@ impl Package {
         Ok(())
     }
 
+    /// Read a packed package into memory without unpacking it. The bundled libraries will be
+    /// [`LibraryBinary::Memory`] and the executables, the readme and the assets are skipped so the
+    /// returned package is only suitable to look at its metadata, types and library dependencies.
+    /// A package that contains only executables cannot be opened this way.
+    pub fn open_reader<P: Read>(
+        pkg: P,
+        dicts: &[ZstdDictionary],
+        targets: &TargetResolver,
+    ) -> Result<Self, PackageUnpackError> {
+        // Iterate over the entries.
+        let mut scanner = EntryScanner::new(pkg, dicts)?;
+        let mut nexe = 0;
+        let mut libs = Vec::new();
+
+        while let Some(ty) = scanner.next()? {
+            match ty {
+                Self::ENTRY_README => Self::skip_data(scanner.pkg())?,
+                Self::ENTRY_EXE => {
+                    let pkg = scanner.pkg();
+                    let mut data = [0u8; 18];
+                    pkg.read_exact(&mut data)?;
+                    let ndep = u16::from_be_bytes([data[16], data[17]]);
+
+                    for i in 0..ndep.into() {
+                        if let Err(e) = BinaryDependency::deserialize(&mut *pkg) {
+                            return Err(PackageUnpackError::InvalidExecutableDependency(
+                                nexe, i, e,
+                            ));
+                        }
+                    }
+
+                    Self::skip_data(pkg)?;
+                    nexe += 1;
+                }
+                Self::ENTRY_LIB => {
+                    // Read target.
+                    let pkg = scanner.pkg();
+                    let mut data = [0u8; 16];
+                    pkg.read_exact(&mut data)?;
+
+                    let id = Uuid::from_bytes(data);
+                    let target = match targets.resolve(&id) {
+                        Ok(v) => v,
+                        Err(e) => return Err(PackageUnpackError::ResolveTargetFailed(id, e)),
+                    };
+
+                    // Read dependencies.
+                    let mut data = [0u8; 2];
+                    pkg.read_exact(&mut data)?;
+                    let ndep: usize = u16::from_be_bytes(data).into();
+                    let mut deps = HashSet::with_capacity(ndep);
+
+                    for i in 0..ndep {
+                        match BinaryDependency::deserialize(&mut *pkg) {
+                            Ok(v) => {
+                                if !deps.insert(v) {
+                                    return Err(PackageUnpackError::DuplicatedLibraryDependency(
+                                        libs.len(),
+                                        i,
+                                    ));
+                                }
+                            }
+                            Err(e) => {
+                                return Err(PackageUnpackError::InvalidLibraryDependency(
+                                    libs.len(),
+                                    i,
+                                    e,
+                                ));
+                            }
+                        };
+                    }
+
+                    // Read the library.
+                    let lib = match Library::read(scanner.data()?) {
+                        Ok(v) => v,
+                        Err(e) => return Err(PackageUnpackError::ReadLibraryFailed(libs.len(), e)),
+                    };
+
+                    libs.push((target, lib, deps));
+                }
+                Self::ENTRY_ASSET => {
+                    let pkg = scanner.pkg();
+                    let mut data = [0u8; 2];
+                    pkg.read_exact(&mut data)?;
+                    let mut name = vec![0u8; u16::from_be_bytes(data).into()];
+                    pkg.read_exact(&mut name)?;
+
+                    Self::skip_data(pkg)?;
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        // Build the package.
+        let mut out = Self::builder(scanner.meta()?);
+
+        for (target, lib, deps) in libs {
+            if let Err(e) = out.add_library(target, lib, deps, None) {
+                return Err(PackageUnpackError::BuildPackageFailed(e));
+            }
+        }
+
+        out.build().map_err(PackageUnpackError::BuildPackageFailed)
+    }
+
+    /// Create a file for an unpacked executable. The file will be executable on Unix.
+    fn create_executable(path: &Path) -> std::io::Result<File> {
+        let mut opts = OpenOptions::new();
+
+        opts.write(true).create(true).truncate(true);
+
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut opts, 0o755);
+
+        opts.open(path)
+    }
+
+    /// Skip a length-prefixed data entry.
+    fn skip_data<P: Read>(pkg: &mut P) -> Result<(), PackageUnpackError> {
+        let mut data = [0; 4];
+        pkg.read_exact(&mut data)?;
+        let len: u64 = u32::from_be_bytes(data).into();
+
+        if std::io::copy(&mut pkg.take(len), &mut std::io::sink())? != len {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+
+        Ok(())
+    }
+
+    /// Read the metadata entries from a packed package without unpacking it.
+    pub fn inspect<P: Read>(
+        pkg: P,
+        dicts: &[ZstdDictionary],
+    ) -> Result<PackageInfo, PackageUnpackError> {
+        // Metadata entries always precede the other entries so we can stop on the first one that
+        // is not a metadata. This also means an out of order entry is only detected by the readers
+        // that go through the whole package.
+        let mut scanner = EntryScanner::new(pkg, dicts)?;
+        let mut readme = None;
+
+        while let Some(Self::ENTRY_README) = scanner.next()? {
+            let mut data = Vec::new();
+
+            scanner.data()?.read_to_end(&mut data)?;
+            readme = Some(data);
+        }
+
+        Ok(PackageInfo {
+            meta: scanner.meta()?,
+            readme,
+            tool: scanner.tool().map(String::from),
+        })
+    }
+
+    /// Returns the value of the tool entry to write to a package.
+    fn tool() -> String {
+        format!("nitro {}", env!("CARGO_PKG_VERSION"))
+    }
+
+    fn read_tool<P: Read>(pkg: &mut P) -> Result<String, PackageUnpackError> {
+        let mut len = 0;
+        pkg.read_exact(std::slice::from_mut(&mut len))?;
+        let mut data = vec![0u8; len.into()];
+        pkg.read_exact(&mut data)?;
+
+        String::from_utf8(data).map_err(|_| PackageUnpackError::InvalidToolEntry)
+    }
+
+    fn read_dictionary<'a, P: Read>(
+        pkg: &mut P,
+        dicts: &'a [ZstdDictionary],
+    ) -> Result<&'a ZstdDictionary, PackageUnpackError> {
+        let mut data = [0u8; 4];
+        pkg.read_exact(&mut data)?;
+        let id = u32::from_be_bytes(data);
+
+        dicts
+            .iter()
+            .find(|d| d.id() == id)
+            .ok_or(PackageUnpackError::UnknownDictionary(id))
+    }
+
+    /// Add all binaries and assets from `other` to this package. Both packages must have the same
+    /// name and version. A target or an asset that present in both packages must have the same
+    /// content.
+    pub fn merge(&mut self, other: Self) -> Result<(), PackageMergeError> {
+        // Check metadata.
+        let (a, b) = (&self.meta, &other.meta);
+
+        if a.name() != b.name() {
+            return Err(PackageMergeError::NameMismatch(
+                a.name().clone(),
+                b.name().clone(),
+            ));
+        } else if a.version() != b.version() {
+            return Err(PackageMergeError::VersionMismatch(
+                a.version().clone(),
+                b.version().clone(),
+            ));
+        } else if a.mangling() != b.mangling() {
+            return Err(PackageMergeError::ManglingMismatch);
+        }
+
+        // Merge executables.
+        for (target, exe) in other.exes {
+            if let Some(v) = self.exes.get(&target) {
+                let a = Self::exe_content(v).map_err(PackageMergeError::ReadBinaryFailed)?;
+                let b = Self::exe_content(&exe).map_err(PackageMergeError::ReadBinaryFailed)?;
+
+                if a != b {
+                    return Err(PackageMergeError::ConflictingExecutable(target));
+                }
+            } else {
+                self.exes.insert(target, exe);
+            }
+        }
+
+        // Merge libraries.
+        for (target, lib) in other.libs {
+            if let Some(v) = self.libs.get(&target) {
+                let a = Self::lib_content(v).map_err(PackageMergeError::ReadBinaryFailed)?;
+                let b = Self::lib_content(&lib).map_err(PackageMergeError::ReadBinaryFailed)?;
+
+                if a != b {
+                    return Err(PackageMergeError::ConflictingLibrary(target));
+                }
+            } else {
+                self.libs.insert(target, lib);
+            }
+        }
+
+        // Merge assets.
+        for (name, path) in other.assets {
+            if let Some(v) = self.assets.get(&name) {
+                let a = match std::fs::read(v) {
+                    Ok(v) => v,
+                    Err(e) => return Err(PackageMergeError::ReadAssetFailed(v.clone(), e)),
+                };
+
+                let b = match std::fs::read(&path) {
+                    Ok(v) => v,
+                    Err(e) => return Err(PackageMergeError::ReadAssetFailed(path, e)),
+                };
+
+                if a != b {
+                    return Err(PackageMergeError::ConflictingAsset(name));
+                }
+            } else {
+                self.assets.insert(name, path);
+            }
+        }
+
+        // Use the readme from the other package only if we don't have one.
+        if self.readme.is_none() {
+            self.readme = other.readme;
+        }
+
+        Ok(())
+    }
+
+    fn exe_content(exe: &Binary<PathBuf>) -> Result<Vec<u8>, std::io::Error> {
+        let mut data = Self::deps_content(&exe.deps)?;
+        let mut file = File::open(&exe.bin)?;
+
+        file.read_to_end(&mut data)?;
+
+        Ok(data)
+    }
+
+    fn lib_content(lib: &Binary<Library>) -> Result<Vec<u8>, std::io::Error> {
+        let mut data = Self::deps_content(&lib.deps)?;
+
+        lib.bin.serialize(&mut data)?;
+
+        Ok(data)
+    }
+
+    fn deps_content(deps: &HashSet<BinaryDependency>) -> Result<Vec<u8>, std::io::Error> {
+        let mut list = Vec::with_capacity(deps.len());
+
+        for dep in deps {
+            let mut data = Vec::new();
+            dep.serialize(&mut data)?;
+            list.push(data);
+        }
+
+        list.sort_unstable();
+
+        Ok(list.concat())
+    }
+
+    /// Returns `true` if `path` is a relative path that use `/` as a separator and does not contains
+    /// any `.` or `..` component.
+    pub fn is_valid_asset(path: &str) -> bool {
+        !path.is_empty()
+            && !path.contains(['\\', ':'])
+            && path
+                .split('/')
+                .all(|c| !c.is_empty() && c != "." && c != "..")
+    }
+
+    fn is_meta_entry(ty: u8) -> bool {
+        matches!(
+            ty,
+            Self::ENTRY_NAME
+                | Self::ENTRY_VERSION
+                | Self::ENTRY_DATE
+                | Self::ENTRY_README
+                | Self::ENTRY_MANGLING
+                | Self::ENTRY_DICTIONARY
+                | Self::ENTRY_TOOL
+                | Self::ENTRY_CHECKSUM
+        )
+    }
+
     pub fn open(
         path: impl AsRef<Path>,
         targets: &TargetResolver,
@@ -365,23 +1223,131 @@ impl Package {
         };
 
         // Read metadata.
-        let meta = match serde_yaml::from_reader(meta) {
+        let meta = match serde_yaml::from_reader::<_, PackageMeta>(meta) {
             Ok(v) => v,
             Err(e) => return Err(PackageOpenError::ReadPackageMetaFailed(path, e)),
         };
 
+        // Enumerate executables. A package that was unpacked before executables were stored does
+        // not have this directory.
+        let mut out = Self::builder(meta);
+        let path = root.join("exes");
+
+        if path.is_dir() {
+            for (target, path) in Self::open_binary_dirs(&path, targets)? {
+                let deps = Self::open_deps(path.join("deps.yml"))?;
+
+                if let Err(e) = out.add_executable(target, path.join("bin"), deps, None) {
+                    return Err(PackageOpenError::BuildPackageFailed(e));
+                }
+            }
+        }
+
         // Enumerate libraries.
-        let mut libs = HashMap::new();
         let path = root.join("libs");
-        let items = match read_dir(&path) {
+        for (target, path) in Self::open_binary_dirs(&path, targets)? {
+            // Load library.
+            let bin = match Library::open(path.join("bin"), path.join("types")) {
+                Ok(v) => v,
+                Err(e) => return Err(PackageOpenError::OpenLibraryFailed(e)),
+            };
+
+            let deps = Self::open_deps(path.join("deps.yml"))?;
+
+            if let Err(e) = out.add_library(target, bin, deps, None) {
+                return Err(PackageOpenError::BuildPackageFailed(e));
+            }
+        }
+
+        // Enumerate assets.
+        let path = root.join("assets");
+        let mut dirs = VecDeque::new();
+
+        if path.is_dir() {
+            dirs.push_back(path.clone());
+        }
+
+        while let Some(dir) = dirs.pop_front() {
+            let items = match read_dir(&dir) {
+                Ok(v) => v,
+                Err(e) => return Err(PackageOpenError::OpenDirectoryFailed(dir, e)),
+            };
+
+            for item in items {
+                let item = match item {
+                    Ok(v) => v,
+                    Err(e) => return Err(PackageOpenError::OpenDirectoryFailed(dir, e)),
+                };
+
+                // Check if directory.
+                let file = item.path();
+                let meta = match std::fs::metadata(&file) {
+                    Ok(v) => v,
+                    Err(e) => return Err(PackageOpenError::GetFileMetaFailed(file, e)),
+                };
+
+                if meta.is_dir() {
+                    dirs.push_back(file);
+                    continue;
+                }
+
+                // Build asset name.
+                let mut name = String::new();
+
+                for c in file.strip_prefix(&path).unwrap().components() {
+                    let c = match c.as_os_str().to_str() {
+                        Some(v) => v,
+                        None => return Err(PackageOpenError::InvalidAssetFile(file)),
+                    };
+
+                    if !name.is_empty() {
+                        name.push('/');
+                    }
+
+                    name.push_str(c);
+                }
+
+                if !Self::is_valid_asset(&name) {
+                    return Err(PackageOpenError::InvalidAssetFile(file));
+                }
+
+                if let Err(e) = out.add_asset(name, file) {
+                    return Err(PackageOpenError::BuildPackageFailed(e));
+                }
+            }
+        }
+
+        // Check for readme.
+        let mut pkg = match out.build() {
             Ok(v) => v,
-            Err(e) => return Err(PackageOpenError::OpenDirectoryFailed(path, e)),
+            Err(e) => return Err(PackageOpenError::BuildPackageFailed(e)),
         };
+        let path = root.join("README.md");
+
+        if path.is_file() {
+            pkg.set_readme(path);
+        }
+
+        Ok(pkg)
+    }
+
+    /// Returns the target and the path of each directory in `path` that is named after a target
+    /// ID (e.g. `libs/<uuid>`).
+    fn open_binary_dirs(
+        path: &Path,
+        targets: &TargetResolver,
+    ) -> Result<Vec<(Target, PathBuf)>, PackageOpenError> {
+        let items = match read_dir(path) {
+            Ok(v) => v,
+            Err(e) => return Err(PackageOpenError::OpenDirectoryFailed(path.to_owned(), e)),
+        };
+
+        let mut dirs = Vec::new();
 
         for item in items {
             let item = match item {
                 Ok(v) => v,
-                Err(e) => return Err(PackageOpenError::OpenDirectoryFailed(path, e)),
+                Err(e) => return Err(PackageOpenError::OpenDirectoryFailed(path.to_owned(), e)),
             };
 
             // Check if directory.
@@ -398,13 +1364,13 @@ impl Package {
             // Check if directory name is UTF-8.
             let name = match path.file_name().unwrap().to_str() {
                 Some(v) => v,
-                None => return Err(PackageOpenError::InvalidLibraryDirectory(path)),
+                None => return Err(PackageOpenError::InvalidBinaryDirectory(path)),
             };
 
             // Get target ID.
             let target: Uuid = match name.parse() {
                 Ok(v) => v,
-                Err(_) => return Err(PackageOpenError::InvalidLibraryDirectory(path)),
+                Err(_) => return Err(PackageOpenError::InvalidBinaryDirectory(path)),
             };
 
             // Resolve target.
@@ -413,55 +1379,169 @@ impl Package {
                 Err(e) => return Err(PackageOpenError::ResolveTargetFailed(target, e)),
             };
 
-            // Load library.
-            let bin = match Library::open(path.join("bin"), path.join("types")) {
-                Ok(v) => v,
-                Err(e) => return Err(PackageOpenError::OpenLibraryFailed(e)),
-            };
+            dirs.push((target, path));
+        }
 
-            // Open dependencies.
-            let path = path.join("deps.yml");
-            let deps = match File::open(&path) {
-                Ok(v) => v,
-                Err(e) => return Err(PackageOpenError::OpenFileFailed(path, e)),
-            };
+        Ok(dirs)
+    }
 
-            // Read dependencies.
-            let list: Vec<Dependency> = match serde_yaml::from_reader(deps) {
-                Ok(v) => v,
-                Err(e) => return Err(PackageOpenError::ReadDependenciesFailed(path, e)),
-            };
+    /// Read the dependencies of an unpacked binary from `path`.
+    fn open_deps(path: PathBuf) -> Result<HashSet<BinaryDependency>, PackageOpenError> {
+        // Open dependencies.
+        let deps = match File::open(&path) {
+            Ok(v) => v,
+            Err(e) => return Err(PackageOpenError::OpenFileFailed(path, e)),
+        };
 
-            // Build dependency list.
-            let mut deps = HashSet::with_capacity(list.len());
+        // Read dependencies.
+        let list: Vec<BinaryDependency> = match serde_yaml::from_reader(deps) {
+            Ok(v) => v,
+            Err(e) => return Err(PackageOpenError::ReadDependenciesFailed(path, e)),
+        };
 
-            for dep in list {
-                if let Some(dep) = deps.replace(dep) {
-                    return Err(PackageOpenError::DuplicatedDependency(path, dep));
-                }
+        // Build dependency list.
+        let mut deps = HashSet::with_capacity(list.len());
+
+        for dep in list {
+            if let Some(dep) = deps.replace(dep) {
+                return Err(PackageOpenError::DuplicatedDependency(path, dep));
             }
+        }
 
-            assert!(libs.insert(target, Binary::new(bin, deps)).is_none());
+        Ok(deps)
+    }
+}
+
+/// Metadata of a packed package.
+pub struct PackageInfo {
+    meta: PackageMeta,
+    readme: Option<Vec<u8>>,
+    tool: Option<String>,
+}
+
+impl PackageInfo {
+    pub fn meta(&self) -> &PackageMeta {
+        &self.meta
+    }
+
+    pub fn readme(&self) -> Option<&[u8]> {
+        self.readme.as_deref()
+    }
+
+    /// Returns the compiler that produced the package or [`None`] if it was produced before the
+    /// tool entry was introduced.
+    pub fn tool(&self) -> Option<&str> {
+        self.tool.as_deref()
+    }
+}
+
+/// A builder for [`Package`].
+pub struct PackageBuilder {
+    meta: PackageMeta,
+    exes: HashMap<Target, Binary<PathBuf>>,
+    libs: HashMap<Target, Binary<Library>>,
+    assets: BTreeMap<String, PathBuf>,
+}
+
+impl PackageBuilder {
+    pub fn meta(&self) -> &PackageMeta {
+        &self.meta
+    }
+
+    /// Returns the libraries that was added so far.
+    pub fn libs(&self) -> &HashMap<Target, Binary<Library>> {
+        &self.libs
+    }
+
+    /// `hash` is the FNV-1a hash of `bin`, which will be verified before it is exported.
+    pub fn add_executable(
+        &mut self,
+        target: Target,
+        bin: PathBuf,
+        deps: HashSet<BinaryDependency>,
+        hash: Option<u64>,
+    ) -> Result<&mut Self, PackageBuildError> {
+        if self.exes.contains_key(&target) {
+            return Err(PackageBuildError::DuplicatedExecutable(target));
         }
 
-        Ok(Self::new(meta, HashMap::new(), libs))
+        self.exes.insert(target, Binary::new(bin, deps, hash));
+
+        Ok(self)
+    }
+
+    /// `hash` is the FNV-1a hash of the bundled binary, which will be verified before it is
+    /// exported.
+    pub fn add_library(
+        &mut self,
+        target: Target,
+        lib: Library,
+        deps: HashSet<BinaryDependency>,
+        hash: Option<u64>,
+    ) -> Result<&mut Self, PackageBuildError> {
+        if self.libs.contains_key(&target) {
+            return Err(PackageBuildError::DuplicatedLibrary(target));
+        }
+
+        self.libs.insert(target, Binary::new(lib, deps, hash));
+
+        Ok(self)
+    }
+
+    /// `name` is a relative path (using `/` as a separator) and `file` is the file on the local
+    /// filesystem.
+    pub fn add_asset(
+        &mut self,
+        name: String,
+        file: PathBuf,
+    ) -> Result<&mut Self, PackageBuildError> {
+        if !Package::is_valid_asset(&name) {
+            return Err(PackageBuildError::InvalidAsset(name));
+        } else if self.assets.contains_key(&name) {
+            return Err(PackageBuildError::DuplicatedAsset(name));
+        }
+
+        self.assets.insert(name, file);
+
+        Ok(self)
+    }
+
+    pub fn build(self) -> Result<Package, PackageBuildError> {
+        if self.exes.is_empty() && self.libs.is_empty() {
+            return Err(PackageBuildError::NoBinary);
+        }
+
+        Ok(Package {
+            meta: self.meta,
+            exes: self.exes,
+            libs: self.libs,
+            assets: self.assets,
+            readme: None,
+        })
     }
 }
 
 /// A compiled binary file.
 pub struct Binary<T> {
     bin: T,
-    deps: HashSet<Dependency>,
+    deps: HashSet<BinaryDependency>,
+    /// FNV-1a hash of the binary file when it was built. This is [`None`] if the binary was not
+    /// built in the current process (e.g. it was loaded from a package).
+    hash: Option<u64>,
 }
 
 impl<T> Binary<T> {
-    pub fn new(bin: T, deps: HashSet<Dependency>) -> Self {
-        Self { bin, deps }
+    pub fn new(bin: T, deps: HashSet<BinaryDependency>, hash: Option<u64>) -> Self {
+        Self { bin, deps, hash }
     }
 
     pub fn bin(&self) -> &T {
         &self.bin
     }
+
+    pub fn deps(&self) -> &HashSet<BinaryDependency> {
+        &self.deps
+    }
 }
 
 /// Represents an error when a package is failed to open.
@@ -479,8 +1559,8 @@ pub enum PackageOpenError {
     #[error("cannot read package metadata from {0}")]
     ReadPackageMetaFailed(PathBuf, #[source] serde_yaml::Error),
 
-    #[error("name of {0} is not a valid name for library directory")]
-    InvalidLibraryDirectory(PathBuf),
+    #[error("name of {0} is not a valid name for binary directory")]
+    InvalidBinaryDirectory(PathBuf),
 
     #[error("cannot resolve target {0}")]
     ResolveTargetFailed(Uuid, #[source] TargetResolveError),
@@ -492,7 +1572,32 @@ pub enum PackageOpenError {
     ReadDependenciesFailed(PathBuf, #[source] serde_yaml::Error),
 
     #[error("multiple definition of {1} in {0}")]
-    DuplicatedDependency(PathBuf, Dependency),
+    DuplicatedDependency(PathBuf, BinaryDependency),
+
+    #[error("{0} is not a valid asset")]
+    InvalidAssetFile(PathBuf),
+
+    #[error("cannot build the package")]
+    BuildPackageFailed(#[source] PackageBuildError),
+}
+
+/// Represents an error when [`PackageBuilder`] is failed.
+#[derive(Debug, Error)]
+pub enum PackageBuildError {
+    #[error("an executable for {0} already exists")]
+    DuplicatedExecutable(Target),
+
+    #[error("a library for {0} already exists")]
+    DuplicatedLibrary(Target),
+
+    #[error("{0} is not a valid asset name")]
+    InvalidAsset(String),
+
+    #[error("asset {0} already exists")]
+    DuplicatedAsset(String),
+
+    #[error("the package must contains at least one binary")]
+    NoBinary,
 }
 
 /// Represents an error when a package is failed to pack.
@@ -503,6 +1608,67 @@ pub enum PackagePackError {
 
     #[error("cannot write the specified file")]
     WriteFailed(#[source] std::io::Error),
+
+    #[error("cannot open {0}")]
+    OpenAssetFailed(PathBuf, #[source] std::io::Error),
+
+    #[error("cannot read {0}")]
+    ReadAssetFailed(PathBuf, #[source] std::io::Error),
+
+    #[error("cannot open {0}")]
+    OpenExecutableFailed(PathBuf, #[source] std::io::Error),
+
+    #[error("cannot read {0}")]
+    ReadExecutableFailed(PathBuf, #[source] std::io::Error),
+
+    #[error("cannot open readme {0}")]
+    OpenReadmeFailed(PathBuf, #[source] std::io::Error),
+
+    #[error("cannot read readme {0}")]
+    ReadReadmeFailed(PathBuf, #[source] std::io::Error),
+
+    #[error("{0} is larger than {} bytes", Package::README_MAX)]
+    ReadmeTooLarge(PathBuf),
+
+    #[error("cannot read {0}")]
+    ReadLibraryFailed(PathBuf, #[source] std::io::Error),
+
+    #[error("{0} is not a library for {1}")]
+    LibraryTargetMismatch(PathBuf, &'static PrimitiveTarget),
+
+    #[error("{0} has unknown binary format")]
+    UnknownLibraryFormat(PathBuf),
+}
+
+/// A [`Write`] that discard the data but keep track of the size.
+#[derive(Default)]
+struct SizeCounter {
+    pos: u64,
+    len: u64,
+}
+
+impl Write for SizeCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.pos += buf.len() as u64;
+        self.len = self.len.max(self.pos);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for SizeCounter {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.pos = match pos {
+            SeekFrom::Start(v) => v,
+            SeekFrom::End(v) => self.len.checked_add_signed(v).unwrap(),
+            SeekFrom::Current(v) => self.pos.checked_add_signed(v).unwrap(),
+        };
+
+        Ok(self.pos)
+    }
 }
 
 impl From<std::io::Error> for PackagePackError {
@@ -511,6 +1677,22 @@ impl From<std::io::Error> for PackagePackError {
     }
 }
 
+/// An inconsistency that was found by [`Package::verify()`].
+#[derive(Debug, Error)]
+pub enum VerifyIssue {
+    #[error("target {0} cannot be resolved")]
+    UnresolvedTarget(Target, #[source] TargetResolveError),
+
+    #[error("{1} on the library for {0} references {2} v{3} but it is not a dependency")]
+    MissingDependency(Target, String, String, u16),
+
+    #[error("{1} and {2} on the library for {0} have the same symbol")]
+    DuplicatedSymbol(Target, String, String),
+
+    #[error("the executable for {0} depends on {1} but the library depends on {2}")]
+    ConflictingDependency(Target, Dependency, Dependency),
+}
+
 /// Represents an error when a package is failed to export.
 #[derive(Debug, Error)]
 pub enum PackageExportError {
@@ -526,8 +1708,58 @@ pub enum PackageExportError {
     #[error("a system library cannot be exported")]
     SystemLibrary,
 
+    #[error("a binary that was not unpacked cannot be exported")]
+    NotUnpacked,
+
     #[error("cannot copy {0} to {1}")]
     CopyFailed(PathBuf, PathBuf, #[source] std::io::Error),
+
+    #[error("cannot resolve dependency {0}")]
+    ResolveDependencyFailed(Dependency, #[source] DependencyResolveError),
+
+    #[error("cannot resolve parent target of {0}")]
+    ResolveParentTargetFailed(Target, #[source] TargetResolveError),
+
+    #[error("dependency {0} does not have a library for the specified target")]
+    DependencyNotFound(Dependency),
+
+    #[error("cannot read {0}")]
+    ReadArtifactFailed(PathBuf, #[source] std::io::Error),
+
+    #[error("{path} was changed after the build (expected {expected:016x}, found {found:016x})")]
+    StaleArtifact {
+        path: PathBuf,
+        expected: u64,
+        found: u64,
+    },
+}
+
+/// Represents an error when [`Package::merge()`] is failed.
+#[derive(Debug, Error)]
+pub enum PackageMergeError {
+    #[error("package name {0} does not match with {1}")]
+    NameMismatch(PackageName, PackageName),
+
+    #[error("package version {0} does not match with {1}")]
+    VersionMismatch(PackageVersion, PackageVersion),
+
+    #[error("both packages were built with a different mangling scheme")]
+    ManglingMismatch,
+
+    #[error("cannot read a binary")]
+    ReadBinaryFailed(#[source] std::io::Error),
+
+    #[error("cannot read {0}")]
+    ReadAssetFailed(PathBuf, #[source] std::io::Error),
+
+    #[error("both packages have a different executable for {0}")]
+    ConflictingExecutable(Target),
+
+    #[error("both packages have a different library for {0}")]
+    ConflictingLibrary(Target),
+
+    #[error("both packages have a different asset '{0}'")]
+    ConflictingAsset(String),
 }
 
 /// Represents an error when a package is failed to unpack.
@@ -548,12 +1780,30 @@ pub enum PackageUnpackError {
     #[error("unknown entry {0} in the package")]
     UnknownEntry(u8),
 
+    #[error("entry {0} appears multiple times in the package")]
+    DuplicateEntry(u8),
+
+    #[error("entry {0} appears after a binary entry")]
+    EntryOutOfOrder(u8),
+
+    #[error("tool entry in the package is not valid")]
+    InvalidToolEntry,
+
+    #[error("asset path '{0}' is not valid")]
+    InvalidAssetPath(String),
+
+    #[error("the package was compressed with an unknown dictionary {0:#010x}")]
+    UnknownDictionary(u32),
+
     #[error("no name entry in the package")]
     NoNameEntry,
 
     #[error("no version entry in the package")]
     NoVersionEntry,
 
+    #[error("dependency #{1} for executable entry #{0} is not valid")]
+    InvalidExecutableDependency(usize, usize, #[source] DependencyError),
+
     #[error("dependency #{1} for library entry #{0} is not valid")]
     InvalidLibraryDependency(usize, usize, #[source] DependencyError),
 
@@ -562,6 +1812,18 @@ pub enum PackageUnpackError {
 
     #[error("cannot write {0}")]
     WriteFileFailed(PathBuf, #[source] std::io::Error),
+
+    #[error("cannot resolve target {0}")]
+    ResolveTargetFailed(Uuid, #[source] TargetResolveError),
+
+    #[error("dependency #{1} for library entry #{0} appears multiple times")]
+    DuplicatedLibraryDependency(usize, usize),
+
+    #[error("cannot read library entry #{0}")]
+    ReadLibraryFailed(usize, #[source] LibraryUnpackError),
+
+    #[error("the package is not valid")]
+    BuildPackageFailed(#[source] PackageBuildError),
 }
 
 impl From<std::io::Error> for PackageUnpackError {
@@ -569,3 +1831,589 @@ impl From<std::io::Error> for PackageUnpackError {
         Self::ReadPackageFailed(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duplicate_entry() {
+        let entries: [&[u8]; 6] = [
+            &name(),
+            &version(),
+            &[Package::ENTRY_DATE, 0, 0, 0, 0, 0, 0, 0, 0],
+            &[Package::ENTRY_MANGLING, 2],
+            &tool(),
+            &[Package::ENTRY_CHECKSUM, 0, 0, 0, 0, 0, 0, 0, 0],
+        ];
+
+        for e in entries {
+            let mut data = b"\x7FNPK".to_vec();
+
+            data.extend_from_slice(&name());
+            data.extend_from_slice(&version());
+
+            if e[0] != Package::ENTRY_NAME && e[0] != Package::ENTRY_VERSION {
+                data.extend_from_slice(e);
+            }
+
+            data.extend_from_slice(e);
+            data.push(Package::ENTRY_END);
+
+            let targets = TargetResolver::new();
+            let dir = temp_dir(e[0]);
+
+            assert_duplicate(Package::unpack(data.as_slice(), &dir, &[]), e[0]);
+            assert_duplicate(Package::open_reader(data.as_slice(), &[], &targets), e[0]);
+            assert_duplicate(Package::inspect(data.as_slice(), &[]), e[0]);
+
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn entry_out_of_order() {
+        let entries: [&[u8]; 5] = [
+            &name(),
+            &[Package::ENTRY_DATE, 0, 0, 0, 0, 0, 0, 0, 0],
+            &[Package::ENTRY_MANGLING, 2],
+            &tool(),
+            &[Package::ENTRY_CHECKSUM, 0, 0, 0, 0, 0, 0, 0, 0],
+        ];
+
+        for e in entries {
+            let mut data = b"\x7FNPK".to_vec();
+
+            if e[0] != Package::ENTRY_NAME {
+                data.extend_from_slice(&name());
+            }
+
+            data.extend_from_slice(&version());
+            data.extend_from_slice(&[Package::ENTRY_ASSET, 0, 1, b'a', 0, 0, 0, 0]);
+            data.extend_from_slice(e);
+            data.push(Package::ENTRY_END);
+
+            let targets = TargetResolver::new();
+
+            match Package::open_reader(data.as_slice(), &[], &targets) {
+                Err(PackageUnpackError::EntryOutOfOrder(v)) => assert_eq!(v, e[0]),
+                Err(e) => panic!("unexpected error: {e}"),
+                Ok(_) => panic!("a misplaced entry {} was accepted", e[0]),
+            }
+
+            // The scan only looks at the metadata so it does not see the misplaced entry.
+            if e[0] != Package::ENTRY_NAME {
+                assert!(Package::inspect(data.as_slice(), &[]).is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_tool() {
+        let mut data = b"\x7FNPK".to_vec();
+
+        data.extend_from_slice(&name());
+        data.extend_from_slice(&version());
+        data.extend_from_slice(&[Package::ENTRY_TOOL, 2, 0xC3, 0x28, Package::ENTRY_END]);
+
+        assert!(matches!(
+            Package::inspect(data.as_slice(), &[]),
+            Err(PackageUnpackError::InvalidToolEntry)
+        ));
+    }
+
+    #[test]
+    fn read_tool() {
+        let mut data = b"\x7FNPK".to_vec();
+
+        data.extend_from_slice(&name());
+        data.extend_from_slice(&version());
+        data.extend_from_slice(&tool());
+        data.push(Package::ENTRY_END);
+
+        let info = Package::inspect(data.as_slice(), &[]).unwrap();
+
+        assert_eq!(info.meta().name(), "foo");
+        assert_eq!(info.tool(), Some(Package::tool().as_str()));
+    }
+
+    #[test]
+    fn open_reader_round_trip() {
+        let pkg = synthetic_package();
+        let mut data = std::io::Cursor::new(Vec::new());
+
+        pkg.pack_to(&mut data, None).unwrap();
+
+        let targets = TargetResolver::new();
+        let read = Package::open_reader(data.get_ref().as_slice(), &[], &targets).unwrap();
+
+        assert_same_package(&pkg, &read);
+
+        for lib in read.libs().values() {
+            assert!(matches!(lib.bin().bin(), LibraryBinary::Memory(v) if v == b"binary"));
+        }
+    }
+
+    #[test]
+    fn open_round_trip() {
+        let pkg = synthetic_package();
+        let dir = temp_dir(0);
+        let file = dir.with_extension("npk");
+
+        pkg.pack(&file, None).unwrap();
+        Package::unpack(File::open(&file).unwrap(), &dir, &[]).unwrap();
+
+        let targets = TargetResolver::new();
+        let opened = Package::open(&dir, &targets).unwrap();
+
+        assert_same_package(&pkg, &opened);
+
+        std::fs::remove_file(&file).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn export_stale_artifact() {
+        // Build a package with an executable in the workspace.
+        let dir = temp_dir(u8::MAX);
+        let ws = dir.join("ws");
+        let out = dir.join("out");
+        let exe = ws.join("foo");
+        let target = Target::Primitive(&PrimitiveTarget::ALL[0]);
+
+        std::fs::create_dir_all(&ws).unwrap();
+        std::fs::write(&exe, b"built").unwrap();
+
+        let mut h = Fnv64::new();
+
+        hash_file(&exe, &mut h).unwrap();
+
+        let expected = h.finish();
+        let meta = PackageMeta::new("foo".parse().unwrap(), "1.0.0".parse().unwrap());
+        let mut pkg = Package::builder(meta);
+
+        pkg.add_executable(target.clone(), exe.clone(), HashSet::new(), Some(expected))
+            .unwrap();
+
+        let pkg = pkg.build().unwrap();
+
+        // Export the untampered executable.
+        let targets = TargetResolver::new();
+        let deps = DependencyResolver::new(dir.join("cache"), dir.join("std.npk"));
+
+        pkg.export(&out, &target, false, false, &targets, &deps)
+            .unwrap();
+
+        assert_eq!(std::fs::read(out.join("foo")).unwrap(), b"built");
+
+        // Replace the executable after the build.
+        std::fs::write(&exe, b"replaced").unwrap();
+
+        let mut h = Fnv64::new();
+
+        hash_file(&exe, &mut h).unwrap();
+
+        let r = pkg.export(&out, &target, false, false, &targets, &deps);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        match r {
+            Err(PackageExportError::StaleArtifact {
+                path,
+                expected: e,
+                found,
+            }) => {
+                assert_eq!(path, exe);
+                assert_eq!(e, expected);
+                assert_eq!(found, h.finish());
+            }
+            r => panic!("unexpected result {r:?}"),
+        }
+    }
+
+    #[test]
+    fn export_child_target() {
+        // Build a package with a library for the first primitive target only.
+        let dir = temp_dir(u8::MAX - 1);
+        let bin = dir.join("libfoo.so");
+        let out = dir.join("out");
+        let primitive = Target::Primitive(&PrimitiveTarget::ALL[0]);
+
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&bin, b"library").unwrap();
+
+        let meta = PackageMeta::new("foo".parse().unwrap(), "1.0.0".parse().unwrap());
+        let lib = Library::builder(LibraryBinary::Bundle(bin)).build();
+        let mut pkg = Package::builder(meta);
+
+        pkg.add_library(primitive.clone(), lib, HashSet::new(), None)
+            .unwrap();
+
+        let pkg = pkg.build().unwrap();
+
+        // Export for a child of a custom target that does not have a library.
+        let mut targets = TargetResolver::new();
+        let parent = targets.add(
+            uuid::uuid!("0c9f2a4e-5b1d-4c7e-9a3f-6d8e2b1c4a70"),
+            *primitive.id(),
+        );
+        let child = targets.add(
+            uuid::uuid!("7e3b1d9a-2c4f-4a8e-b6d1-3f9c8e2a5b14"),
+            *parent.id(),
+        );
+        let deps = DependencyResolver::new(dir.join("cache"), dir.join("std.npk"));
+        let r = pkg.export(&out, &child, false, false, &targets, &deps);
+        let data = std::fs::read(out.join("libfoo-v1.so"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        r.unwrap();
+
+        assert_eq!(data.unwrap(), b"library");
+    }
+
+    #[test]
+    fn export_with_deps() {
+        // Cache the libraries of the dependencies.
+        let dir = temp_dir(u8::MAX - 2);
+        let cache = dir.join("cache");
+        let target = Target::Primitive(&PrimitiveTarget::ALL[0]);
+
+        for name in ["bar", "baz"] {
+            let path = cache.join(format!("{name}-1.0.0"));
+            let lib = path.join("libs").join(target.id().to_string());
+            let meta = PackageMeta::new(name.parse().unwrap(), "1.0.0".parse().unwrap());
+            let mut data = Vec::new();
+
+            std::fs::create_dir_all(&lib).unwrap();
+            std::fs::write(lib.join("deps.yml"), "[]").unwrap();
+            serde_yaml::to_writer(File::create(path.join("meta.yml")).unwrap(), &meta).unwrap();
+
+            Library::builder(LibraryBinary::Memory(format!("lib{name}").into_bytes()))
+                .build()
+                .serialize(&mut data)
+                .unwrap();
+
+            Library::unpack(data.as_slice(), lib.join("bin"), lib.join("types")).unwrap();
+        }
+
+        // Build a package with an executable that depends on both of them.
+        let exe = dir.join("foo");
+        let out = dir.join("out");
+        let meta = PackageMeta::new("foo".parse().unwrap(), "1.0.0".parse().unwrap());
+        let mut pkg = Package::builder(meta);
+        let deps = HashSet::from([
+            BinaryDependency::new(
+                Dependency::new("bar".parse().unwrap(), "1.0.0".parse().unwrap()),
+                DependencyKind::Runtime,
+            ),
+            BinaryDependency::new(
+                Dependency::new("baz".parse().unwrap(), "1.0.0".parse().unwrap()),
+                DependencyKind::TypesOnly,
+            ),
+        ]);
+
+        std::fs::write(&exe, b"foo").unwrap();
+
+        pkg.add_executable(target.clone(), exe, deps, None).unwrap();
+
+        let pkg = pkg.build().unwrap();
+
+        // Only the runtime dependency is exported next to the executable.
+        let targets = TargetResolver::new();
+        let deps = DependencyResolver::new(&cache, dir.join("std.npk"));
+        let r = pkg.export(&out, &target, false, true, &targets, &deps);
+        let files = std::fs::read_dir(&out).map(|d| {
+            let mut v: Vec<String> = d
+                .map(|e| e.unwrap().file_name().into_string().unwrap())
+                .collect();
+
+            v.sort_unstable();
+            v
+        });
+        let bar = std::fs::read(out.join("libbar-v1.so"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        r.unwrap();
+
+        let files = files.unwrap();
+
+        assert_eq!(files, ["foo", "libbar-v1.so"]);
+        assert_eq!(bar.unwrap(), b"libbar");
+    }
+
+    #[test]
+    fn verify_consistent() {
+        let targets = TargetResolver::new();
+        let issues = synthetic_package().verify(&targets);
+
+        assert!(issues.is_empty(), "{issues:?}");
+    }
+
+    #[test]
+    fn verify_unresolved_target() {
+        // The custom target is known only when the package was built.
+        let mut other = TargetResolver::new();
+        let id = uuid::uuid!("5a1c7e9d-3b2f-4d8a-9e6c-1f4b8d2a7c93");
+        let target = other.add(id, *PrimitiveTarget::ALL[0].id());
+        let meta = PackageMeta::new("foo".parse().unwrap(), "1.0.0".parse().unwrap());
+        let mut pkg = Package::builder(meta);
+
+        pkg.add_executable(target, "foo".into(), HashSet::new(), None)
+            .unwrap();
+
+        let targets = TargetResolver::new();
+        let issues = pkg.build().unwrap().verify(&targets);
+
+        match issues.as_slice() {
+            [VerifyIssue::UnresolvedTarget(t, TargetResolveError::UnknownTarget(v))] => {
+                assert_eq!(*t.id(), id);
+                assert_eq!(*v, id);
+            }
+            v => panic!("unexpected issues {v:?}"),
+        }
+    }
+
+    #[test]
+    fn verify_missing_dependency() {
+        let bar = |ver: &str| {
+            let dep = Dependency::new("bar".parse().unwrap(), ver.parse().unwrap());
+            BinaryDependency::new(dep, DependencyKind::Runtime)
+        };
+
+        // The function references bar v1 but the library depends on nothing or bar v2.
+        for deps in [HashSet::new(), HashSet::from([bar("2.0.0")])] {
+            let param = FunctionParam::new(
+                "v".into(),
+                Type::Struct {
+                    ptr: 0,
+                    pkg: Some(("bar".into(), 1)),
+                    name: "Bar".into(),
+                },
+            );
+            let func = Function::new("Get".into(), vec![param], unit(), None, Abi::Nitro);
+            let pkg = verify_library(deps, vec![("Foo", func)]);
+
+            let targets = TargetResolver::new();
+            let issues = pkg.verify(&targets);
+
+            match issues.as_slice() {
+                [VerifyIssue::MissingDependency(t, f, p, v)] => {
+                    assert_eq!(*t, Target::Primitive(&PrimitiveTarget::ALL[0]));
+                    assert_eq!(f, "Foo.Get");
+                    assert_eq!(p, "bar");
+                    assert_eq!(*v, 1);
+                }
+                v => panic!("unexpected issues {v:?}"),
+            }
+        }
+
+        // Any minor version satisfies the reference.
+        let ret = Type::Class {
+            ptr: 0,
+            pkg: Some(("bar".into(), 1)),
+            name: "Bar".into(),
+        };
+        let func = Function::new("Get".into(), Vec::new(), ret, None, Abi::Nitro);
+        let pkg = verify_library(HashSet::from([bar("1.5.0")]), vec![("Foo", func)]);
+
+        let targets = TargetResolver::new();
+
+        assert!(pkg.verify(&targets).is_empty());
+    }
+
+    #[test]
+    fn verify_duplicated_symbol() {
+        // The symbols are ambiguous when the names have a leading digit.
+        let first = Function::new("abcdefghiF1x".into(), Vec::new(), unit(), None, Abi::C);
+        let second = Function::new("x".into(), Vec::new(), unit(), None, Abi::C);
+
+        assert_eq!(
+            first.mangle(Some(("foo", 1)), "2"),
+            second.mangle(Some(("foo", 1)), "F12abcdefghi")
+        );
+
+        let pkg = verify_library(HashSet::new(), vec![("2", first), ("F12abcdefghi", second)]);
+
+        let targets = TargetResolver::new();
+        let issues = pkg.verify(&targets);
+
+        match issues.as_slice() {
+            [VerifyIssue::DuplicatedSymbol(_, a, b)] => {
+                assert_eq!(a, "2.abcdefghiF1x");
+                assert_eq!(b, "F12abcdefghi.x");
+            }
+            v => panic!("unexpected issues {v:?}"),
+        }
+    }
+
+    #[test]
+    fn verify_conflicting_dependency() {
+        let target = Target::Primitive(&PrimitiveTarget::ALL[0]);
+        let bar = |ver: &str| {
+            let dep = Dependency::new("bar".parse().unwrap(), ver.parse().unwrap());
+            HashSet::from([BinaryDependency::new(dep, DependencyKind::Runtime)])
+        };
+
+        let meta = PackageMeta::new("foo".parse().unwrap(), "1.0.0".parse().unwrap());
+        let lib = Library::builder(LibraryBinary::Memory(b"binary".to_vec())).build();
+        let mut pkg = Package::builder(meta);
+
+        pkg.add_executable(target.clone(), "foo".into(), bar("1.0.0"), None)
+            .unwrap();
+        pkg.add_library(target.clone(), lib, bar("1.1.0"), None)
+            .unwrap();
+
+        let targets = TargetResolver::new();
+        let issues = pkg.build().unwrap().verify(&targets);
+
+        match issues.as_slice() {
+            [VerifyIssue::ConflictingDependency(t, e, l)] => {
+                assert_eq!(*t, target);
+                assert_eq!(e.version().to_string(), "1.0.0");
+                assert_eq!(l.version().to_string(), "1.1.0");
+            }
+            v => panic!("unexpected issues {v:?}"),
+        }
+    }
+
+    /// Returns `foo` v1 with a library for the first primitive target that depends on `deps`. Each
+    /// function in `funcs` is added to the class with the paired name.
+    fn verify_library(deps: HashSet<BinaryDependency>, funcs: Vec<(&str, Function)>) -> Package {
+        let mut types: BTreeMap<String, HashSet<Function>> = BTreeMap::new();
+
+        for (ty, func) in funcs {
+            types.entry(ty.into()).or_default().insert(func);
+        }
+
+        let mut lib = Library::builder(LibraryBinary::Memory(b"binary".to_vec()));
+
+        for (name, funcs) in types {
+            let attrs = Attributes::new(Some(Public::External), None, None);
+            let ty = BasicType::new(true, attrs, name, funcs, HashSet::new());
+
+            lib.add_type(TypeDeclaration::Basic(ty)).unwrap();
+        }
+
+        let meta = PackageMeta::new("foo".parse().unwrap(), "1.0.0".parse().unwrap());
+        let mut pkg = Package::builder(meta);
+        let target = Target::Primitive(&PrimitiveTarget::ALL[0]);
+
+        pkg.add_library(target, lib.build(), deps, None).unwrap();
+        pkg.build().unwrap()
+    }
+
+    fn unit() -> Type {
+        Type::Unit { ptr: 0 }
+    }
+
+    /// Returns a package with a library for the first primitive target.
+    fn synthetic_package() -> Package {
+        let mut meta = PackageMeta::new("foo".parse().unwrap(), "1.2.3".parse().unwrap());
+        let mut lib = Library::builder(LibraryBinary::Memory(b"binary".to_vec()));
+        let mut funcs = HashSet::new();
+        let ret = Type::Struct {
+            ptr: 0,
+            pkg: None,
+            name: "Foo".into(),
+        };
+
+        funcs.insert(Function::new(
+            "Get".into(),
+            Vec::new(),
+            ret,
+            None,
+            Abi::Nitro,
+        ));
+
+        let ty = BasicType::new(
+            false,
+            Attributes::new(Some(Public::External), None, Some(Representation::I32)),
+            "Foo".into(),
+            funcs,
+            HashSet::new(),
+        );
+
+        lib.add_type(TypeDeclaration::Basic(ty)).unwrap();
+        meta.set_mangling(MANGLING_VERSION);
+
+        let mut pkg = Package::builder(meta);
+        let target = Target::Primitive(&PrimitiveTarget::ALL[0]);
+
+        pkg.add_library(target, lib.build(), HashSet::new(), None)
+            .unwrap();
+        pkg.build().unwrap()
+    }
+
+    /// Assert the metadata and the exported types of the libraries.
+    fn assert_same_package(expected: &Package, actual: &Package) {
+        let (e, a) = (expected.meta(), actual.meta());
+
+        assert_eq!(e.name(), a.name());
+        assert_eq!(e.version(), a.version());
+        assert_eq!(e.mangling(), a.mangling());
+        assert_eq!(expected.libs().len(), actual.libs().len());
+
+        for (target, e) in expected.libs() {
+            let a = &actual.libs()[target];
+            let types = |l: &Library| -> Vec<(String, Vec<String>)> {
+                l.sorted_types()
+                    .into_iter()
+                    .map(|t| {
+                        let TypeDeclaration::Basic(t) = t;
+                        let funcs = sorted_by_name(t.funcs())
+                            .into_iter()
+                            .map(|f| f.name().to_owned());
+
+                        (t.name().to_owned(), funcs.collect())
+                    })
+                    .collect()
+            };
+
+            assert_eq!(types(e.bin()), types(a.bin()));
+        }
+    }
+
+    fn name() -> Vec<u8> {
+        let mut data = vec![Package::ENTRY_NAME];
+        let name: PackageName = "foo".parse().unwrap();
+
+        data.extend_from_slice(&name.to_bin());
+        data
+    }
+
+    fn version() -> Vec<u8> {
+        let mut data = vec![Package::ENTRY_VERSION];
+        let version: PackageVersion = "1.0.0".parse().unwrap();
+
+        data.extend_from_slice(&version.to_bin().to_be_bytes());
+        data
+    }
+
+    fn tool() -> Vec<u8> {
+        let tool = Package::tool();
+        let mut data = vec![Package::ENTRY_TOOL, tool.len().try_into().unwrap()];
+
+        data.extend_from_slice(tool.as_bytes());
+        data
+    }
+
+    fn temp_dir(ty: u8) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nitro-pkg-{}-{}", std::process::id(), ty));
+
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+
+        dir
+    }
+
+    fn assert_duplicate<T>(r: Result<T, PackageUnpackError>, ty: u8) {
+        match r {
+            Err(PackageUnpackError::DuplicateEntry(v)) => assert_eq!(v, ty),
+            Err(e) => panic!("unexpected error: {e}"),
+            Ok(_) => panic!("a duplicated entry {ty} was accepted"),
+        }
+    }
+}
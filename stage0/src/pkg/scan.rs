@@ -0,0 +1,183 @@
+use super::{Package, PackageMeta, PackageName, PackageUnpackError, PackageVersion};
+use crate::zstd::{ZstdDictionary, ZstdReader};
+use std::io::{Read, Take};
+
+/// A scanner over the entries of a packed package.
+///
+/// The metadata entries are consumed by the scanner itself. The other entries are returned to the
+/// caller, which must read the whole data of the entry before requesting the next one.
+pub struct EntryScanner<'a, P> {
+    pkg: P,
+    dicts: &'a [ZstdDictionary],
+    dict: Option<&'a ZstdDictionary>,
+    name: Option<PackageName>,
+    version: Option<PackageVersion>,
+    date: bool,
+    readme: bool,
+    mangling: Option<u8>,
+    tool: Option<String>,
+    checksum: bool,
+    binary: bool,
+}
+
+impl<'a, P: Read> EntryScanner<'a, P> {
+    /// `dicts` is a list of dictionaries to select from if the package was packed with a
+    /// dictionary.
+    pub fn new(mut pkg: P, dicts: &'a [ZstdDictionary]) -> Result<Self, PackageUnpackError> {
+        // Check magic.
+        let mut magic = [0u8; 4];
+
+        pkg.read_exact(&mut magic)?;
+
+        if magic.ne(b"\x7FNPK") {
+            return Err(PackageUnpackError::NotNitroPackage);
+        }
+
+        Ok(Self {
+            pkg,
+            dicts,
+            dict: None,
+            name: None,
+            version: None,
+            date: false,
+            readme: false,
+            mangling: None,
+            tool: None,
+            checksum: false,
+            binary: false,
+        })
+    }
+
+    /// Returns the underlying reader to read the data of the entry that was returned from
+    /// [`Self::next()`].
+    pub fn pkg(&mut self) -> &mut P {
+        &mut self.pkg
+    }
+
+    /// Returns the value of the tool entry if it was scanned.
+    pub fn tool(&self) -> Option<&str> {
+        self.tool.as_deref()
+    }
+
+    /// Returns a reader to decompress the length-prefixed data of the current entry.
+    pub fn data(&mut self) -> Result<ZstdReader<Take<&mut P>>, PackageUnpackError> {
+        let mut data = [0; 4];
+        self.pkg.read_exact(&mut data)?;
+        let len: u64 = u32::from_be_bytes(data).into();
+
+        Ok(Package::decompress(self.pkg.by_ref().take(len), self.dict)?)
+    }
+
+    /// Returns the type of the next entry that is not a metadata or [`None`] on the end entry. The
+    /// readme entry is returned here since its content is not a part of [`PackageMeta`].
+    pub fn next(&mut self) -> Result<Option<u8>, PackageUnpackError> {
+        loop {
+            // Read entry type.
+            let mut ty = 0;
+
+            self.pkg.read_exact(std::slice::from_mut(&mut ty))?;
+
+            // Metadata entries must precede all binary entries.
+            if self.binary && Package::is_meta_entry(ty) {
+                return Err(PackageUnpackError::EntryOutOfOrder(ty));
+            }
+
+            // Process the entry.
+            match ty {
+                Package::ENTRY_END => return Ok(None),
+                Package::ENTRY_NAME => {
+                    if self.name.is_some() {
+                        return Err(PackageUnpackError::DuplicateEntry(ty));
+                    }
+
+                    let mut data = [0u8; 32];
+                    self.pkg.read_exact(&mut data)?;
+                    self.name = Some(
+                        PackageName::from_bin(&data)
+                            .map_err(PackageUnpackError::InvalidNameEntry)?,
+                    );
+                }
+                Package::ENTRY_VERSION => {
+                    if self.version.is_some() {
+                        return Err(PackageUnpackError::DuplicateEntry(ty));
+                    }
+
+                    let mut data = [0u8; 8];
+                    self.pkg.read_exact(&mut data)?;
+                    self.version = Some(PackageVersion::from_bin(u64::from_be_bytes(data)));
+                }
+                Package::ENTRY_DATE => {
+                    if self.date {
+                        return Err(PackageUnpackError::DuplicateEntry(ty));
+                    }
+
+                    let mut data = [0u8; 8];
+                    self.pkg.read_exact(&mut data)?;
+                    self.date = true;
+                }
+                Package::ENTRY_MANGLING => {
+                    if self.mangling.is_some() {
+                        return Err(PackageUnpackError::DuplicateEntry(ty));
+                    }
+
+                    let mut data = 0;
+                    self.pkg.read_exact(std::slice::from_mut(&mut data))?;
+                    self.mangling = Some(data);
+                }
+                Package::ENTRY_TOOL => {
+                    if self.tool.is_some() {
+                        return Err(PackageUnpackError::DuplicateEntry(ty));
+                    }
+
+                    self.tool = Some(Package::read_tool(&mut self.pkg)?);
+                }
+                Package::ENTRY_CHECKSUM => {
+                    if self.checksum {
+                        return Err(PackageUnpackError::DuplicateEntry(ty));
+                    }
+
+                    let mut data = [0u8; 8];
+                    self.pkg.read_exact(&mut data)?;
+                    self.checksum = true;
+                }
+                Package::ENTRY_DICTIONARY => {
+                    if self.dict.is_some() {
+                        return Err(PackageUnpackError::DuplicateEntry(ty));
+                    }
+
+                    self.dict = Some(Package::read_dictionary(&mut self.pkg, self.dicts)?);
+                }
+                Package::ENTRY_README => {
+                    if self.readme {
+                        return Err(PackageUnpackError::DuplicateEntry(ty));
+                    }
+
+                    self.readme = true;
+
+                    return Ok(Some(ty));
+                }
+                Package::ENTRY_EXE | Package::ENTRY_LIB | Package::ENTRY_ASSET => {
+                    self.binary = true;
+
+                    return Ok(Some(ty));
+                }
+                v => return Err(PackageUnpackError::UnknownEntry(v)),
+            }
+        }
+    }
+
+    /// Returns the metadata from the entries that have been scanned.
+    pub fn meta(&self) -> Result<PackageMeta, PackageUnpackError> {
+        let name = self.name.clone().ok_or(PackageUnpackError::NoNameEntry)?;
+        let version = self
+            .version
+            .clone()
+            .ok_or(PackageUnpackError::NoVersionEntry)?;
+        let mut meta = PackageMeta::new(name, version);
+
+        // A package without the mangling entry was built with the first version.
+        meta.set_mangling(self.mangling.unwrap_or(1));
+
+        Ok(meta)
+    }
+}
@@ -1,17 +1,33 @@
 use crate::ffi::llvm_process_triple;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Display, Formatter};
 use std::hash::{Hash, Hasher};
-use std::rc::Rc;
 use std::str::FromStr;
+use std::sync::Arc;
 use thiserror::Error;
 use uuid::{uuid, Uuid};
 
 /// Struct to resolve [`Target`] from identifier.
-pub struct TargetResolver {}
+pub struct TargetResolver {
+    custom: HashMap<Uuid, Arc<CustomTarget>>,
+}
 
 impl TargetResolver {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            custom: HashMap::new(),
+        }
+    }
+
+    /// Add a custom target with `parent` as its parent. Loading the custom targets from their
+    /// definition is not implemented yet so only the tests can add them for now.
+    #[cfg(test)]
+    pub fn add(&mut self, id: Uuid, parent: Uuid) -> Target {
+        let target = Arc::new(CustomTarget { id, parent });
+
+        self.custom.insert(id, target.clone());
+
+        Target::Custom(target)
     }
 
     pub fn resolve(&self, id: &Uuid) -> Result<Target, TargetResolveError> {
@@ -20,23 +36,65 @@ impl TargetResolver {
             return Ok(Target::Primitive(v));
         }
 
-        todo!()
+        // Check if custom target.
+        match self.custom.get(id) {
+            Some(v) => Ok(Target::Custom(v.clone())),
+            None => Err(TargetResolveError::UnknownTarget(*id)),
+        }
     }
 
+    /// Returns the primitive target at the end of the parent chain of `target`.
     pub fn primitive(
         &self,
         target: &Target,
     ) -> Result<&'static PrimitiveTarget, TargetResolveError> {
-        match target {
-            Target::Primitive(v) => Ok(v),
-            Target::Custom(_) => todo!(),
+        let mut visited = HashSet::new();
+        let mut current = target.clone();
+
+        loop {
+            let parent = match &current {
+                Target::Primitive(v) => break Ok(v),
+                Target::Custom(v) => v.parent,
+            };
+
+            if !visited.insert(*current.id()) {
+                break Err(TargetResolveError::CyclicParent(*target.id()));
+            }
+
+            current = self.resolve(&parent)?;
         }
     }
 
     pub fn parent(&self, target: &Target) -> Result<Option<Target>, TargetResolveError> {
         match target {
             Target::Primitive(_) => Ok(None),
-            Target::Custom(_) => todo!(),
+            Target::Custom(v) => self.resolve(&v.parent).map(Some),
+        }
+    }
+
+    /// Find the value for `start` in `map`. If there is no value for `start` its parent will be
+    /// looked up instead, and so on.
+    pub fn find_nearest<'m, T>(
+        &self,
+        start: &Target,
+        map: &'m HashMap<Target, T>,
+    ) -> Result<Option<(Target, &'m T)>, TargetResolveError> {
+        let mut visited = HashSet::new();
+        let mut target = start.clone();
+
+        loop {
+            if let Some(v) = map.get(&target) {
+                break Ok(Some((target, v)));
+            }
+
+            if !visited.insert(*target.id()) {
+                break Err(TargetResolveError::CyclicParent(*start.id()));
+            }
+
+            target = match self.parent(&target)? {
+                Some(v) => v,
+                None => break Ok(None),
+            };
         }
     }
 }
@@ -45,7 +103,7 @@ impl TargetResolver {
 #[derive(Debug, Clone)]
 pub enum Target {
     Primitive(&'static PrimitiveTarget),
-    Custom(Rc<CustomTarget>),
+    Custom(Arc<CustomTarget>),
 }
 
 impl Target {
@@ -119,10 +177,15 @@ impl PrimitiveTarget {
         },
     ];
 
-    pub fn current() -> &'static Self {
+    /// Returns the target of the current process as detected by LLVM.
+    pub fn current() -> Result<&'static Self, PrimitiveTargetError> {
         let mut name = String::new();
         unsafe { llvm_process_triple(&mut name) };
-        name.parse().unwrap()
+        name.parse()
+    }
+
+    pub fn id(&self) -> &Uuid {
+        &self.id
     }
 
     pub fn arch(&self) -> TargetArch {
@@ -185,12 +248,10 @@ impl FromStr for &'static PrimitiveTarget {
         };
 
         // Lookup.
-        let target = PrimitiveTarget::ALL
+        PrimitiveTarget::ALL
             .iter()
             .find(move |&t| t.arch == arch && t.vendor == vendor && t.os == os && t.env == env)
-            .unwrap();
-
-        Ok(target)
+            .ok_or_else(|| PrimitiveTargetError::UnsupportedTarget(s.to_owned()))
     }
 }
 
@@ -301,7 +362,13 @@ impl TargetEnv {
 
 /// Represents an error when [`TargetResolver`] is failed.
 #[derive(Debug, Error)]
-pub enum TargetResolveError {}
+pub enum TargetResolveError {
+    #[error("unknown target {0}")]
+    UnknownTarget(Uuid),
+
+    #[error("parent chain of {0} contains a cycle")]
+    CyclicParent(Uuid),
+}
 
 /// Represents an error parsing a [`PrimitiveTarget`] from a string is failed.
 #[derive(Debug, Error)]
@@ -320,4 +387,83 @@ pub enum PrimitiveTargetError {
 
     #[error("unknown environment '{0}'")]
     UnknownEnv(String),
+
+    #[error("target '{0}' is not supported")]
+    UnsupportedTarget(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_nearest() {
+        // Setup a chain of child -> parent -> primitive.
+        let mut targets = TargetResolver::new();
+        let primitive = Target::Primitive(&PrimitiveTarget::ALL[0]);
+        let parent = targets.add(
+            uuid!("0c9f2a4e-5b1d-4c7e-9a3f-6d8e2b1c4a70"),
+            *primitive.id(),
+        );
+        let child = targets.add(uuid!("7e3b1d9a-2c4f-4a8e-b6d1-3f9c8e2a5b14"), *parent.id());
+
+        assert_eq!(targets.parent(&child).unwrap(), Some(parent.clone()));
+        assert_eq!(targets.parent(&parent).unwrap(), Some(primitive.clone()));
+        assert_eq!(targets.parent(&primitive).unwrap(), None);
+        assert_eq!(targets.primitive(&child).unwrap().id(), primitive.id());
+
+        // Only the primitive target has a value.
+        let mut map = HashMap::from([(primitive.clone(), "primitive")]);
+
+        assert_eq!(
+            targets.find_nearest(&child, &map).unwrap(),
+            Some((primitive.clone(), &"primitive"))
+        );
+
+        // The parent is nearer than the primitive target.
+        map.insert(parent.clone(), "parent");
+
+        assert_eq!(
+            targets.find_nearest(&child, &map).unwrap(),
+            Some((parent.clone(), &"parent"))
+        );
+        assert_eq!(
+            targets.find_nearest(&primitive, &map).unwrap(),
+            Some((primitive.clone(), &"primitive"))
+        );
+
+        // The target itself is always the nearest.
+        map.insert(child.clone(), "child");
+
+        assert_eq!(
+            targets.find_nearest(&child, &map).unwrap(),
+            Some((child.clone(), &"child"))
+        );
+
+        // No value in the whole chain.
+        let map = HashMap::<Target, &str>::new();
+
+        assert_eq!(targets.find_nearest(&child, &map).unwrap(), None);
+    }
+
+    #[test]
+    fn find_nearest_cycle() {
+        let mut targets = TargetResolver::new();
+        let a = uuid!("4d2e8f1a-9b3c-4e7d-a5f2-1c6b9e8d3a40");
+        let b = uuid!("b81f3c6e-2d9a-4f5b-8e1c-7a4d2f9b6c35");
+        let start = targets.add(a, b);
+
+        targets.add(b, a);
+
+        let map = HashMap::<Target, ()>::new();
+
+        assert!(matches!(
+            targets.find_nearest(&start, &map),
+            Err(TargetResolveError::CyclicParent(v)) if v == a
+        ));
+        assert!(matches!(
+            targets.primitive(&start),
+            Err(TargetResolveError::CyclicParent(v)) if v == a
+        ));
+    }
 }
@@ -1,10 +1,38 @@
-use std::collections::HashSet;
+use crate::limit::encode_len;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Display, Formatter};
 use std::hash::{Hash, Hasher};
 use std::io::{Read, Write};
+use std::ops::RangeInclusive;
 use thiserror::Error;
 
+/// Version of the symbol mangling scheme (see [`Function::mangle()`]). This must be bumped each time
+/// the output of the mangler is changed since packages that was built with a different scheme
+/// cannot link together.
+pub const MANGLING_VERSION: u8 = 1;
+
+/// Returns items in `items` sorted by its name.
+///
+/// Any output that is produced from an unordered collection (e.g. [`HashSet`]) must be emitted in
+/// this order so the same input always produce the same output.
+pub fn sorted_by_name<'a, T, I>(items: I) -> Vec<&'a T>
+where
+    T: Named + 'a,
+    I: IntoIterator<Item = &'a T>,
+{
+    let mut list: Vec<&T> = items.into_iter().collect();
+    list.sort_unstable_by(|a, b| a.name().cmp(b.name()));
+    list
+}
+
+/// An item that has a unique name within its collection.
+pub trait Named {
+    fn name(&self) -> &str;
+}
+
 /// A type that was exported from a package.
+#[derive(Clone)]
 pub enum TypeDeclaration {
     Basic(BasicType),
 }
@@ -15,6 +43,8 @@ impl TypeDeclaration {
     const ENTRY_STRUCT: u8 = 2;
     const ENTRY_CLASS: u8 = 3;
     const ENTRY_FUNC: u8 = 4;
+    const ENTRY_CONST: u8 = 5;
+    const ENTRY_ORIGIN: u8 = 6;
 
     /// Returns a fully qualified type name (no package name is prefixed).
     pub fn name(&self) -> &str {
@@ -23,10 +53,14 @@ impl TypeDeclaration {
         }
     }
 
-    pub(super) fn serialize<W: Write>(&self, w: &mut W) -> Result<(), std::io::Error> {
+    pub(super) fn serialize<W: Write>(
+        &self,
+        w: &mut W,
+        strings: &mut StringTable,
+    ) -> Result<(), std::io::Error> {
         // Name.
         let name = self.name();
-        let len: u16 = name.len().try_into().unwrap();
+        let len: u16 = encode_len(name.len(), "type name")?;
 
         w.write_all(&[Self::ENTRY_NAME])?;
         w.write_all(&len.to_be_bytes())?;
@@ -47,8 +81,31 @@ impl TypeDeclaration {
                 w.write_all(&[Self::ENTRY_FUNC])?;
                 w.write_all(&len.to_be_bytes())?;
 
-                for f in &ty.funcs {
-                    f.serialize(w)?;
+                for f in sorted_by_name(&ty.funcs) {
+                    f.serialize(w, strings)?;
+                }
+
+                // Constants. This entry is omitted when there are no constants so the type will be
+                // readable by the compiler that does not know about it.
+                if !ty.consts.is_empty() {
+                    let len: u32 = ty.consts.len().try_into().unwrap();
+
+                    w.write_all(&[Self::ENTRY_CONST])?;
+                    w.write_all(&len.to_be_bytes())?;
+
+                    for c in sorted_by_name(&ty.consts) {
+                        c.serialize(w, strings)?;
+                    }
+                }
+
+                // Origin. This entry is omitted for a type that was defined in this package.
+                if let Some((pkg, major)) = &ty.origin {
+                    let len: u16 = encode_len(pkg.len(), "package name")?;
+
+                    w.write_all(&[Self::ENTRY_ORIGIN])?;
+                    w.write_all(&len.to_be_bytes())?;
+                    w.write_all(pkg.as_bytes())?;
+                    w.write_all(&major.to_be_bytes())?;
                 }
             }
         }
@@ -57,7 +114,10 @@ impl TypeDeclaration {
         w.write_all(&[Self::ENTRY_END])
     }
 
-    pub(super) fn deserialize<R>(mut r: R) -> Result<Self, TypeDeserializeError>
+    pub(super) fn deserialize<R>(
+        mut r: R,
+        strings: &StringTable,
+    ) -> Result<Self, TypeDeserializeError>
     where
         R: Read,
     {
@@ -66,6 +126,8 @@ impl TypeDeclaration {
         let mut struc = false;
         let mut class = false;
         let mut funcs = HashSet::new();
+        let mut consts = HashSet::new();
+        let mut origin = None;
         let mut entries = 0;
 
         loop {
@@ -110,11 +172,44 @@ impl TypeDeclaration {
 
                     // Read functions.
                     for i in 0..count {
-                        if let Some(f) = funcs.replace(Function::deserialize(&mut r, i)?) {
-                            return Err(TypeDeserializeError::DuplicatedFunction(f));
+                        if let Some(f) = funcs.replace(Function::deserialize(&mut r, strings, i)?) {
+                            return Err(TypeDeserializeError::DuplicatedFunction(Box::new(f)));
                         }
                     }
                 }
+                Self::ENTRY_CONST => {
+                    // Read constant count.
+                    let mut buf = [0u8; 4];
+                    r.read_exact(&mut buf)?;
+                    let count: usize = u32::from_be_bytes(buf).try_into().unwrap();
+
+                    // Read constants.
+                    for i in 0..count {
+                        if let Some(c) = consts.replace(Constant::deserialize(&mut r, strings, i)?)
+                        {
+                            return Err(TypeDeserializeError::DuplicatedConstant(c.name));
+                        }
+                    }
+                }
+                Self::ENTRY_ORIGIN => {
+                    // Read package name.
+                    let mut buf = [0u8; 2];
+                    r.read_exact(&mut buf)?;
+                    let len: usize = u16::from_be_bytes(buf).into();
+                    let mut buf = vec![0u8; len];
+                    r.read_exact(&mut buf)?;
+
+                    let pkg = match String::from_utf8(buf) {
+                        Ok(v) => v,
+                        Err(_) => return Err(TypeDeserializeError::InvalidOrigin),
+                    };
+
+                    // Read major version.
+                    let mut buf = [0u8; 2];
+                    r.read_exact(&mut buf)?;
+
+                    origin = Some((pkg, u16::from_be_bytes(buf)));
+                }
                 v => return Err(TypeDeserializeError::UnknownTypeEntry(v)),
             }
         }
@@ -136,6 +231,8 @@ impl TypeDeclaration {
                 },
                 name,
                 funcs,
+                consts,
+                origin,
             }),
             (false, true) => Self::Basic(BasicType {
                 is_class: true,
@@ -146,6 +243,8 @@ impl TypeDeclaration {
                 },
                 name,
                 funcs,
+                consts,
+                origin,
             }),
         };
 
@@ -153,6 +252,12 @@ impl TypeDeclaration {
     }
 }
 
+impl Named for TypeDeclaration {
+    fn name(&self) -> &str {
+        self.name()
+    }
+}
+
 impl PartialEq for TypeDeclaration {
     fn eq(&self, other: &Self) -> bool {
         self.name() == other.name()
@@ -175,23 +280,50 @@ impl Hash for TypeDeclaration {
 ///
 /// Class in Nitro is a reference type, which mean any variable of a class type will be a pointer to
 /// the heap allocated. All fields in the class will always private.
+#[derive(Clone)]
 pub struct BasicType {
     is_class: bool,
     attrs: Attributes,
     name: String,
     funcs: HashSet<Function>,
+    consts: HashSet<Constant>,
+    origin: Option<(String, u16)>,
 }
 
 impl BasicType {
-    pub fn new(is_class: bool, attrs: Attributes, name: String, funcs: HashSet<Function>) -> Self {
+    pub fn new(
+        is_class: bool,
+        attrs: Attributes,
+        name: String,
+        funcs: HashSet<Function>,
+        consts: HashSet<Constant>,
+    ) -> Self {
         Self {
             is_class,
             attrs,
             name,
             funcs,
+            consts,
+            origin: None,
         }
     }
 
+    /// Returns a copy of this type to be re-exported from another package. `pkg` and `major` is the
+    /// package that contains this type, which is ignored if this type itself is a re-export.
+    ///
+    /// The declaration is embedded in the re-exporting package so the consumers does not need to
+    /// load the original package to resolve it. The downside is the copy will be stale if the
+    /// original package was updated without rebuilding the re-exporting package.
+    pub fn reexport(&self, pkg: &str, major: u16) -> Self {
+        let mut ty = self.clone();
+
+        if ty.origin.is_none() {
+            ty.origin = Some((pkg.to_owned(), major));
+        }
+
+        ty
+    }
+
     pub fn is_class(&self) -> bool {
         self.is_class
     }
@@ -207,14 +339,26 @@ impl BasicType {
     pub fn funcs(&self) -> impl Iterator<Item = &Function> {
         self.funcs.iter()
     }
+
+    pub fn constant(&self, name: &str) -> Option<&Constant> {
+        self.consts.iter().find(|c| c.name == name)
+    }
+
+    /// Returns the package name and its major version that contains this type if it was
+    /// re-exported from a dependency.
+    pub fn origin(&self) -> Option<(&str, u16)> {
+        self.origin.as_ref().map(|(p, v)| (p.as_str(), *v))
+    }
 }
 
 /// A function.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Function {
     name: String,
     params: Vec<FunctionParam>,
     ret: Type,
+    ext: Option<Extern>,
+    abi: Abi,
 }
 
 impl Function {
@@ -222,9 +366,23 @@ impl Function {
     const ENTRY_NAME: u8 = 1;
     const ENTRY_RET: u8 = 2;
     const ENTRY_PARAMS: u8 = 3;
+    const ENTRY_EXT: u8 = 4;
+    const ENTRY_ABI: u8 = 5;
 
-    pub fn new(name: String, params: Vec<FunctionParam>, ret: Type) -> Self {
-        Self { name, params, ret }
+    pub fn new(
+        name: String,
+        params: Vec<FunctionParam>,
+        ret: Type,
+        ext: Option<Extern>,
+        abi: Abi,
+    ) -> Self {
+        Self {
+            name,
+            params,
+            ret,
+            ext,
+            abi,
+        }
     }
 
     pub fn name(&self) -> &str {
@@ -239,33 +397,64 @@ impl Function {
         &self.ret
     }
 
-    pub fn mangle(&self, lib: Option<(&str, u16)>, ty: &str) -> String {
-        use std::fmt::Write;
+    /// Returns the calling convention if this function was declared with `@ext`.
+    pub fn ext(&self) -> Option<Extern> {
+        self.ext
+    }
+
+    /// Returns the calling convention, which is independent from the symbol naming of `@ext`.
+    pub fn abi(&self) -> Abi {
+        self.abi
+    }
+
+    pub fn signature(&self) -> FunctionSignature {
+        let params = self
+            .params
+            .iter()
+            .map(|p| SignatureParam::new(p.name.clone(), p.ty.to_string()))
+            .collect();
+
+        FunctionSignature::new(self.name.clone(), params, self.ret.to_string())
+    }
 
+    /// Returns the symbol of this function. `lib` is the package name and its major version of
+    /// the library that contains this function or [`None`] if it is in an executable.
+    ///
+    /// The symbol is the ABI of the package so the output of this function must not be changed
+    /// without bumping [`MANGLING_VERSION`]. The grammar is:
+    ///
+    /// ```text
+    /// symbol  = ("_NEF" package | "_NIF") name "F" ident cc type type*
+    /// package = ident ["V" major] "T"   ; major is omitted when it is zero
+    /// name    = ident ident*            ; one ident for each component of the type name
+    /// ident   = length chars            ; length is a decimal without leading zero
+    /// cc      = "0" | "1"               ; C or Nitro calling convention
+    /// ```
+    ///
+    /// The first `type` is the return type followed by the type of each parameter. See
+    /// [`Type::mangle()`] for the grammar of `type`.
+    pub fn mangle(&self, lib: Option<(&str, u16)>, ty: &str) -> String {
         // Check if executable.
         let mut buf = String::new();
 
         match lib {
             Some((pkg, ver)) => {
-                write!(buf, "_NEF{}{}", pkg.len(), pkg).unwrap();
-
-                if ver != 0 {
-                    write!(buf, "V{ver}").unwrap();
-                }
-
-                write!(buf, "T").unwrap();
+                buf.push_str("_NEF");
+                Self::mangle_pkg(&mut buf, pkg, ver);
             }
-            None => write!(buf, "_NIF").unwrap(),
+            None => buf.push_str("_NIF"),
         }
 
         // Type name.
-        for p in ty.split('.') {
-            write!(buf, "{}{}", p.len(), p).unwrap();
-        }
+        Self::mangle_name(&mut buf, ty);
 
         // Function name.
-        write!(buf, "F{}{}", self.name.len(), self.name).unwrap();
-        write!(buf, "0").unwrap(); // C calling convention.
+        buf.push('F');
+        Self::mangle_ident(&mut buf, &self.name);
+        buf.push(match self.abi {
+            Abi::C => '0',
+            Abi::Nitro => '1',
+        });
 
         // Return type.
         self.ret.mangle(&mut buf);
@@ -278,9 +467,137 @@ impl Function {
         buf
     }
 
-    fn serialize<W: Write>(&self, w: &mut W) -> Result<(), std::io::Error> {
+    /// Write `package` production of [`Self::mangle()`].
+    fn mangle_pkg(buf: &mut String, pkg: &str, ver: u16) {
+        Self::mangle_ident(buf, pkg);
+
+        if ver != 0 {
+            buf.push('V');
+            buf.push_str(&ver.to_string());
+        }
+
+        buf.push('T');
+    }
+
+    /// Write `name` production of [`Self::mangle()`].
+    fn mangle_name(buf: &mut String, name: &str) {
+        for p in name.split('.') {
+            Self::mangle_ident(buf, p);
+        }
+    }
+
+    /// Write `ident` production of [`Self::mangle()`].
+    fn mangle_ident(buf: &mut String, ident: &str) {
+        buf.push_str(&ident.len().to_string());
+        buf.push_str(ident);
+    }
+
+    /// Reverse the symbol that was produced by [`Self::mangle()`] to a readable signature. Returns
+    /// [`None`] if `sym` is not a symbol of Nitro function.
+    ///
+    /// Parameter names are not part of the symbol so only the types will be shown.
+    pub fn demangle(sym: &str) -> Option<String> {
+        let mut s = sym;
+
+        // Check if executable.
+        let pkg = if let Some(v) = s.strip_prefix("_NEF") {
+            s = v;
+            Some(Self::demangle_pkg(&mut s)?)
+        } else {
+            s = s.strip_prefix("_NIF")?;
+            None
+        };
+
+        // Type name.
+        let ty = Self::demangle_name(&mut s)?;
+
+        // Function name.
+        s = s.strip_prefix('F')?;
+
+        let name = Self::demangle_ident(&mut s)?;
+
+        s = s.strip_prefix(['0', '1'])?; // Calling convention.
+
+        // Return type.
+        let ret = Type::demangle(&mut s)?;
+
+        // Parameters.
+        let mut params = Vec::new();
+
+        while !s.is_empty() {
+            params.push(Type::demangle(&mut s)?.to_string());
+        }
+
+        Some(match pkg {
+            Some((pkg, ver)) => format!("{pkg}:{ver}.{ty}.{name}({}): {ret}", params.join(", ")),
+            None => format!("self.{ty}.{name}({}): {ret}", params.join(", ")),
+        })
+    }
+
+    fn demangle_pkg(s: &mut &str) -> Option<(String, u16)> {
+        let name = Self::demangle_ident(s)?.to_owned();
+        let ver = match s.strip_prefix('V') {
+            Some(v) => {
+                let len = v.find(|c: char| !c.is_ascii_digit()).unwrap_or(v.len());
+
+                // Version zero is never encoded.
+                if v.starts_with('0') {
+                    return None;
+                }
+
+                let ver: u16 = v[..len].parse().ok()?;
+
+                *s = &v[len..];
+                ver
+            }
+            None => 0,
+        };
+
+        *s = s.strip_prefix('T')?;
+
+        Some((name, ver))
+    }
+
+    fn demangle_name(s: &mut &str) -> Option<String> {
+        let mut name = String::new();
+
+        while s.starts_with(|c: char| c.is_ascii_digit()) {
+            if !name.is_empty() {
+                name.push('.');
+            }
+
+            name.push_str(Self::demangle_ident(s)?);
+        }
+
+        if name.is_empty() {
+            None
+        } else {
+            Some(name)
+        }
+    }
+
+    fn demangle_ident<'a>(s: &mut &'a str) -> Option<&'a str> {
+        // The length is never zero or has a leading zero.
+        if s.starts_with('0') {
+            return None;
+        }
+
+        let len = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let end = len.checked_add(s[..len].parse().ok()?)?;
+        let ident = s.get(len..end)?;
+
+        *s = &s[end..];
+
+        Some(ident)
+    }
+
+    fn serialize<W: Write>(
+        &self,
+        w: &mut W,
+        strings: &mut StringTable,
+    ) -> Result<(), std::io::Error> {
         // Name.
-        let len: u16 = self.name.len().try_into().unwrap();
+        let len: u16 = encode_len(self.name.len(), "function name")?;
 
         w.write_all(&[Self::ENTRY_NAME])?;
         w.write_all(&len.to_be_bytes())?;
@@ -288,26 +605,45 @@ impl Function {
 
         // Return.
         w.write_all(&[Self::ENTRY_RET])?;
-        self.ret.serialize(w)?;
+        self.ret.serialize(w, strings)?;
 
         // Params.
-        let len: u8 = self.params.len().try_into().unwrap();
+        let len: u8 = encode_len(self.params.len(), "parameter list")?;
 
-        w.write_all(&[Self::ENTRY_PARAMS, len.try_into().unwrap()])?;
+        w.write_all(&[Self::ENTRY_PARAMS, len])?;
 
         for p in &self.params {
-            p.serialize(w)?;
+            p.serialize(w, strings)?;
+        }
+
+        // Extern. This is omitted when not present so the other functions are encoded the same as
+        // before this entry was introduced.
+        match self.ext {
+            Some(Extern::C) => w.write_all(&[Self::ENTRY_EXT, 0])?,
+            None => {}
+        }
+
+        // ABI. The C ABI is omitted for the same reason as the extern.
+        match self.abi {
+            Abi::C => {}
+            Abi::Nitro => w.write_all(&[Self::ENTRY_ABI, 1])?,
         }
 
         // End.
         w.write_all(&[Self::ENTRY_END])
     }
 
-    fn deserialize<R: Read>(mut r: R, i: usize) -> Result<Self, TypeDeserializeError> {
+    fn deserialize<R: Read>(
+        mut r: R,
+        strings: &StringTable,
+        i: usize,
+    ) -> Result<Self, TypeDeserializeError> {
         // Iterate over the entries.
         let mut name = None;
         let mut params = Vec::new();
         let mut ret = None;
+        let mut ext = None;
+        let mut abi = Abi::C;
 
         loop {
             // Read entry type.
@@ -333,7 +669,7 @@ impl Function {
                         Err(_) => return Err(TypeDeserializeError::InvalidFunctionName(i)),
                     }
                 }
-                Self::ENTRY_RET => match Type::deserialize(&mut r) {
+                Self::ENTRY_RET => match Type::deserialize(&mut r, strings) {
                     Some(v) => ret = Some(v),
                     None => return Err(TypeDeserializeError::InvalidFunctionRet(i)),
                 },
@@ -345,9 +681,28 @@ impl Function {
 
                     // Read params.
                     for p in 0..count {
-                        params.push(FunctionParam::deserialize(&mut r, i, p)?);
+                        params.push(FunctionParam::deserialize(&mut r, strings, i, p)?);
                     }
                 }
+                Self::ENTRY_EXT => {
+                    let mut buf = 0u8;
+                    r.read_exact(std::slice::from_mut(&mut buf))?;
+
+                    ext = match buf {
+                        0 => Some(Extern::C),
+                        v => return Err(TypeDeserializeError::UnknownFunctionExtern(i, v)),
+                    };
+                }
+                Self::ENTRY_ABI => {
+                    let mut buf = 0u8;
+                    r.read_exact(std::slice::from_mut(&mut buf))?;
+
+                    abi = match buf {
+                        0 => Abi::C,
+                        1 => Abi::Nitro,
+                        v => return Err(TypeDeserializeError::UnknownFunctionAbi(i, v)),
+                    };
+                }
                 v => return Err(TypeDeserializeError::UnknownFunctionEntry(i, v)),
             }
         }
@@ -356,7 +711,19 @@ impl Function {
         let name = name.ok_or(TypeDeserializeError::FunctionNameNotFound(i))?;
         let ret = ret.ok_or(TypeDeserializeError::FunctionNameRetFound(i))?;
 
-        Ok(Self { name, params, ret })
+        Ok(Self {
+            name,
+            params,
+            ret,
+            ext,
+            abi,
+        })
+    }
+}
+
+impl Named for Function {
+    fn name(&self) -> &str {
+        &self.name
     }
 }
 
@@ -376,14 +743,265 @@ impl Hash for Function {
 
 impl Display for Function {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let params: Vec<String> = self.params.iter().map(|p| p.to_string()).collect();
+        self.signature().fmt(f)
+    }
+}
+
+/// A constant that was declared in an `impl` block.
+///
+/// The value is folded at the use site so the user of the constant does not need to link with its
+/// symbol.
+#[derive(Debug, Clone)]
+pub struct Constant {
+    name: String,
+    ty: Type,
+    value: ConstValue,
+}
+
+impl Constant {
+    const ENTRY_END: u8 = 0;
+    const ENTRY_NAME: u8 = 1;
+    const ENTRY_TYPE: u8 = 2;
+    const ENTRY_UNSIGNED: u8 = 3;
+    const ENTRY_FLOAT: u8 = 4;
+    const ENTRY_STRING: u8 = 5;
+
+    pub fn new(name: String, ty: Type, value: ConstValue) -> Self {
+        Self { name, ty, value }
+    }
+
+    pub fn ty(&self) -> &Type {
+        &self.ty
+    }
+
+    pub fn value(&self) -> &ConstValue {
+        &self.value
+    }
+
+    /// Returns the symbol of this constant. The arguments is the same as [`Function::mangle()`].
+    /// The grammar is:
+    ///
+    /// ```text
+    /// symbol = ("_NEC" package | "_NIC") name "C" ident type
+    /// ```
+    ///
+    /// See [`Function::mangle()`] for the other productions.
+    pub fn mangle(&self, lib: Option<(&str, u16)>, ty: &str) -> String {
+        // Check if executable.
+        let mut buf = String::new();
+
+        match lib {
+            Some((pkg, ver)) => {
+                buf.push_str("_NEC");
+                Function::mangle_pkg(&mut buf, pkg, ver);
+            }
+            None => buf.push_str("_NIC"),
+        }
+
+        // Type name.
+        Function::mangle_name(&mut buf, ty);
+
+        // Constant name.
+        buf.push('C');
+        Function::mangle_ident(&mut buf, &self.name);
+
+        // Type.
+        self.ty.mangle(&mut buf);
+
+        buf
+    }
+
+    fn serialize<W: Write>(
+        &self,
+        w: &mut W,
+        strings: &mut StringTable,
+    ) -> Result<(), std::io::Error> {
+        // Name.
+        let len: u16 = encode_len(self.name.len(), "constant name")?;
 
-        write!(f, "fn {}({}): {}", self.name, params.join(", "), self.ret)
+        w.write_all(&[Self::ENTRY_NAME])?;
+        w.write_all(&len.to_be_bytes())?;
+        w.write_all(self.name.as_bytes())?;
+
+        // Type.
+        w.write_all(&[Self::ENTRY_TYPE])?;
+        self.ty.serialize(w, strings)?;
+
+        // Value.
+        match &self.value {
+            ConstValue::Unsigned(v) => {
+                w.write_all(&[Self::ENTRY_UNSIGNED])?;
+                w.write_all(&v.to_be_bytes())?;
+            }
+            ConstValue::Float(v) => {
+                w.write_all(&[Self::ENTRY_FLOAT])?;
+                w.write_all(&v.to_bits().to_be_bytes())?;
+            }
+            ConstValue::String(v) => {
+                let len: u32 = encode_len(v.len(), "string constant")?;
+
+                w.write_all(&[Self::ENTRY_STRING])?;
+                w.write_all(&len.to_be_bytes())?;
+                w.write_all(v.as_bytes())?;
+            }
+        }
+
+        // End.
+        w.write_all(&[Self::ENTRY_END])
+    }
+
+    fn deserialize<R: Read>(
+        mut r: R,
+        strings: &StringTable,
+        i: usize,
+    ) -> Result<Self, TypeDeserializeError> {
+        // Iterate over the entries.
+        let mut name = None;
+        let mut ty = None;
+        let mut value = None;
+
+        loop {
+            // Read entry type.
+            let mut entry = 0;
+
+            r.read_exact(std::slice::from_mut(&mut entry))?;
+
+            // Process the entry.
+            match entry {
+                Self::ENTRY_END => break,
+                Self::ENTRY_NAME => {
+                    // Read name length.
+                    let mut buf = [0u8; 2];
+                    r.read_exact(&mut buf)?;
+                    let len: usize = u16::from_be_bytes(buf).into();
+
+                    // Read name.
+                    let mut buf = vec![0u8; len];
+                    r.read_exact(&mut buf)?;
+
+                    match String::from_utf8(buf) {
+                        Ok(v) => name = Some(v),
+                        Err(_) => return Err(TypeDeserializeError::InvalidConstantName(i)),
+                    }
+                }
+                Self::ENTRY_TYPE => match Type::deserialize(&mut r, strings) {
+                    Some(v) => ty = Some(v),
+                    None => return Err(TypeDeserializeError::InvalidConstantType(i)),
+                },
+                Self::ENTRY_UNSIGNED => {
+                    let mut buf = [0u8; 8];
+                    r.read_exact(&mut buf)?;
+                    value = Some(ConstValue::Unsigned(u64::from_be_bytes(buf)));
+                }
+                Self::ENTRY_FLOAT => {
+                    let mut buf = [0u8; 8];
+                    r.read_exact(&mut buf)?;
+                    value = Some(ConstValue::Float(f64::from_bits(u64::from_be_bytes(buf))));
+                }
+                Self::ENTRY_STRING => {
+                    // Read string length.
+                    let mut buf = [0u8; 4];
+                    r.read_exact(&mut buf)?;
+                    let len: usize = u32::from_be_bytes(buf).try_into().unwrap();
+
+                    // Read string.
+                    let mut buf = vec![0u8; len];
+                    r.read_exact(&mut buf)?;
+
+                    match String::from_utf8(buf) {
+                        Ok(v) => value = Some(ConstValue::String(v)),
+                        Err(_) => return Err(TypeDeserializeError::InvalidConstantValue(i)),
+                    }
+                }
+                v => return Err(TypeDeserializeError::UnknownConstantEntry(i, v)),
+            }
+        }
+
+        // Construct the constant.
+        let name = name.ok_or(TypeDeserializeError::ConstantNameNotFound(i))?;
+        let ty = ty.ok_or(TypeDeserializeError::ConstantTypeNotFound(i))?;
+        let value = value.ok_or(TypeDeserializeError::ConstantValueNotFound(i))?;
+
+        Ok(Self { name, ty, value })
+    }
+}
+
+impl Named for Constant {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl PartialEq for Constant {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for Constant {}
+
+impl Hash for Constant {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+    }
+}
+
+/// A value of [`Constant`].
+#[derive(Debug, Clone)]
+pub enum ConstValue {
+    Unsigned(u64),
+    Float(f64),
+    String(String),
+}
+
+/// A human-readable signature of a function (e.g. for a hover text in the editor).
+///
+/// This can be produced from both a function in the source file and a function in the package so
+/// both of them will be shown the same way.
+#[derive(Serialize)]
+pub struct FunctionSignature {
+    name: String,
+    params: Vec<SignatureParam>,
+    ret: String,
+}
+
+impl FunctionSignature {
+    pub fn new(name: String, params: Vec<SignatureParam>, ret: String) -> Self {
+        Self { name, params, ret }
+    }
+}
+
+impl Display for FunctionSignature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "fn {}(", self.name)?;
+
+        for (i, p) in self.params.iter().enumerate() {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+
+            write!(f, "{}: {}", p.name, p.ty)?;
+        }
+
+        write!(f, "): {}", self.ret)
+    }
+}
+
+/// A parameter in [`FunctionSignature`].
+#[derive(Serialize)]
+pub struct SignatureParam {
+    name: String,
+    ty: String,
+}
+
+impl SignatureParam {
+    pub fn new(name: String, ty: String) -> Self {
+        Self { name, ty }
     }
 }
 
 /// A function parameter.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FunctionParam {
     name: String,
     ty: Type,
@@ -406,22 +1024,31 @@ impl FunctionParam {
         &self.ty
     }
 
-    fn serialize<W: Write>(&self, w: &mut W) -> Result<(), std::io::Error> {
+    fn serialize<W: Write>(
+        &self,
+        w: &mut W,
+        strings: &mut StringTable,
+    ) -> Result<(), std::io::Error> {
         // Name.
-        let len: u8 = self.name.len().try_into().unwrap();
+        let len: u8 = encode_len(self.name.len(), "parameter name")?;
 
         w.write_all(&[Self::ENTRY_NAME, len])?;
         w.write_all(self.name.as_bytes())?;
 
         // Type.
         w.write_all(&[Self::ENTRY_TYPE])?;
-        self.ty.serialize(w)?;
+        self.ty.serialize(w, strings)?;
 
         // End.
         w.write_all(&[Self::ENTRY_END])
     }
 
-    fn deserialize<R: Read>(mut r: R, f: usize, i: usize) -> Result<Self, TypeDeserializeError> {
+    fn deserialize<R: Read>(
+        mut r: R,
+        strings: &StringTable,
+        f: usize,
+        i: usize,
+    ) -> Result<Self, TypeDeserializeError> {
         // Iterate over the entries.
         let mut name = None;
         let mut ty = None;
@@ -450,7 +1077,7 @@ impl FunctionParam {
                         Err(_) => return Err(TypeDeserializeError::InvalidParamName(f, i)),
                     }
                 }
-                Self::ENTRY_TYPE => match Type::deserialize(&mut r) {
+                Self::ENTRY_TYPE => match Type::deserialize(&mut r, strings) {
                     Some(v) => ty = Some(v),
                     None => return Err(TypeDeserializeError::InvalidParamType(f, i)),
                 },
@@ -473,7 +1100,7 @@ impl Display for FunctionParam {
 }
 
 /// Type of something (e.g. function parameter).
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Type {
     Unit {
         ptr: usize,
@@ -489,15 +1116,45 @@ pub enum Type {
         pkg: Option<(String, u16)>,
         name: String,
     },
+    Tuple {
+        ptr: usize,
+        elems: Vec<Type>,
+    },
 }
 
 impl Type {
+    /// Push the name and the major version of each package other than the current one that is
+    /// referenced by this type to `out`.
+    pub fn external_packages<'a>(&'a self, out: &mut Vec<(&'a str, u16)>) {
+        match self {
+            Self::Unit { .. } | Self::Never => {}
+            Self::Struct { pkg, .. } | Self::Class { pkg, .. } => {
+                if let Some((name, ver)) = pkg {
+                    out.push((name, *ver));
+                }
+            }
+            Self::Tuple { elems, .. } => {
+                for e in elems {
+                    e.external_packages(out);
+                }
+            }
+        }
+    }
+
+    /// Write the symbol of this type. The grammar is:
+    ///
+    /// ```text
+    /// type  = "P"* ("U" | "C" owner name | "S" owner name | "T" type type+ "E") | "N"
+    /// owner = "E" package | "S"   ; "S" for a type in the same package
+    /// ```
+    ///
+    /// Each `P` is a pointer prefix. `U` is the unit, `N` is the never type (which cannot be a
+    /// pointer), `C` is a class, `S` is a struct and `T` is a tuple. See [`Function::mangle()`]
+    /// for `package` and `name`.
     fn mangle(&self, buf: &mut String) {
         match self {
             Self::Unit { ptr } => {
-                for _ in 0..*ptr {
-                    buf.push('P');
-                }
+                Self::mangle_ptr(buf, *ptr);
                 buf.push('U');
             }
             Self::Never => buf.push('N'),
@@ -507,9 +1164,82 @@ impl Type {
             Self::Class { ptr, pkg, name } => {
                 Self::mangle_basic(buf, true, *ptr, pkg.as_ref(), name)
             }
+            Self::Tuple { ptr, elems } => {
+                Self::mangle_ptr(buf, *ptr);
+                buf.push('T');
+
+                for t in elems {
+                    t.mangle(buf);
+                }
+
+                buf.push('E');
+            }
         }
     }
 
+    fn demangle(s: &mut &str) -> Option<Self> {
+        // Get pointer prefixes.
+        let ptr = s.find(|c| c != 'P').unwrap_or(s.len());
+
+        *s = &s[ptr..];
+
+        // Get type.
+        let class = match s.chars().next()? {
+            'U' => {
+                *s = &s[1..];
+                return Some(Self::Unit { ptr });
+            }
+            'N' if ptr == 0 => {
+                *s = &s[1..];
+                return Some(Self::Never);
+            }
+            'T' => {
+                let mut elems = Vec::new();
+
+                *s = &s[1..];
+
+                while !s.starts_with('E') {
+                    elems.push(Self::demangle(s)?);
+                }
+
+                *s = &s[1..];
+
+                return if elems.len() < 2 {
+                    None
+                } else {
+                    Some(Self::Tuple { ptr, elems })
+                };
+            }
+            'C' => true,
+            'S' => false,
+            _ => return None,
+        };
+
+        *s = &s[1..];
+
+        // Get package.
+        let pkg = match s.chars().next()? {
+            'E' => {
+                *s = &s[1..];
+                Some(Function::demangle_pkg(s)?)
+            }
+            'S' => {
+                *s = &s[1..];
+                None
+            }
+            _ => return None,
+        };
+
+        // Get name.
+        let name = Function::demangle_name(s)?;
+
+        Some(if class {
+            Self::Class { ptr, pkg, name }
+        } else {
+            Self::Struct { ptr, pkg, name }
+        })
+    }
+
     fn mangle_basic(
         buf: &mut String,
         class: bool,
@@ -517,37 +1247,49 @@ impl Type {
         pkg: Option<&(String, u16)>,
         name: &str,
     ) {
-        use std::fmt::Write;
-
-        for _ in 0..ptr {
-            buf.push('P');
-        }
+        Self::mangle_ptr(buf, ptr);
 
         buf.push(if class { 'C' } else { 'S' });
 
         match pkg {
             Some((pkg, ver)) => {
-                write!(buf, "E{}{}", pkg.len(), pkg).unwrap();
-
-                if *ver != 0 {
-                    write!(buf, "V{ver}T").unwrap();
-                } else {
-                    buf.push('T');
-                }
+                buf.push('E');
+                Function::mangle_pkg(buf, pkg, *ver);
             }
             None => buf.push('S'),
         }
 
-        for p in name.split('.') {
-            write!(buf, "{}{}", p.len(), p).unwrap();
+        Function::mangle_name(buf, name);
+    }
+
+    fn mangle_ptr(buf: &mut String, ptr: usize) {
+        for _ in 0..ptr {
+            buf.push('P');
         }
     }
 
-    fn serialize<W: Write>(&self, w: &mut W) -> Result<(), std::io::Error> {
+    fn serialize<W: Write>(
+        &self,
+        w: &mut W,
+        strings: &mut StringTable,
+    ) -> Result<(), std::io::Error> {
         // Check if struct or class.
         let (ptr, pkg, name) = match self {
-            Self::Unit { ptr } => return w.write_all(&[0, (*ptr).try_into().unwrap()]),
+            Self::Unit { ptr } => return w.write_all(&[0, encode_len(*ptr, "pointer prefix")?]),
             Self::Never => return w.write_all(&[3]),
+            Self::Tuple { ptr, elems } => {
+                w.write_all(&[
+                    4,
+                    encode_len(*ptr, "pointer prefix")?,
+                    encode_len(elems.len(), "tuple")?,
+                ])?;
+
+                for t in elems {
+                    t.serialize(w, strings)?;
+                }
+
+                return Ok(());
+            }
             Self::Struct { ptr, pkg, name } => {
                 w.write_all(&[1])?;
                 (*ptr, pkg, name)
@@ -559,26 +1301,23 @@ impl Type {
         };
 
         // Write prefixes.
-        w.write_all(&[ptr.try_into().unwrap()])?;
+        w.write_all(&[encode_len(ptr, "pointer prefix")?])?;
 
         // Write package.
         match pkg {
             Some((pkg, ver)) => {
-                w.write_all(&[pkg.len().try_into().unwrap()])?;
-                w.write_all(pkg.as_bytes())?;
+                w.write_all(&[1])?;
+                w.write_all(&strings.intern(pkg).to_be_bytes())?;
                 w.write_all(&ver.to_be_bytes())?;
             }
             None => w.write_all(&[0])?,
         }
 
         // Write name.
-        let len: u16 = name.len().try_into().unwrap();
-
-        w.write_all(&len.to_be_bytes())?;
-        w.write_all(name.as_bytes())
+        w.write_all(&strings.intern(name).to_be_bytes())
     }
 
-    fn deserialize<R: Read>(mut r: R) -> Option<Self> {
+    fn deserialize<R: Read>(r: &mut R, strings: &StringTable) -> Option<Self> {
         // Get category.
         let mut cat = 0;
         r.read_exact(std::slice::from_mut(&mut cat)).ok()?;
@@ -594,20 +1333,37 @@ impl Type {
 
         if cat == 0 {
             return Some(Self::Unit { ptr: ptr.into() });
-        }
+        } else if cat == 4 {
+            let mut len = 0;
+            r.read_exact(std::slice::from_mut(&mut len)).ok()?;
 
-        // Get package length.
-        let mut len = 0;
-        r.read_exact(std::slice::from_mut(&mut len)).ok()?;
+            if len < 2 {
+                return None;
+            }
+
+            let mut elems = Vec::with_capacity(len.into());
+
+            for _ in 0..len {
+                elems.push(Self::deserialize(r, strings)?);
+            }
+
+            return Some(Self::Tuple {
+                ptr: ptr.into(),
+                elems,
+            });
+        }
 
         // Get package.
-        let pkg = match len {
+        let mut has = 0;
+        r.read_exact(std::slice::from_mut(&mut has)).ok()?;
+
+        let pkg = match has {
             0 => None,
-            v => {
+            1 => {
                 // Read name.
-                let mut buf = vec![0u8; v.into()];
+                let mut buf = [0u8; 4];
                 r.read_exact(&mut buf).ok()?;
-                let name = String::from_utf8(buf).ok()?;
+                let name = strings.get(u32::from_be_bytes(buf))?.to_owned();
 
                 // Read version.
                 let mut buf = [0u8; 2];
@@ -616,17 +1372,13 @@ impl Type {
 
                 Some((name, ver))
             }
+            _ => return None,
         };
 
-        // Read name length.
-        let mut buf = [0u8; 2];
-        r.read_exact(&mut buf).ok()?;
-        let len: usize = u16::from_be_bytes(buf).into();
-
         // Read name.
-        let mut buf = vec![0u8; len];
+        let mut buf = [0u8; 4];
         r.read_exact(&mut buf).ok()?;
-        let name = String::from_utf8(buf).ok()?;
+        let name = strings.get(u32::from_be_bytes(buf))?.to_owned();
 
         // Construct type.
         let ty = match cat {
@@ -669,11 +1421,105 @@ impl Display for Type {
 
                 f.write_str(name)
             }
+            Self::Tuple { ptr, elems } => {
+                for _ in 0..*ptr {
+                    f.write_str("*")?;
+                }
+
+                f.write_str("(")?;
+
+                for (i, t) in elems.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(", ")?;
+                    }
+
+                    t.fmt(f)?;
+                }
+
+                f.write_str(")")
+            }
         }
     }
 }
 
+/// A list of unique strings that was referenced by index from the serialized [`Type`].
+///
+/// This is a format optimization so the package name and type name that are repeated on every
+/// parameter of every function will be stored only once.
+#[derive(Default)]
+pub(super) struct StringTable {
+    list: Vec<String>,
+    index: HashMap<String, u32>,
+}
+
+impl StringTable {
+    pub fn intern(&mut self, v: &str) -> u32 {
+        if let Some(&i) = self.index.get(v) {
+            return i;
+        }
+
+        let i: u32 = self.list.len().try_into().unwrap();
+
+        self.list.push(v.to_owned());
+        self.index.insert(v.to_owned(), i);
+
+        i
+    }
+
+    pub fn get(&self, i: u32) -> Option<&str> {
+        self.list.get(usize::try_from(i).ok()?).map(|v| v.as_str())
+    }
+
+    pub fn serialize<W: Write>(&self, w: &mut W) -> Result<(), std::io::Error> {
+        let count: u32 = self.list.len().try_into().unwrap();
+
+        w.write_all(&count.to_be_bytes())?;
+
+        for v in &self.list {
+            let len: u16 = encode_len(v.len(), "string")?;
+
+            w.write_all(&len.to_be_bytes())?;
+            w.write_all(v.as_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn deserialize<R: Read>(mut r: R) -> Result<Self, TypeDeserializeError> {
+        // Read string count.
+        let mut buf = [0u8; 4];
+        r.read_exact(&mut buf)?;
+        let count = u32::from_be_bytes(buf);
+
+        // Read strings.
+        let mut table = Self::default();
+
+        for i in 0..count {
+            // Read length.
+            let mut buf = [0u8; 2];
+            r.read_exact(&mut buf)?;
+            let len: usize = u16::from_be_bytes(buf).into();
+
+            // Read string.
+            let mut buf = vec![0u8; len];
+            r.read_exact(&mut buf)?;
+
+            let v = match String::from_utf8(buf) {
+                Ok(v) => v,
+                Err(_) => return Err(TypeDeserializeError::InvalidString(i)),
+            };
+
+            if table.intern(&v) != i {
+                return Err(TypeDeserializeError::DuplicatedString(v));
+            }
+        }
+
+        Ok(table)
+    }
+}
+
 /// A collection of attributes.
+#[derive(Clone)]
 pub struct Attributes {
     public: Option<Public>,
     ext: Option<Extern>,
@@ -701,11 +1547,19 @@ pub enum Public {
 }
 
 /// Argument of `@ext`.
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Extern {
     C,
 }
 
+/// Argument of `@abi`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Abi {
+    C,
+    /// The calling convention for Nitro. This is LLVM `fastcc` until Nitro has its own convention.
+    Nitro,
+}
+
 /// Argument of `@repr`
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum Representation {
@@ -714,6 +1568,43 @@ pub enum Representation {
     Un,
 }
 
+impl Representation {
+    /// Returns the name of the type in the standard library that use this representation.
+    pub fn std_name(self) -> &'static str {
+        match self {
+            Self::I32 => "Int32",
+            Self::U8 => "UInt8",
+            Self::Un => "UInt",
+        }
+    }
+
+    /// Returns the range of the values that can be represented. `ptr` is the size of a pointer on
+    /// the target in bytes, which is the size of [`Representation::Un`].
+    pub fn range(self, ptr: u32) -> RangeInclusive<i128> {
+        match self {
+            Self::I32 => i32::MIN.into()..=i32::MAX.into(),
+            Self::U8 => u8::MIN.into()..=u8::MAX.into(),
+            Self::Un => 0..=(1 << (ptr * 8)) - 1,
+        }
+    }
+
+    /// Returns an error message if an integer literal `v` of the type `name` cannot be
+    /// represented. See [`Representation::range()`] for `ptr`.
+    pub fn check_literal(self, name: &str, v: u64, ptr: u32) -> Result<(), String> {
+        let range = self.range(ptr);
+
+        if range.contains(&v.into()) {
+            Ok(())
+        } else {
+            Err(format!(
+                "{v} is out of range for {name} ({}..={})",
+                range.start(),
+                range.end()
+            ))
+        }
+    }
+}
+
 /// Represents an error when [`TypeDeclaration`] is failed to deserialize from the data.
 #[derive(Debug, Error)]
 pub enum TypeDeserializeError {
@@ -723,6 +1614,12 @@ pub enum TypeDeserializeError {
     #[error("cannot read data")]
     ReadDataFailed(#[source] std::io::Error),
 
+    #[error("invalid string #{0} in the string table")]
+    InvalidString(u32),
+
+    #[error("multiple definition of '{0}' in the string table")]
+    DuplicatedString(String),
+
     #[error("invalid type name")]
     InvalidTypeName,
 
@@ -750,6 +1647,12 @@ pub enum TypeDeserializeError {
     #[error("unknown entry {1} for function #{0}")]
     UnknownFunctionEntry(usize, u8),
 
+    #[error("unknown calling convention {1} for function #{0}")]
+    UnknownFunctionExtern(usize, u8),
+
+    #[error("unknown ABI {1} for function #{0}")]
+    UnknownFunctionAbi(usize, u8),
+
     #[error("name for function #{0} is not found")]
     FunctionNameNotFound(usize),
 
@@ -757,7 +1660,34 @@ pub enum TypeDeserializeError {
     FunctionNameRetFound(usize),
 
     #[error("multiple definition of '{0}'")]
-    DuplicatedFunction(Function),
+    DuplicatedFunction(Box<Function>),
+
+    #[error("invalid name for constant #{0}")]
+    InvalidConstantName(usize),
+
+    #[error("invalid type for constant #{0}")]
+    InvalidConstantType(usize),
+
+    #[error("invalid value for constant #{0}")]
+    InvalidConstantValue(usize),
+
+    #[error("unknown entry {1} for constant #{0}")]
+    UnknownConstantEntry(usize, u8),
+
+    #[error("name for constant #{0} is not found")]
+    ConstantNameNotFound(usize),
+
+    #[error("type for constant #{0} is not found")]
+    ConstantTypeNotFound(usize),
+
+    #[error("value for constant #{0} is not found")]
+    ConstantValueNotFound(usize),
+
+    #[error("multiple definition of constant '{0}'")]
+    DuplicatedConstant(String),
+
+    #[error("invalid origin of the type")]
+    InvalidOrigin,
 
     #[error("unknown type entry {0}")]
     UnknownTypeEntry(u8),
@@ -774,3 +1704,296 @@ impl From<std::io::Error> for TypeDeserializeError {
         Self::ReadDataFailed(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The symbols are the ABI of every package. If any of these fail the change must bump
+    // MANGLING_VERSION and update the expected symbols here.
+    #[test]
+    fn mangle_function() {
+        assert_eq!(MANGLING_VERSION, 1);
+
+        let unit = Type::Unit { ptr: 0 };
+        let f = Function::new("Run".into(), Vec::new(), unit.clone(), None, Abi::C);
+
+        // Internal vs external and zero vs non-zero major version.
+        assert_eq!(f.mangle(None, "Foo"), "_NIF3FooF3Run0U");
+        assert_eq!(f.mangle(Some(("foo", 0)), "Foo"), "_NEF3fooT3FooF3Run0U");
+        assert_eq!(
+            f.mangle(Some(("foo", 12)), "Foo"),
+            "_NEF3fooV12T3FooF3Run0U"
+        );
+
+        // Nested namespace.
+        assert_eq!(
+            f.mangle(Some(("foo", 1)), "Bar.Baz.Qux"),
+            "_NEF3fooV1T3Bar3Baz3QuxF3Run0U"
+        );
+
+        // Calling convention.
+        let f = Function::new("Run".into(), Vec::new(), unit, None, Abi::Nitro);
+
+        assert_eq!(f.mangle(None, "Foo"), "_NIF3FooF3Run1U");
+    }
+
+    #[test]
+    fn mangle_type() {
+        let types = [
+            (Type::Unit { ptr: 0 }, "U"),
+            (Type::Unit { ptr: 2 }, "PPU"),
+            (Type::Never, "N"),
+            (external(false, 0, "nitro", 0, "UInt8"), "SE5nitroT5UInt8"),
+            (external(true, 1, "nitro", 0, "Int32"), "PCE5nitroT5Int32"),
+            (external(true, 0, "foo", 3, "List"), "CE3fooV3T4List"),
+            (
+                external(false, 3, "foo", 0, "Collections.Map"),
+                "PPPSE3fooT11Collections3Map",
+            ),
+            (
+                Type::Struct {
+                    ptr: 0,
+                    pkg: None,
+                    name: "A.B".into(),
+                },
+                "SS1A1B",
+            ),
+            (
+                Type::Class {
+                    ptr: 1,
+                    pkg: None,
+                    name: "Foo".into(),
+                },
+                "PCS3Foo",
+            ),
+            (
+                Type::Tuple {
+                    ptr: 1,
+                    elems: vec![
+                        Type::Unit { ptr: 0 },
+                        Type::Struct {
+                            ptr: 0,
+                            pkg: None,
+                            name: "X".into(),
+                        },
+                    ],
+                },
+                "PTUSS1XE",
+            ),
+            (
+                Type::Tuple {
+                    ptr: 0,
+                    elems: vec![
+                        Type::Unit { ptr: 1 },
+                        Type::Tuple {
+                            ptr: 0,
+                            elems: vec![Type::Unit { ptr: 0 }, Type::Unit { ptr: 0 }],
+                        },
+                    ],
+                },
+                "TPUTUUEE",
+            ),
+        ];
+
+        for (ty, expected) in types {
+            // As the return type.
+            let f = Function::new("Get".into(), Vec::new(), ty.clone(), None, Abi::Nitro);
+
+            assert_eq!(f.mangle(None, "Foo"), format!("_NIF3FooF3Get1{expected}"));
+
+            // As a parameter.
+            if matches!(ty, Type::Never) {
+                continue;
+            }
+
+            let params = vec![
+                FunctionParam::new("a".into(), ty.clone()),
+                FunctionParam::new("b".into(), Type::Unit { ptr: 0 }),
+            ];
+            let f = Function::new("Set".into(), params, Type::Never, None, Abi::C);
+
+            assert_eq!(
+                f.mangle(Some(("bar", 2)), "Foo"),
+                format!("_NEF3barV2T3FooF3Set0N{expected}U")
+            );
+        }
+    }
+
+    #[test]
+    fn mangle_constant() {
+        let c = Constant::new(
+            "MAX".into(),
+            external(false, 0, "nitro", 0, "Int32"),
+            ConstValue::Unsigned(0x7fffffff),
+        );
+
+        assert_eq!(c.mangle(None, "Foo"), "_NIC3FooC3MAXSE5nitroT5Int32");
+        assert_eq!(
+            c.mangle(Some(("nitro", 0)), "Int32"),
+            "_NEC5nitroT5Int32C3MAXSE5nitroT5Int32"
+        );
+        assert_eq!(
+            c.mangle(Some(("foo", 4)), "A.B"),
+            "_NEC3fooV4T1A1BC3MAXSE5nitroT5Int32"
+        );
+    }
+
+    #[test]
+    fn representation_range() {
+        assert_eq!(Representation::I32.range(8), -2147483648..=2147483647);
+        assert_eq!(Representation::U8.range(8), 0..=255);
+        assert_eq!(Representation::Un.range(4), 0..=4294967295);
+        assert_eq!(Representation::Un.range(8), 0..=18446744073709551615);
+    }
+
+    #[test]
+    fn check_literal() {
+        assert_eq!(Representation::U8.check_literal("UInt8", 255, 8), Ok(()));
+        assert_eq!(
+            Representation::U8.check_literal("UInt8", 300, 8),
+            Err("300 is out of range for UInt8 (0..=255)".into())
+        );
+        assert_eq!(
+            Representation::I32.check_literal("Int32", 2147483647, 8),
+            Ok(())
+        );
+        assert_eq!(
+            Representation::I32.check_literal("self.Errno", 2147483648, 8),
+            Err("2147483648 is out of range for self.Errno (-2147483648..=2147483647)".into())
+        );
+        assert_eq!(
+            Representation::Un.check_literal("UInt", u64::MAX, 8),
+            Ok(())
+        );
+        assert_eq!(
+            Representation::Un.check_literal("UInt", 4294967296, 4),
+            Err("4294967296 is out of range for UInt (0..=4294967295)".into())
+        );
+    }
+
+    #[test]
+    fn demangle_round_trip() {
+        let types = [
+            Type::Unit { ptr: 0 },
+            Type::Unit { ptr: 2 },
+            Type::Never,
+            external(false, 0, "nitro", 0, "Int32"),
+            external(true, 1, "foo", 12, "Collections.List"),
+            Type::Struct {
+                ptr: 0,
+                pkg: None,
+                name: "Bar.Baz".into(),
+            },
+            Type::Class {
+                ptr: 3,
+                pkg: None,
+                name: "Qux".into(),
+            },
+            Type::Tuple {
+                ptr: 1,
+                elems: vec![
+                    Type::Unit { ptr: 0 },
+                    external(false, 1, "nitro", 0, "UInt8"),
+                ],
+            },
+        ];
+
+        for lib in [None, Some(("foo", 0)), Some(("foo", 3))] {
+            for abi in [Abi::C, Abi::Nitro] {
+                for ret in &types {
+                    // No parameters.
+                    let f = Function::new("Run".into(), Vec::new(), ret.clone(), None, abi);
+
+                    assert_round_trip(&f, lib, "Bar.Baz");
+
+                    // All types as parameters.
+                    let params = types
+                        .iter()
+                        .filter(|t| !matches!(t, Type::Never))
+                        .enumerate()
+                        .map(|(i, t)| FunctionParam::new(format!("p{i}"), t.clone()))
+                        .collect();
+                    let f = Function::new("Run".into(), params, ret.clone(), None, abi);
+
+                    assert_round_trip(&f, lib, "A.B.C");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn demangle_signature() {
+        let params = vec![
+            FunctionParam::new("v".into(), external(false, 1, "nitro", 0, "Int32")),
+            FunctionParam::new("l".into(), external(true, 0, "foo", 2, "List")),
+        ];
+        let f = Function::new(
+            "Run".into(),
+            params,
+            Type::Unit { ptr: 0 },
+            None,
+            Abi::Nitro,
+        );
+
+        assert_eq!(
+            Function::demangle(&f.mangle(Some(("bar", 1)), "Baz.Qux")).as_deref(),
+            Some("bar:1.Baz.Qux.Run(*nitro:0.Int32, foo:2.List): ()")
+        );
+        assert_eq!(
+            Function::demangle(&f.mangle(None, "Baz")).as_deref(),
+            Some("self.Baz.Run(*nitro:0.Int32, foo:2.List): ()")
+        );
+    }
+
+    #[test]
+    fn demangle_invalid() {
+        for sym in [
+            "",
+            "main",
+            "_ZN3foo3barEv",
+            "_NIF",
+            "_NIF3FooF3Run",             // No calling convention.
+            "_NIF3FooF3Run1",            // No return type.
+            "_NIF3FooF3Run2U",           // Unknown calling convention.
+            "_NIF3FooF03Run1U",          // Leading zero in the length.
+            "_NIF3FooF9Run1U",           // Length beyond the symbol.
+            "_NIF3FooF3Run1UX",          // Unknown type.
+            "_NIF3FooF3Run1PN",          // Pointer to never.
+            "_NIF3FooF3Run1TUE",         // Tuple with a single element.
+            "_NEF3fooV0T3FooF3Run1U",    // Zero major version is never encoded.
+            "_NEF3fooV1X3FooF3Run1U",    // No end of the package.
+            "_NEF3fooT3FooF3Run1SE3Bar", // No end of the package in the type.
+        ] {
+            assert_eq!(Function::demangle(sym), None, "{sym}");
+        }
+    }
+
+    fn external(class: bool, ptr: usize, pkg: &str, ver: u16, name: &str) -> Type {
+        let pkg = Some((pkg.to_owned(), ver));
+        let name = name.to_owned();
+
+        if class {
+            Type::Class { ptr, pkg, name }
+        } else {
+            Type::Struct { ptr, pkg, name }
+        }
+    }
+
+    fn assert_round_trip(f: &Function, lib: Option<(&str, u16)>, ty: &str) {
+        let sym = f.mangle(lib, ty);
+        let params: Vec<String> = f.params().iter().map(|p| p.ty().to_string()).collect();
+        let owner = match lib {
+            Some((pkg, ver)) => format!("{pkg}:{ver}"),
+            None => "self".into(),
+        };
+        let expected = format!(
+            "{owner}.{ty}.{}({}): {}",
+            f.name(),
+            params.join(", "),
+            f.ret()
+        );
+
+        assert_eq!(Function::demangle(&sym), Some(expected), "{sym}");
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// A Makefile-style dependency file. All outputs share the same prerequisites.
+#[derive(Default)]
+pub struct DepFile {
+    outputs: BTreeSet<PathBuf>,
+    inputs: BTreeSet<PathBuf>,
+}
+
+impl DepFile {
+    pub fn add_output(&mut self, path: impl Into<PathBuf>) {
+        self.outputs.insert(path.into());
+    }
+
+    pub fn add_input(&mut self, path: impl Into<PathBuf>) {
+        self.inputs.insert(path.into());
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), std::io::Error> {
+        let mut rule = String::new();
+
+        for (i, out) in self.outputs.iter().enumerate() {
+            if i != 0 {
+                rule.push(' ');
+            }
+
+            rule.push_str(&Self::escape(out));
+        }
+
+        rule.push(':');
+
+        for path in &self.inputs {
+            rule.push_str(" \\\n ");
+            rule.push_str(&Self::escape(path));
+        }
+
+        rule.push('\n');
+
+        std::fs::write(path, rule)
+    }
+
+    fn escape(path: &Path) -> String {
+        let mut buf = String::new();
+
+        for c in path.to_string_lossy().chars() {
+            match c {
+                ' ' | '#' => buf.push('\\'),
+                '$' => buf.push('$'),
+                _ => {}
+            }
+
+            buf.push(c);
+        }
+
+        buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write() {
+        let path = std::env::temp_dir().join(format!("nitro-depfile-{}.d", std::process::id()));
+        let mut df = DepFile::default();
+
+        df.add_output("ws/app.o");
+        df.add_output("ws/my app");
+        df.add_input("src/App.nt");
+        df.add_input("Nitro.yml");
+        df.add_input("stubs/lib$ #1.a");
+        df.add_input("src/App.nt");
+
+        df.write(&path).unwrap();
+
+        let data = std::fs::read_to_string(&path).unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            data,
+            "ws/app.o ws/my\\ app: \\\n Nitro.yml \\\n src/App.nt \\\n stubs/lib$$\\ \\#1.a\n"
+        );
+    }
+}
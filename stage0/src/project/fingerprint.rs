@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+/// Inputs of a build for a single target.
+///
+/// Two builds with the same fingerprint are expected to produce the same outputs. All hashes are
+/// 64-bit FNV-1a (see [`crate::hash::Fnv64`]) in hexadecimal.
+#[derive(Serialize, Deserialize)]
+pub struct Fingerprint {
+    pub(super) compiler: String,
+    pub(super) llvm: String,
+    pub(super) target: String,
+    #[serde(rename = "target-id")]
+    pub(super) target_id: String,
+    pub(super) stubs: String,
+    pub(super) sources: BTreeMap<String, String>,
+    pub(super) dependencies: BTreeMap<String, DependencyFingerprint>,
+    pub(super) options: BTreeMap<String, String>,
+}
+
+impl Fingerprint {
+    /// Returns a description of the first field that is different from `other`.
+    pub fn diff(&self, other: &Self) -> Option<String> {
+        let fields = [
+            ("compiler", &self.compiler, &other.compiler),
+            ("llvm", &self.llvm, &other.llvm),
+            ("target", &self.target, &other.target),
+            ("target-id", &self.target_id, &other.target_id),
+            ("stubs", &self.stubs, &other.stubs),
+        ];
+
+        for (name, a, b) in fields {
+            if a != b {
+                return Some(format!("{name}: {a} != {b}"));
+            }
+        }
+
+        Self::diff_map("sources", &self.sources, &other.sources)
+            .or_else(|| Self::diff_map("dependencies", &self.dependencies, &other.dependencies))
+            .or_else(|| Self::diff_map("options", &self.options, &other.options))
+    }
+
+    fn diff_map<V: PartialEq + Debug>(
+        name: &str,
+        a: &BTreeMap<String, V>,
+        b: &BTreeMap<String, V>,
+    ) -> Option<String> {
+        let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+
+        keys.sort_unstable();
+        keys.dedup();
+
+        for k in keys {
+            match (a.get(k), b.get(k)) {
+                (Some(x), Some(y)) if x == y => {}
+                (Some(x), Some(y)) => return Some(format!("{name}.{k}: {x:?} != {y:?}")),
+                (Some(x), None) => return Some(format!("{name}.{k}: {x:?} != (none)")),
+                (None, Some(y)) => return Some(format!("{name}.{k}: (none) != {y:?}")),
+                (None, None) => unreachable!(),
+            }
+        }
+
+        None
+    }
+}
+
+/// A dependency in [`Fingerprint`].
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct DependencyFingerprint {
+    version: String,
+    hash: Option<String>,
+}
+
+impl DependencyFingerprint {
+    /// `hash` is [`None`] if the dependency does not have a bundled library for the target.
+    pub fn new(version: String, hash: Option<String>) -> Self {
+        Self { version, hash }
+    }
+}
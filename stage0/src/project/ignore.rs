@@ -0,0 +1,203 @@
+/// A list of gitignore-like patterns to exclude files from the sources.
+///
+/// Only a subset of gitignore is supported:
+///
+/// - `*` matches zero or more characters except `/` and `?` matches a single character except
+///   `/`.
+/// - A pattern without `/` matches the name of file or directory at any level.
+/// - A pattern with `/` in the beginning or in the middle matches the path relative to the root.
+/// - A pattern ending with `/` matches only a directory.
+/// - A pattern beginning with `!` re-includes the path that was excluded by the previous patterns.
+/// - An empty line and a line beginning with `#` are ignored.
+#[derive(Default)]
+pub struct IgnoreList {
+    patterns: Vec<IgnorePattern>,
+}
+
+impl IgnoreList {
+    /// Add a single line of the pattern.
+    pub fn add(&mut self, line: &str) {
+        let line = line.trim_end();
+
+        if line.is_empty() || line.starts_with('#') {
+            return;
+        }
+
+        // Parse the pattern.
+        let (negate, line) = match line.strip_prefix('!') {
+            Some(v) => (true, v),
+            None => (false, line),
+        };
+
+        let (dir, line) = match line.strip_suffix('/') {
+            Some(v) => (true, v),
+            None => (false, line),
+        };
+
+        let (anchored, glob) = match line.strip_prefix('/') {
+            Some(v) => (true, v),
+            None => (line.contains('/'), line),
+        };
+
+        if glob.is_empty() {
+            return;
+        }
+
+        self.patterns.push(IgnorePattern {
+            glob: glob.to_owned(),
+            dir,
+            anchored,
+            negate,
+        });
+    }
+
+    /// `path` is relative to the root using `/` as a separator.
+    pub fn is_ignored(&self, path: &str, dir: bool) -> bool {
+        let name = path.rsplit('/').next().unwrap();
+        let mut ignored = false;
+
+        for p in &self.patterns {
+            if p.dir && !dir {
+                continue;
+            }
+
+            let target = if p.anchored { path } else { name };
+
+            if Self::matches(p.glob.as_bytes(), target.as_bytes()) {
+                ignored = !p.negate;
+            }
+        }
+
+        ignored
+    }
+
+    fn matches(glob: &[u8], text: &[u8]) -> bool {
+        let mut g = 0;
+        let mut t = 0;
+        let mut star = None;
+
+        while t < text.len() {
+            match glob.get(g) {
+                Some(b'*') => {
+                    star = Some((g, t));
+                    g += 1;
+                    continue;
+                }
+                Some(b'?') if text[t] != b'/' => {
+                    g += 1;
+                    t += 1;
+                    continue;
+                }
+                Some(&c) if c == text[t] => {
+                    g += 1;
+                    t += 1;
+                    continue;
+                }
+                _ => {}
+            }
+
+            // Backtrack to the last star and let it consume one more character.
+            match star {
+                Some((sg, st)) if text[st] != b'/' => {
+                    star = Some((sg, st + 1));
+                    g = sg + 1;
+                    t = st + 1;
+                }
+                _ => return false,
+            }
+        }
+
+        glob[g..].iter().all(|&c| c == b'*')
+    }
+}
+
+/// A single pattern in the [`IgnoreList`].
+struct IgnorePattern {
+    glob: String,
+    dir: bool,
+    anchored: bool,
+    negate: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name() {
+        let list = new(&["*.tmp", ".#*", "Foo?.nt"]);
+
+        assert!(list.is_ignored("a.tmp", false));
+        assert!(list.is_ignored("sub/dir/a.tmp", false));
+        assert!(list.is_ignored(".tmp", false));
+        assert!(list.is_ignored(".#App.nt", false));
+        assert!(list.is_ignored("sub/.#App.nt", false));
+        assert!(list.is_ignored("Foo1.nt", false));
+        assert!(!list.is_ignored("Foo.nt", false));
+        assert!(!list.is_ignored("Foo12.nt", false));
+        assert!(!list.is_ignored("a.tmp.nt", false));
+        assert!(!list.is_ignored("App.nt", false));
+
+        // A directory is matched the same way.
+        assert!(list.is_ignored("a.tmp", true));
+    }
+
+    #[test]
+    fn anchored() {
+        let list = new(&["/App.nt", "sub/*.nt", "/a/*/c"]);
+
+        assert!(list.is_ignored("App.nt", false));
+        assert!(!list.is_ignored("dir/App.nt", false));
+        assert!(list.is_ignored("sub/Foo.nt", false));
+        assert!(!list.is_ignored("other/sub/Foo.nt", false));
+
+        // A star does not match a separator.
+        assert!(!list.is_ignored("sub/dir/Foo.nt", false));
+        assert!(list.is_ignored("a/b/c", false));
+        assert!(!list.is_ignored("a/b/b/c", false));
+    }
+
+    #[test]
+    fn directory() {
+        let list = new(&["scratch/", "/build/"]);
+
+        assert!(list.is_ignored("scratch", true));
+        assert!(list.is_ignored("sub/scratch", true));
+        assert!(!list.is_ignored("scratch", false));
+        assert!(list.is_ignored("build", true));
+        assert!(!list.is_ignored("sub/build", true));
+    }
+
+    #[test]
+    fn negate() {
+        let list = new(&["*.nt", "!Keep.nt"]);
+
+        assert!(list.is_ignored("Foo.nt", false));
+        assert!(!list.is_ignored("Keep.nt", false));
+        assert!(!list.is_ignored("sub/Keep.nt", false));
+
+        // The last matching pattern wins.
+        let list = new(&["!Keep.nt", "*.nt"]);
+
+        assert!(list.is_ignored("Keep.nt", false));
+    }
+
+    #[test]
+    fn skipped_lines() {
+        let list = new(&["", "   ", "# *.nt", "/", "!", "Foo.nt   "]);
+
+        assert_eq!(list.patterns.len(), 1);
+        assert!(!list.is_ignored("Bar.nt", false));
+        assert!(list.is_ignored("Foo.nt", false));
+    }
+
+    fn new(lines: &[&str]) -> IgnoreList {
+        let mut list = IgnoreList::default();
+
+        for l in lines {
+            list.add(l);
+        }
+
+        list
+    }
+}
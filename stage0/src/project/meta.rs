@@ -1,13 +1,27 @@
-use crate::pkg::PackageMeta;
-use serde::Deserialize;
+use crate::pkg::{Dependency, PackageMeta, PackageName, PackageVersion};
+use serde::de::{MapAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::fmt::Formatter;
 use std::path::PathBuf;
 
 /// Contains information that was loaded from `Nitro.yml` file.
 #[derive(Deserialize)]
 pub struct ProjectMeta {
     package: PackageMeta,
+    namespace: Option<String>,
     executable: Option<ProjectBinary>,
     library: Option<ProjectBinary>,
+    #[serde(default, deserialize_with = "deserialize_deps")]
+    dependencies: Vec<Dependency>,
+    #[serde(default)]
+    assets: Vec<PathBuf>,
+    #[serde(rename = "out-dir")]
+    out_dir: Option<PathBuf>,
+    #[serde(default)]
+    defines: BTreeMap<String, String>,
+    #[serde(default, rename = "allow-intrinsics")]
+    allow_intrinsics: bool,
 }
 
 impl ProjectMeta {
@@ -15,6 +29,11 @@ impl ProjectMeta {
         &self.package
     }
 
+    /// Returns the namespace to prepend to the name of all types in the project.
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
     pub fn executable(&self) -> Option<&ProjectBinary> {
         self.executable.as_ref()
     }
@@ -22,16 +41,120 @@ impl ProjectMeta {
     pub fn library(&self) -> Option<&ProjectBinary> {
         self.library.as_ref()
     }
+
+    /// Returns the dependencies in the same order as `Nitro.yml`. The same package may appear more
+    /// than once since the duplicates are reported by [`super::Project::open()`].
+    pub fn dependencies(&self) -> &[Dependency] {
+        &self.dependencies
+    }
+
+    /// Returns a list of project-relative files or directories to include in the package.
+    pub fn assets(&self) -> &[PathBuf] {
+        &self.assets
+    }
+
+    /// Returns the keys that can be tested with `@if`.
+    pub fn defines(&self) -> &BTreeMap<String, String> {
+        &self.defines
+    }
+
+    /// Returns `true` if the project can call the compiler intrinsics in `nitro.intrin`.
+    pub fn allow_intrinsics(&self) -> bool {
+        self.allow_intrinsics
+    }
+
+    /// Returns a project-relative directory to place the build artifacts instead of `.build`.
+    pub fn out_dir(&self) -> Option<&PathBuf> {
+        self.out_dir.as_ref()
+    }
 }
 
 /// Contains information of the binary that the project will output.
 #[derive(Deserialize)]
 pub struct ProjectBinary {
     sources: PathBuf,
+    #[serde(default)]
+    ignore: Vec<String>,
+    #[serde(rename = "entry-symbol")]
+    entry_symbol: Option<String>,
+    entry: Option<EntryPoint>,
 }
 
 impl ProjectBinary {
     pub fn sources(&self) -> &PathBuf {
         &self.sources
     }
+
+    /// Returns a list of gitignore-like patterns (see [`super::IgnoreList`]) relative to
+    /// [`Self::sources()`].
+    pub fn ignore(&self) -> &[String] {
+        &self.ignore
+    }
+
+    /// Returns the name of the symbol the process start at. Only applicable for the executable.
+    pub fn entry_symbol(&self) -> Option<&str> {
+        self.entry_symbol.as_deref()
+    }
+
+    /// Returns the entry point to use instead of the function with `@entry`. Only applicable for
+    /// the executable.
+    pub fn entry(&self) -> Option<&EntryPoint> {
+        self.entry.as_ref()
+    }
+}
+
+/// Value of `entry` in the `executable` section.
+pub enum EntryPoint {
+    /// A function with its fully qualified type name (e.g. `App.Main`).
+    Function(String),
+    /// `none`, which does not generate the entry point so the executable must provide its own
+    /// symbol to start at.
+    None,
+}
+
+impl<'a> Deserialize<'a> for EntryPoint {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'a>,
+    {
+        let v = String::deserialize(deserializer)?;
+
+        Ok(if v == "none" {
+            Self::None
+        } else {
+            Self::Function(v)
+        })
+    }
+}
+
+/// Deserialize `dependencies` from a map of the package name to its version. The entries are
+/// returned as-is so the duplicates can be reported with a better error than serde.
+fn deserialize_deps<'a, D>(deserializer: D) -> Result<Vec<Dependency>, D::Error>
+where
+    D: Deserializer<'a>,
+{
+    struct DepsVisitor;
+
+    impl<'a> Visitor<'a> for DepsVisitor {
+        type Value = Vec<Dependency>;
+
+        fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+            f.write_str("a map of package name to version")
+        }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: MapAccess<'a>,
+        {
+            let mut deps = Vec::new();
+
+            while let Some((n, v)) = map.next_entry::<PackageName, PackageVersion>()? {
+                deps.push(Dependency::new(n, v));
+            }
+
+            Ok(deps)
+        }
+    }
+
+    deserializer.deserialize_map(DepsVisitor)
 }
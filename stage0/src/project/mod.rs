@@ -1,35 +1,59 @@
+pub use self::fingerprint::*;
+pub use self::ignore::*;
 pub use self::meta::*;
 
+use self::depfile::DepFile;
+use self::size::SizeReport;
 use crate::ast::{ParseError, SourceFile};
-use crate::codegen::{BuildError, Codegen, TypeResolver};
-use crate::lexer::SyntaxError;
+use crate::codegen::{BuildError, Codegen, MachineCache, TypeResolver};
+use crate::ffi::llvm_version;
+use crate::hash::{hash_dir, hash_file, Fnv64};
+use crate::lexer::{Lexer, SyntaxError};
+use crate::limit::MAX_TYPE_NAME;
 use crate::pkg::{
-    Binary, Dependency, DependencyResolveError, DependencyResolver, Library, LibraryBinary,
-    Package, PackageMeta, PackageName, PackageVersion, PrimitiveTarget, Target, TargetArch,
-    TargetEnv, TargetOs, TargetResolveError, TargetResolver, TypeDeclaration,
+    c_header, sorted_by_name, Binary, BinaryDependency, CHeaderError, Dependency, DependencyGraph,
+    DependencyKind, DependencyResolveError, DependencyResolver, Library, LibraryBinary, Package,
+    PackageMeta, PackageName, PackageVersion, PrimitiveTarget, Target, TargetArch, TargetEnv,
+    TargetOs, TargetResolveError, TargetResolver, TypeDeclaration, MANGLING_VERSION,
 };
 use std::borrow::Cow;
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::error::Error;
 use std::ffi::{c_char, CStr, CString};
 use std::fmt::{Display, Formatter};
 use std::fs::{create_dir_all, File};
-use std::io::Write;
+use std::hash::Hasher;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::ptr::null;
+use std::sync::Arc;
 use thiserror::Error;
 
+mod depfile;
+mod fingerprint;
+mod ignore;
 mod meta;
+mod size;
 
 /// A Nitro project.
 pub struct Project<'a> {
     path: PathBuf,
     meta: ProjectMeta,
-    exe: HashMap<String, SourceFile>,
-    lib: HashMap<String, SourceFile>,
+    exe: Sources,
+    lib: Sources,
     targets: &'a TargetResolver,
     stubs: &'a Path,
     deps: &'a DependencyResolver,
+    strip: bool,
+    emit_deps: bool,
+    deps_file: Option<PathBuf>,
+    out_dir: Option<PathBuf>,
+    defines: HashMap<String, String>,
+    max_depth: usize,
+    allow_no_entry: bool,
+    size_report: bool,
+    strict: bool,
+    machines: MachineCache,
 }
 
 impl<'a> Project<'a> {
@@ -57,14 +81,103 @@ impl<'a> Project<'a> {
             return Err(ProjectOpenError::MissingBinary(project));
         }
 
+        if let Some(v) = meta.executable().and_then(|b| b.entry_symbol()) {
+            let mut chars = v.chars();
+            let valid = chars
+                .next()
+                .is_some_and(|c| c == '_' || c.is_ascii_alphabetic())
+                && chars.all(|c| c == '_' || c.is_ascii_alphanumeric());
+
+            if !valid {
+                return Err(ProjectOpenError::InvalidEntrySymbol(project, v.to_owned()));
+            }
+        }
+
+        match meta.executable().and_then(|b| b.entry()) {
+            Some(EntryPoint::Function(v)) => {
+                let valid = v.split('.').count() >= 2
+                    && v.split('.').all(|c| {
+                        let mut chars = c.chars();
+
+                        chars
+                            .next()
+                            .is_some_and(|c| c == '_' || c.is_ascii_alphabetic())
+                            && chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+                    });
+
+                if !valid {
+                    return Err(ProjectOpenError::InvalidEntry(project, v.clone()));
+                }
+            }
+            Some(EntryPoint::None) if meta.executable().unwrap().entry_symbol().is_some() => {
+                return Err(ProjectOpenError::EntrySymbolWithoutEntry(project));
+            }
+            Some(EntryPoint::None) | None => {}
+        }
+
+        if let Some(v) = meta.namespace() {
+            let valid = v.split('.').all(|c| {
+                let mut chars = c.chars();
+
+                chars
+                    .next()
+                    .is_some_and(|c| c == '_' || c.is_ascii_alphabetic())
+                    && chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+            });
+
+            if !valid {
+                return Err(ProjectOpenError::InvalidNamespace(project, v.to_owned()));
+            }
+        }
+
+        if let Some(k) = meta.defines().keys().find(|k| !is_define_key(k)) {
+            return Err(ProjectOpenError::InvalidDefine(project, k.clone()));
+        }
+
+        // Check dependencies. The standard library is always a dependency of the other packages.
+        let pkg = meta.package().name();
+        let mut names = HashSet::new();
+
+        for dep in meta.dependencies() {
+            let name = dep.name();
+
+            if name == pkg {
+                return Err(ProjectOpenError::SelfDependency(project, name.clone()));
+            } else if name == "nitro" {
+                return Err(ProjectOpenError::ImplicitDependency(project, name.clone()));
+            } else if !names.insert(name) {
+                return Err(ProjectOpenError::DuplicatedDependency(
+                    project,
+                    name.clone(),
+                ));
+            }
+        }
+
+        let out_dir = meta.out_dir().map(|v| path.join(v));
+        let defines = meta
+            .defines()
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+
         Ok(Self {
             path,
             meta,
-            exe: HashMap::new(),
-            lib: HashMap::new(),
+            exe: Sources::default(),
+            lib: Sources::default(),
             targets,
             stubs,
             deps,
+            strip: true,
+            emit_deps: false,
+            deps_file: None,
+            out_dir,
+            defines,
+            max_depth: Lexer::DEFAULT_MAX_DEPTH,
+            allow_no_entry: false,
+            size_report: false,
+            strict: false,
+            machines: MachineCache::new(),
         })
     }
 
@@ -72,27 +185,104 @@ impl<'a> Project<'a> {
         &self.path
     }
 
+    /// Returns the `.build` directory in each sources directory, which may not exist. The custom
+    /// output directory is not included since it may be shared with the other projects.
+    pub fn build_dirs(&self) -> Vec<PathBuf> {
+        let exe = self.meta.executable().into_iter();
+        let lib = self.meta.library().into_iter();
+
+        exe.chain(lib)
+            .map(|b| self.path.join(b.sources()).join(".build"))
+            .collect()
+    }
+
+    /// Returns the entry point from `Nitro.yml` or [`None`] to use the function with `@entry`.
+    fn entry(&self) -> Option<&EntryPoint> {
+        self.meta.executable().and_then(|b| b.entry())
+    }
+
+    /// Returns `true` if the executable has the generated entry point. A test harness always has
+    /// one.
+    fn has_main(&self, test: bool) -> bool {
+        test || !matches!(self.entry(), Some(EntryPoint::None))
+    }
+
+    /// Returns the name of the symbol the executable start at.
+    fn entry_symbol(&self) -> &str {
+        self.meta
+            .executable()
+            .and_then(|b| b.entry_symbol())
+            .unwrap_or(Codegen::ENTRY_SYMBOL)
+    }
+
+    /// Returns all loaded source files in the project.
+    pub fn sources(&self) -> impl Iterator<Item = &SourceFile> {
+        self.lib.iter().chain(self.exe.iter())
+    }
+
+    /// Set to `false` to keep symbols in the executables.
+    pub fn set_strip(&mut self, v: bool) {
+        self.strip = v;
+    }
+
+    /// Set to `true` to write a Makefile-style dependency file next to each object file.
+    pub fn set_emit_deps(&mut self, v: bool) {
+        self.emit_deps = v;
+    }
+
+    /// Set the path to write a Makefile-style dependency file for the whole build. The outputs are
+    /// the objects and the linked binaries of all targets and the prerequisites are all files that
+    /// was read by the build.
+    pub fn set_deps_file(&mut self, v: PathBuf) {
+        self.deps_file = Some(v);
+    }
+
+    /// Set the directory to place the build artifacts instead of `.build` in the sources directory.
+    /// This override `out-dir` in `Nitro.yml`.
+    pub fn set_out_dir(&mut self, v: PathBuf) {
+        self.out_dir = Some(v);
+    }
+
+    /// Set the value of a key that can be tested with `@if`. This override `defines` in
+    /// `Nitro.yml`. `key` must be valid according to [`is_define_key()`].
+    pub fn set_define(&mut self, key: String, value: String) {
+        assert!(is_define_key(&key));
+        self.defines.insert(key, value);
+    }
+
+    /// Set to `true` to build the executable that exit immediately when there is no `@entry`
+    /// function.
+    pub fn set_allow_no_entry(&mut self, v: bool) {
+        self.allow_no_entry = v;
+    }
+
+    /// Set to `true` to write `size-report.yml` with the size of each function to the workspace
+    /// of each target.
+    pub fn set_size_report(&mut self, v: bool) {
+        self.size_report = v;
+    }
+
+    /// Set to `true` to fail the build on the first warning instead of printing it.
+    pub fn set_strict(&mut self, v: bool) {
+        self.strict = v;
+    }
+
+    /// Set the maximum nesting level of the source files. This must be called before [`Self::load()`].
+    pub fn set_max_depth(&mut self, v: usize) {
+        self.max_depth = v;
+    }
+
     pub fn load(&mut self) -> Result<(), ProjectLoadError> {
         // Load executable sources.
-        if let Some(bin) = self.meta.executable() {
-            let root = bin.sources();
+        let ns = self.meta.namespace();
 
-            self.exe = if root.is_absolute() {
-                Self::load_sources(root)?
-            } else {
-                Self::load_sources(self.path.join(root))?
-            };
+        if let Some(bin) = self.meta.executable() {
+            self.exe = Self::load_sources(self.path.join(bin.sources()), bin, ns, self.max_depth)?;
         }
 
         // Load library sources.
         if let Some(bin) = self.meta.library() {
-            let root = bin.sources();
-
-            self.lib = if root.is_absolute() {
-                Self::load_sources(root)?
-            } else {
-                Self::load_sources(self.path.join(root))?
-            };
+            self.lib = Self::load_sources(self.path.join(bin.sources()), bin, ns, self.max_depth)?;
         }
 
         Ok(())
@@ -100,32 +290,27 @@ impl<'a> Project<'a> {
 
     pub fn build(&self) -> Result<Package, ProjectBuildError> {
         let pkg = self.meta.package();
-        let meta = PackageMeta::new(pkg.name().clone(), pkg.version().clone());
-        let mut exes = HashMap::new();
-        let mut libs = HashMap::new();
-
-        // Resolve dependencies.
-        let mut deps = Vec::new();
+        let mut out = Package::builder(PackageMeta::new(pkg.name().clone(), pkg.version().clone()));
 
-        if pkg.name() != "nitro" {
-            let version = env!("CARGO_PKG_VERSION").parse().unwrap();
-            let id = Dependency::new("nitro".parse().unwrap(), version);
+        // Report the warnings before the dependencies so it will be shown even if it is failed.
+        self.lint()?;
 
-            match self.deps.resolve(&id, self.targets) {
-                Ok(v) => deps.push(v),
-                Err(e) => return Err(ProjectBuildError::ResolveDependencyFailed(id, e)),
-            };
-        }
+        // Resolve dependencies.
+        let deps = self.resolve_deps()?;
+        let mut df = DepFile::default();
 
         // Build library.
         if !self.lib.is_empty() {
             let root = self.meta.library().unwrap().sources();
 
-            for target in PrimitiveTarget::ALL.iter().map(|t| Target::Primitive(t)) {
+            for pt in &PrimitiveTarget::ALL {
+                let target = Target::Primitive(pt);
+                let sources = self.lib.select(pt)?;
+
                 // Populate type resolver with internal types.
                 let mut resolver = TypeResolver::new();
 
-                resolver.populate_internal_types(&self.lib);
+                resolver.populate_internal_types(sources.iter().copied());
 
                 // Populate types from dependencies.
                 for dep in &deps {
@@ -135,18 +320,26 @@ impl<'a> Project<'a> {
                 }
 
                 // Build.
-                let br = self.build_for(root, false, &target, &self.lib, &resolver)?;
-                let out = self.link_lib(&br)?;
+                let br = self.build_for(root, false, false, &target, sources, &resolver)?;
+                let lib = self.link_lib(&br)?;
 
-                assert!(libs
-                    .insert(
-                        target,
-                        Binary::new(
-                            Library::new(LibraryBinary::Bundle(out), br.exports),
-                            HashSet::new()
-                        )
-                    )
-                    .is_none());
+                if self.deps_file.is_some() {
+                    self.collect_deps_file(&mut df, &br.object, &lib, &target, &deps)?;
+                }
+
+                let hash = Self::hash_binary(&lib)?;
+                let mut bin = Library::builder(LibraryBinary::Bundle(lib));
+
+                self.write_fingerprint(&br.workspace, pt, &deps)?;
+
+                let deps = self.binary_deps(&target, &deps)?;
+
+                for ty in br.exports {
+                    bin.add_type(ty).unwrap();
+                }
+
+                out.add_library(target, bin.build(), deps, Some(hash))
+                    .unwrap();
             }
         }
 
@@ -154,16 +347,19 @@ impl<'a> Project<'a> {
         if !self.exe.is_empty() {
             let root = self.meta.executable().unwrap().sources();
 
-            for target in PrimitiveTarget::ALL.iter().map(|t| Target::Primitive(t)) {
+            for pt in &PrimitiveTarget::ALL {
+                let target = Target::Primitive(pt);
+                let sources = self.exe.select(pt)?;
+
                 // Populate type resolver with internal types.
                 let mut resolver = TypeResolver::new();
 
-                resolver.populate_internal_types(&self.exe);
+                resolver.populate_internal_types(sources.iter().copied());
 
                 // Populate types from package library.
-                if !libs.is_empty() {
-                    if let Some(lib) = self.resolve_lib(target.clone(), &libs)? {
-                        resolver.populate_external_types(&meta, lib.bin().types());
+                if !out.libs().is_empty() {
+                    if let Some(lib) = self.resolve_lib(target.clone(), out.libs())? {
+                        resolver.populate_external_types(out.meta(), lib.bin().types());
                     }
                 }
 
@@ -175,50 +371,606 @@ impl<'a> Project<'a> {
                 }
 
                 // Build.
-                let br = self.build_for(root, true, &target, &self.exe, &resolver)?;
-                let out = self.link_exe(br)?;
+                let br = self.build_for(root, true, false, &target, sources, &resolver)?;
+                let ws = br.workspace.clone();
+                let obj = br.object.clone();
+                let bin = self.link_exe(br)?;
 
-                assert!(exes
-                    .insert(target, Binary::new(out, HashSet::new()))
-                    .is_none());
+                if self.deps_file.is_some() {
+                    self.collect_deps_file(&mut df, &obj, &bin, &target, &deps)?;
+                }
+
+                self.write_fingerprint(&ws, pt, &deps)?;
+
+                let deps = self.binary_deps(&target, &deps)?;
+
+                let hash = Self::hash_binary(&bin)?;
+
+                out.add_executable(target, bin, deps, Some(hash)).unwrap();
             }
         }
 
-        Ok(Package::new(meta, exes, libs))
+        // Collect assets.
+        for (name, file) in self.collect_assets()? {
+            out.add_asset(name, file).unwrap();
+        }
+
+        // Write dependency file.
+        if let Some(path) = &self.deps_file {
+            df.add_input(self.path.join("Nitro.yml"));
+
+            for src in self.sources() {
+                df.add_input(src.path());
+            }
+
+            if let Err(e) = df.write(path) {
+                return Err(ProjectBuildError::WriteDepsFailed(path.clone(), e));
+            }
+        }
+
+        let mut pkg = out.build().unwrap();
+
+        if let Some(v) = self.meta.package().readme() {
+            pkg.set_readme(self.path.join(v));
+        }
+
+        Ok(pkg)
     }
 
-    fn load_sources<'b, R>(root: R) -> Result<HashMap<String, SourceFile>, ProjectLoadError>
-    where
-        R: AsRef<Path> + 'b,
-    {
+    /// Build a test harness for each binary that has at least one source file. The harness will
+    /// run all `@test` functions in the binary when it is executed.
+    pub fn build_tests(&self, target: &Target) -> Result<Vec<TestBinary>, ProjectBuildError> {
+        let pkg = self.meta.package();
+        let meta = PackageMeta::new(pkg.name().clone(), pkg.version().clone());
+        let deps = self.resolve_deps()?;
+        let mut bins = Vec::new();
+        let mut lib = None;
+
+        // Get primitive target.
+        let pt = match self.targets.primitive(target) {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(ProjectBuildError::ResolvePrimitiveTargetFailed(
+                    target.clone(),
+                    e,
+                ));
+            }
+        };
+
+        // Build library.
+        if !self.lib.is_empty() {
+            let root = self.meta.library().unwrap().sources();
+
+            // Populate type resolver.
+            let sources = self.lib.select(pt)?;
+            let mut resolver = TypeResolver::new();
+
+            resolver.populate_internal_types(sources.iter().copied());
+
+            for dep in &deps {
+                if let Some(lib) = self.resolve_lib(target.clone(), dep.libs())? {
+                    resolver.populate_external_types(dep.meta(), lib.bin().types());
+                }
+            }
+
+            // Build the library itself so the executable harness can use its types.
+            let br = self.build_for(root, false, false, target, sources.clone(), &resolver)?;
+
+            self.link_lib(&br)?;
+            lib = Some(br.exports);
+
+            // Build the harness.
+            let br = self.build_for(root, true, true, target, sources, &resolver)?;
+            let tests = br.tests.clone();
+            let path = self.link_exe(br)?;
+
+            bins.push(TestBinary { path, tests });
+        }
+
+        // Build executable.
+        if !self.exe.is_empty() {
+            let root = self.meta.executable().unwrap().sources();
+
+            // Populate type resolver.
+            let sources = self.exe.select(pt)?;
+            let mut resolver = TypeResolver::new();
+
+            resolver.populate_internal_types(sources.iter().copied());
+
+            if let Some(types) = &lib {
+                resolver.populate_external_types(&meta, types);
+            }
+
+            for dep in &deps {
+                if let Some(lib) = self.resolve_lib(target.clone(), dep.libs())? {
+                    resolver.populate_external_types(dep.meta(), lib.bin().types());
+                }
+            }
+
+            // Build the harness.
+            let br = self.build_for(root, true, true, target, sources, &resolver)?;
+            let tests = br.tests.clone();
+            let path = self.link_exe(br)?;
+
+            bins.push(TestBinary { path, tests });
+        }
+
+        Ok(bins)
+    }
+
+    fn collect_assets(&self) -> Result<BTreeMap<String, PathBuf>, ProjectBuildError> {
+        let mut assets = BTreeMap::new();
+        let mut items = VecDeque::new();
+
+        for path in self.meta.assets() {
+            if !Self::is_valid_asset_path(path) {
+                return Err(ProjectBuildError::InvalidAssetPath(path.clone()));
+            }
+
+            items.push_back(self.path.join(path));
+        }
+
+        while let Some(path) = items.pop_front() {
+            // Get metadata without following a link.
+            let meta = match std::fs::symlink_metadata(&path) {
+                Ok(v) => v,
+                Err(e) => return Err(ProjectBuildError::GetAssetMetadataFailed(path, e)),
+            };
+
+            // A link to a directory may point to one of its parents so only a link to a file inside
+            // the project is followed.
+            if meta.is_symlink() {
+                let target = match std::fs::canonicalize(&path) {
+                    Ok(v) => v,
+                    Err(e) => return Err(ProjectBuildError::GetAssetMetadataFailed(path, e)),
+                };
+
+                if target.is_dir() {
+                    return Err(ProjectBuildError::AssetDirectoryLink(path));
+                }
+
+                match std::fs::canonicalize(&self.path) {
+                    Ok(v) if target.starts_with(&v) => {}
+                    Ok(_) => return Err(ProjectBuildError::AssetOutsideProject(path, target)),
+                    Err(e) => return Err(ProjectBuildError::GetAssetMetadataFailed(path, e)),
+                }
+            }
+
+            // Enumerate the directory.
+            if meta.is_dir() {
+                let list = match std::fs::read_dir(&path) {
+                    Ok(v) => v,
+                    Err(e) => return Err(ProjectBuildError::EnumerateAssetsFailed(path, e)),
+                };
+
+                for item in list {
+                    match item {
+                        Ok(v) => items.push_back(v.path()),
+                        Err(e) => return Err(ProjectBuildError::EnumerateAssetsFailed(path, e)),
+                    }
+                }
+
+                continue;
+            }
+
+            // Build asset name.
+            let mut name = String::new();
+
+            for c in path.strip_prefix(&self.path).unwrap().components() {
+                let c = match c {
+                    std::path::Component::Normal(v) => match v.to_str() {
+                        Some(v) => v,
+                        None => return Err(ProjectBuildError::InvalidAssetPath(path)),
+                    },
+                    _ => continue,
+                };
+
+                if !name.is_empty() {
+                    name.push('/');
+                }
+
+                name.push_str(c);
+            }
+
+            if !Package::is_valid_asset(&name) {
+                return Err(ProjectBuildError::InvalidAssetPath(path));
+            }
+
+            assets.insert(name, path);
+        }
+
+        Ok(assets)
+    }
+
+    /// Returns `true` if `path` from the project file is relative to the project and does not
+    /// contains any `..` component.
+    fn is_valid_asset_path(path: &Path) -> bool {
+        path.components().all(|c| {
+            matches!(
+                c,
+                std::path::Component::Normal(_) | std::path::Component::CurDir
+            )
+        })
+    }
+
+    /// Returns the hash to be recorded in the package for the binary at `path` so the exported
+    /// binary can be verified.
+    fn hash_binary(path: &Path) -> Result<u64, ProjectBuildError> {
+        let mut h = Fnv64::new();
+
+        match hash_file(path, &mut h) {
+            Ok(_) => Ok(h.finish()),
+            Err(e) => Err(ProjectBuildError::HashFileFailed(path.to_owned(), e)),
+        }
+    }
+
+    /// Returns the inputs of the build for `target`. See [`Fingerprint`] for more details.
+    pub fn fingerprint(
+        &self,
+        target: &'static PrimitiveTarget,
+    ) -> Result<Fingerprint, ProjectBuildError> {
+        let deps = self.resolve_deps()?;
+
+        self.fingerprint_with(target, &deps)
+    }
+
+    fn fingerprint_with(
+        &self,
+        target: &'static PrimitiveTarget,
+        deps: &[Arc<Package>],
+    ) -> Result<Fingerprint, ProjectBuildError> {
+        let hash = |path: &Path, dir: bool| {
+            let mut h = Fnv64::new();
+            let res = if dir {
+                hash_dir(path, &mut h)
+            } else {
+                hash_file(path, &mut h)
+            };
+
+            match res {
+                Ok(_) => Ok(format!("{:016x}", h.finish())),
+                Err(e) => Err(ProjectBuildError::HashFileFailed(path.to_owned(), e)),
+            }
+        };
+
+        // Get LLVM version.
+        let mut llvm = String::new();
+
+        unsafe { llvm_version(&mut llvm) };
+
+        // Hash the sources.
+        let mut sources = BTreeMap::new();
+
+        for (_, src) in self
+            .lib
+            .select(target)?
+            .into_iter()
+            .chain(self.exe.select(target)?)
+        {
+            let path = src.path();
+            let name = path.strip_prefix(&self.path).unwrap_or(path);
+
+            sources.insert(name.to_string_lossy().into_owned(), hash(path, false)?);
+        }
+
+        // Hash the dependencies.
+        let mut dependencies = BTreeMap::new();
+
+        for dep in deps {
+            let meta = dep.meta();
+            let lib = self.resolve_lib(Target::Primitive(target), dep.libs())?;
+            let hash = match lib.map(|l| l.bin().bin()) {
+                Some(LibraryBinary::Bundle(p)) => Some(hash(p, false)?),
+                _ => None,
+            };
+
+            dependencies.insert(
+                meta.name().to_string(),
+                DependencyFingerprint::new(meta.version().to_string(), hash),
+            );
+        }
+
+        // Collect the options that affect the outputs.
+        let mut options = BTreeMap::from([
+            ("strip".to_owned(), self.strip.to_string()),
+            ("allow-no-entry".to_owned(), self.allow_no_entry.to_string()),
+            ("entry-symbol".to_owned(), self.entry_symbol().to_owned()),
+            (
+                "entry".to_owned(),
+                match self.entry() {
+                    Some(EntryPoint::Function(v)) => v.clone(),
+                    Some(EntryPoint::None) => "none".to_owned(),
+                    None => String::new(),
+                },
+            ),
+        ]);
+
+        for (k, v) in &self.defines {
+            options.insert(format!("define.{k}"), v.clone());
+        }
+
+        Ok(Fingerprint {
+            compiler: env!("CARGO_PKG_VERSION").to_owned(),
+            llvm,
+            target: target.to_string(),
+            target_id: target.id().to_string(),
+            stubs: hash(&self.stubs.join(stubs_subdir(target)), true)?,
+            sources,
+            dependencies,
+            options,
+        })
+    }
+
+    /// Write `fingerprint.yml` to `ws`.
+    fn write_fingerprint(
+        &self,
+        ws: &Path,
+        target: &'static PrimitiveTarget,
+        deps: &[Arc<Package>],
+    ) -> Result<(), ProjectBuildError> {
+        let fp = self.fingerprint_with(target, deps)?;
+        let path = ws.join("fingerprint.yml");
+
+        if let Err(e) = std::fs::write(&path, serde_yaml::to_string(&fp).unwrap()) {
+            return Err(ProjectBuildError::WriteFingerprintFailed(path, e));
+        }
+
+        Ok(())
+    }
+
+    /// Resolve the dependencies of this project and all of its dependencies without building.
+    pub fn dependency_graph(&self) -> Result<DependencyGraph, ProjectBuildError> {
+        let id = |m: &PackageMeta| Dependency::new(m.name().clone(), m.version().clone());
+        let pkg = self.meta.package();
+        let root = Dependency::new(pkg.name().clone(), pkg.version().clone());
+        let mut graph = DependencyGraph::new(root.clone());
+        let mut queue = VecDeque::new();
+
+        for dep in self.resolve_deps()? {
+            graph.add(root.clone(), id(dep.meta()));
+            queue.push_back(dep);
+        }
+
+        // Only a library can be depended on so the dependencies of the executables are not a
+        // part of the graph.
+        let mut visited = HashSet::from([root]);
+
+        while let Some(pkg) = queue.pop_front() {
+            let from = id(pkg.meta());
+
+            if !visited.insert(from.clone()) {
+                continue;
+            }
+
+            let deps: BTreeSet<&Dependency> = pkg
+                .libs()
+                .values()
+                .flat_map(|l| l.deps())
+                .map(|d| d.dep())
+                .collect();
+
+            for dep in deps {
+                graph.add(from.clone(), dep.clone());
+
+                if visited.contains(dep) {
+                    continue;
+                }
+
+                match self.deps.resolve(dep, self.targets) {
+                    Ok(v) => queue.push_back(v),
+                    Err(e) => {
+                        return Err(ProjectBuildError::ResolveDependencyFailed(dep.clone(), e))
+                    }
+                }
+            }
+        }
+
+        Ok(graph)
+    }
+
+    /// Generate a C header for the `@ext(C)` functions in the library of `pkg` (the output of
+    /// [`Project::build()`]) for `target`. Returns [`None`] if `pkg` does not have a library.
+    pub fn c_header(
+        &self,
+        pkg: &Package,
+        target: &Target,
+    ) -> Result<Option<String>, ProjectBuildError> {
+        let lib = match self.resolve_lib(target.clone(), pkg.libs())? {
+            Some(v) => v.bin(),
+            None => return Ok(None),
+        };
+
+        // Collect the libraries to look up the representation of the structs.
+        let deps = self.resolve_deps()?;
+        let mut libs = vec![(None, lib)];
+
+        for dep in &deps {
+            if let Some(v) = self.resolve_lib(target.clone(), dep.libs())? {
+                libs.push((Some(dep.meta().name().as_str()), v.bin()));
+            }
+        }
+
+        let repr = |owner: Option<&str>, name: &str| {
+            libs.iter()
+                .flat_map(|(p, l)| l.types().iter().map(move |t| (*p, t)))
+                .find_map(|(p, t)| match t {
+                    // A re-exported type is still belong to its original package.
+                    TypeDeclaration::Basic(t)
+                        if t.origin().map(|v| v.0).or(p) == owner
+                            && t.name() == name
+                            && !t.is_class() =>
+                    {
+                        t.attrs().repr()
+                    }
+                    _ => None,
+                })
+        };
+
+        c_header(pkg.meta().name(), lib, repr)
+            .map(Some)
+            .map_err(ProjectBuildError::GenerateHeaderFailed)
+    }
+
+    fn resolve_deps(&self) -> Result<Vec<Arc<Package>>, ProjectBuildError> {
+        let pkg = self.meta.package();
+        let mut deps = Vec::new();
+
+        if pkg.name() != "nitro" {
+            let version = env!("CARGO_PKG_VERSION").parse().unwrap();
+            let id = Dependency::new("nitro".parse().unwrap(), version);
+
+            match self.deps.resolve(&id, self.targets) {
+                Ok(v) => deps.push(v),
+                Err(e) => return Err(ProjectBuildError::ResolveDependencyFailed(id, e)),
+            };
+        }
+
+        // Resolve the dependencies from Nitro.yml. Their types are populated and they are recorded
+        // in the binaries the same as the standard library.
+        for dep in self.meta.dependencies() {
+            match self.deps.resolve(dep, self.targets) {
+                Ok(v) => deps.push(v),
+                Err(e) => return Err(ProjectBuildError::ResolveDependencyFailed(dep.clone(), e)),
+            }
+        }
+
+        // The symbols of a dependency that was built with a different mangling scheme will not
+        // resolve at link time.
+        for dep in &deps {
+            let meta = dep.meta();
+
+            if let Some(v) = meta.mangling().filter(|&v| v != MANGLING_VERSION) {
+                eprintln!(
+                    "Warning: {} {} was built with mangling scheme {} but this compiler use {}.",
+                    meta.name(),
+                    meta.version(),
+                    v,
+                    MANGLING_VERSION
+                );
+            }
+        }
+
+        Ok(deps)
+    }
+
+    /// Returns the dependencies of a binary for `target`. A dependency without any function cannot
+    /// be referenced at run time so it is recorded as types-only.
+    fn binary_deps(
+        &self,
+        target: &Target,
+        deps: &[Arc<Package>],
+    ) -> Result<HashSet<BinaryDependency>, ProjectBuildError> {
+        let mut set = HashSet::with_capacity(deps.len());
+
+        for dep in deps {
+            let lib = match self.resolve_lib(target.clone(), dep.libs())? {
+                Some(v) => v,
+                None => continue,
+            };
+
+            let funcs = lib.bin().types().iter().any(|t| match t {
+                TypeDeclaration::Basic(v) => v.funcs().next().is_some(),
+            });
+
+            let meta = dep.meta();
+            let id = Dependency::new(meta.name().clone(), meta.version().clone());
+            let kind = if funcs {
+                DependencyKind::Runtime
+            } else {
+                DependencyKind::TypesOnly
+            };
+
+            set.insert(BinaryDependency::new(id, kind));
+        }
+
+        Ok(set)
+    }
+
+    fn load_sources(
+        root: PathBuf,
+        bin: &ProjectBinary,
+        ns: Option<&str>,
+        max_depth: usize,
+    ) -> Result<Sources, ProjectLoadError> {
+        // Check if the root is a directory.
+        let root = root.as_path();
+
+        match std::fs::metadata(root) {
+            Ok(v) if !v.is_dir() => {
+                return Err(ProjectLoadError::SourceRootNotDirectory(root.to_owned()))
+            }
+            Ok(_) => {}
+            Err(e) => return Err(ProjectLoadError::GetMetadataFailed(root.to_owned(), e)),
+        }
+
+        // Load ignore list.
+        let mut ignore = IgnoreList::default();
+        let path = root.join(".nitroignore");
+
+        for line in bin.ignore() {
+            ignore.add(line);
+        }
+
+        match std::fs::read_to_string(&path) {
+            Ok(v) => v.lines().for_each(|l| ignore.add(l)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(ProjectLoadError::ReadIgnoreFailed(path, e)),
+        }
+
         // Enumerate source files.
-        let root = root.as_ref();
-        let mut sources = HashMap::new();
+        let mut sources = Sources::default();
         let mut dirs = VecDeque::from([Cow::Borrowed(root)]);
+        let mut names = HashMap::new();
+        let mut visited = HashSet::from([Self::real_path(root)?]);
 
         while let Some(dir) = dirs.pop_front() {
-            // Enumerate items.
+            // Enumerate items. The items are sorted so the path that is used for a file that is
+            // reachable from multiple paths does not depend on the order of the file system.
             let items = match std::fs::read_dir(&dir) {
                 Ok(v) => v,
                 Err(e) => return Err(ProjectLoadError::EnumerateFilesFailed(dir.into_owned(), e)),
             };
 
+            let mut paths = Vec::new();
+
             for item in items {
-                // Unwrap the item.
-                let item = match item {
-                    Ok(v) => v,
+                match item {
+                    Ok(v) => paths.push(v.path()),
                     Err(e) => return Err(ProjectLoadError::AccessFileFailed(dir.into_owned(), e)),
-                };
+                }
+            }
 
+            paths.sort_unstable();
+
+            for path in paths {
                 // Get metadata.
-                let path = item.path();
                 let meta = match std::fs::metadata(&path) {
                     Ok(v) => v,
                     Err(e) => return Err(ProjectLoadError::GetMetadataFailed(path, e)),
                 };
 
+                // Check if ignored.
+                let rel = path
+                    .strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/");
+
+                if ignore.is_ignored(&rel, meta.is_dir()) {
+                    continue;
+                }
+
+                // Skip the entry that was already loaded via a symlink. This also prevent a symlink
+                // loop.
+                if (meta.is_dir() || path.extension().is_some_and(|v| v == "nt"))
+                    && !visited.insert(Self::real_path(&path)?)
+                {
+                    continue;
+                }
+
                 // Check if directory.
                 if meta.is_dir() {
+                    Self::check_case(&mut names, &rel, &path, CASE_INSENSITIVE_FS)?;
                     dirs.push_back(Cow::Owned(path));
                     continue;
                 }
@@ -231,7 +983,8 @@ impl<'a> Project<'a> {
 
                 // Check file type.
                 if ext == "nt" {
-                    Self::load_source(root, path, &mut sources)?;
+                    Self::check_case(&mut names, &rel, &path, CASE_INSENSITIVE_FS)?;
+                    Self::load_source(root, path, ns, max_depth, &mut sources)?;
                 }
             }
         }
@@ -239,23 +992,66 @@ impl<'a> Project<'a> {
         Ok(sources)
     }
 
+    fn real_path(path: &Path) -> Result<PathBuf, ProjectLoadError> {
+        std::fs::canonicalize(path)
+            .map_err(|e| ProjectLoadError::ResolvePathFailed(path.to_owned(), e))
+    }
+
+    /// Check if `path` has the same name as the other entry in `names` when the case is ignored.
+    /// `rel` is `path` relative to the source root.
+    ///
+    /// Both entries cannot exist on a case-insensitive file system so the project will silently
+    /// lose one of them when it is checked out there. The collision is an error if `insensitive` is
+    /// `true`, otherwise it is a warning.
+    fn check_case(
+        names: &mut HashMap<String, PathBuf>,
+        rel: &str,
+        path: &Path,
+        insensitive: bool,
+    ) -> Result<(), ProjectLoadError> {
+        let other = match names.insert(rel.to_lowercase(), path.to_owned()) {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+
+        let (a, b) = if other.as_path() < path {
+            (other, path.to_owned())
+        } else {
+            (path.to_owned(), other)
+        };
+
+        if insensitive {
+            return Err(ProjectLoadError::CaseCollision(a, b));
+        }
+
+        eprintln!(
+            "Warning: {} and {} differ only by case so one of them will be lost on a case-insensitive file system.",
+            a.display(),
+            b.display()
+        );
+
+        Ok(())
+    }
+
     fn load_source<R>(
         root: R,
         path: PathBuf,
-        set: &mut HashMap<String, SourceFile>,
+        ns: Option<&str>,
+        max_depth: usize,
+        set: &mut Sources,
     ) -> Result<(), ProjectLoadError>
     where
         R: AsRef<Path>,
     {
         // Parse the source.
-        let source = match SourceFile::parse(path.as_path()) {
+        let source = match SourceFile::parse(path.as_path(), max_depth) {
             Ok(v) => v,
             Err(e) => return Err(ProjectLoadError::ParseSourceFailed(path, e)),
         };
 
         // Get fully qualified type name.
-        if source.has_type() {
-            let mut fqtn = String::new();
+        if source.has_type() || source.has_reexports() {
+            let mut fqtn = ns.map(String::from).unwrap_or_default();
 
             for c in path.strip_prefix(root).unwrap().components() {
                 let name = match c {
@@ -278,7 +1074,28 @@ impl<'a> Project<'a> {
             fqtn.pop();
             fqtn.pop();
 
-            assert!(set.insert(fqtn, source).is_none());
+            if fqtn.len() > MAX_TYPE_NAME {
+                return Err(ProjectLoadError::TypeNameTooLong(path));
+            }
+
+            // Check if the file is for a specific platform (e.g. Foo.linux.nt).
+            let filter = path
+                .file_stem()
+                .and_then(|v| v.to_str())
+                .and_then(|v| v.rsplit_once('.'))
+                .and_then(|(_, v)| PlatformFilter::parse(v));
+
+            match filter {
+                Some(filter) => {
+                    fqtn.truncate(fqtn.rfind('.').unwrap());
+                    set.platform.push(PlatformSource {
+                        fqtn,
+                        filter,
+                        source,
+                    });
+                }
+                None => assert!(set.common.insert(fqtn, source).is_none()),
+            }
         }
 
         Ok(())
@@ -286,21 +1103,12 @@ impl<'a> Project<'a> {
 
     fn resolve_lib<'b>(
         &self,
-        mut target: Target,
+        target: Target,
         libs: &'b HashMap<Target, Binary<Library>>,
     ) -> Result<Option<&'b Binary<Library>>, ProjectBuildError> {
-        loop {
-            if let Some(v) = libs.get(&target) {
-                break Ok(Some(v));
-            }
-
-            target = match self.targets.parent(&target) {
-                Ok(v) => match v {
-                    Some(v) => v,
-                    None => break Ok(None),
-                },
-                Err(e) => break Err(ProjectBuildError::ResolveParentTargetFailed(target, e)),
-            };
+        match self.targets.find_nearest(&target, libs) {
+            Ok(v) => Ok(v.map(|v| v.1)),
+            Err(e) => Err(ProjectBuildError::ResolveParentTargetFailed(target, e)),
         }
     }
 
@@ -308,6 +1116,7 @@ impl<'a> Project<'a> {
         &self,
         root: R,
         exe: bool,
+        test: bool,
         target: &Target,
         sources: S,
         resolver: &TypeResolver<'b>,
@@ -316,8 +1125,16 @@ impl<'a> Project<'a> {
         R: AsRef<Path>,
         S: IntoIterator<Item = (&'b String, &'b SourceFile)>,
     {
-        // Create workspace directory.
-        let mut ws = root.as_ref().join(".build");
+        // Create workspace directory. The sources directory is kept in the custom output directory
+        // so the library and the executable does not share the same workspace.
+        let mut ws = match &self.out_dir {
+            Some(v) => v.join(root.as_ref()),
+            None => root.as_ref().join(".build"),
+        };
+
+        if test {
+            ws.push("test");
+        }
 
         ws.push(target.to_string());
 
@@ -337,42 +1154,112 @@ impl<'a> Project<'a> {
         };
 
         // Compile.
+        let sources: Vec<_> = sources.into_iter().collect();
         let obj = ws.join(format!("{}.o", self.meta.package().name()));
-        let types = self.compile(exe, pt, sources, &obj, resolver)?;
+        let (types, tests) =
+            self.compile(exe, test, pt, sources.iter().copied(), &obj, resolver)?;
+
+        // Write dependency file. An executable also depends on the library sources since it can
+        // use the types from the library.
+        if self.emit_deps {
+            let mut deps: Vec<&Path> = sources.iter().map(|(_, s)| s.path()).collect();
+
+            if self
+                .meta
+                .executable()
+                .is_some_and(|b| root.as_ref() == b.sources())
+            {
+                deps.extend(self.lib.iter().map(|s| s.path()));
+            }
+
+            self.write_deps(&obj, deps)?;
+        }
+
+        // Write size report.
+        if self.size_report {
+            let report = match SizeReport::from_object(&obj) {
+                Ok(v) => v,
+                Err(e) => return Err(ProjectBuildError::ReadSymbolsFailed(obj, e)),
+            };
+
+            let path = ws.join("size-report.yml");
+
+            if let Err(e) = std::fs::write(&path, serde_yaml::to_string(&report).unwrap()) {
+                return Err(ProjectBuildError::WriteSizeReportFailed(path, e));
+            }
+        }
 
         Ok(BuildResult {
             target: pt,
             workspace: ws,
             object: obj,
             exports: types,
+            tests,
+            main: exe && self.has_main(test),
         })
     }
 
     fn compile<'b, S, O>(
         &self,
         exe: bool,
+        test: bool,
         target: &'static PrimitiveTarget,
         sources: S,
         output: O,
         resolver: &TypeResolver<'b>,
-    ) -> Result<HashSet<TypeDeclaration>, ProjectBuildError>
+    ) -> Result<(HashSet<TypeDeclaration>, Vec<String>), ProjectBuildError>
     where
         S: IntoIterator<Item = (&'b String, &'b SourceFile)>,
         O: AsRef<Path>,
     {
+        // Sort the sources so the build and its diagnostics are deterministic.
+        let mut sources: Vec<(&String, &SourceFile)> = sources.into_iter().collect();
+
+        sources.sort_unstable_by(|a, b| a.1.path().cmp(b.1.path()));
+
+        // Check the entry point before doing any LLVM work.
+        if exe && !test {
+            self.check_entry(target, &sources)?;
+        }
+
         // Setup codegen context.
         let pkg = self.meta.package();
-        let mut cg = Codegen::new(pkg.name(), pkg.version(), target, exe, resolver);
+        let obj = output.as_ref();
+        let machine = match self.machines.get(target) {
+            Ok(v) => v,
+            Err(e) => return Err(ProjectBuildError::BuildFailed(obj.to_owned(), e)),
+        };
+        let mut cg = Codegen::new(
+            pkg.name(),
+            pkg.version(),
+            machine,
+            exe,
+            resolver,
+            &self.defines,
+        );
+
+        cg.set_test(test);
+        cg.set_main(self.has_main(test));
+
+        if let (true, false, Some(EntryPoint::Function(v))) = (exe, test, self.entry()) {
+            cg.set_entry_function(Some(v));
+        }
+
+        cg.set_entry_symbol(self.entry_symbol());
+        cg.set_allow_no_entry(self.allow_no_entry);
+        cg.set_allow_intrinsics(self.meta.allow_intrinsics());
 
         // Compile source files.
         let mut types = HashSet::new();
 
-        for (fqtn, src) in sources {
+        for &(fqtn, src) in &sources {
             cg.set_namespace(match fqtn.rfind('.') {
                 Some(i) => &fqtn[..i],
                 None => "",
             });
 
+            cg.set_prelude(src.prelude());
+
             match src.build(&mut cg) {
                 Ok(v) => {
                     if let Some(v) = v {
@@ -383,17 +1270,225 @@ impl<'a> Project<'a> {
             }
         }
 
+        // Collect re-exported types after the local types so a conflict is reported on the
+        // re-export.
+        for (_, src) in &sources {
+            let list = match src.reexports(&cg) {
+                Ok(v) => v,
+                Err(e) => return Err(ProjectBuildError::InvalidSyntax(src.path().to_owned(), e)),
+            };
+
+            for (span, ty) in list {
+                if !types.insert(ty) {
+                    return Err(ProjectBuildError::InvalidSyntax(
+                        src.path().to_owned(),
+                        SyntaxError::new(span, "a type with the same name is already exported"),
+                    ));
+                }
+            }
+        }
+
         // Build the object file.
-        let obj = output.as_ref();
+        let tests = cg.tests().iter().map(|t| t.name().to_owned()).collect();
 
         if let Err(e) = cg.build(obj) {
             return Err(ProjectBuildError::BuildFailed(obj.to_owned(), e));
         }
 
-        Ok(types)
+        Ok((types, tests))
+    }
+
+    fn lint(&self) -> Result<(), ProjectBuildError> {
+        let mut sources: Vec<&SourceFile> = self.sources().collect();
+
+        sources.sort_unstable_by(|a, b| a.path().cmp(b.path()));
+
+        for src in sources {
+            for w in src.lints() {
+                if self.strict {
+                    return Err(ProjectBuildError::InvalidSyntax(src.path().to_owned(), w));
+                }
+
+                eprintln!("Warning: {}: {}", src.path().display(), w);
+            }
+        }
+
+        // The @entry has no effect when the entry point is specified in Nitro.yml.
+        let entry = match self.entry() {
+            Some(EntryPoint::Function(v)) => Some(v.as_str()),
+            Some(EntryPoint::None) => None,
+            None => return Ok(()),
+        };
+
+        let mut sources: Vec<(&String, &SourceFile)> = self.exe.named().collect();
+
+        sources.sort_unstable_by(|a, b| a.1.path().cmp(b.1.path()));
+
+        for (fqtn, src) in sources {
+            for name in src.annotated_entries() {
+                if entry.is_some_and(|v| *v == format!("{fqtn}.{}", name.value())) {
+                    continue;
+                }
+
+                let w = SyntaxError::new(
+                    name.span(),
+                    "@entry is ignored since the entry point is specified in Nitro.yml",
+                );
+
+                if self.strict {
+                    return Err(ProjectBuildError::InvalidSyntax(src.path().to_owned(), w));
+                }
+
+                eprintln!("Warning: {}: {}", src.path().display(), w);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_entry(
+        &self,
+        target: &'static PrimitiveTarget,
+        sources: &[(&String, &SourceFile)],
+    ) -> Result<(), ProjectBuildError> {
+        // Check if the function from Nitro.yml exists. The @entry was already reported by lint.
+        match self.entry() {
+            Some(EntryPoint::Function(v)) => {
+                let (ty, name) = v.rsplit_once('.').unwrap();
+                let mut annotated = Vec::new();
+
+                for (fqtn, src) in sources {
+                    if *fqtn == ty {
+                        match src.has_function(name, target.os(), &self.defines) {
+                            Ok(true) => return Ok(()),
+                            Ok(false) => {}
+                            Err(e) => {
+                                return Err(ProjectBuildError::InvalidSyntax(
+                                    src.path().to_owned(),
+                                    e,
+                                ));
+                            }
+                        }
+                    }
+
+                    annotated.extend(
+                        src.annotated_entries()
+                            .map(|n| format!("{fqtn}.{}", n.value())),
+                    );
+                }
+
+                return Err(ProjectBuildError::EntryNotFound(v.clone(), annotated));
+            }
+            Some(EntryPoint::None) => return Ok(()),
+            None => {}
+        }
+
+        let mut found: Option<(&Path, usize)> = None;
+
+        for (_, src) in sources {
+            let entries = match src.entries(target.os(), &self.defines) {
+                Ok(v) => v,
+                Err(e) => return Err(ProjectBuildError::InvalidSyntax(src.path().to_owned(), e)),
+            };
+
+            for name in entries {
+                let line = name.span().line();
+
+                if let Some((path, first)) = found {
+                    return Err(ProjectBuildError::MultipleEntryPoints(
+                        path.to_owned(),
+                        first,
+                        src.path().to_owned(),
+                        line,
+                    ));
+                }
+
+                found = Some((src.path(), line));
+            }
+        }
+
+        if found.is_none() && !self.allow_no_entry {
+            return Err(ProjectBuildError::NoEntryPoint);
+        }
+
+        Ok(())
+    }
+
+    fn write_deps(&self, obj: &Path, list: Vec<&Path>) -> Result<(), ProjectBuildError> {
+        let mut df = DepFile::default();
+
+        df.add_output(obj);
+
+        for path in list {
+            df.add_input(path);
+        }
+
+        // Write the file.
+        let path = obj.with_extension("d");
+
+        if let Err(e) = df.write(&path) {
+            return Err(ProjectBuildError::WriteDepsFailed(path, e));
+        }
+
+        Ok(())
+    }
+
+    /// Add the outputs of `target` to `df` together with the stubs and the dependency binaries that
+    /// was given to the linker.
+    fn collect_deps_file(
+        &self,
+        df: &mut DepFile,
+        obj: &Path,
+        bin: &Path,
+        target: &Target,
+        deps: &[Arc<Package>],
+    ) -> Result<(), ProjectBuildError> {
+        df.add_output(obj);
+        df.add_output(bin);
+
+        // Add stubs.
+        let pt = match self.targets.primitive(target) {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(ProjectBuildError::ResolvePrimitiveTargetFailed(
+                    target.clone(),
+                    e,
+                ));
+            }
+        };
+
+        let dir = self.stubs.join(stubs_subdir(pt));
+        let items = match std::fs::read_dir(&dir) {
+            Ok(v) => v,
+            Err(e) => return Err(ProjectBuildError::EnumerateStubsFailed(dir, e)),
+        };
+
+        for item in items {
+            let path = match item {
+                Ok(v) => v.path(),
+                Err(e) => return Err(ProjectBuildError::EnumerateStubsFailed(dir, e)),
+            };
+
+            if path.is_file() {
+                df.add_input(path);
+            }
+        }
+
+        // Add dependencies.
+        for dep in deps {
+            if let Some(lib) = self.resolve_lib(target.clone(), dep.libs())? {
+                if let LibraryBinary::Bundle(v) = lib.bin().bin() {
+                    df.add_input(v);
+                }
+            }
+        }
+
+        Ok(())
     }
 
     fn link_exe(&self, br: BuildResult) -> Result<PathBuf, ProjectBuildError> {
+        self.check_stubs(br.target)?;
+
         // Get output path.
         let pkg = self.meta.package();
         let out = match br.target.os() {
@@ -401,36 +1496,75 @@ impl<'a> Project<'a> {
             TargetOs::Win32 => br.workspace.join(format!("{}.exe", pkg.name())),
         };
 
-        // Build linker command.
+        // Link.
+        let (linker, args) = self.exe_link_args(br.target, &out, &br.object, br.main);
+
+        match Self::link(linker, &args) {
+            Ok(_) => Ok(out),
+            Err(e) => Err(ProjectBuildError::LinkFailed(out, e)),
+        }
+    }
+
+    /// Returns the linker and its arguments to link `obj` into the executable at `out`. `main` is
+    /// `false` if the executable does not have the generated entry point.
+    fn exe_link_args(
+        &self,
+        target: &'static PrimitiveTarget,
+        out: &Path,
+        obj: &Path,
+        main: bool,
+    ) -> (&'static str, Vec<Cow<'static, str>>) {
         let mut args: Vec<Cow<'static, str>> = Vec::new();
-        let linker = match br.target.os() {
+        let linker = match target.os() {
             TargetOs::Darwin => {
-                self.set_link_args_darwin(&mut args, br.target, &out);
+                self.set_link_args_darwin(&mut args, target, out);
+
+                if self.strip {
+                    args.push("-S".into());
+                    args.push("-x".into());
+                }
+
+                // C symbols on Mach-O have an underscore prefix.
+                if main {
+                    args.push("-e".into());
+                    args.push(format!("_{}", self.entry_symbol()).into());
+                }
+
                 "ld64.lld"
             }
             TargetOs::Linux => {
-                self.set_link_args_linux(&mut args, br.target, &out);
-                args.push("--entry=main".into());
+                self.set_link_args_linux(&mut args, target, out);
+
+                if self.strip {
+                    args.push("--strip-all".into());
+                }
+
+                if main {
+                    args.push(format!("--entry={}", self.entry_symbol()).into());
+                }
+
                 args.push("--dynamic-linker=/lib64/ld-linux-x86-64.so.2".into());
                 "ld.lld"
             }
             TargetOs::Win32 => {
-                self.set_link_args_win32(&mut args, br.target, &out);
-                args.push("/entry:main".into());
+                self.set_link_args_win32(&mut args, target, out);
+
+                if main {
+                    args.push(format!("/entry:{}", self.entry_symbol()).into());
+                }
+
                 "lld-link"
             }
         };
 
-        args.push(br.object.to_str().unwrap().to_owned().into());
+        args.push(obj.to_str().unwrap().to_owned().into());
 
-        // Link.
-        match Self::link(linker, &args) {
-            Ok(_) => Ok(out),
-            Err(e) => Err(ProjectBuildError::LinkFailed(out, e)),
-        }
+        (linker, args)
     }
 
     fn link_lib(&self, br: &BuildResult) -> Result<PathBuf, ProjectBuildError> {
+        self.check_stubs(br.target)?;
+
         // Get output path.
         let pkg = self.meta.package();
         let out = br.workspace.join(match br.target.os() {
@@ -498,7 +1632,7 @@ impl<'a> Project<'a> {
         args.push("-L".into());
         args.push(
             self.stubs
-                .join("darwin")
+                .join(stubs_subdir(target))
                 .into_os_string()
                 .into_string()
                 .unwrap()
@@ -512,12 +1646,7 @@ impl<'a> Project<'a> {
         target: &'static PrimitiveTarget,
         out: &Path,
     ) {
-        let stubs = self
-            .stubs
-            .join(match (target.env().unwrap(), target.arch()) {
-                (TargetEnv::Gnu, TargetArch::X86_64) => "linux-gnu-x86_64",
-                _ => todo!(),
-            });
+        let stubs = self.stubs.join(stubs_subdir(target));
 
         args.push("-o".into());
         args.push(out.to_str().unwrap().to_owned().into());
@@ -533,16 +1662,75 @@ impl<'a> Project<'a> {
         target: &'static PrimitiveTarget,
         out: &Path,
     ) {
-        let stubs = self.stubs.join(match target.arch() {
-            TargetArch::X86_64 => "win32-x86_64",
-            _ => todo!(),
-        });
+        let stubs = self.stubs.join(stubs_subdir(target));
 
         args.push(format!("/out:{}", out.to_str().unwrap()).into());
         args.push(format!("/libpath:{}", stubs.to_str().unwrap()).into());
         args.push("/defaultlib:msvcrt".into());
     }
 
+    /// Check if the stubs for `target` can be used so the user get a clear error instead of a
+    /// cryptic one from the linker. Only the beginning of the stub is read.
+    fn check_stubs(&self, target: &'static PrimitiveTarget) -> Result<(), ProjectBuildError> {
+        let (name, magic): (&str, &[u8]) = match target.os() {
+            TargetOs::Darwin => ("libSystem.tbd", b"--- !tapi-tbd"),
+            TargetOs::Linux => ("libc.so", b"\x7fELF"),
+            TargetOs::Win32 => ("msvcrt.lib", b"!<arch>\n"),
+        };
+
+        // Read the header.
+        let path = self.stubs.join(stubs_subdir(target)).join(name);
+        let mut data = Vec::new();
+
+        if let Err(e) = File::open(&path).and_then(|f| f.take(4096).read_to_end(&mut data)) {
+            return Err(ProjectBuildError::StubMismatch(path, target, e.to_string()));
+        }
+
+        if !data.starts_with(magic) {
+            return Err(ProjectBuildError::StubMismatch(
+                path,
+                target,
+                "unexpected file format".into(),
+            ));
+        }
+
+        // Check architecture. The machine of an import library is stored on each member so we only
+        // check the format for Win32.
+        let matched = match target.os() {
+            TargetOs::Darwin => {
+                let arch = match target.arch() {
+                    TargetArch::AArch64 => "arm64-macos",
+                    TargetArch::X86_64 => "x86_64-macos",
+                };
+
+                String::from_utf8_lossy(&data)
+                    .lines()
+                    .find(|l| l.starts_with("targets:"))
+                    .is_some_and(|l| l.contains(arch))
+            }
+            TargetOs::Linux => {
+                // EI_CLASS must be ELFCLASS64 and e_machine is at offset 18.
+                let machine: u16 = match target.arch() {
+                    TargetArch::AArch64 => 183,
+                    TargetArch::X86_64 => 62,
+                };
+
+                data.get(4) == Some(&2) && data.get(18..20) == Some(&machine.to_le_bytes())
+            }
+            TargetOs::Win32 => true,
+        };
+
+        if !matched {
+            return Err(ProjectBuildError::StubMismatch(
+                path,
+                target,
+                "the stub was built for a different architecture".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
     fn link(linker: &str, args: &[Cow<'static, str>]) -> Result<(), LinkError> {
         // Setup arguments.
         let args: Vec<CString> = args
@@ -564,6 +1752,8 @@ impl<'a> Project<'a> {
         }
     }
 
+    /// Write a module-definition file for `link.exe`. The lines are always terminated with LF, the
+    /// same as the other generated text files, which the MSVC tools accept.
     fn write_module_definition<'b, F, T>(
         pkg: &PackageName,
         ver: &PackageVersion,
@@ -579,13 +1769,17 @@ impl<'a> Project<'a> {
 
         file.write_all(b"EXPORTS\n")?;
 
-        // Dump public types.
-        for ty in types {
+        // Dump public types. The functions of a re-exported type is in its original package.
+        for ty in sorted_by_name(types) {
             let ty = match ty {
                 TypeDeclaration::Basic(v) => v,
             };
 
-            for func in ty.funcs() {
+            if ty.origin().is_some() {
+                continue;
+            }
+
+            for func in sorted_by_name(ty.funcs()) {
                 let name = func.mangle(Some((pkg.as_str(), ver.major())), ty.name());
 
                 file.write_all(b"    ")?;
@@ -598,6 +1792,31 @@ impl<'a> Project<'a> {
     }
 }
 
+/// `true` if the default file system of the host ignore the case of the file names.
+const CASE_INSENSITIVE_FS: bool = cfg!(any(target_os = "macos", target_os = "windows"));
+
+/// Returns `true` if `key` can be used as a key of `--define` and `defines` in `Nitro.yml`. The key
+/// must be an identifier other than `os`.
+pub fn is_define_key(key: &str) -> bool {
+    let mut chars = key.chars();
+
+    chars
+        .next()
+        .is_some_and(|c| c == '_' || c.is_ascii_alphabetic())
+        && chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+        && key != "os"
+}
+
+/// Returns the name of directory inside the stubs directory that contains the stubs for `target`.
+pub fn stubs_subdir(target: &PrimitiveTarget) -> &'static str {
+    match (target.os(), target.env(), target.arch()) {
+        (TargetOs::Darwin, _, _) => "darwin",
+        (TargetOs::Linux, Some(TargetEnv::Gnu), TargetArch::X86_64) => "linux-gnu-x86_64",
+        (TargetOs::Win32, _, TargetArch::X86_64) => "win32-x86_64",
+        _ => todo!(),
+    }
+}
+
 #[allow(improper_ctypes)]
 extern "C" {
     fn lld_link(linker: *const c_char, args: *const *const c_char, err: &mut String) -> bool;
@@ -614,6 +1833,25 @@ struct BuildResult {
     workspace: PathBuf,
     object: PathBuf,
     exports: HashSet<TypeDeclaration>,
+    tests: Vec<String>,
+    main: bool,
+}
+
+/// A test harness that was built by [`Project::build_tests()`].
+pub struct TestBinary {
+    path: PathBuf,
+    tests: Vec<String>,
+}
+
+impl TestBinary {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the name of each test in the same order as the harness run it.
+    pub fn tests(&self) -> &[String] {
+        &self.tests
+    }
 }
 
 /// Represents an error when a [`Project`] is failed to open.
@@ -627,6 +1865,30 @@ pub enum ProjectOpenError {
 
     #[error("{0} must contain at least executable or library definition")]
     MissingBinary(PathBuf),
+
+    #[error("'{1}' in {0} is not a valid symbol name")]
+    InvalidEntrySymbol(PathBuf, String),
+
+    #[error("'{1}' in {0} is not a valid namespace")]
+    InvalidNamespace(PathBuf, String),
+
+    #[error("'{1}' in {0} is not a valid define key")]
+    InvalidDefine(PathBuf, String),
+
+    #[error("'{1}' in {0} is not a valid entry point (expect a function with its type name)")]
+    InvalidEntry(PathBuf, String),
+
+    #[error("{0} has entry-symbol but the entry point is disabled")]
+    EntrySymbolWithoutEntry(PathBuf),
+
+    #[error("{1} in {0} is the package itself and cannot be a dependency")]
+    SelfDependency(PathBuf, PackageName),
+
+    #[error("{1} in {0} is always a dependency and cannot be declared")]
+    ImplicitDependency(PathBuf, PackageName),
+
+    #[error("{1} appears more than once in the dependencies of {0}")]
+    DuplicatedDependency(PathBuf, PackageName),
 }
 
 /// Represents an error when a [`Project`] is failed to load.
@@ -646,6 +1908,21 @@ pub enum ProjectLoadError {
 
     #[error("path {0} is not UTF-8")]
     NonUtf8Path(PathBuf),
+
+    #[error("fully qualified type name of {0} is longer than {MAX_TYPE_NAME} bytes")]
+    TypeNameTooLong(PathBuf),
+
+    #[error("{0} is not a directory")]
+    SourceRootNotDirectory(PathBuf),
+
+    #[error("cannot read {0}")]
+    ReadIgnoreFailed(PathBuf, #[source] std::io::Error),
+
+    #[error("cannot resolve the real path of {0}")]
+    ResolvePathFailed(PathBuf, #[source] std::io::Error),
+
+    #[error("{0} and {1} differ only by case")]
+    CaseCollision(PathBuf, PathBuf),
 }
 
 /// Represents an error when a [`Project`] is failed to build.
@@ -674,6 +1951,145 @@ pub enum ProjectBuildError {
 
     #[error("cannot link {0}")]
     LinkFailed(PathBuf, #[source] LinkError),
+
+    #[error("asset {0} is not a valid path inside the project")]
+    InvalidAssetPath(PathBuf),
+
+    #[error("cannot get metadata of {0}")]
+    GetAssetMetadataFailed(PathBuf, #[source] std::io::Error),
+
+    #[error("cannot enumerate files in {0}")]
+    EnumerateAssetsFailed(PathBuf, #[source] std::io::Error),
+
+    #[error("asset {0} is a link to a directory")]
+    AssetDirectoryLink(PathBuf),
+
+    #[error("asset {0} is a link to {1}, which is outside the project")]
+    AssetOutsideProject(PathBuf, PathBuf),
+
+    #[error("cannot write {0}")]
+    WriteDepsFailed(PathBuf, #[source] std::io::Error),
+
+    #[error("cannot enumerate stubs in {0}")]
+    EnumerateStubsFailed(PathBuf, #[source] std::io::Error),
+
+    #[error("cannot read symbols from {0}: {1}")]
+    ReadSymbolsFailed(PathBuf, String),
+
+    #[error("cannot write {0}")]
+    WriteSizeReportFailed(PathBuf, #[source] std::io::Error),
+
+    #[error("cannot read {0}")]
+    HashFileFailed(PathBuf, #[source] std::io::Error),
+
+    #[error("cannot write {0}")]
+    WriteFingerprintFailed(PathBuf, #[source] std::io::Error),
+
+    #[error("cannot generate C header")]
+    GenerateHeaderFailed(#[source] CHeaderError),
+
+    #[error("{0} is not a valid stub for {1} ({2})")]
+    StubMismatch(PathBuf, &'static PrimitiveTarget, String),
+
+    #[error("no entry point has been defined")]
+    NoEntryPoint,
+
+    #[error("entry point {0} specified in Nitro.yml does not exist (functions with @entry: {})", if .1.is_empty() { "none".to_owned() } else { .1.join(", ") })]
+    EntryNotFound(String, Vec<String>),
+
+    #[error("entry point is defined in both {0}:{1} and {2}:{3}")]
+    MultipleEntryPoints(PathBuf, usize, PathBuf, usize),
+
+    #[error("both {0} and {1} define the same type for {2}")]
+    ConflictingSources(PathBuf, PathBuf, &'static PrimitiveTarget),
+}
+
+/// Source files of a binary in the project.
+#[derive(Default)]
+struct Sources {
+    common: HashMap<String, SourceFile>,
+    platform: Vec<PlatformSource>,
+}
+
+impl Sources {
+    fn is_empty(&self) -> bool {
+        self.common.is_empty() && self.platform.is_empty()
+    }
+
+    /// Returns all source files regardless of the target together with its fully qualified type
+    /// name.
+    fn named(&self) -> impl Iterator<Item = (&String, &SourceFile)> {
+        self.common
+            .iter()
+            .chain(self.platform.iter().map(|s| (&s.fqtn, &s.source)))
+    }
+
+    /// Returns all source files regardless of the target.
+    fn iter(&self) -> impl Iterator<Item = &SourceFile> {
+        self.common
+            .values()
+            .chain(self.platform.iter().map(|s| &s.source))
+    }
+
+    /// Returns the source files to build for `target` together with its fully qualified type name.
+    fn select(
+        &self,
+        target: &'static PrimitiveTarget,
+    ) -> Result<Vec<(&String, &SourceFile)>, ProjectBuildError> {
+        let mut list: Vec<(&String, &SourceFile)> = self.common.iter().collect();
+        let mut index: HashMap<&str, &Path> =
+            list.iter().map(|(n, s)| (n.as_str(), s.path())).collect();
+
+        for s in self.platform.iter().filter(|s| s.filter.matches(target)) {
+            if let Some(v) = index.insert(&s.fqtn, s.source.path()) {
+                return Err(ProjectBuildError::ConflictingSources(
+                    v.to_owned(),
+                    s.source.path().to_owned(),
+                    target,
+                ));
+            }
+
+            list.push((&s.fqtn, &s.source));
+        }
+
+        Ok(list)
+    }
+}
+
+/// A source file that will be built only for a specific platform.
+struct PlatformSource {
+    fqtn: String,
+    filter: PlatformFilter,
+    source: SourceFile,
+}
+
+/// A platform infix on the name of source file (e.g. `linux` in `Foo.linux.nt`).
+#[derive(Clone, Copy)]
+enum PlatformFilter {
+    Os(TargetOs),
+    Arch(TargetArch),
+}
+
+impl PlatformFilter {
+    fn parse(v: &str) -> Option<Self> {
+        [TargetOs::Darwin, TargetOs::Linux, TargetOs::Win32]
+            .into_iter()
+            .find(|os| os.name() == v)
+            .map(Self::Os)
+            .or_else(|| {
+                [TargetArch::AArch64, TargetArch::X86_64]
+                    .into_iter()
+                    .find(|arch| arch.name() == v)
+                    .map(Self::Arch)
+            })
+    }
+
+    fn matches(self, target: &PrimitiveTarget) -> bool {
+        match self {
+            Self::Os(v) => target.os() == v,
+            Self::Arch(v) => target.arch() == v,
+        }
+    }
 }
 
 /// Represents an error when a [`Project`] is failed to link.
@@ -687,3 +2103,534 @@ impl Display for LinkError {
         self.0.fmt(f)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stubs_subdir_mapping() {
+        let dirs: Vec<(String, &str)> = PrimitiveTarget::ALL
+            .iter()
+            .map(|t| (t.to_string(), stubs_subdir(t)))
+            .collect();
+
+        assert_eq!(
+            dirs,
+            [
+                ("x86_64-unknown-linux-gnu".to_owned(), "linux-gnu-x86_64"),
+                ("aarch64-apple-darwin".to_owned(), "darwin"),
+                ("x86_64-apple-darwin".to_owned(), "darwin"),
+                ("x86_64-pc-win32-msvc".to_owned(), "win32-x86_64"),
+            ]
+        );
+    }
+
+    #[test]
+    fn dependencies() {
+        let deps = open("foo: 1.0.0\nbar: 2.1.0\n").unwrap();
+
+        assert_eq!(deps, ["foo 1.0.0", "bar 2.1.0"]);
+    }
+
+    #[test]
+    fn self_dependency() {
+        match open("app: 1.0.0\n") {
+            Err(ProjectOpenError::SelfDependency(p, n)) => {
+                assert!(p.ends_with("Nitro.yml"));
+                assert_eq!(n, *"app");
+            }
+            _ => panic!("self dependency is not rejected"),
+        }
+    }
+
+    #[test]
+    fn implicit_dependency() {
+        assert!(matches!(
+            open("nitro: 1.0.0\n"),
+            Err(ProjectOpenError::ImplicitDependency(_, n)) if n == *"nitro"
+        ));
+    }
+
+    #[test]
+    fn duplicated_dependency() {
+        // The same package with a different version.
+        match open("foo: 1.0.0\nbar: 1.0.0\nfoo: 2.0.0\n") {
+            Err(ProjectOpenError::DuplicatedDependency(p, n)) => {
+                assert!(p.ends_with("Nitro.yml"));
+                assert_eq!(n, *"foo");
+            }
+            Err(e) => panic!("unexpected error: {e}"),
+            Ok(_) => panic!("duplicated dependency is not rejected"),
+        }
+    }
+
+    #[test]
+    fn asset_path() {
+        assert!(Project::is_valid_asset_path(Path::new("data")));
+        assert!(Project::is_valid_asset_path(Path::new("./data/config.yml")));
+        assert!(!Project::is_valid_asset_path(Path::new("../data")));
+        assert!(!Project::is_valid_asset_path(Path::new("data/../../etc")));
+        assert!(!Project::is_valid_asset_path(Path::new("/etc/passwd")));
+    }
+
+    #[test]
+    fn nested_assets() {
+        let assets = collect("nested", |p| {
+            create_dir_all(p.join("data").join("sub")).unwrap();
+            std::fs::write(p.join("data").join("a.txt"), "a").unwrap();
+            std::fs::write(p.join("data").join("sub").join("b.txt"), "b").unwrap();
+        });
+
+        assert_eq!(assets.unwrap(), ["data/a.txt", "data/sub/b.txt"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn asset_links() {
+        use std::os::unix::fs::symlink;
+
+        // A link to a file inside the project is followed.
+        let assets = collect("link-file", |p| {
+            create_dir_all(p.join("data")).unwrap();
+            std::fs::write(p.join("Nitro.md"), "a").unwrap();
+            symlink(p.join("Nitro.md"), p.join("data").join("a.md")).unwrap();
+        });
+
+        assert_eq!(assets.unwrap(), ["data/a.md"]);
+
+        // A link to a parent directory would never end.
+        let assets = collect("link-dir", |p| {
+            create_dir_all(p.join("data")).unwrap();
+            symlink(p, p.join("data").join("loop")).unwrap();
+        });
+
+        assert!(matches!(
+            assets,
+            Err(ProjectBuildError::AssetDirectoryLink(p)) if p.ends_with("data/loop")
+        ));
+
+        // A link to a file outside the project.
+        let outside = std::env::temp_dir().join(format!("nitro-outside-{}", std::process::id()));
+
+        std::fs::write(&outside, "a").unwrap();
+
+        let assets = collect("link-outside", |p| {
+            create_dir_all(p.join("data")).unwrap();
+            symlink(&outside, p.join("data").join("host")).unwrap();
+        });
+
+        std::fs::remove_file(&outside).unwrap();
+
+        assert!(matches!(
+            assets,
+            Err(ProjectBuildError::AssetOutsideProject(p, _)) if p.ends_with("data/host")
+        ));
+    }
+
+    #[test]
+    fn entry_meta() {
+        let parse = |v: &str| serde_yaml::from_str::<ProjectBinary>(v).unwrap();
+
+        assert!(matches!(
+            parse("sources: src\nentry: App.Main\n").entry(),
+            Some(EntryPoint::Function(v)) if v == "App.Main"
+        ));
+        assert!(matches!(
+            parse("sources: src\nentry: none\n").entry(),
+            Some(EntryPoint::None)
+        ));
+        assert!(parse("sources: src\n").entry().is_none());
+
+        // Validation on open.
+        assert!(matches!(
+            with_project("entry-invalid", "entry: Main\n", &[], |_| ()),
+            Err(ProjectOpenError::InvalidEntry(_, v)) if v == "Main"
+        ));
+        assert!(matches!(
+            with_project(
+                "entry-symbol",
+                "entry: none\nentry-symbol: start\n",
+                &[],
+                |_| ()
+            ),
+            Err(ProjectOpenError::EntrySymbolWithoutEntry(_))
+        ));
+    }
+
+    #[test]
+    fn explicit_entry() {
+        let sources = [("App", ENTRY_SOURCE), ("Other", OTHER_SOURCE)];
+        let (check, lint, strict) =
+            with_project("entry-explicit", "entry: Other.Run\n", &sources, |p| {
+                let pt = &PrimitiveTarget::ALL[0];
+                let check = p.check_entry(pt, &p.exe.select(pt).unwrap());
+                let lint = p.lint();
+
+                p.set_strict(true);
+
+                (check, lint, p.lint())
+            })
+            .unwrap();
+
+        assert!(check.is_ok());
+
+        // The @entry on App.Main is downgraded to a warning.
+        assert!(lint.is_ok());
+
+        match strict {
+            Err(ProjectBuildError::InvalidSyntax(p, e)) => {
+                assert!(p.ends_with("App.nt"));
+                assert_eq!(e.span().as_str(), "Main");
+                assert_eq!(
+                    e.reason(),
+                    "@entry is ignored since the entry point is specified in Nitro.yml"
+                );
+            }
+            _ => panic!("the ignored @entry is not reported"),
+        }
+    }
+
+    #[test]
+    fn missing_entry() {
+        let sources = [("App", ENTRY_SOURCE), ("Other", OTHER_SOURCE)];
+        let check = with_project("entry-missing", "entry: App.Start\n", &sources, |p| {
+            let pt = &PrimitiveTarget::ALL[0];
+
+            p.check_entry(pt, &p.exe.select(pt).unwrap())
+        })
+        .unwrap();
+
+        match check {
+            Err(ProjectBuildError::EntryNotFound(v, found)) => {
+                assert_eq!(v, "App.Start");
+                assert_eq!(found, ["App.Main"]);
+            }
+            _ => panic!("the missing entry point is not reported"),
+        }
+    }
+
+    #[test]
+    fn multiple_entries() {
+        let other = OTHER_SOURCE.replace("fn Run", "@entry\n    fn Run");
+        let sources = [("App", ENTRY_SOURCE), ("Other", other.as_str())];
+        let check = |p: &Project| {
+            let pt = &PrimitiveTarget::ALL[0];
+
+            p.check_entry(pt, &p.exe.select(pt).unwrap())
+        };
+
+        // Multiple @entry is an error without the entry key.
+        let r = with_project("entry-multiple", "", &sources, |p| check(p)).unwrap();
+
+        assert!(matches!(r, Err(ProjectBuildError::MultipleEntryPoints(..))));
+
+        // The entry key selects one of them and the others are a warning.
+        let (r, lint) = with_project("entry-selected", "entry: App.Main\n", &sources, |p| {
+            (check(p), p.lint())
+        })
+        .unwrap();
+
+        assert!(r.is_ok());
+        assert!(lint.is_ok());
+    }
+
+    #[test]
+    fn no_entry_link_args() {
+        let (main, none) = with_project("entry-none", "entry: none\n", &[], |p| {
+            let pt = &PrimitiveTarget::ALL;
+            let out = Path::new("app");
+            let obj = Path::new("app.o");
+            let args = |main| -> Vec<Vec<String>> {
+                pt.iter()
+                    .map(|t| {
+                        let (_, args) = p.exe_link_args(t, out, obj, main);
+                        args.into_iter().map(|v| v.into_owned()).collect()
+                    })
+                    .collect()
+            };
+
+            // The test harness always has the generated entry point.
+            assert!(!p.has_main(false));
+            assert!(p.has_main(true));
+
+            (args(true), args(false))
+        })
+        .unwrap();
+
+        for (i, t) in PrimitiveTarget::ALL.iter().enumerate() {
+            let removed: Vec<&String> = main[i].iter().filter(|a| !none[i].contains(a)).collect();
+            let expected: &[&str] = match t.os() {
+                TargetOs::Darwin => &["-e", "_main"],
+                TargetOs::Linux => &["--entry=main"],
+                TargetOs::Win32 => &["/entry:main"],
+            };
+
+            assert_eq!(removed, expected, "{t}");
+            assert_eq!(main[i].len(), none[i].len() + expected.len(), "{t}");
+        }
+    }
+
+    #[test]
+    fn type_name_limit() {
+        let root = std::env::temp_dir().join(format!("nitro-fqtn-{}", std::process::id()));
+        let path = root.join("App.nt");
+
+        create_dir_all(&root).unwrap();
+        std::fs::write(&path, "class App;\n").unwrap();
+
+        // The namespace plus ".App".
+        let load = |len| {
+            let ns = "a".repeat(len);
+            let mut sources = Sources::default();
+
+            Project::load_source(
+                &root,
+                path.clone(),
+                Some(&ns),
+                Lexer::DEFAULT_MAX_DEPTH,
+                &mut sources,
+            )
+            .map(|_| sources.common.into_keys().next().unwrap().len())
+        };
+
+        let ok = load(MAX_TYPE_NAME - 4);
+        let err = load(MAX_TYPE_NAME - 3);
+
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(ok.unwrap(), MAX_TYPE_NAME);
+        assert!(matches!(err, Err(ProjectLoadError::TypeNameTooLong(p)) if p == path));
+    }
+
+    #[test]
+    fn ignored_sources() {
+        // A junk source that would fail the type and file name check.
+        let sources = [("App", ENTRY_SOURCE), (".#App", "class Junk {")];
+        let (names, lint) = with_project("ignore", "ignore:\n  - .#*\n", &sources, |p| {
+            let names: Vec<String> = p.exe.named().map(|(n, _)| n.clone()).collect();
+
+            (names, p.lint())
+        })
+        .unwrap();
+
+        assert_eq!(names, ["App"]);
+        assert!(lint.is_ok());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn case_collision() {
+        // Both files and namespace directories are only a warning on Linux.
+        let sources = [
+            ("App", ENTRY_SOURCE),
+            ("APP", "class APP;\n"),
+            ("Lib/Foo", "class Foo;\n"),
+            ("lib/Bar", "class Bar;\n"),
+        ];
+        let mut names = with_project("case", "", &sources, |p| {
+            p.exe
+                .named()
+                .map(|(n, _)| n.clone())
+                .collect::<Vec<String>>()
+        })
+        .unwrap();
+
+        names.sort_unstable();
+
+        assert_eq!(names, ["APP", "App", "Lib.Foo", "lib.Bar"]);
+    }
+
+    #[test]
+    fn case_severity() {
+        let check = |insensitive| {
+            let mut names = HashMap::new();
+
+            Project::check_case(
+                &mut names,
+                "foo/App.nt",
+                Path::new("/src/foo/App.nt"),
+                insensitive,
+            )?;
+            Project::check_case(&mut names, "Foo", Path::new("/src/Foo"), insensitive)?;
+            Project::check_case(
+                &mut names,
+                "foo/app.nt",
+                Path::new("/src/foo/app.nt"),
+                insensitive,
+            )?;
+            Project::check_case(&mut names, "FOO", Path::new("/src/FOO"), insensitive)
+        };
+
+        assert!(check(false).is_ok());
+        assert!(matches!(
+            check(true),
+            Err(ProjectLoadError::CaseCollision(a, b)) if a == Path::new("/src/foo/App.nt") && b == Path::new("/src/foo/app.nt")
+        ));
+    }
+
+    #[test]
+    fn fingerprint_diff() {
+        // Use the name of the standard library so it does not need to be resolved.
+        let path = std::env::temp_dir().join(format!("nitro-fingerprint-{}", std::process::id()));
+        let src = path.join("src");
+        let target = &PrimitiveTarget::ALL[0];
+
+        create_dir_all(&src).unwrap();
+        create_dir_all(path.join(stubs_subdir(target))).unwrap();
+        std::fs::write(
+            path.join("Nitro.yml"),
+            "package:\n  name: nitro\n  version: 1.0.0\nexecutable:\n  sources: src\n",
+        )
+        .unwrap();
+        std::fs::write(src.join("App.nt"), ENTRY_SOURCE).unwrap();
+        std::fs::write(src.join("Other.nt"), OTHER_SOURCE).unwrap();
+
+        // Build the fingerprints before and after changing one source.
+        let targets = TargetResolver::new();
+        let resolver = DependencyResolver::new(path.join("cache"), path.join("std.npk"));
+        let fingerprint = || {
+            let mut p = Project::open(&path, &targets, &path, &resolver).unwrap();
+
+            p.load().unwrap();
+            p.fingerprint(target).unwrap()
+        };
+
+        let a = fingerprint();
+
+        std::fs::write(src.join("Other.nt"), OTHER_SOURCE.replace("0\n", "1\n")).unwrap();
+
+        let b = fingerprint();
+
+        std::fs::remove_dir_all(&path).unwrap();
+
+        assert!(a.diff(&a).is_none());
+
+        let name = Path::new("src").join("Other.nt");
+        let name = name.to_str().unwrap();
+        let (x, y) = (&a.sources[name], &b.sources[name]);
+
+        assert_ne!(x, y);
+        assert_eq!(
+            a.diff(&b).unwrap(),
+            format!("sources.{name}: {x:?} != {y:?}")
+        );
+    }
+
+    const ENTRY_SOURCE: &str =
+        "class App;\n\nimpl App {\n    @entry\n    fn Main(): Int32 {\n        0\n    }\n}\n";
+    const OTHER_SOURCE: &str =
+        "class Other;\n\nimpl Other {\n    fn Run(): Int32 {\n        0\n    }\n}\n";
+
+    /// Open and load a project with `exe` appended to its executable section and `sources` as the
+    /// content of each type, then invoke `f` with it.
+    fn with_project<F, R>(
+        name: &str,
+        exe: &str,
+        sources: &[(&str, &str)],
+        f: F,
+    ) -> Result<R, ProjectOpenError>
+    where
+        F: FnOnce(&mut Project) -> R,
+    {
+        let path =
+            std::env::temp_dir().join(format!("nitro-project-{}-{}", std::process::id(), name));
+        let src = path.join("src");
+        let mut meta = String::from("package:\n  name: app\n  version: 1.0.0\n");
+
+        meta.push_str("executable:\n  sources: src\n");
+
+        for l in exe.lines() {
+            meta.push_str("  ");
+            meta.push_str(l);
+            meta.push('\n');
+        }
+
+        create_dir_all(&src).unwrap();
+        std::fs::write(path.join("Nitro.yml"), meta).unwrap();
+
+        for (ty, data) in sources {
+            let path = src.join(format!("{ty}.nt"));
+
+            create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, data).unwrap();
+        }
+
+        // Open the project.
+        let targets = TargetResolver::new();
+        let resolver = DependencyResolver::new(path.join("cache"), path.join("std.npk"));
+        let result = Project::open(&path, &targets, &path, &resolver).map(|mut p| {
+            p.load().unwrap();
+            f(&mut p)
+        });
+
+        std::fs::remove_dir_all(&path).unwrap();
+        result
+    }
+
+    /// Open a project that have `data` directory as its assets and returns the name of the collected
+    /// assets. `setup` is called with the project directory to populate the assets.
+    fn collect<F>(name: &str, setup: F) -> Result<Vec<String>, ProjectBuildError>
+    where
+        F: FnOnce(&Path),
+    {
+        let path =
+            std::env::temp_dir().join(format!("nitro-assets-{}-{}", std::process::id(), name));
+        let meta = "package:\n  name: app\n  version: 1.0.0\nexecutable:\n  sources: src\nassets:\n  - data\n";
+
+        create_dir_all(&path).unwrap();
+        std::fs::write(path.join("Nitro.yml"), meta).unwrap();
+        setup(&path);
+
+        // Collect the assets.
+        let targets = TargetResolver::new();
+        let resolver = DependencyResolver::new(path.join("cache"), path.join("std.npk"));
+        let result = Project::open(&path, &targets, &path, &resolver)
+            .unwrap()
+            .collect_assets()
+            .map(|v| v.into_keys().collect());
+
+        std::fs::remove_dir_all(&path).unwrap();
+        result
+    }
+
+    /// Open a project named `app` with `deps` as its dependencies and returns the dependencies.
+    fn open(deps: &str) -> Result<Vec<String>, ProjectOpenError> {
+        // Write the project. Each test use a different directory since the tests run in parallel.
+        let mut hash = Fnv64::new();
+
+        hash.write(deps.as_bytes());
+
+        let path = std::env::temp_dir().join(format!(
+            "nitro-project-{}-{:x}",
+            std::process::id(),
+            hash.finish()
+        ));
+        let mut meta = String::from("package:\n  name: app\n  version: 1.0.0\n");
+
+        meta.push_str("executable:\n  sources: src\ndependencies:\n");
+
+        for l in deps.lines() {
+            meta.push_str("  ");
+            meta.push_str(l);
+            meta.push('\n');
+        }
+
+        create_dir_all(&path).unwrap();
+        std::fs::write(path.join("Nitro.yml"), meta).unwrap();
+
+        // Open the project.
+        let targets = TargetResolver::new();
+        let resolver = DependencyResolver::new(path.join("cache"), path.join("std.npk"));
+        let result = Project::open(&path, &targets, &path, &resolver).map(|p| {
+            p.meta
+                .dependencies()
+                .iter()
+                .map(|d| format!("{} {}", d.name(), d.version()))
+                .collect()
+        });
+
+        std::fs::remove_dir_all(&path).unwrap();
+        result
+    }
+}
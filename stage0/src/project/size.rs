@@ -0,0 +1,150 @@
+use crate::ffi::llvm_object_symbol_sizes;
+use serde::Serialize;
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::path::Path;
+
+/// Size of each function in an object file.
+#[derive(Serialize)]
+pub struct SizeReport {
+    total: u64,
+    functions: Vec<FunctionSize>,
+}
+
+impl SizeReport {
+    /// Read the symbol table of `obj`. The functions will be sorted by its size in descending
+    /// order.
+    pub fn from_object(obj: &Path) -> Result<Self, String> {
+        let path = CString::new(obj.to_str().unwrap()).unwrap();
+        let mut functions: Vec<FunctionSize> = Vec::new();
+        let mut err = String::new();
+        let cx = (&mut functions as *mut Vec<FunctionSize>).cast();
+
+        if !unsafe { llvm_object_symbol_sizes(path.as_ptr(), cx, Self::push, &mut err) } {
+            return Err(err);
+        }
+
+        functions.sort_unstable_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+
+        Ok(Self {
+            total: functions.iter().map(|f| f.size).sum(),
+            functions,
+        })
+    }
+
+    unsafe extern "C" fn push(cx: *mut c_void, name: *const c_char, size: u64) {
+        let list: &mut Vec<FunctionSize> = &mut *cx.cast();
+        let name = CStr::from_ptr(name).to_string_lossy().into_owned();
+
+        list.push(FunctionSize { name, size });
+    }
+}
+
+/// Size of a function in [`SizeReport`].
+#[derive(Serialize)]
+struct FunctionSize {
+    name: String,
+    size: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SizeReport;
+
+    #[test]
+    fn from_object() {
+        // The smaller function comes first in the symbol table.
+        let obj = elf(&[("Small", 0, 4), ("Big", 4, 60)]);
+        let path = std::env::temp_dir().join(format!("nitro-size-{}.o", std::process::id()));
+
+        std::fs::write(&path, obj).unwrap();
+
+        let r = SizeReport::from_object(&path);
+
+        std::fs::remove_file(&path).unwrap();
+
+        let r = r.unwrap();
+        let funcs: Vec<(&str, u64)> = r
+            .functions
+            .iter()
+            .map(|f| (f.name.as_str(), f.size))
+            .collect();
+
+        assert_eq!(funcs, [("Big", 60), ("Small", 4)]);
+        assert_eq!(r.total, 64);
+    }
+
+    /// Returns an x86-64 ELF relocatable with a 64 bytes `.text` and a global function for each
+    /// name, offset and size in `funcs`.
+    fn elf(funcs: &[(&str, u64, u64)]) -> Vec<u8> {
+        // Build the tables.
+        let mut strtab = vec![0];
+        let mut symtab = vec![0; 24];
+
+        for &(name, value, size) in funcs {
+            symtab.extend_from_slice(&u32::try_from(strtab.len()).unwrap().to_le_bytes());
+            symtab.push(0x12); // STB_GLOBAL and STT_FUNC.
+            symtab.push(0);
+            symtab.extend_from_slice(&1u16.to_le_bytes());
+            symtab.extend_from_slice(&value.to_le_bytes());
+            symtab.extend_from_slice(&size.to_le_bytes());
+            strtab.extend_from_slice(name.as_bytes());
+            strtab.push(0);
+        }
+
+        let shstrtab = b"\0.text\0.symtab\0.strtab\0.shstrtab\0";
+        let text = [0x90; 64];
+
+        // Layout the sections after the header.
+        let sections: [(u32, u32, u64, &[u8], u32, u32, u64); 4] = [
+            (1, 1, 6, &text, 0, 0, 0),
+            (7, 2, 0, &symtab, 3, 1, 24),
+            (15, 3, 0, &strtab, 0, 0, 0),
+            (23, 3, 0, shstrtab, 0, 0, 0),
+        ];
+
+        let mut data = vec![0; 64];
+        let mut headers = vec![0; 64];
+
+        for (name, ty, flags, content, link, info, entsize) in sections {
+            let offset = data.len() as u64;
+
+            data.extend_from_slice(content);
+
+            headers.extend_from_slice(&name.to_le_bytes());
+            headers.extend_from_slice(&ty.to_le_bytes());
+            headers.extend_from_slice(&flags.to_le_bytes());
+            headers.extend_from_slice(&0u64.to_le_bytes());
+            headers.extend_from_slice(&offset.to_le_bytes());
+            headers.extend_from_slice(&(content.len() as u64).to_le_bytes());
+            headers.extend_from_slice(&link.to_le_bytes());
+            headers.extend_from_slice(&info.to_le_bytes());
+            headers.extend_from_slice(&1u64.to_le_bytes());
+            headers.extend_from_slice(&entsize.to_le_bytes());
+        }
+
+        data.resize(data.len().next_multiple_of(8), 0);
+
+        // Write the header.
+        let shoff = data.len() as u64;
+        let mut header = b"\x7FELF\x02\x01\x01".to_vec();
+
+        header.resize(16, 0);
+        header.extend_from_slice(&1u16.to_le_bytes()); // ET_REL.
+        header.extend_from_slice(&62u16.to_le_bytes()); // EM_X86_64.
+        header.extend_from_slice(&1u32.to_le_bytes());
+        header.extend_from_slice(&0u64.to_le_bytes());
+        header.extend_from_slice(&0u64.to_le_bytes());
+        header.extend_from_slice(&shoff.to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes());
+        header.extend_from_slice(&64u16.to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(&64u16.to_le_bytes());
+        header.extend_from_slice(&5u16.to_le_bytes());
+        header.extend_from_slice(&4u16.to_le_bytes());
+
+        data[..64].copy_from_slice(&header);
+        data.extend_from_slice(&headers);
+        data
+    }
+}
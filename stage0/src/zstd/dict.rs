@@ -0,0 +1,29 @@
+use crate::ffi::ZSTD_getDictID_fromDict;
+
+/// A dictionary trained by `zstd --train`.
+pub struct ZstdDictionary {
+    data: Vec<u8>,
+    id: u32,
+}
+
+impl ZstdDictionary {
+    /// Returns [`None`] if `data` is not a zstd dictionary. A raw content dictionary is not
+    /// supported since it does not have an ID to identify it.
+    pub fn new(data: Vec<u8>) -> Option<Self> {
+        let id = unsafe { ZSTD_getDictID_fromDict(data.as_ptr(), data.len()) };
+
+        if id == 0 {
+            None
+        } else {
+            Some(Self { data, id })
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
@@ -1,15 +1,17 @@
+pub use self::dict::*;
 pub use self::read::*;
 
 use crate::ffi::{
-    ZSTD_CCtx, ZSTD_CStreamInSize, ZSTD_CStreamOutSize, ZSTD_EndDirective, ZSTD_compressStream2,
-    ZSTD_createCStream, ZSTD_freeCStream, ZSTD_getErrorName, ZSTD_inBuffer, ZSTD_isError,
-    ZSTD_outBuffer,
+    ZSTD_CCtx, ZSTD_CCtx_loadDictionary, ZSTD_CStreamInSize, ZSTD_CStreamOutSize,
+    ZSTD_EndDirective, ZSTD_compressStream2, ZSTD_createCStream, ZSTD_freeCStream,
+    ZSTD_getErrorName, ZSTD_inBuffer, ZSTD_isError, ZSTD_outBuffer,
 };
 use std::cmp::min;
 use std::ffi::CStr;
-use std::io::{Error, ErrorKind, Write};
+use std::io::{Error, Write};
 use std::ptr::null;
 
+mod dict;
 mod read;
 
 /// An implementation of [`Write`] that compress the data with zstd before writing to the underlying
@@ -30,6 +32,18 @@ impl<D> ZstdWriter<D> {
             dest,
         }
     }
+
+    /// Compress the data with `dict`. This must be called before writing any data.
+    pub fn load_dictionary(&mut self, dict: &ZstdDictionary) -> std::io::Result<()> {
+        let data = dict.data();
+        let res = unsafe { ZSTD_CCtx_loadDictionary(self.cx, data.as_ptr(), data.len()) };
+
+        if unsafe { ZSTD_isError(res) } != 0 {
+            return Err(Error::other(error_name(res)));
+        }
+
+        Ok(())
+    }
 }
 
 impl<D> Drop for ZstdWriter<D> {
@@ -68,7 +82,7 @@ impl<D: Write> Write for ZstdWriter<D> {
             };
 
             if unsafe { ZSTD_isError(remain) } != 0 {
-                return Err(Error::new(ErrorKind::Other, error_name(remain)));
+                return Err(Error::other(error_name(remain)));
             }
 
             // Write the destination.
@@ -109,7 +123,7 @@ impl<D: Write> Write for ZstdWriter<D> {
             };
 
             if unsafe { ZSTD_isError(remain) } != 0 {
-                break Err(Error::new(ErrorKind::Other, error_name(remain)));
+                break Err(Error::other(error_name(remain)));
             }
 
             // Write the destination.
@@ -1,7 +1,7 @@
-use super::error_name;
+use super::{error_name, ZstdDictionary};
 use crate::ffi::{
-    ZSTD_DCtx, ZSTD_DStreamInSize, ZSTD_createDStream, ZSTD_decompressStream, ZSTD_freeDStream,
-    ZSTD_inBuffer, ZSTD_isError, ZSTD_outBuffer,
+    ZSTD_DCtx, ZSTD_DCtx_loadDictionary, ZSTD_DStreamInSize, ZSTD_createDStream,
+    ZSTD_decompressStream, ZSTD_freeDStream, ZSTD_inBuffer, ZSTD_isError, ZSTD_outBuffer,
 };
 use std::io::{Error, ErrorKind, Read};
 
@@ -24,6 +24,18 @@ impl<F> ZstdReader<F> {
             from,
         }
     }
+
+    /// Decompress the data with `dict`. This must be called before reading any data.
+    pub fn load_dictionary(&mut self, dict: &ZstdDictionary) -> std::io::Result<()> {
+        let data = dict.data();
+        let res = unsafe { ZSTD_DCtx_loadDictionary(self.cx, data.as_ptr(), data.len()) };
+
+        if unsafe { ZSTD_isError(res) } != 0 {
+            return Err(Error::other(error_name(res)));
+        }
+
+        Ok(())
+    }
 }
 
 impl<F> Drop for ZstdReader<F> {
@@ -79,7 +91,7 @@ impl<F: Read> Read for ZstdReader<F> {
             let res = unsafe { ZSTD_decompressStream(self.cx, &mut output, &mut input) };
 
             if unsafe { ZSTD_isError(res) } != 0 {
-                return Err(Error::new(ErrorKind::Other, error_name(res)));
+                return Err(Error::other(error_name(res)));
             }
 
             // Update state.